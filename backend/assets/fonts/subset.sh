@@ -0,0 +1,18 @@
+#!/bin/sh
+# 生成分享卡片内置的中文字体子集
+#
+# 用法: ./subset.sh <包含 NotoSansSC-Regular.otf 和 NotoSansSC-Bold.otf 的目录>
+# 需要 fonttools（pip install fonttools）。字符集见 charset.txt：GB2312 的全部汉字和中文标点。
+set -eu
+
+cd "$(dirname "$0")"
+src=${1:?用法: ./subset.sh <Noto Sans SC 字体目录>}
+
+for weight in Regular Bold; do
+    pyftsubset "$src/NotoSansSC-$weight.otf" \
+        --text-file=charset.txt \
+        --layout-features='*' \
+        --name-IDs='*' \
+        --output-file="NotoSansSC-$weight.subset.otf"
+done
+ls -l NotoSansSC-*.subset.otf
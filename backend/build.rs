@@ -0,0 +1,84 @@
+//! 构建信息：把 git 提交和构建时间写入编译期环境变量，供 `/version` 使用
+//!
+//! 在没有 `.git` 的环境中构建（例如 Docker）时，可以通过 `GIT_SHA` 环境变量传入提交；
+//! 设置了 `SOURCE_DATE_EPOCH` 时用它作为构建时间，便于可重复构建。
+//!
+//! 同时检查 `assets/fonts` 中的中文字体子集，存在时开启 `embedded_cjk_font`，由分享卡片内置。
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    // 切换分支或提交时重新生成
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]).map(PathBuf::from) {
+        println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+        println!(
+            "cargo:rerun-if-changed={}",
+            git_dir.join("packed-refs").display()
+        );
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!(
+                "cargo:rerun-if-changed={}",
+                git_dir.join(head_ref).display()
+            );
+        }
+    }
+
+    let sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| git(&["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let build_time = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        });
+
+    println!("cargo:rustc-env=ROWAN_GIT_SHA={sha}");
+    println!("cargo:rustc-env=ROWAN_BUILD_TIME={build_time}");
+
+    cjk_font();
+}
+
+/// 分享卡片内置的中文字体子集，生成方法见 `assets/fonts/README.md`
+///
+/// 子集文件齐全时开启 `embedded_cjk_font`；缺少时照常构建，卡片中的中文依赖
+/// `OG_FONT_DIR` 或系统字体，运行时也会给出警告。
+fn cjk_font() {
+    const FONTS: [&str; 2] = [
+        "assets/fonts/NotoSansSC-Regular.subset.otf",
+        "assets/fonts/NotoSansSC-Bold.subset.otf",
+    ];
+
+    println!("cargo::rustc-check-cfg=cfg(embedded_cjk_font)");
+    // 监听目录而不是文件：文件不存在时监听它会让构建脚本每次都重新执行
+    println!("cargo:rerun-if-changed=assets/fonts");
+    if FONTS.iter().all(|font| Path::new(font).is_file()) {
+        println!("cargo:rustc-cfg=embedded_cjk_font");
+    } else {
+        println!(
+            "cargo:warning=未找到中文字体子集 {}，分享卡片中的中文需要 OG_FONT_DIR 或系统字体",
+            FONTS.join(" / ")
+        );
+    }
+}
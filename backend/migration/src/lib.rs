@@ -6,19 +6,80 @@ mod m20250724_013111_create_visitor_profiles_table;
 mod m20250724_014021_create_friends_links_table;
 mod m20250724_015502_create_likes_table;
 mod m20250724_035017_create_essays_table;
+mod m20261019_080000_create_baseline_tables_portable;
+mod m20261019_090000_create_note_daily_stats_table;
+mod m20261019_100000_create_admins_table;
+mod m20261019_110000_create_sessions_table;
+mod m20261019_120000_add_admin_totp;
+mod m20261019_130000_add_essay_status_and_revisions;
+mod m20261019_140000_add_friend_link_health_and_applications;
+mod m20261019_150000_create_slug_redirects_table;
+mod m20261019_160000_create_assets_table;
+mod m20261019_170000_create_asset_variants_table;
+mod m20261019_180000_create_site_daily_stats_table;
+mod m20261019_190000_add_admin_mfa_challenge;
 
 pub struct Migrator;
 
+/// 只在 SQLite 上执行的迁移
+///
+/// 最早的几个建表迁移依赖 SQLite 的宽松行为（外键指向尚未创建的表、
+/// 同一列同时声明 `NOT NULL` 与 `NULL`），在 PostgreSQL 上无法执行。
+/// 这些迁移已经发布，内容保持不变；其他数据库上的建表由
+/// `m20261019_080000_create_baseline_tables_portable` 负责。
+struct SqliteOnly<M>(M);
+
+impl<M: MigrationName> MigrationName for SqliteOnly<M> {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: MigrationTrait> MigrationTrait for SqliteOnly<M> {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == sea_orm::DatabaseBackend::Sqlite {
+            self.0.up(manager).await
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == sea_orm::DatabaseBackend::Sqlite {
+            self.0.down(manager).await
+        } else {
+            Ok(())
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
             Box::new(m20250723_235336_create_notes_metadata_table::Migration),
-            Box::new(m20250724_002939_create_comments_table::Migration),
-            Box::new(m20250724_013111_create_visitor_profiles_table::Migration),
+            Box::new(SqliteOnly(
+                m20250724_002939_create_comments_table::Migration,
+            )),
+            Box::new(SqliteOnly(
+                m20250724_013111_create_visitor_profiles_table::Migration,
+            )),
             Box::new(m20250724_014021_create_friends_links_table::Migration),
             Box::new(m20250724_015502_create_likes_table::Migration),
-            Box::new(m20250724_035017_create_essays_table::Migration),
+            Box::new(SqliteOnly(m20250724_035017_create_essays_table::Migration)),
+            Box::new(m20261019_080000_create_baseline_tables_portable::Migration),
+            Box::new(m20261019_090000_create_note_daily_stats_table::Migration),
+            Box::new(m20261019_100000_create_admins_table::Migration),
+            Box::new(m20261019_110000_create_sessions_table::Migration),
+            Box::new(m20261019_120000_add_admin_totp::Migration),
+            Box::new(m20261019_130000_add_essay_status_and_revisions::Migration),
+            Box::new(m20261019_140000_add_friend_link_health_and_applications::Migration),
+            Box::new(m20261019_150000_create_slug_redirects_table::Migration),
+            Box::new(m20261019_160000_create_assets_table::Migration),
+            Box::new(m20261019_170000_create_asset_variants_table::Migration),
+            Box::new(m20261019_180000_create_site_daily_stats_table::Migration),
+            Box::new(m20261019_190000_add_admin_mfa_challenge::Migration),
         ]
     }
 }
@@ -0,0 +1,166 @@
+use sea_orm_migration::sea_orm::DatabaseBackend;
+use sea_orm_migration::{prelude::*, schema::*};
+
+/// 在 SQLite 以外的数据库上创建 `visitor_profiles`、`essays`、`comments` 三张表
+///
+/// 最早的三个建表迁移只在 SQLite 上执行（见 `lib.rs` 中的 `SqliteOnly`）：
+/// `comments` 引用了之后才创建的表，`ip`、`title` 列同时声明了 `NOT NULL` 和 `NULL`，
+/// SQLite 能接受（实际效果是 `NOT NULL`），PostgreSQL 会直接报错。
+/// 这里按依赖顺序建出与 SQLite 中实际结构一致的表；SQLite 上什么也不做。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DatabaseBackend::Sqlite {
+            return Ok(());
+        }
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(VisitorProfiles::Table)
+                    .if_not_exists()
+                    .col(pk_auto(VisitorProfiles::Id))
+                    .col(string_len(VisitorProfiles::CookieId, 40).unique_key())
+                    .col(string_len(VisitorProfiles::Name, 13).unique_key())
+                    .col(string_len(VisitorProfiles::Ip, 45))
+                    .col(
+                        timestamp_with_time_zone(VisitorProfiles::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        timestamp_with_time_zone(VisitorProfiles::UpdatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Essays::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Essays::Id))
+                    .col(string_len(Essays::Title, 20))
+                    .col(text(Essays::Content))
+                    .col(
+                        timestamp_with_time_zone(Essays::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        timestamp_with_time_zone(Essays::UpdatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Comments::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Comments::Id))
+                    .col(integer_null(Comments::NoteMetadataId))
+                    .col(integer_null(Comments::EssayId))
+                    .col(integer(Comments::VisitorProfileId))
+                    .col(text(Comments::Content))
+                    .col(integer_null(Comments::ParentId))
+                    .col(
+                        timestamp_with_time_zone(Comments::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(boolean(Comments::IsApproved).default(false))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-comments-note_metadata_id")
+                            .from(Comments::Table, Comments::NoteMetadataId)
+                            .to(NotesMetadata::Table, NotesMetadata::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-comments-essay_id")
+                            .from(Comments::Table, Comments::EssayId)
+                            .to(Essays::Table, Essays::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-comments-parent_id")
+                            .from(Comments::Table, Comments::ParentId)
+                            .to(Comments::Table, Comments::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-comments-visitor_profile_id")
+                            .from(Comments::Table, Comments::VisitorProfileId)
+                            .to(VisitorProfiles::Table, VisitorProfiles::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DatabaseBackend::Sqlite {
+            return Ok(());
+        }
+
+        for table in [
+            Comments::Table.into_iden(),
+            Essays::Table.into_iden(),
+            VisitorProfiles::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum VisitorProfiles {
+    Table,
+    Id,
+    CookieId,
+    Name,
+    Ip,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Essays {
+    Table,
+    Id,
+    Title,
+    Content,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Comments {
+    Table,
+    Id,
+    NoteMetadataId,
+    EssayId,
+    VisitorProfileId,
+    Content,
+    ParentId,
+    CreatedAt,
+    IsApproved,
+}
+
+#[derive(DeriveIden)]
+enum NotesMetadata {
+    Table,
+    Id,
+}
@@ -0,0 +1,116 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(NoteDailyStats::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NoteDailyStats::Id))
+                    .col(integer(NoteDailyStats::NoteMetadataId).not_null())
+                    .col(date(NoteDailyStats::Day).not_null())
+                    .col(integer(NoteDailyStats::Views).not_null().default(0))
+                    .col(integer(NoteDailyStats::UniqueVisitors).not_null().default(0))
+                    .col(integer(NoteDailyStats::Likes).not_null().default(0))
+                    .col(integer(NoteDailyStats::Comments).not_null().default(0))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-note_daily_stats-note_metadata_id")
+                            .from(NoteDailyStats::Table, NoteDailyStats::NoteMetadataId)
+                            .to(NotesMetadata::Table, NotesMetadata::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 每篇笔记每天只有一行，写入时按这个唯一键做 upsert
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_note_daily_stats_note_day_unique")
+                    .table(NoteDailyStats::Table)
+                    .col(NoteDailyStats::NoteMetadataId)
+                    .col(NoteDailyStats::Day)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(NoteReferrerStats::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NoteReferrerStats::Id))
+                    .col(integer(NoteReferrerStats::NoteMetadataId).not_null())
+                    .col(date(NoteReferrerStats::Day).not_null())
+                    .col(string_len(NoteReferrerStats::Referrer, 255).not_null())
+                    .col(integer(NoteReferrerStats::Views).not_null().default(0))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-note_referrer_stats-note_metadata_id")
+                            .from(NoteReferrerStats::Table, NoteReferrerStats::NoteMetadataId)
+                            .to(NotesMetadata::Table, NotesMetadata::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_note_referrer_stats_note_day_referrer_unique")
+                    .table(NoteReferrerStats::Table)
+                    .col(NoteReferrerStats::NoteMetadataId)
+                    .col(NoteReferrerStats::Day)
+                    .col(NoteReferrerStats::Referrer)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(NoteReferrerStats::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(NoteDailyStats::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum NoteDailyStats {
+    Table,
+    Id,
+    NoteMetadataId,
+    Day,
+    Views,
+    UniqueVisitors,
+    Likes,
+    Comments,
+}
+
+#[derive(DeriveIden)]
+enum NoteReferrerStats {
+    Table,
+    Id,
+    NoteMetadataId,
+    Day,
+    Referrer,
+    Views,
+}
+
+#[derive(DeriveIden)]
+enum NotesMetadata {
+    Table,
+    Id,
+}
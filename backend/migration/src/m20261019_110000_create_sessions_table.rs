@@ -0,0 +1,78 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Sessions::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Sessions::Id))
+                    .col(integer(Sessions::AdminId).not_null())
+                    .col(string_len(Sessions::FamilyId, 36).not_null()) // 同一次登录轮换出的所有刷新令牌共享一个 family
+                    .col(string_len(Sessions::TokenHash, 64).not_null().unique_key()) // 刷新令牌的 SHA-256，不保存明文
+                    .col(string_len_null(Sessions::UserAgent, 255))
+                    .col(string_len_null(Sessions::Ip, 45))
+                    .col(timestamp_with_time_zone(Sessions::StartedAt).not_null())
+                    .col(
+                        timestamp_with_time_zone(Sessions::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(Sessions::ExpiresAt).not_null())
+                    .col(timestamp_with_time_zone_null(Sessions::RotatedAt))
+                    .col(timestamp_with_time_zone_null(Sessions::RevokedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-sessions-admin_id")
+                            .from(Sessions::Table, Sessions::AdminId)
+                            .to(Admins::Table, Admins::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_sessions_family_id")
+                    .table(Sessions::Table)
+                    .col(Sessions::FamilyId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Sessions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Sessions {
+    Table,
+    Id,
+    AdminId,
+    FamilyId,
+    TokenHash,
+    UserAgent,
+    Ip,
+    StartedAt,
+    CreatedAt,
+    ExpiresAt,
+    RotatedAt,
+    RevokedAt,
+}
+
+#[derive(DeriveIden)]
+enum Admins {
+    Table,
+    Id,
+}
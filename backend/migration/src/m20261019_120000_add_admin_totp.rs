@@ -0,0 +1,113 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 的 ALTER TABLE 一次只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admins::Table)
+                    .add_column(string_len_null(Admins::TotpSecret, 64)) // Base32 编码的 TOTP 密钥，绑定完成前也会先写入
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admins::Table)
+                    .add_column(timestamp_with_time_zone_null(Admins::TotpEnabledAt))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admins::Table)
+                    .add_column(big_integer_null(Admins::TotpLastStep)) // 最近一次验证通过的时间步，防止验证码重放
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecoveryCodes::Table)
+                    .if_not_exists()
+                    .col(pk_auto(RecoveryCodes::Id))
+                    .col(integer(RecoveryCodes::AdminId).not_null())
+                    .col(string_len(RecoveryCodes::CodeHash, 64).not_null()) // 恢复码的 SHA-256，不保存明文
+                    .col(
+                        timestamp_with_time_zone(RecoveryCodes::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(RecoveryCodes::UsedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-recovery_codes-admin_id")
+                            .from(RecoveryCodes::Table, RecoveryCodes::AdminId)
+                            .to(Admins::Table, Admins::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_recovery_codes_admin_code_unique")
+                    .table(RecoveryCodes::Table)
+                    .col(RecoveryCodes::AdminId)
+                    .col(RecoveryCodes::CodeHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecoveryCodes::Table).to_owned())
+            .await?;
+
+        for column in [
+            Admins::TotpLastStep,
+            Admins::TotpEnabledAt,
+            Admins::TotpSecret,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Admins::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Admins {
+    Table,
+    Id,
+    TotpSecret,
+    TotpEnabledAt,
+    TotpLastStep,
+}
+
+#[derive(DeriveIden)]
+enum RecoveryCodes {
+    Table,
+    Id,
+    AdminId,
+    CodeHash,
+    CreatedAt,
+    UsedAt,
+}
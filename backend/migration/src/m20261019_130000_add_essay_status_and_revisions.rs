@@ -0,0 +1,131 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 已有的随笔都视为已发布
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Essays::Table)
+                    .add_column(
+                        string_len(Essays::Status, 16)
+                            .not_null()
+                            .default("published"),
+                    ) // draft / published / scheduled
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Essays::Table)
+                    .add_column(timestamp_with_time_zone_null(Essays::PublishAt)) // 定时发布时间，发布后即为实际发布时间
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE essays SET publish_at = created_at WHERE publish_at IS NULL",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_essays_status_publish_at")
+                    .table(Essays::Table)
+                    .col(Essays::Status)
+                    .col(Essays::PublishAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(EssayRevisions::Table)
+                    .if_not_exists()
+                    .col(pk_auto(EssayRevisions::Id))
+                    .col(integer(EssayRevisions::EssayId).not_null())
+                    .col(integer(EssayRevisions::Revision).not_null()) // 从 1 开始递增的版本号
+                    .col(string_len_null(EssayRevisions::Title, 20))
+                    .col(text(EssayRevisions::Content).not_null())
+                    .col(
+                        timestamp_with_time_zone(EssayRevisions::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-essay_revisions-essay_id")
+                            .from(EssayRevisions::Table, EssayRevisions::EssayId)
+                            .to(Essays::Table, Essays::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_essay_revisions_essay_revision_unique")
+                    .table(EssayRevisions::Table)
+                    .col(EssayRevisions::EssayId)
+                    .col(EssayRevisions::Revision)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EssayRevisions::Table).to_owned())
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_essays_status_publish_at")
+                    .table(Essays::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [Essays::PublishAt, Essays::Status] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Essays::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Essays {
+    Table,
+    Id,
+    Status,
+    PublishAt,
+}
+
+#[derive(DeriveIden)]
+enum EssayRevisions {
+    Table,
+    Id,
+    EssayId,
+    Revision,
+    Title,
+    Content,
+    CreatedAt,
+}
@@ -0,0 +1,135 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 友链健康检查结果，SQLite 一次只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FriendsLinks::Table)
+                    .add_column(integer_null(FriendsLinks::LastStatus)) // 最近一次检查的 HTTP 状态码，请求失败时为空
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FriendsLinks::Table)
+                    .add_column(string_len_null(FriendsLinks::LastError, 255))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FriendsLinks::Table)
+                    .add_column(timestamp_with_time_zone_null(FriendsLinks::LastCheckedAt))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FriendsLinks::Table)
+                    .add_column(timestamp_with_time_zone_null(FriendsLinks::LastSeenAt)) // 最近一次能正常访问的时间
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FriendLinkApplications::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FriendLinkApplications::Id))
+                    .col(string_len(FriendLinkApplications::Name, 15).not_null())
+                    .col(string_len(FriendLinkApplications::Url, 255).not_null())
+                    .col(string_len_null(FriendLinkApplications::Description, 100))
+                    .col(string_len_null(FriendLinkApplications::LogoUrl, 255))
+                    .col(string_len_null(FriendLinkApplications::Contact, 100)) // 联系方式，如邮箱
+                    .col(string_len_null(FriendLinkApplications::Message, 500))
+                    .col(
+                        string_len(FriendLinkApplications::Status, 16)
+                            .not_null()
+                            .default("pending"),
+                    ) // pending / approved / rejected
+                    .col(string_len(FriendLinkApplications::Ip, 45).not_null())
+                    .col(
+                        timestamp_with_time_zone(FriendLinkApplications::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(
+                        FriendLinkApplications::ReviewedAt,
+                    ))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_friend_link_applications_status")
+                    .table(FriendLinkApplications::Table)
+                    .col(FriendLinkApplications::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(FriendLinkApplications::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            FriendsLinks::LastSeenAt,
+            FriendsLinks::LastCheckedAt,
+            FriendsLinks::LastError,
+            FriendsLinks::LastStatus,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(FriendsLinks::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum FriendsLinks {
+    Table,
+    LastStatus,
+    LastError,
+    LastCheckedAt,
+    LastSeenAt,
+}
+
+#[derive(DeriveIden)]
+enum FriendLinkApplications {
+    Table,
+    Id,
+    Name,
+    Url,
+    Description,
+    LogoUrl,
+    Contact,
+    Message,
+    Status,
+    Ip,
+    CreatedAt,
+    ReviewedAt,
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SlugRedirects::Table)
+                    .if_not_exists()
+                    .col(pk_auto(SlugRedirects::Id))
+                    .col(string(SlugRedirects::OldSlug).not_null().unique_key()) // 旧 slug，同一个旧 slug 只能指向一篇笔记
+                    .col(integer(SlugRedirects::NoteId).not_null()) // 指向笔记 ID 而不是新 slug，多次改名也只需跳转一次
+                    .col(
+                        timestamp_with_time_zone(SlugRedirects::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-slug_redirects-note_id")
+                            .from(SlugRedirects::Table, SlugRedirects::NoteId)
+                            .to(NotesMetadata::Table, NotesMetadata::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_slug_redirects_note_id")
+                    .table(SlugRedirects::Table)
+                    .col(SlugRedirects::NoteId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SlugRedirects::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SlugRedirects {
+    Table,
+    Id,
+    OldSlug,
+    NoteId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum NotesMetadata {
+    Table,
+    Id,
+}
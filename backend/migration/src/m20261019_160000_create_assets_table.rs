@@ -0,0 +1,47 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Assets::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Assets::Id))
+                    .col(string_len(Assets::Hash, 64).not_null().unique_key()) // 去除元数据后内容的 SHA-256，同一文件只存一份
+                    .col(string(Assets::MimeType).not_null()) // 按文件头识别出的类型，不信任客户端声明
+                    .col(string(Assets::Extension).not_null())
+                    .col(big_integer(Assets::Size).not_null())
+                    .col(string_null(Assets::OriginalName))
+                    .col(
+                        timestamp_with_time_zone(Assets::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Assets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Assets {
+    Table,
+    Id,
+    Hash,
+    MimeType,
+    Extension,
+    Size,
+    OriginalName,
+    CreatedAt,
+}
@@ -0,0 +1,105 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 的 ALTER TABLE 一次只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Assets::Table)
+                    .add_column(integer_null(Assets::Width)) // 按方向标记旋转后的显示尺寸，非图片为空
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Assets::Table)
+                    .add_column(integer_null(Assets::Height))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AssetVariants::Table)
+                    .if_not_exists()
+                    .col(pk_auto(AssetVariants::Id))
+                    .col(integer(AssetVariants::AssetId).not_null())
+                    .col(integer(AssetVariants::Width).not_null())
+                    .col(integer(AssetVariants::Height).not_null())
+                    .col(string_len(AssetVariants::Format, 16).not_null()) // webp、avif
+                    .col(big_integer(AssetVariants::Size).not_null())
+                    .col(
+                        timestamp_with_time_zone(AssetVariants::CreatedAt)
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-asset_variants-asset_id")
+                            .from(AssetVariants::Table, AssetVariants::AssetId)
+                            .to(Assets::Table, Assets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_asset_variants_unique")
+                    .table(AssetVariants::Table)
+                    .col(AssetVariants::AssetId)
+                    .col(AssetVariants::Width)
+                    .col(AssetVariants::Format)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AssetVariants::Table).to_owned())
+            .await?;
+
+        for column in [Assets::Height, Assets::Width] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Assets::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Assets {
+    Table,
+    Id,
+    Width,
+    Height,
+}
+
+#[derive(DeriveIden)]
+enum AssetVariants {
+    Table,
+    Id,
+    AssetId,
+    Width,
+    Height,
+    Format,
+    Size,
+    CreatedAt,
+}
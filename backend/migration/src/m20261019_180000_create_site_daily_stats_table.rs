@@ -0,0 +1,51 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SiteDailyStats::Table)
+                    .if_not_exists()
+                    .col(pk_auto(SiteDailyStats::Id))
+                    .col(date(SiteDailyStats::Day).not_null())
+                    .col(
+                        integer(SiteDailyStats::UniqueVisitors)
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 每天只有一行，写入时按这个唯一键做 upsert
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_site_daily_stats_day_unique")
+                    .table(SiteDailyStats::Table)
+                    .col(SiteDailyStats::Day)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SiteDailyStats::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SiteDailyStats {
+    Table,
+    Id,
+    Day,
+    UniqueVisitors,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 的 ALTER TABLE 一次只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admins::Table)
+                    .add_column(string_len_null(Admins::MfaChallengeJti, 36)) // 当前有效的 MFA 令牌 jti，提交一次即清空
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admins::Table)
+                    .add_column(integer(Admins::MfaFailedAttempts).not_null().default(0))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admins::Table)
+                    .add_column(timestamp_with_time_zone_null(Admins::MfaLockedUntil))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Admins::MfaLockedUntil,
+            Admins::MfaFailedAttempts,
+            Admins::MfaChallengeJti,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Admins::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Admins {
+    Table,
+    MfaChallengeJti,
+    MfaFailedAttempts,
+    MfaLockedUntil,
+}
@@ -0,0 +1,384 @@
+//! API 路由聚合
+//!
+//! 各处理器模块通过 `routes` 函数把自己的端点注册到 `AnnotatedRouter` 上，
+//! 这里负责把它们串起来，并提供处理器共用的请求信息提取器。
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{HeaderMap, HeaderValue, StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::ServerConfig,
+    error::{AppError, AppResult},
+    infra::{db::AppState, repositories::VisitorRepository},
+    schema::AnnotatedRouter,
+    service::{
+        auth_service::{self, TokenType},
+        session_service::{self, DeviceInfo},
+    },
+};
+
+pub mod asset_handler;
+pub mod auth_handler;
+pub mod essay_handler;
+pub mod feed_handler;
+pub mod friend_link_handler;
+pub mod health_handler;
+pub mod metrics_handler;
+pub mod mfa_handler;
+pub mod note_handler;
+pub mod og_handler;
+#[cfg(feature = "html")]
+pub mod page_handler;
+pub mod sitemap_handler;
+pub mod stats_handler;
+
+/// 访客 Cookie 名称，值对应 `visitor_profiles.cookie_id`
+pub const VISITOR_COOKIE: &str = "rowan_visitor";
+
+/// 创建完整的 API 路由
+pub fn create_api_router() -> AnnotatedRouter {
+    let router = AnnotatedRouter::new();
+    let router = asset_handler::routes(router);
+    let router = auth_handler::routes(router);
+    let router = mfa_handler::routes(router);
+    let router = essay_handler::routes(router);
+    let router = feed_handler::routes(router);
+    let router = friend_link_handler::routes(router);
+    let router = health_handler::routes(router);
+    let router = metrics_handler::routes(router);
+    let router = note_handler::routes(router);
+    let router = og_handler::routes(router);
+    #[cfg(feature = "html")]
+    let router = page_handler::routes(router);
+    let router = sitemap_handler::routes(router);
+    stats_handler::routes(router)
+}
+
+/// 客户端信息提取器
+///
+/// 对端是 `TRUSTED_PROXIES` 中的可信反向代理时才采信 `X-Forwarded-For` / `X-Real-IP`，
+/// 否则直接使用 TCP 连接的对端地址，避免客户端伪造请求头绕过浏览量去重。
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip: String,
+    pub user_agent: Option<String>,
+    pub visitor_id: Option<String>,
+    /// 原始 Referer 头
+    pub referrer: Option<String>,
+    /// 请求的 Host 头，用于识别站内跳转
+    pub host: Option<String>,
+}
+
+impl ClientInfo {
+    /// 用于去重的访客标识
+    ///
+    /// 访客 Cookie 对应已有的访客资料时才采信，否则使用 IP：
+    /// 客户端每次随便换一个 Cookie 值不能刷高浏览量和独立访客数。
+    pub async fn visitor_key(&self, visitors: &dyn VisitorRepository) -> AppResult<String> {
+        if let Some(cookie) = &self.visitor_id
+            && let Some(visitor) = visitors.find_visitor_by_cookie(cookie).await?
+        {
+            return Ok(format!("visitor:{}", visitor.id));
+        }
+        Ok(self.ip.clone())
+    }
+
+    /// 创建会话时记录的设备信息
+    pub fn device(&self) -> DeviceInfo {
+        DeviceInfo {
+            user_agent: self.user_agent.clone(),
+            ip: Some(self.ip.clone()),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let headers = &parts.headers;
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let server = parts
+            .extensions
+            .get::<AppState>()
+            .map(|state| &state.server);
+        let ip = client_ip(headers, peer, server)
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        Ok(Self {
+            ip,
+            user_agent: header_str(headers, header::USER_AGENT.as_str()),
+            visitor_id: cookie_value(headers, VISITOR_COOKIE),
+            referrer: header_str(headers, header::REFERER.as_str()),
+            host: header_str(headers, header::HOST.as_str()),
+        })
+    }
+}
+
+/// 管理员认证提取器
+///
+/// 从 `Authorization: Bearer <token>` 中读取访问令牌并校验，
+/// 处理器只要声明这个参数就只允许站长访问。
+#[derive(Debug, Clone)]
+pub struct AdminAuth {
+    pub admin_id: i32,
+    pub username: String,
+    /// 当前访问令牌所属的会话ID
+    pub session_id: String,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for AdminAuth {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let state = parts
+            .extensions
+            .get::<AppState>()
+            .ok_or_else(|| AppError::Internal("AppState 未注册".to_string()))?;
+
+        let token = bearer_token(&parts.headers).ok_or(AppError::Unauthorized)?;
+        let claims = auth_service::verify_token(&token, TokenType::Access, &state.jwt)?;
+        let admin_id = claims.admin_id()?;
+        let session_id = claims.sid.ok_or(AppError::Unauthorized)?;
+
+        // 会话被吊销后，尚未过期的访问令牌也随之失效
+        if !session_service::is_active(&state.db, admin_id, &session_id).await? {
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(Self {
+            admin_id,
+            username: claims.username,
+            session_id,
+        })
+    }
+}
+
+/// 读取 `Authorization: Bearer <token>` 中的令牌
+pub fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    header_str(headers, header::AUTHORIZATION.as_str())
+        .and_then(|value| {
+            value
+                .strip_prefix("Bearer ")
+                .map(|token| token.trim().to_string())
+        })
+        .filter(|token| !token.is_empty())
+}
+
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// 确定客户端地址
+///
+/// 对端不是可信代理时直接返回对端地址。否则从右往左查看 `X-Forwarded-For`，
+/// 跳过可信代理自己追加的地址，第一个不可信的地址就是客户端；
+/// 全部可信时取最左边的地址，遇到无法解析的地址则停下。没有 `X-Forwarded-For` 时再看 `X-Real-IP`。
+fn client_ip(
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    server: Option<&ServerConfig>,
+) -> Option<IpAddr> {
+    let (Some(peer), Some(server)) = (peer, server) else {
+        return peer;
+    };
+    if !server.is_trusted_proxy(peer) {
+        return Some(peer);
+    }
+
+    if let Some(value) = header_str(headers, "x-forwarded-for") {
+        let mut client = None;
+        // 遇到无法解析的地址就停下，更左边的内容不可信
+        for ip in value
+            .rsplit(',')
+            .map_while(|ip| ip.trim().parse::<IpAddr>().ok())
+        {
+            if !server.is_trusted_proxy(ip) {
+                return Some(ip);
+            }
+            client = Some(ip);
+        }
+        return client.or(Some(peer));
+    }
+    header_str(headers, "x-real-ip")
+        .and_then(|ip| ip.trim().parse().ok())
+        .or(Some(peer))
+}
+
+/// 从 Cookie 头中读取指定名称的值
+pub fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// 带缓存校验的响应
+///
+/// `ETag` 取响应体的 SHA-256，`Last-Modified` 由调用方给出；
+/// 客户端的 `If-None-Match` / `If-Modified-Since` 命中时返回 304。
+pub fn cacheable_response(
+    headers: &HeaderMap,
+    content_type: &'static str,
+    body: impl AsRef<[u8]> + IntoResponse,
+    last_modified: Option<DateTime<Utc>>,
+) -> Response {
+    let etag = format!("\"{}\"", hex::encode(Sha256::digest(body.as_ref())));
+    let last_modified = last_modified.map(|at| at.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
+
+    let not_modified = match header_str(headers, header::IF_NONE_MATCH.as_str()) {
+        // 有 If-None-Match 时忽略 If-Modified-Since（RFC 9110 13.1.3）
+        Some(value) => value
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag),
+        None => match (
+            header_str(headers, header::IF_MODIFIED_SINCE.as_str())
+                .and_then(|value| DateTime::parse_from_rfc2822(&value).ok()),
+            &last_modified,
+        ) {
+            (Some(since), Some(modified)) => {
+                DateTime::parse_from_rfc2822(modified).is_ok_and(|modified| modified <= since)
+            }
+            _ => false,
+        },
+    };
+
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        ([(header::CONTENT_TYPE, content_type)], body).into_response()
+    };
+    let response_headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, value);
+    }
+    if let Some(value) = last_modified.and_then(|value| HeaderValue::from_str(&value).ok()) {
+        response_headers.insert(header::LAST_MODIFIED, value);
+    }
+    response
+}
+
+/// 301 永久重定向
+pub fn moved_permanently(location: &str) -> Response {
+    match HeaderValue::from_str(location) {
+        Ok(location) => (
+            StatusCode::MOVED_PERMANENTLY,
+            [(header::LOCATION, location)],
+        )
+            .into_response(),
+        Err(_) => AppError::Internal(format!("无效的跳转地址: {location}")).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_ip_only_trusts_configured_proxies() {
+        let server = ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 8000,
+            trusted_proxies: vec![
+                "10.0.0.0/8".parse().unwrap(),
+                "127.0.0.1/32".parse().unwrap(),
+            ],
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("1.1.1.1, 2.2.2.2, 10.0.0.2"),
+        );
+        let ip = |peer: &str, headers: &HeaderMap| {
+            client_ip(headers, Some(peer.parse().unwrap()), Some(&server)).map(|ip| ip.to_string())
+        };
+
+        // 客户端直连时伪造的请求头被忽略
+        assert_eq!(ip("8.8.8.8", &headers).as_deref(), Some("8.8.8.8"));
+        // 经过可信代理时取最右边的不可信地址，客户端自己填的 1.1.1.1 不算数
+        assert_eq!(ip("10.0.0.1", &headers).as_deref(), Some("2.2.2.2"));
+        assert_eq!(ip("::ffff:127.0.0.1", &headers).as_deref(), Some("2.2.2.2"));
+
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("garbage, 10.0.0.3"),
+        );
+        assert_eq!(ip("10.0.0.1", &headers).as_deref(), Some("10.0.0.3"));
+
+        headers.remove("x-forwarded-for");
+        headers.insert("x-real-ip", HeaderValue::from_static("3.3.3.3"));
+        assert_eq!(ip("10.0.0.1", &headers).as_deref(), Some("3.3.3.3"));
+        assert_eq!(ip("8.8.8.8", &headers).as_deref(), Some("8.8.8.8"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_visitor_cookie_falls_back_to_ip() {
+        use chrono::Utc;
+
+        use crate::{
+            config::ViewConfig,
+            infra::{db::entities::visitor_profiles, repositories::memory::InMemoryRepository},
+            service::view_service::ViewTracker,
+        };
+
+        let repo = InMemoryRepository::new();
+        let now = Utc::now();
+        let known = repo
+            .insert_visitor(visitor_profiles::Model {
+                id: 0,
+                cookie_id: "known-cookie".to_string(),
+                name: "小明".to_string(),
+                ip: "5.6.7.8".to_string(),
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+        let client = |cookie: &str| ClientInfo {
+            ip: "1.2.3.4".to_string(),
+            user_agent: Some("Mozilla/5.0 (X11; Linux x86_64) Firefox/128.0".to_string()),
+            visitor_id: Some(cookie.to_string()),
+            referrer: None,
+            host: None,
+        };
+
+        assert_eq!(
+            client("known-cookie").visitor_key(&repo).await.unwrap(),
+            format!("visitor:{}", known.id)
+        );
+        // 每次请求换一个伪造的 Cookie，仍然按 IP 识别为同一个访客
+        let tracker = ViewTracker::new(&ViewConfig {
+            dedup_window_secs: 60,
+            flush_interval_secs: 30,
+            max_tracked: 100,
+        });
+        for (i, cookie) in ["forged-1", "forged-2", "forged-3"].into_iter().enumerate() {
+            let client = client(cookie);
+            let key = client.visitor_key(&repo).await.unwrap();
+            assert_eq!(key, "1.2.3.4");
+            assert_eq!(
+                tracker.record(1, &key, client.user_agent.as_deref(), "direct"),
+                i == 0
+            );
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use axum::{
+    Extension, Json,
+    extract::{DefaultBodyLimit, Multipart, Path, Query},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+};
+use bytes::BytesMut;
+use meta_macros::Schema;
+use serde::Serialize;
+
+use crate::{
+    api::AdminAuth,
+    error::{AppError, AppResult},
+    infra::{
+        db::{AppState, entities::assets},
+        media::VariantFormat,
+    },
+    schema::{AnnotatedRouter, Method},
+    service::{
+        PaginationQuery, asset_service,
+        image_service::{self, ResponsiveImage},
+    },
+};
+
+/// 上传表单中文件字段的名字
+const FILE_FIELD: &str = "file";
+
+/// 媒体文件
+#[derive(Debug, Serialize, Schema)]
+pub struct AssetResponse {
+    pub id: i32,
+    /// 访问地址
+    pub url: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub original_name: Option<String>,
+    /// 图片的显示宽高，非图片为空
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub created_at: String,
+}
+
+impl AssetResponse {
+    fn new(state: &AppState, asset: assets::Model) -> Self {
+        Self {
+            id: asset.id,
+            url: state.site.media_url(&asset_service::file_name(&asset)),
+            mime_type: asset.mime_type,
+            size: asset.size,
+            original_name: asset.original_name,
+            width: asset.width,
+            height: asset.height,
+            created_at: asset.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// 媒体文件列表
+#[derive(Debug, Serialize, Schema)]
+pub struct AssetListResponse {
+    pub assets: Vec<AssetResponse>,
+    pub total: u64,
+}
+
+/// 图片变体
+#[derive(Debug, Serialize, Schema)]
+pub struct ImageVariantResponse {
+    pub url: String,
+    pub width: i32,
+    pub height: i32,
+    pub mime_type: String,
+    pub size: i64,
+}
+
+/// 同一格式的一组变体，对应 `<source type srcset>`
+#[derive(Debug, Serialize, Schema)]
+pub struct ImageSourceResponse {
+    pub mime_type: String,
+    pub srcset: String,
+}
+
+/// 响应式图片信息
+#[derive(Debug, Serialize, Schema)]
+pub struct ResponsiveImageResponse {
+    /// 原图地址
+    pub url: String,
+    pub mime_type: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// `sizes` 属性
+    pub sizes: Option<String>,
+    /// 按浏览器应优先选择的格式排列
+    pub sources: Vec<ImageSourceResponse>,
+    pub variants: Vec<ImageVariantResponse>,
+}
+
+impl ResponsiveImageResponse {
+    fn new(state: &AppState, image: ResponsiveImage) -> Self {
+        let site = &state.site;
+        Self {
+            url: image.src(site),
+            mime_type: image.asset.mime_type.clone(),
+            width: image.asset.width,
+            height: image.asset.height,
+            sizes: image.sizes(),
+            sources: image
+                .sources(site)
+                .into_iter()
+                .map(|source| ImageSourceResponse {
+                    mime_type: source.format.mime_type().to_string(),
+                    srcset: source.srcset,
+                })
+                .collect(),
+            variants: image
+                .variants
+                .iter()
+                .map(|variant| ImageVariantResponse {
+                    url: image_service::variant_url(site, &image.asset, variant),
+                    width: variant.width,
+                    height: variant.height,
+                    mime_type: variant
+                        .format
+                        .parse::<VariantFormat>()
+                        .map(|format| format.mime_type().to_string())
+                        .unwrap_or_default(),
+                    size: variant.size,
+                })
+                .collect(),
+        }
+    }
+}
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<AssetResponse>(
+            "/api/assets",
+            // 大小上限由处理器按配置检查
+            post(upload).layer(DefaultBodyLimit::disable()),
+            Method::POST,
+            "上传媒体文件（multipart，字段名 file）",
+        )
+        .route::<AssetListResponse>(
+            "/api/assets",
+            get(list_assets),
+            Method::GET,
+            "获取媒体文件列表",
+        )
+        .route::<bool>(
+            "/api/assets/{id}",
+            delete(delete_asset),
+            Method::DELETE,
+            "删除媒体文件",
+        )
+        .route::<ResponsiveImageResponse>(
+            "/api/assets/{id}/variants",
+            post(sync_variants),
+            Method::POST,
+            "按当前配置重新生成图片变体",
+        )
+        .route::<ResponsiveImageResponse>(
+            "/api/media/{file}",
+            get(image_info),
+            Method::GET,
+            "获取图片的宽高和各尺寸变体（srcset）",
+        )
+        .route::<String>(
+            "/media/{file}",
+            get(serve),
+            Method::GET,
+            "读取媒体文件，如 /media/{sha256}.jpg",
+        )
+        .route::<String>(
+            "/media/{hash}/{variant}",
+            get(serve_variant),
+            Method::GET,
+            "读取图片变体，如 /media/{sha256}/960w.webp",
+        )
+}
+
+async fn upload(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    mut multipart: Multipart,
+) -> AppResult<Json<AssetResponse>> {
+    let bad_form = |e: axum::extract::multipart::MultipartError| {
+        AppError::BadRequest(format!("上传表单格式错误: {e}"))
+    };
+    let max_bytes = state.assets.max_bytes;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(bad_form)? {
+        if field.name() != Some(FILE_FIELD) {
+            continue;
+        }
+        let original_name = field.file_name().map(str::to_string);
+
+        // 边读边检查大小，超限时不再继续接收
+        let mut data = BytesMut::new();
+        while let Some(chunk) = field.chunk().await.map_err(bad_form)? {
+            if data.len() + chunk.len() > max_bytes {
+                return Err(AppError::BadRequest(format!(
+                    "文件超过大小上限 {max_bytes} 字节"
+                )));
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        let (asset, created) = asset_service::upload(
+            &state.writer,
+            state.blobs.as_ref(),
+            data.freeze(),
+            original_name.as_deref(),
+        )
+        .await?;
+        if created {
+            log::info!(
+                "🖼️ 已上传媒体文件 {}（{} 字节）",
+                asset_service::file_name(&asset),
+                asset.size
+            );
+        }
+        // 重复上传时也检查一遍，补上之前失败或配置变化后缺少的变体
+        image_service::spawn_sync(
+            state.writer.clone(),
+            state.blobs.clone(),
+            state.assets.variants.clone(),
+            asset.clone(),
+        );
+        return Ok(Json(AssetResponse::new(&state, asset)));
+    }
+
+    Err(AppError::BadRequest(format!("缺少文件字段 {FILE_FIELD}")))
+}
+
+async fn list_assets(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Query(query): Query<PaginationQuery>,
+) -> AppResult<Json<AssetListResponse>> {
+    let (assets, total) = asset_service::list(&state.db, &query).await?;
+    Ok(Json(AssetListResponse {
+        assets: assets
+            .into_iter()
+            .map(|asset| AssetResponse::new(&state, asset))
+            .collect(),
+        total,
+    }))
+}
+
+async fn delete_asset(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<bool>> {
+    asset_service::delete(&state.writer, state.blobs.as_ref(), id).await?;
+    Ok(Json(true))
+}
+
+async fn sync_variants(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<ResponsiveImageResponse>> {
+    let asset = asset_service::get(&state.db, id).await?;
+    let (asset, variants) = image_service::sync_variants(
+        &state.writer,
+        state.blobs.as_ref(),
+        &state.assets.variants,
+        &asset,
+    )
+    .await?;
+    Ok(Json(ResponsiveImageResponse::new(
+        &state,
+        ResponsiveImage { asset, variants },
+    )))
+}
+
+async fn image_info(
+    Extension(state): Extension<AppState>,
+    Path(file): Path<String>,
+) -> AppResult<Json<ResponsiveImageResponse>> {
+    let image = image_service::find_image(&state.db, &file).await?;
+    Ok(Json(ResponsiveImageResponse::new(&state, image)))
+}
+
+async fn serve(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Path(file): Path<String>,
+) -> AppResult<Response> {
+    let asset = asset_service::find_by_file_name(&state.db, &file).await?;
+    media_response(
+        &state,
+        &headers,
+        &asset.hash,
+        &asset.mime_type,
+        &asset_service::storage_key(&asset),
+    )
+    .await
+}
+
+async fn serve_variant(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Path((hash, file)): Path<(String, String)>,
+) -> AppResult<Response> {
+    let (asset, variant) = image_service::find_variant(&state.db, &hash, &file).await?;
+    let format: VariantFormat = variant.format.parse().map_err(AppError::Internal)?;
+    media_response(
+        &state,
+        &headers,
+        &format!(
+            "{}-{}",
+            asset.hash,
+            image_service::variant_file_name(&variant)
+        ),
+        format.mime_type(),
+        &image_service::variant_storage_key(&asset, &variant),
+    )
+    .await
+}
+
+/// 文件内容由哈希决定、永不改变，所以可以让浏览器和 CDN 永久缓存
+async fn media_response(
+    state: &AppState,
+    headers: &HeaderMap,
+    tag: &str,
+    mime_type: &str,
+    key: &str,
+) -> AppResult<Response> {
+    let etag = format!("\"{tag}\"");
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .any(|tag| tag.trim().trim_start_matches("W/") == etag)
+        });
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        let data = state
+            .blobs
+            .get(key)
+            .await?
+            .ok_or_else(|| AppError::Internal(format!("媒体文件 {key} 在存储中丢失")))?;
+        data.into_response()
+    };
+
+    let response_headers = response.headers_mut();
+    if let Ok(content_type) = HeaderValue::from_str(mime_type) {
+        response_headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    response_headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=31536000, immutable"),
+    );
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, etag);
+    }
+    response_headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    // 图片和 PDF 直接显示，压缩包等其他文件下载
+    if !(mime_type.starts_with("image/") || mime_type == "application/pdf") {
+        response_headers.insert(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_static("attachment"),
+        );
+    }
+    Ok(response)
+}
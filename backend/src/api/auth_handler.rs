@@ -0,0 +1,204 @@
+use axum::{
+    Extension, Json,
+    extract::Path,
+    routing::{delete, get, post},
+};
+use meta_macros::Schema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{AdminAuth, ClientInfo},
+    error::{AppError, AppResult},
+    infra::db::{AppState, entities::sessions},
+    schema::{AnnotatedRouter, Method},
+    service::{
+        auth_service::{self, LoginRequest, TokenPair},
+        session_service, totp_service,
+    },
+};
+
+/// 刷新令牌请求
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// 令牌响应
+#[derive(Debug, Serialize, Schema)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+impl From<TokenPair> for TokenResponse {
+    fn from(pair: TokenPair) -> Self {
+        Self {
+            access_token: pair.access_token,
+            refresh_token: pair.refresh_token,
+            token_type: "Bearer".to_string(),
+            expires_in: pair.expires_in,
+        }
+    }
+}
+
+/// 登录响应：未启用两步验证时直接返回令牌，否则返回 MFA 令牌
+#[derive(Debug, Serialize, Schema)]
+pub struct LoginResponse {
+    pub mfa_required: bool,
+    /// 提交验证码时使用，见 `POST /api/auth/2fa/verify`
+    pub mfa_token: Option<String>,
+    pub tokens: Option<TokenResponse>,
+}
+
+/// 当前管理员信息
+#[derive(Debug, Serialize, Schema)]
+pub struct AdminResponse {
+    pub id: i32,
+    pub username: String,
+    pub last_login_at: Option<String>,
+    pub totp_enabled: bool,
+}
+
+/// 登录会话信息
+#[derive(Debug, Serialize, Schema)]
+pub struct SessionResponse {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub started_at: String,
+    /// 最近一次刷新的时间
+    pub last_used_at: String,
+    pub expires_at: String,
+    /// 是否为发起本次请求的会话
+    pub current: bool,
+}
+
+impl SessionResponse {
+    fn new(session: sessions::Model, current_id: &str) -> Self {
+        Self {
+            current: session.family_id == current_id,
+            id: session.family_id,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            started_at: session.started_at.to_rfc3339(),
+            last_used_at: session.created_at.to_rfc3339(),
+            expires_at: session.expires_at.to_rfc3339(),
+        }
+    }
+}
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<LoginResponse>("/api/auth/login", post(login), Method::POST, "管理员登录")
+        .route::<TokenResponse>("/api/auth/refresh", post(refresh), Method::POST, "刷新令牌")
+        .route::<AdminResponse>("/api/auth/me", get(me), Method::GET, "获取当前管理员信息")
+        .route::<Vec<SessionResponse>>(
+            "/api/auth/sessions",
+            get(list_sessions),
+            Method::GET,
+            "列出有效的登录会话",
+        )
+        .route::<u64>(
+            "/api/auth/sessions",
+            delete(revoke_all_sessions),
+            Method::DELETE,
+            "吊销全部登录会话",
+        )
+        .route::<bool>(
+            "/api/auth/sessions/{id}",
+            delete(revoke_session),
+            Method::DELETE,
+            "吊销指定登录会话",
+        )
+}
+
+async fn login(
+    Extension(state): Extension<AppState>,
+    client: ClientInfo,
+    Json(req): Json<LoginRequest>,
+) -> AppResult<Json<LoginResponse>> {
+    let admin = auth_service::login(&state.db, req).await?;
+
+    if admin.totp_enabled_at.is_some() {
+        let mfa_token =
+            totp_service::begin_challenge(&state.writer, &admin, &state.totp, &state.jwt).await?;
+        return Ok(Json(LoginResponse {
+            mfa_required: true,
+            mfa_token: Some(mfa_token),
+            tokens: None,
+        }));
+    }
+
+    log::info!("🔐 管理员 {} 登录成功", admin.username);
+    let pair =
+        auth_service::start_session(&state.writer, &admin, client.device(), &state.jwt).await?;
+    Ok(Json(LoginResponse {
+        mfa_required: false,
+        mfa_token: None,
+        tokens: Some(pair.into()),
+    }))
+}
+
+async fn refresh(
+    Extension(state): Extension<AppState>,
+    client: ClientInfo,
+    Json(req): Json<RefreshRequest>,
+) -> AppResult<Json<TokenResponse>> {
+    let pair = auth_service::refresh(
+        &state.writer,
+        &req.refresh_token,
+        client.device(),
+        &state.jwt,
+    )
+    .await?;
+    Ok(Json(pair.into()))
+}
+
+async fn me(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+) -> AppResult<Json<AdminResponse>> {
+    let admin = auth_service::get_admin_by_id(&state.db, admin.admin_id).await?;
+    Ok(Json(AdminResponse {
+        id: admin.id,
+        username: admin.username,
+        last_login_at: admin.last_login_at.map(|at| at.to_rfc3339()),
+        totp_enabled: admin.totp_enabled_at.is_some(),
+    }))
+}
+
+async fn list_sessions(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+) -> AppResult<Json<Vec<SessionResponse>>> {
+    let sessions = session_service::list_active(&state.db, admin.admin_id).await?;
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|session| SessionResponse::new(session, &admin.session_id))
+            .collect(),
+    ))
+}
+
+async fn revoke_session(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<Json<bool>> {
+    if !session_service::revoke_family(&state.writer, admin.admin_id, &id).await? {
+        return Err(AppError::NotFound);
+    }
+    log::info!("🔒 管理员 {} 吊销了会话 {id}", admin.username);
+    Ok(Json(true))
+}
+
+async fn revoke_all_sessions(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+) -> AppResult<Json<u64>> {
+    let revoked = session_service::revoke_all(&state.writer, admin.admin_id).await?;
+    log::info!("🔒 管理员 {} 吊销了全部会话", admin.username);
+    Ok(Json(revoked))
+}
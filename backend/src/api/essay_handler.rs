@@ -0,0 +1,260 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query},
+    routing::{delete, get, post, put},
+};
+use meta_macros::Schema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::AdminAuth,
+    error::AppResult,
+    infra::db::{
+        AppState,
+        entities::{essay_revisions, essays},
+    },
+    schema::{AnnotatedRouter, Method},
+    service::{
+        PaginationQuery,
+        essay_service::{self, CreateEssayRequest, EssayDiff, EssayStatus, UpdateEssayRequest},
+    },
+};
+
+/// 随笔
+#[derive(Debug, Serialize, Schema)]
+pub struct EssayResponse {
+    pub id: i32,
+    pub title: String,
+    pub content: String,
+    pub status: String,
+    pub publish_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<essays::Model> for EssayResponse {
+    fn from(essay: essays::Model) -> Self {
+        Self {
+            id: essay.id,
+            title: essay.title,
+            content: essay.content,
+            status: essay.status,
+            publish_at: essay.publish_at.map(|at| at.to_rfc3339()),
+            created_at: essay.created_at.to_rfc3339(),
+            updated_at: essay.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// 随笔列表
+#[derive(Debug, Serialize, Schema)]
+pub struct EssayListResponse {
+    pub essays: Vec<EssayResponse>,
+    pub total: u64,
+}
+
+/// 随笔的历史版本
+#[derive(Debug, Serialize, Schema)]
+pub struct EssayRevisionResponse {
+    pub revision: i32,
+    pub title: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+impl From<essay_revisions::Model> for EssayRevisionResponse {
+    fn from(revision: essay_revisions::Model) -> Self {
+        Self {
+            revision: revision.revision,
+            title: revision.title,
+            content: revision.content,
+            created_at: revision.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// 版本差异
+#[derive(Debug, Serialize, Schema)]
+pub struct EssayDiffResponse {
+    pub from: i32,
+    pub to: Option<i32>,
+    pub unified: String,
+    pub title_changed: bool,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+impl From<EssayDiff> for EssayDiffResponse {
+    fn from(diff: EssayDiff) -> Self {
+        Self {
+            from: diff.from,
+            to: diff.to,
+            unified: diff.unified,
+            title_changed: diff.title_changed,
+            additions: diff.additions as u64,
+            deletions: diff.deletions as u64,
+        }
+    }
+}
+
+/// 站长查看随笔列表时的筛选条件
+#[derive(Debug, Deserialize)]
+pub struct EssayFilter {
+    pub status: Option<EssayStatus>,
+}
+
+/// 版本比较参数，`to` 省略时与当前版本比较
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    pub from: i32,
+    pub to: Option<i32>,
+}
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<EssayListResponse>(
+            "/api/essays",
+            get(list_published),
+            Method::GET,
+            "获取已发布的随笔列表",
+        )
+        .route::<EssayResponse>(
+            "/api/essays/{id}",
+            get(get_published),
+            Method::GET,
+            "获取已发布的随笔",
+        )
+        .route::<EssayResponse>("/api/essays", post(create_essay), Method::POST, "创建随笔")
+        .route::<EssayResponse>(
+            "/api/essays/{id}",
+            put(update_essay),
+            Method::PUT,
+            "更新随笔",
+        )
+        .route::<bool>(
+            "/api/essays/{id}",
+            delete(delete_essay),
+            Method::DELETE,
+            "删除随笔",
+        )
+        .route::<Vec<EssayRevisionResponse>>(
+            "/api/essays/{id}/revisions",
+            get(list_revisions),
+            Method::GET,
+            "获取随笔的历史版本",
+        )
+        .route::<EssayDiffResponse>(
+            "/api/essays/{id}/diff",
+            get(diff),
+            Method::GET,
+            "比较随笔的两个版本",
+        )
+        .route::<EssayListResponse>(
+            "/api/admin/essays",
+            get(list_all),
+            Method::GET,
+            "获取全部随笔（含草稿和定时）",
+        )
+        .route::<EssayResponse>(
+            "/api/admin/essays/{id}",
+            get(get_any),
+            Method::GET,
+            "获取任意状态的随笔",
+        )
+}
+
+fn to_list((essays, total): (Vec<essays::Model>, u64)) -> EssayListResponse {
+    EssayListResponse {
+        essays: essays.into_iter().map(EssayResponse::from).collect(),
+        total,
+    }
+}
+
+async fn list_published(
+    Extension(state): Extension<AppState>,
+    Query(query): Query<PaginationQuery>,
+) -> AppResult<Json<EssayListResponse>> {
+    let page = essay_service::list_published(&state.repo(), &query).await?;
+    Ok(Json(to_list(page)))
+}
+
+async fn get_published(
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<EssayResponse>> {
+    let essay = essay_service::get_published(&state.repo(), id).await?;
+    Ok(Json(essay.into()))
+}
+
+async fn list_all(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Query(query): Query<PaginationQuery>,
+    Query(filter): Query<EssayFilter>,
+) -> AppResult<Json<EssayListResponse>> {
+    let page = essay_service::list(&state.repo(), filter.status, &query).await?;
+    Ok(Json(to_list(page)))
+}
+
+async fn get_any(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<EssayResponse>> {
+    let essay = essay_service::get_essay(&state.repo(), id).await?;
+    Ok(Json(essay.into()))
+}
+
+async fn create_essay(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Json(req): Json<CreateEssayRequest>,
+) -> AppResult<Json<EssayResponse>> {
+    let essay = essay_service::create_essay(&state.writer_repo(), req).await?;
+    log::info!("📝 管理员 {} 创建了随笔 #{}", admin.username, essay.id);
+    Ok(Json(essay.into()))
+}
+
+async fn update_essay(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+    Json(req): Json<UpdateEssayRequest>,
+) -> AppResult<Json<EssayResponse>> {
+    let essay = essay_service::update_essay(&state.writer, id, req).await?;
+    Ok(Json(essay.into()))
+}
+
+async fn delete_essay(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<bool>> {
+    essay_service::delete_essay(&state.writer_repo(), id).await?;
+    log::info!("🗑️ 管理员 {} 删除了随笔 #{id}", admin.username);
+    Ok(Json(true))
+}
+
+async fn list_revisions(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<Vec<EssayRevisionResponse>>> {
+    let revisions = essay_service::list_revisions(&state.repo(), id).await?;
+    Ok(Json(
+        revisions
+            .into_iter()
+            .map(EssayRevisionResponse::from)
+            .collect(),
+    ))
+}
+
+async fn diff(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<DiffQuery>,
+) -> AppResult<Json<EssayDiffResponse>> {
+    let diff = essay_service::diff(&state.repo(), id, query.from, query.to).await?;
+    Ok(Json(diff.into()))
+}
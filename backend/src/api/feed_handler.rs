@@ -0,0 +1,74 @@
+use axum::{Extension, extract::Query, http::HeaderMap, response::Response, routing::get};
+use serde::Deserialize;
+
+use crate::{
+    api::cacheable_response,
+    error::AppResult,
+    infra::db::AppState,
+    schema::{AnnotatedRouter, Method},
+    service::feed_service::{self, FeedFormat, FeedMode, FeedScope},
+};
+
+/// 订阅参数：按标签或分类筛选、全文或摘要、条目数
+#[derive(Debug, Deserialize)]
+pub struct FeedQuery {
+    pub tag: Option<String>,
+    pub category: Option<String>,
+    pub mode: Option<FeedMode>,
+    pub limit: Option<u64>,
+}
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<String>(
+            "/feed.xml",
+            get(|state, headers, query| feed(state, headers, query, FeedFormat::Rss)),
+            Method::GET,
+            "RSS 2.0 订阅源",
+        )
+        .route::<String>(
+            "/atom.xml",
+            get(|state, headers, query| feed(state, headers, query, FeedFormat::Atom)),
+            Method::GET,
+            "Atom 订阅源",
+        )
+        .route::<String>(
+            "/feed.json",
+            get(|state, headers, query| feed(state, headers, query, FeedFormat::Json)),
+            Method::GET,
+            "JSON Feed 订阅源",
+        )
+}
+
+async fn feed(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<FeedQuery>,
+    format: FeedFormat,
+) -> AppResult<Response> {
+    let scope = FeedScope::from_query(query.tag, query.category);
+    let mode = query.mode.unwrap_or(if state.site.feed_full_content {
+        FeedMode::Full
+    } else {
+        FeedMode::Summary
+    });
+    let limit = query.limit.unwrap_or(state.site.feed_limit);
+
+    let feed = feed_service::build_feed(
+        &state.db,
+        &state.content,
+        &state.site,
+        &scope,
+        format,
+        mode,
+        limit,
+    )
+    .await?;
+
+    Ok(cacheable_response(
+        &headers,
+        format.content_type(),
+        feed_service::render(&feed, format),
+        feed.updated(),
+    ))
+}
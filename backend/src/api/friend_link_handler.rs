@@ -0,0 +1,243 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query},
+    routing::{delete, get, post, put},
+};
+use meta_macros::Schema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{AdminAuth, ClientInfo},
+    error::AppResult,
+    infra::db::{
+        AppState,
+        entities::{friend_link_applications, friends_links},
+    },
+    schema::{AnnotatedRouter, Method},
+    service::friend_link_service::{
+        self, ApplicationRequest, ApplicationStatus, FriendLinkRequest,
+    },
+};
+
+/// 友链
+#[derive(Debug, Serialize, Schema)]
+pub struct FriendLinkResponse {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub logo_url: Option<String>,
+    pub sort_order: i32,
+    /// 最近一次检查的 HTTP 状态码
+    pub last_status: Option<i32>,
+    pub last_checked_at: Option<String>,
+    /// 最近一次能正常访问的时间
+    pub last_seen_at: Option<String>,
+}
+
+impl From<friends_links::Model> for FriendLinkResponse {
+    fn from(link: friends_links::Model) -> Self {
+        Self {
+            id: link.id,
+            name: link.name,
+            url: link.url,
+            description: link.description,
+            logo_url: link.logo_url,
+            sort_order: link.sort_order,
+            last_status: link.last_status,
+            last_checked_at: link.last_checked_at.map(|at| at.to_rfc3339()),
+            last_seen_at: link.last_seen_at.map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
+/// 友链申请
+#[derive(Debug, Serialize, Schema)]
+pub struct ApplicationResponse {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub logo_url: Option<String>,
+    pub contact: Option<String>,
+    pub message: Option<String>,
+    pub status: String,
+    pub created_at: String,
+    pub reviewed_at: Option<String>,
+}
+
+impl From<friend_link_applications::Model> for ApplicationResponse {
+    fn from(application: friend_link_applications::Model) -> Self {
+        Self {
+            id: application.id,
+            name: application.name,
+            url: application.url,
+            description: application.description,
+            logo_url: application.logo_url,
+            contact: application.contact,
+            message: application.message,
+            status: application.status,
+            created_at: application.created_at.to_rfc3339(),
+            reviewed_at: application.reviewed_at.map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
+/// 拖拽排序后的友链ID列表
+#[derive(Debug, Deserialize)]
+pub struct ReorderRequest {
+    pub ids: Vec<i32>,
+}
+
+/// 申请列表筛选条件
+#[derive(Debug, Deserialize)]
+pub struct ApplicationFilter {
+    pub status: Option<ApplicationStatus>,
+}
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<Vec<FriendLinkResponse>>(
+            "/api/friends-links",
+            get(list_links),
+            Method::GET,
+            "获取友链列表",
+        )
+        .route::<FriendLinkResponse>(
+            "/api/friends-links",
+            post(create_link),
+            Method::POST,
+            "添加友链",
+        )
+        .route::<FriendLinkResponse>(
+            "/api/friends-links/{id}",
+            put(update_link),
+            Method::PUT,
+            "修改友链",
+        )
+        .route::<bool>(
+            "/api/friends-links/{id}",
+            delete(delete_link),
+            Method::DELETE,
+            "删除友链",
+        )
+        .route::<bool>(
+            "/api/friends-links/order",
+            put(reorder),
+            Method::PUT,
+            "调整友链顺序",
+        )
+        .route::<ApplicationResponse>(
+            "/api/friends-links/applications",
+            post(submit_application),
+            Method::POST,
+            "提交友链申请",
+        )
+        .route::<Vec<ApplicationResponse>>(
+            "/api/friends-links/applications",
+            get(list_applications),
+            Method::GET,
+            "获取友链申请列表",
+        )
+        .route::<Option<FriendLinkResponse>>(
+            "/api/friends-links/applications/{id}/approve",
+            post(approve_application),
+            Method::POST,
+            "通过友链申请",
+        )
+        .route::<Option<FriendLinkResponse>>(
+            "/api/friends-links/applications/{id}/reject",
+            post(reject_application),
+            Method::POST,
+            "拒绝友链申请",
+        )
+}
+
+async fn list_links(
+    Extension(state): Extension<AppState>,
+) -> AppResult<Json<Vec<FriendLinkResponse>>> {
+    let links = friend_link_service::list_links(&state.repo()).await?;
+    Ok(Json(
+        links.into_iter().map(FriendLinkResponse::from).collect(),
+    ))
+}
+
+async fn create_link(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Json(req): Json<FriendLinkRequest>,
+) -> AppResult<Json<FriendLinkResponse>> {
+    let link = friend_link_service::create_link(&state.writer_repo(), req).await?;
+    Ok(Json(link.into()))
+}
+
+async fn update_link(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+    Json(req): Json<FriendLinkRequest>,
+) -> AppResult<Json<FriendLinkResponse>> {
+    let link = friend_link_service::update_link(&state.writer_repo(), id, req).await?;
+    Ok(Json(link.into()))
+}
+
+async fn delete_link(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<bool>> {
+    friend_link_service::delete_link(&state.writer_repo(), id).await?;
+    Ok(Json(true))
+}
+
+async fn reorder(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Json(req): Json<ReorderRequest>,
+) -> AppResult<Json<bool>> {
+    friend_link_service::reorder(&state.writer, &req.ids).await?;
+    Ok(Json(true))
+}
+
+async fn submit_application(
+    Extension(state): Extension<AppState>,
+    client: ClientInfo,
+    Json(req): Json<ApplicationRequest>,
+) -> AppResult<Json<ApplicationResponse>> {
+    let application =
+        friend_link_service::submit_application(&state.writer_repo(), req, &client.ip).await?;
+    log::info!("🤝 收到新的友链申请: {}", application.url);
+    Ok(Json(application.into()))
+}
+
+async fn list_applications(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Query(filter): Query<ApplicationFilter>,
+) -> AppResult<Json<Vec<ApplicationResponse>>> {
+    let applications = friend_link_service::list_applications(&state.repo(), filter.status).await?;
+    Ok(Json(
+        applications
+            .into_iter()
+            .map(ApplicationResponse::from)
+            .collect(),
+    ))
+}
+
+async fn approve_application(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<Option<FriendLinkResponse>>> {
+    let link = friend_link_service::review_application(&state.writer, id, true).await?;
+    Ok(Json(link.map(FriendLinkResponse::from)))
+}
+
+async fn reject_application(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<Option<FriendLinkResponse>>> {
+    let link = friend_link_service::review_application(&state.writer, id, false).await?;
+    Ok(Json(link.map(FriendLinkResponse::from)))
+}
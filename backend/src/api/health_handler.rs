@@ -0,0 +1,118 @@
+//! 健康检查与构建信息
+//!
+//! 供 docker-compose、Kubernetes 或负载均衡器探测使用，不需要认证：
+//! - `/healthz`：进程存活，始终返回 200
+//! - `/readyz`：依赖就绪时返回 200，否则返回 503，响应体中列出每一项检查的结果
+//! - `/version`：版本号、git 提交、构建时间和启用的 feature
+
+use axum::{Extension, Json, http::StatusCode, routing::get};
+use meta_macros::Schema;
+use serde::Serialize;
+
+use crate::{
+    infra::db::AppState,
+    schema::{AnnotatedRouter, Method},
+    service::health_service::{self, BuildInfo, Check},
+};
+
+/// 存活检查
+#[derive(Debug, Serialize, Schema)]
+pub struct HealthResponse {
+    pub status: String,
+    /// 服务已运行的秒数
+    pub uptime_secs: u64,
+}
+
+/// 单项就绪检查
+#[derive(Debug, Serialize, Schema)]
+pub struct CheckResponse {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+    pub elapsed_ms: u64,
+}
+
+impl From<Check> for CheckResponse {
+    fn from(check: Check) -> Self {
+        Self {
+            name: check.name.to_string(),
+            ok: check.ok,
+            detail: check.detail,
+            elapsed_ms: check.elapsed.as_millis() as u64,
+        }
+    }
+}
+
+/// 就绪检查
+#[derive(Debug, Serialize, Schema)]
+pub struct ReadinessResponse {
+    /// ready / not_ready
+    pub status: String,
+    pub checks: Vec<CheckResponse>,
+}
+
+/// 构建信息
+#[derive(Debug, Serialize, Schema)]
+pub struct VersionResponse {
+    pub version: String,
+    pub git_sha: String,
+    /// RFC 3339，未知时为空
+    pub build_time: Option<String>,
+    pub features: Vec<String>,
+    pub profile: String,
+}
+
+/// 注册健康检查路由
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<HealthResponse>("/healthz", get(healthz), Method::GET, "存活检查")
+        .route::<ReadinessResponse>(
+            "/readyz",
+            get(readyz),
+            Method::GET,
+            "就绪检查（数据库、迁移、笔记目录），未就绪时返回 503",
+        )
+        .route::<VersionResponse>("/version", get(version), Method::GET, "构建信息")
+}
+
+async fn healthz(Extension(state): Extension<AppState>) -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+        uptime_secs: state.started_at.elapsed().as_secs(),
+    })
+}
+
+async fn readyz(Extension(state): Extension<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let readiness = health_service::readiness(&state.db, &state.writer, &state.content).await;
+    let (code, status) = if readiness.is_ready() {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+    (
+        code,
+        Json(ReadinessResponse {
+            status: status.to_string(),
+            checks: readiness
+                .checks
+                .into_iter()
+                .map(CheckResponse::from)
+                .collect(),
+        }),
+    )
+}
+
+async fn version() -> Json<VersionResponse> {
+    let info = BuildInfo::current();
+    Json(VersionResponse {
+        version: info.version.to_string(),
+        git_sha: info.git_sha.to_string(),
+        build_time: info.build_time_rfc3339(),
+        features: info
+            .features
+            .iter()
+            .map(|feature| feature.to_string())
+            .collect(),
+        profile: info.profile.to_string(),
+    })
+}
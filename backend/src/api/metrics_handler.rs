@@ -0,0 +1,112 @@
+//! Prometheus 指标
+//!
+//! `track_requests` 中间件记录每个请求的路由模板、状态码和耗时，
+//! `/metrics` 按 Prometheus 文本格式输出全部指标。配置了 `METRICS_TOKEN` 时抓取需要带上该令牌。
+
+use std::time::Instant;
+
+use axum::{
+    Extension,
+    extract::{MatchedPath, Request},
+    http::{HeaderMap, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    api::bearer_token,
+    error::{AppError, AppResult},
+    infra::{db::AppState, metrics},
+    schema::{AnnotatedRouter, Method},
+};
+
+/// 注册指标路由
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router.route::<String>(
+        "/metrics",
+        get(export),
+        Method::GET,
+        "Prometheus 指标（配置了 METRICS_TOKEN 时需要 Bearer 令牌）",
+    )
+}
+
+async fn export(Extension(state): Extension<AppState>, headers: HeaderMap) -> AppResult<Response> {
+    if let Some(expected) = &state.metrics.token {
+        // 比较摘要而不是原文，避免按字节提前返回泄露令牌前缀
+        let matches = bearer_token(&headers).is_some_and(|token| {
+            Sha256::digest(token.as_bytes()) == Sha256::digest(expected.as_bytes())
+        });
+        if !matches {
+            return Err(AppError::Unauthorized);
+        }
+    }
+    Ok((
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        metrics::render(&state.db, &state.writer),
+    )
+        .into_response())
+}
+
+/// 记录请求数和耗时的中间件，需要用 `Router::layer` 挂在路由上才能拿到路由模板
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+
+    let response = next.run(request).await;
+    metrics::observe_request(
+        method.as_str(),
+        route.as_deref().unwrap_or(metrics::UNMATCHED_ROUTE),
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::test_db;
+
+    #[tokio::test]
+    async fn test_track_requests_by_route_template() {
+        let app = AnnotatedRouter::new()
+            .route::<String>(
+                "/metrics-test/{id}",
+                get(|| async { "ok" }),
+                Method::GET,
+                "测试",
+            )
+            .build()
+            .layer(axum::middleware::from_fn(track_requests));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        for path in ["/metrics-test/1", "/metrics-test/2", "/metrics-missing/3"] {
+            reqwest::get(format!("http://{addr}{path}")).await.unwrap();
+        }
+        metrics::time_job("metrics_test", async { Ok::<_, AppError>(()) })
+            .await
+            .unwrap();
+
+        let db = test_db().await;
+        let text = metrics::render(&db, &db);
+        assert!(text.contains(
+            r#"rowan_http_requests_total{method="GET",route="/metrics-test/{id}",status="200"} 2"#
+        ));
+        assert!(text.contains(r#"route="unmatched",status="404""#));
+        assert!(!text.contains("/metrics-test/1"));
+        assert!(text.contains(r#"rowan_db_pool_max_connections{pool="reader"}"#));
+        // 读写共用同一个连接池时不重复输出
+        assert!(!text.contains(r#"pool="writer""#));
+        assert!(text.contains(
+            r#"rowan_job_duration_seconds_count{job="metrics_test",outcome="success"} 1"#
+        ));
+    }
+}
@@ -0,0 +1,176 @@
+use axum::{
+    Extension, Json,
+    routing::{get, post},
+};
+use meta_macros::Schema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{AdminAuth, ClientInfo, auth_handler::TokenResponse},
+    error::{AppError, AppResult},
+    infra::db::AppState,
+    schema::{AnnotatedRouter, Method},
+    service::{
+        auth_service::{self, TokenType},
+        totp_service::{self, Enrollment},
+    },
+};
+
+/// 登录第二步：提交验证码或恢复码
+#[derive(Debug, Deserialize)]
+pub struct VerifyRequest {
+    pub mfa_token: String,
+    pub code: String,
+}
+
+/// 需要验证码确认的操作
+#[derive(Debug, Deserialize)]
+pub struct CodeRequest {
+    pub code: String,
+}
+
+/// 绑定验证器所需的信息
+#[derive(Debug, Serialize, Schema)]
+pub struct TotpSetupResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub qr_svg: String,
+}
+
+impl From<Enrollment> for TotpSetupResponse {
+    fn from(enrollment: Enrollment) -> Self {
+        Self {
+            secret: enrollment.secret,
+            otpauth_uri: enrollment.otpauth_uri,
+            qr_svg: enrollment.qr_svg,
+        }
+    }
+}
+
+/// 恢复码，只在生成时返回一次
+#[derive(Debug, Serialize, Schema)]
+pub struct RecoveryCodesResponse {
+    pub codes: Vec<String>,
+}
+
+/// 两步验证状态
+#[derive(Debug, Serialize, Schema)]
+pub struct TotpStatusResponse {
+    pub enabled: bool,
+    pub recovery_codes_remaining: u64,
+}
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<TokenResponse>(
+            "/api/auth/2fa/verify",
+            post(verify),
+            Method::POST,
+            "提交两步验证码完成登录",
+        )
+        .route::<TotpStatusResponse>(
+            "/api/auth/2fa",
+            get(status),
+            Method::GET,
+            "获取两步验证状态",
+        )
+        .route::<TotpSetupResponse>(
+            "/api/auth/2fa/setup",
+            post(setup),
+            Method::POST,
+            "生成两步验证密钥和二维码",
+        )
+        .route::<RecoveryCodesResponse>(
+            "/api/auth/2fa/enable",
+            post(enable),
+            Method::POST,
+            "确认验证码并启用两步验证",
+        )
+        .route::<bool>(
+            "/api/auth/2fa/disable",
+            post(disable),
+            Method::POST,
+            "关闭两步验证",
+        )
+        .route::<RecoveryCodesResponse>(
+            "/api/auth/2fa/recovery-codes",
+            post(regenerate_recovery_codes),
+            Method::POST,
+            "重新生成恢复码",
+        )
+}
+
+async fn verify(
+    Extension(state): Extension<AppState>,
+    client: ClientInfo,
+    Json(req): Json<VerifyRequest>,
+) -> AppResult<Json<TokenResponse>> {
+    let claims = auth_service::verify_token(&req.mfa_token, TokenType::Mfa, &state.jwt)?;
+    let admin_id = claims.admin_id()?;
+    totp_service::consume_challenge(&state.writer, admin_id, &claims.jti).await?;
+    let admin = auth_service::get_admin_by_id(&state.writer, admin_id)
+        .await
+        .map_err(|_| AppError::Unauthorized)?;
+    totp_service::verify_second_factor(&state.writer, &admin, &req.code, &state.totp).await?;
+
+    log::info!("🔐 管理员 {} 通过两步验证登录成功", admin.username);
+    let pair =
+        auth_service::start_session(&state.writer, &admin, client.device(), &state.jwt).await?;
+    Ok(Json(pair.into()))
+}
+
+async fn status(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+) -> AppResult<Json<TotpStatusResponse>> {
+    let model = auth_service::get_admin_by_id(&state.db, admin.admin_id).await?;
+    let remaining = totp_service::remaining_recovery_codes(&state.db, admin.admin_id).await?;
+    Ok(Json(TotpStatusResponse {
+        enabled: model.totp_enabled_at.is_some(),
+        recovery_codes_remaining: remaining,
+    }))
+}
+
+async fn setup(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+) -> AppResult<Json<TotpSetupResponse>> {
+    let enrollment =
+        totp_service::begin_enrollment(&state.writer, admin.admin_id, &state.totp).await?;
+    Ok(Json(enrollment.into()))
+}
+
+async fn enable(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Json(req): Json<CodeRequest>,
+) -> AppResult<Json<RecoveryCodesResponse>> {
+    let codes =
+        totp_service::confirm_enrollment(&state.writer, admin.admin_id, &req.code, &state.totp)
+            .await?;
+    Ok(Json(RecoveryCodesResponse { codes }))
+}
+
+async fn disable(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Json(req): Json<CodeRequest>,
+) -> AppResult<Json<bool>> {
+    totp_service::disable(&state.writer, admin.admin_id, &req.code, &state.totp).await?;
+    Ok(Json(true))
+}
+
+async fn regenerate_recovery_codes(
+    admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Json(req): Json<CodeRequest>,
+) -> AppResult<Json<RecoveryCodesResponse>> {
+    let codes = totp_service::regenerate_recovery_codes(
+        &state.writer,
+        admin.admin_id,
+        &req.code,
+        &state.totp,
+    )
+    .await?;
+    Ok(Json(RecoveryCodesResponse { codes }))
+}
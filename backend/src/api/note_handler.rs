@@ -0,0 +1,183 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+};
+use meta_macros::Schema;
+use serde::Serialize;
+
+use crate::{
+    api::{AdminAuth, ClientInfo, moved_permanently},
+    error::{AppError, AppResult},
+    infra::db::{AppState, entities::notes_metadata},
+    schema::{AnnotatedRouter, Method},
+    service::{
+        PaginationQuery,
+        note_service::{self, CreateNoteRequest, UpdateNoteRequest},
+        slug_service, view_service,
+    },
+};
+
+/// 笔记元数据
+#[derive(Debug, Serialize, Schema)]
+pub struct NoteResponse {
+    pub id: i32,
+    pub slug: String,
+    pub title: String,
+    pub summary: Option<String>,
+    pub published_at: String,
+    pub updated_at: String,
+    pub views: i32,
+    pub likes_count: i32,
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+}
+
+impl From<notes_metadata::Model> for NoteResponse {
+    fn from(note: notes_metadata::Model) -> Self {
+        Self {
+            tags: note_service::parse_tags(note.tags.as_deref()),
+            id: note.id,
+            slug: note.slug,
+            title: note.title,
+            summary: note.summary,
+            published_at: note.published_at.to_rfc3339(),
+            updated_at: note.updated_at.to_rfc3339(),
+            views: note.views,
+            likes_count: note.likes_count,
+            category: note.category,
+        }
+    }
+}
+
+/// 笔记列表
+#[derive(Debug, Serialize, Schema)]
+pub struct NoteListResponse {
+    pub notes: Vec<NoteResponse>,
+    pub total: u64,
+}
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<NoteListResponse>("/api/notes", get(list_notes), Method::GET, "获取笔记列表")
+        .route::<NoteResponse>(
+            "/api/notes/{slug}",
+            get(get_note),
+            Method::GET,
+            "获取单个笔记（计入浏览量），旧 slug 返回 301",
+        )
+        .route::<NoteResponse>("/api/notes", post(create_note), Method::POST, "上传笔记")
+        .route::<NoteResponse>(
+            "/api/notes/{slug}",
+            put(update_note),
+            Method::PUT,
+            "修改笔记（可修改 slug）",
+        )
+        .route::<bool>(
+            "/api/notes/{slug}",
+            delete(delete_note),
+            Method::DELETE,
+            "删除笔记（连同评论、点赞和统计）",
+        )
+        .route::<bool>(
+            "/api/notes/{slug}/like",
+            post(like_note),
+            Method::POST,
+            "点赞笔记",
+        )
+        .route::<bool>(
+            "/api/notes/{slug}/like",
+            delete(unlike_note),
+            Method::DELETE,
+            "取消点赞",
+        )
+}
+
+async fn list_notes(
+    Extension(state): Extension<AppState>,
+    Query(query): Query<PaginationQuery>,
+) -> AppResult<Json<NoteListResponse>> {
+    let (notes, total) = note_service::list_notes(&state.repo(), &query).await?;
+    Ok(Json(NoteListResponse {
+        notes: notes.into_iter().map(NoteResponse::from).collect(),
+        total,
+    }))
+}
+
+async fn get_note(
+    Extension(state): Extension<AppState>,
+    Path(slug): Path<String>,
+    client: ClientInfo,
+) -> AppResult<Response> {
+    let note = match note_service::get_published_note_by_slug(&state.repo(), &slug).await {
+        Ok(note) => note,
+        Err(AppError::NotFound) => {
+            // 笔记改过 slug 时跳转到新地址
+            return match slug_service::resolve_redirect(&state.repo(), &slug).await? {
+                Some(current) => Ok(moved_permanently(&format!("/api/notes/{current}"))),
+                None => Err(AppError::NotFound),
+            };
+        }
+        Err(err) => return Err(err),
+    };
+    let referrer = view_service::referrer_host(client.referrer.as_deref(), client.host.as_deref());
+    let visitor = client.visitor_key(&state.repo()).await?;
+    state
+        .views
+        .record(note.id, &visitor, client.user_agent.as_deref(), &referrer);
+    Ok(Json(NoteResponse::from(note)).into_response())
+}
+
+async fn create_note(
+    Extension(state): Extension<AppState>,
+    admin: AdminAuth,
+    Json(req): Json<CreateNoteRequest>,
+) -> AppResult<Json<NoteResponse>> {
+    let note = note_service::create_note(&state.writer, &state.content, req).await?;
+    log::info!("📒 管理员 {} 上传了笔记 {}", admin.username, note.slug);
+    Ok(Json(note.into()))
+}
+
+async fn update_note(
+    Extension(state): Extension<AppState>,
+    admin: AdminAuth,
+    Path(slug): Path<String>,
+    Json(req): Json<UpdateNoteRequest>,
+) -> AppResult<Json<NoteResponse>> {
+    let note = note_service::get_note_by_slug(&state.repo(), &slug).await?;
+    let note = note_service::update_note(&state.writer, &state.content, note.id, req).await?;
+    log::info!("📒 管理员 {} 修改了笔记 {}", admin.username, note.slug);
+    Ok(Json(note.into()))
+}
+
+async fn delete_note(
+    Extension(state): Extension<AppState>,
+    admin: AdminAuth,
+    Path(slug): Path<String>,
+) -> AppResult<Json<bool>> {
+    let note = note_service::get_note_by_slug(&state.repo(), &slug).await?;
+    note_service::delete_note(&state.writer, &state.content, note.id).await?;
+    log::info!("📒 管理员 {} 删除了笔记 {}", admin.username, note.slug);
+    Ok(Json(true))
+}
+
+async fn like_note(
+    Extension(state): Extension<AppState>,
+    Path(slug): Path<String>,
+    client: ClientInfo,
+) -> AppResult<Json<bool>> {
+    let note = note_service::get_published_note_by_slug(&state.repo(), &slug).await?;
+    note_service::like_note(&state.writer, note.id, &client.ip).await?;
+    Ok(Json(true))
+}
+
+async fn unlike_note(
+    Extension(state): Extension<AppState>,
+    Path(slug): Path<String>,
+    client: ClientInfo,
+) -> AppResult<Json<bool>> {
+    let note = note_service::get_published_note_by_slug(&state.repo(), &slug).await?;
+    note_service::unlike_note(&state.writer, note.id, &client.ip).await?;
+    Ok(Json(true))
+}
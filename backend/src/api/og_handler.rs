@@ -0,0 +1,48 @@
+use axum::{Extension, extract::Path, http::HeaderMap, response::Response, routing::get};
+
+use crate::{
+    api::{cacheable_response, moved_permanently},
+    error::{AppError, AppResult},
+    infra::db::AppState,
+    schema::{AnnotatedRouter, Method},
+    service::{note_service, og_service::OgCard, slug_service},
+};
+
+const PNG_CONTENT_TYPE: &str = "image/png";
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router.route::<String>(
+        "/og/{file}",
+        get(note_card),
+        Method::GET,
+        "笔记分享卡片（PNG），如 /og/hello-world.png",
+    )
+}
+
+async fn note_card(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Path(file): Path<String>,
+) -> AppResult<Response> {
+    let slug = file.strip_suffix(".png").ok_or(AppError::NotFound)?;
+    let note = match note_service::get_published_note_by_slug(&state.repo(), slug).await {
+        Ok(note) => note,
+        Err(AppError::NotFound) => {
+            return match slug_service::resolve_redirect(&state.repo(), slug).await? {
+                Some(current) => Ok(moved_permanently(&format!("/og/{current}.png"))),
+                None => Err(AppError::NotFound),
+            };
+        }
+        Err(err) => return Err(err),
+    };
+
+    let markdown = state.content.read_note(note.file_id).await?;
+    let card = OgCard::for_note(&state.site, &note, markdown.as_deref());
+    let png = state.og.render(&card).await?;
+    Ok(cacheable_response(
+        &headers,
+        PNG_CONTENT_TYPE,
+        png,
+        Some(note.updated_at),
+    ))
+}
@@ -0,0 +1,157 @@
+use axum::{Extension, extract::Path, http::HeaderMap, response::Response, routing::get};
+
+use crate::{
+    api::{ClientInfo, cacheable_response, moved_permanently},
+    error::{AppError, AppResult},
+    infra::db::AppState,
+    schema::{AnnotatedRouter, Method},
+    service::{
+        PaginationQuery, essay_service, image_service::MediaImages, note_service, page_service,
+        slug_service, view_service,
+    },
+};
+
+const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<String>("/", get(home), Method::GET, "首页（HTML）")
+        .route::<String>(
+            "/notes/{slug}",
+            get(note),
+            Method::GET,
+            "笔记页面（HTML），旧 slug 返回 301",
+        )
+        .route::<String>("/essays/{id}", get(essay), Method::GET, "随笔页面（HTML）")
+        .route::<String>("/tags/{tag}", get(tag), Method::GET, "标签页面（HTML）")
+        .route::<String>(
+            "/categories/{category}",
+            get(category),
+            Method::GET,
+            "分类页面（HTML）",
+        )
+}
+
+async fn home(Extension(state): Extension<AppState>, headers: HeaderMap) -> AppResult<Response> {
+    let (notes, _) = note_service::list_notes(
+        &state.repo(),
+        &PaginationQuery {
+            page: 1,
+            per_page: page_service::HOME_NOTES,
+        },
+    )
+    .await?;
+    let (essays, _) = essay_service::list_published(
+        &state.repo(),
+        &PaginationQuery {
+            page: 1,
+            per_page: page_service::HOME_ESSAYS,
+        },
+    )
+    .await?;
+
+    let last_modified = notes
+        .iter()
+        .map(|note| note.updated_at)
+        .chain(essays.iter().map(|essay| essay.updated_at))
+        .max();
+    let html = page_service::home_page(&state.site, &notes, &essays)?;
+    Ok(cacheable_response(
+        &headers,
+        HTML_CONTENT_TYPE,
+        html,
+        last_modified,
+    ))
+}
+
+async fn note(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Path(slug): Path<String>,
+    client: ClientInfo,
+) -> AppResult<Response> {
+    let note = match note_service::get_published_note_by_slug(&state.repo(), &slug).await {
+        Ok(note) => note,
+        Err(AppError::NotFound) => {
+            return match slug_service::resolve_redirect(&state.repo(), &slug).await? {
+                Some(current) => Ok(moved_permanently(&format!("/notes/{current}"))),
+                None => Err(AppError::NotFound),
+            };
+        }
+        Err(err) => return Err(err),
+    };
+
+    // 不执行 JS 的访客也计入浏览量，爬虫由 ViewTracker 自行过滤
+    let referrer = view_service::referrer_host(client.referrer.as_deref(), client.host.as_deref());
+    let visitor = client.visitor_key(&state.repo()).await?;
+    state
+        .views
+        .record(note.id, &visitor, client.user_agent.as_deref(), &referrer);
+
+    let markdown = state.content.read_note(note.file_id).await?;
+    let images = match &markdown {
+        Some(markdown) => MediaImages::load(&state.db, &state.site, markdown).await?,
+        None => MediaImages::default(),
+    };
+    let html = page_service::note_page(&state.site, &note, markdown.as_deref(), &images)?;
+    Ok(cacheable_response(
+        &headers,
+        HTML_CONTENT_TYPE,
+        html,
+        Some(note.updated_at),
+    ))
+}
+
+async fn essay(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i32>,
+) -> AppResult<Response> {
+    let essay = essay_service::get_published(&state.repo(), id).await?;
+    let images = MediaImages::load(&state.db, &state.site, &essay.content).await?;
+    let html = page_service::essay_page(&state.site, &essay, &images)?;
+    Ok(cacheable_response(
+        &headers,
+        HTML_CONTENT_TYPE,
+        html,
+        Some(essay.updated_at),
+    ))
+}
+
+async fn tag(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Path(tag): Path<String>,
+) -> AppResult<Response> {
+    let notes = note_service::list_published_by_tag(&state.repo(), &tag).await?;
+    if notes.is_empty() {
+        return Err(AppError::NotFound);
+    }
+    let last_modified = notes.iter().map(|note| note.updated_at).max();
+    let html = page_service::tag_page(&state.site, &tag, &notes)?;
+    Ok(cacheable_response(
+        &headers,
+        HTML_CONTENT_TYPE,
+        html,
+        last_modified,
+    ))
+}
+
+async fn category(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Path(category): Path<String>,
+) -> AppResult<Response> {
+    let notes = note_service::list_published_by_category(&state.repo(), &category).await?;
+    if notes.is_empty() {
+        return Err(AppError::NotFound);
+    }
+    let last_modified = notes.iter().map(|note| note.updated_at).max();
+    let html = page_service::category_page(&state.site, &category, &notes)?;
+    Ok(cacheable_response(
+        &headers,
+        HTML_CONTENT_TYPE,
+        html,
+        last_modified,
+    ))
+}
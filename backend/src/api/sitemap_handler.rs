@@ -0,0 +1,71 @@
+use axum::{
+    Extension,
+    extract::Path,
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use crate::{
+    api::cacheable_response,
+    error::{AppError, AppResult},
+    infra::db::AppState,
+    schema::{AnnotatedRouter, Method},
+};
+
+const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<String>(
+            "/sitemap.xml",
+            get(sitemap),
+            Method::GET,
+            "站点地图（地址过多时为站点地图索引）",
+        )
+        .route::<String>(
+            "/sitemaps/{file}",
+            get(sitemap_part),
+            Method::GET,
+            "站点地图分片，如 /sitemaps/1.xml",
+        )
+        .route::<String>("/robots.txt", get(robots), Method::GET, "robots.txt")
+}
+
+async fn sitemap(Extension(state): Extension<AppState>, headers: HeaderMap) -> AppResult<Response> {
+    let sitemap = state.sitemap.get(&state.db, &state.site).await?;
+    Ok(cacheable_response(
+        &headers,
+        XML_CONTENT_TYPE,
+        sitemap.root.clone(),
+        sitemap.last_modified,
+    ))
+}
+
+async fn sitemap_part(
+    Extension(state): Extension<AppState>,
+    headers: HeaderMap,
+    Path(file): Path<String>,
+) -> AppResult<Response> {
+    let n: usize = file
+        .strip_suffix(".xml")
+        .and_then(|n| n.parse().ok())
+        .ok_or(AppError::NotFound)?;
+
+    let sitemap = state.sitemap.get(&state.db, &state.site).await?;
+    let part = sitemap.part(n).ok_or(AppError::NotFound)?;
+    Ok(cacheable_response(
+        &headers,
+        XML_CONTENT_TYPE,
+        part.to_string(),
+        sitemap.last_modified,
+    ))
+}
+
+async fn robots(Extension(state): Extension<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        state.sitemap.robots().to_string(),
+    )
+        .into_response()
+}
@@ -0,0 +1,194 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query},
+    routing::get,
+};
+use meta_macros::Schema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::AdminAuth,
+    error::AppResult,
+    infra::db::AppState,
+    schema::{AnnotatedRouter, Method},
+    service::{
+        note_service,
+        stats_service::{self, DailyPoint, TopMetric, Window},
+    },
+};
+
+/// 统计查询参数
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// 统计最近多少天，默认 30 天
+    #[serde(default = "default_days")]
+    pub days: u32,
+    /// 排行榜/来源列表的条数，默认 10 条
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// 排行榜指标：views / likes / comments
+    #[serde(default)]
+    pub metric: TopMetric,
+    /// 只统计某篇笔记（来源统计使用）
+    pub slug: Option<String>,
+}
+
+fn default_days() -> u32 {
+    30
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+/// 每日统计
+#[derive(Debug, Serialize, Schema)]
+pub struct DailyPointResponse {
+    pub day: String,
+    pub views: i64,
+    pub unique_visitors: i64,
+    pub likes: i64,
+    pub comments: i64,
+}
+
+impl From<DailyPoint> for DailyPointResponse {
+    fn from(point: DailyPoint) -> Self {
+        Self {
+            day: point.day.to_string(),
+            views: point.views,
+            unique_visitors: point.unique_visitors,
+            likes: point.likes,
+            comments: point.comments,
+        }
+    }
+}
+
+/// 时间序列
+#[derive(Debug, Serialize, Schema)]
+pub struct StatsSeriesResponse {
+    pub from: String,
+    pub to: String,
+    pub points: Vec<DailyPointResponse>,
+}
+
+impl StatsSeriesResponse {
+    fn new(window: Window, points: Vec<DailyPoint>) -> Self {
+        Self {
+            from: window.from.to_string(),
+            to: window.to.to_string(),
+            points: points.into_iter().map(DailyPointResponse::from).collect(),
+        }
+    }
+}
+
+/// 排行榜条目
+#[derive(Debug, Serialize, Schema)]
+pub struct TopNoteResponse {
+    pub slug: String,
+    pub title: String,
+    pub views: i64,
+    pub likes: i64,
+    pub comments: i64,
+}
+
+/// 来源站点统计
+#[derive(Debug, Serialize, Schema)]
+pub struct ReferrerResponse {
+    pub referrer: String,
+    pub views: i64,
+}
+
+pub fn routes(router: AnnotatedRouter) -> AnnotatedRouter {
+    router
+        .route::<StatsSeriesResponse>(
+            "/api/stats/site",
+            get(site_stats),
+            Method::GET,
+            "全站每日统计（管理员）",
+        )
+        .route::<StatsSeriesResponse>(
+            "/api/stats/notes/{slug}",
+            get(note_stats),
+            Method::GET,
+            "单篇笔记每日统计（管理员）",
+        )
+        .route::<Vec<TopNoteResponse>>(
+            "/api/stats/top",
+            get(top_notes),
+            Method::GET,
+            "热门笔记排行（管理员）",
+        )
+        .route::<Vec<ReferrerResponse>>(
+            "/api/stats/referrers",
+            get(referrers),
+            Method::GET,
+            "来源站点统计（管理员）",
+        )
+}
+
+async fn site_stats(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> AppResult<Json<StatsSeriesResponse>> {
+    let window = Window::last_days(query.days)?;
+    let points = stats_service::site_series(&state.db, window).await?;
+    Ok(Json(StatsSeriesResponse::new(window, points)))
+}
+
+async fn note_stats(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<StatsQuery>,
+) -> AppResult<Json<StatsSeriesResponse>> {
+    let window = Window::last_days(query.days)?;
+    let note = note_service::get_note_by_slug(&state.repo(), &slug).await?;
+    let points = stats_service::note_series(&state.db, note.id, window).await?;
+    Ok(Json(StatsSeriesResponse::new(window, points)))
+}
+
+async fn top_notes(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> AppResult<Json<Vec<TopNoteResponse>>> {
+    let window = Window::last_days(query.days)?;
+    let top =
+        stats_service::top_notes(&state.db, window, query.metric, query.limit.min(100)).await?;
+    Ok(Json(
+        top.into_iter()
+            .map(|entry| TopNoteResponse {
+                slug: entry.note.slug,
+                title: entry.note.title,
+                views: entry.views,
+                likes: entry.likes,
+                comments: entry.comments,
+            })
+            .collect(),
+    ))
+}
+
+async fn referrers(
+    _admin: AdminAuth,
+    Extension(state): Extension<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> AppResult<Json<Vec<ReferrerResponse>>> {
+    let window = Window::last_days(query.days)?;
+    let note_id = match &query.slug {
+        Some(slug) => Some(
+            note_service::get_note_by_slug(&state.repo(), slug)
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+    let referrers =
+        stats_service::referrers(&state.db, note_id, window, query.limit.min(100)).await?;
+    Ok(Json(
+        referrers
+            .into_iter()
+            .map(|(referrer, views)| ReferrerResponse { referrer, views })
+            .collect(),
+    ))
+}
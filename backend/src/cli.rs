@@ -0,0 +1,412 @@
+//! 命令行子命令
+//!
+//! 不带子命令时启动 HTTP 服务；其余子命令都是一次性的运维操作，
+//! 执行完就退出。
+
+use std::io::{BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    config::{BackupConfig, MigrationConfig, OgImageConfig, SiteConfig},
+    infra::{
+        content::ContentStore,
+        db::{migrate, sqlite_path},
+    },
+    service::{
+        archive_service, auth_service, backup_service, export_service,
+        import_service::{self, ImportBatch, PostTarget, SlugConflict},
+        og_service::OgRenderer,
+    },
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "rowan-web-backend", version, about = "Rowan Web 后端服务")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 启动 HTTP 服务（默认）
+    Serve,
+    /// 创建管理员账号，密码从终端交互输入或从标准输入读取
+    CreateAdmin {
+        /// 管理员用户名
+        username: String,
+    },
+    /// 把站点导出为静态文件，默认只重新生成有变化的文件
+    Export {
+        /// 输出目录
+        #[arg(short, long, default_value = "dist")]
+        out_dir: PathBuf,
+        /// 忽略导出清单，全部重新生成
+        #[arg(long)]
+        force: bool,
+    },
+    /// 数据库迁移
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// 站点内容的 JSON 归档，用于迁移到其他实例或长期存档
+    Archive {
+        #[command(subcommand)]
+        action: ArchiveAction,
+    },
+    /// 从其他博客系统导入内容
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+    /// SQLite 数据库备份
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+    /// 用备份替换 SQLite 数据库，必须先停止服务
+    Restore {
+        /// 备份文件
+        file: PathBuf,
+        /// 确认替换当前数据库（原数据库会改名保留）
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ArchiveAction {
+    /// 导出笔记、随笔、访客、评论、点赞和友链
+    Export {
+        /// 输出文件
+        #[arg(short, long, default_value = "rowan-archive.json")]
+        out: PathBuf,
+    },
+    /// 导入归档，id 重新分配，时间保持不变
+    Import {
+        /// 归档文件
+        file: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportSource {
+    /// Hexo / Hugo / Jekyll 的 Markdown 目录，导入为笔记
+    Markdown {
+        /// 文章目录，例如 `source/_posts`、`content/posts`、`_posts`
+        dir: PathBuf,
+        #[command(flatten)]
+        options: ImportOptions,
+    },
+    /// WordPress 导出的 WXR 文件
+    Wordpress {
+        /// WXR 文件
+        file: PathBuf,
+        /// 文章导入为笔记还是随笔
+        #[arg(long, value_enum, default_value_t = PostTarget::Notes)]
+        posts_as: PostTarget,
+        #[command(flatten)]
+        options: ImportOptions,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct ImportOptions {
+    /// 只列出将要创建的内容，不写入数据库
+    #[arg(long)]
+    dry_run: bool,
+    /// slug 与已有笔记冲突时的处理方式
+    #[arg(long, value_enum, default_value_t = SlugConflict::Rename)]
+    on_conflict: SlugConflict,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BackupAction {
+    /// 立即备份一次，并按保留策略清理旧备份
+    Create,
+    /// 列出备份目录中的备份
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateAction {
+    /// 查看每个迁移是否已执行
+    Status,
+    /// 执行未执行的迁移
+    Up {
+        /// 最多执行几个，默认全部
+        #[arg(short = 'n', long)]
+        steps: Option<u32>,
+    },
+    /// 回滚最近执行的迁移
+    Down {
+        /// 回滚几个
+        #[arg(short = 'n', long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// 删除所有表后重新执行全部迁移，会清空全部数据
+    Fresh {
+        /// 确认清空数据库
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+/// 创建管理员账号
+pub async fn create_admin(db: &DatabaseConnection, username: &str) -> anyhow::Result<()> {
+    let password = read_new_password()?;
+    let admin = auth_service::create_admin(db, username, &password).await?;
+    println!("✅ 管理员 {} 创建成功 (id = {})", admin.username, admin.id);
+    Ok(())
+}
+
+/// 导出静态站点
+pub async fn export(db: &DatabaseConnection, out_dir: &Path, force: bool) -> anyhow::Result<()> {
+    let site = SiteConfig::from_env();
+    let content = ContentStore::new(&site.notes_dir);
+    let og = OgRenderer::new(&OgImageConfig::from_env());
+    let stats = export_service::export_site(db, &content, &site, &og, out_dir, force).await?;
+    println!(
+        "✅ 已导出到 {}：写入 {}，内容未变 {}，跳过 {}，删除 {}",
+        out_dir.display(),
+        stats.written,
+        stats.unchanged,
+        stats.skipped,
+        stats.removed
+    );
+    Ok(())
+}
+
+/// 数据库迁移
+pub async fn migrate(db: &DatabaseConnection, action: MigrateAction) -> anyhow::Result<()> {
+    let lock_timeout = MigrationConfig::from_env().lock_timeout();
+    match action {
+        MigrateAction::Status => {
+            let status = migrate::status(db).await?;
+            for entry in &status.migrations {
+                let mark = if entry.applied { "✅" } else { "⏳" };
+                println!("{mark} {}", entry.name);
+            }
+            for name in &status.unknown {
+                println!("❗ {name}（程序中没有这个迁移）");
+            }
+            println!(
+                "已执行 {}，待执行 {}",
+                status.applied_count(),
+                status.pending_count()
+            );
+            if status.is_ahead() {
+                println!("⚠️ 数据库结构比当前程序新，请升级程序");
+            }
+        }
+        MigrateAction::Up { steps } => {
+            let count = migrate::up(db, steps, lock_timeout).await?;
+            println!("✅ 已执行 {count} 个迁移");
+        }
+        MigrateAction::Down { steps } => {
+            let count = migrate::down(db, steps, lock_timeout).await?;
+            println!("✅ 已回滚 {count} 个迁移");
+        }
+        MigrateAction::Fresh { yes } => {
+            if !yes {
+                anyhow::bail!("fresh 会删除数据库中的所有表和数据，确认请加 --yes");
+            }
+            migrate::fresh(db).await?;
+            println!("✅ 已清空数据库并重新执行全部迁移");
+        }
+    }
+    Ok(())
+}
+
+/// 站点内容归档
+pub async fn archive(db: &DatabaseConnection, action: ArchiveAction) -> anyhow::Result<()> {
+    let content = ContentStore::new(SiteConfig::from_env().notes_dir);
+    match action {
+        ArchiveAction::Export { out } => {
+            let archive = archive_service::export_archive(db, &content).await?;
+            std::fs::write(&out, serde_json::to_vec_pretty(&archive)?)?;
+            println!(
+                "✅ 已导出到 {}：笔记 {}，随笔 {}，访客 {}，评论 {}，友链 {}",
+                out.display(),
+                archive.notes.len(),
+                archive.essays.len(),
+                archive.visitors.len(),
+                archive.comments.len(),
+                archive.friend_links.len()
+            );
+        }
+        ArchiveAction::Import { file } => {
+            let archive = archive_service::parse_archive(&std::fs::read_to_string(&file)?)?;
+            let report = archive_service::import_archive(db, &content, &archive).await?;
+            println!(
+                "✅ 已导入 {}：笔记 {}，随笔 {}，新访客 {}，评论 {}，点赞 {}，友链 {}",
+                file.display(),
+                report.notes,
+                report.essays,
+                report.visitors,
+                report.comments,
+                report.likes,
+                report.friend_links
+            );
+            for (old, new) in &report.renamed_slugs {
+                println!("🔀 slug {old} 已被占用，改为 {new}");
+            }
+            for url in &report.skipped_links {
+                println!("⏭️ 友链 {url} 已存在，跳过");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 从其他博客系统导入
+pub async fn import(db: &DatabaseConnection, source: ImportSource) -> anyhow::Result<()> {
+    let content = ContentStore::new(SiteConfig::from_env().notes_dir);
+    let (batch, options): (ImportBatch, _) = match source {
+        ImportSource::Markdown { dir, options } => {
+            (import_service::parse_markdown_tree(&dir)?, options)
+        }
+        ImportSource::Wordpress {
+            file,
+            posts_as,
+            options,
+        } => (
+            import_service::parse_wxr(&std::fs::read_to_string(&file)?, posts_as)?,
+            options,
+        ),
+    };
+    let report =
+        import_service::import_batch(db, &content, &batch, options.on_conflict, options.dry_run)
+            .await?;
+
+    for note in &report.notes {
+        match &note.renamed_from {
+            Some(base) => println!(
+                "📝 {} → /{}（slug {base} 已被占用）{}",
+                note.source,
+                note.slug,
+                comment_count(note.comments)
+            ),
+            None => println!(
+                "📝 {} → /{}{}",
+                note.source,
+                note.slug,
+                comment_count(note.comments)
+            ),
+        }
+    }
+    for essay in &report.essays {
+        match &essay.truncated_from {
+            Some(_) => println!(
+                "💬 {}（{}）标题过长，截断为「{}」{}",
+                essay.source,
+                essay.status.as_str(),
+                essay.title,
+                comment_count(essay.comments)
+            ),
+            None => println!(
+                "💬 {}（{}）{}",
+                essay.source,
+                essay.status.as_str(),
+                comment_count(essay.comments)
+            ),
+        }
+    }
+    for skipped in &report.skipped {
+        println!("⏭️ {}：{}", skipped.source, skipped.reason);
+    }
+    println!(
+        "{} 笔记 {}（改名 {}），随笔 {}（截断标题 {}），新访客 {}，跳过 {}",
+        if report.dry_run {
+            "🔍 试运行，未写入："
+        } else {
+            "✅ 已导入："
+        },
+        report.notes.len(),
+        report.renamed(),
+        report.essays.len(),
+        report.truncated(),
+        report.visitors,
+        report.skipped.len()
+    );
+    Ok(())
+}
+
+fn comment_count(count: usize) -> String {
+    if count == 0 {
+        String::new()
+    } else {
+        format!("，评论 {count}")
+    }
+}
+
+/// 数据库备份
+pub async fn backup(db: &DatabaseConnection, action: BackupAction) -> anyhow::Result<()> {
+    let config = BackupConfig::from_env();
+    match action {
+        BackupAction::Create => {
+            let backup = backup_service::create_backup(db, &config).await?;
+            let pruned = backup_service::prune(&config).await?;
+            println!(
+                "✅ 已备份到 {}（{} 字节），清理旧备份 {pruned} 份",
+                backup.path.display(),
+                backup.size
+            );
+        }
+        BackupAction::List => {
+            let backups = backup_service::list_backups(Path::new(&config.dir)).await?;
+            for backup in &backups {
+                println!(
+                    "{}  {:>12} 字节  {}",
+                    backup.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                    backup.size,
+                    backup.path.display()
+                );
+            }
+            println!("共 {} 份备份", backups.len());
+        }
+    }
+    Ok(())
+}
+
+/// 从备份恢复数据库；调用时不能持有数据库连接
+pub async fn restore(database_url: &str, file: &Path, yes: bool) -> anyhow::Result<()> {
+    let Some(database) = sqlite_path(database_url) else {
+        anyhow::bail!("只能恢复 SQLite 文件数据库，PostgreSQL 请使用 pg_restore");
+    };
+    if !yes {
+        anyhow::bail!(
+            "restore 会用备份替换 {}，确认请加 --yes",
+            database.display()
+        );
+    }
+    let previous = backup_service::restore(file, &database).await?;
+    println!(
+        "✅ 已从 {} 恢复数据库 {}",
+        file.display(),
+        database.display()
+    );
+    if let Some(previous) = previous {
+        println!("原数据库已保留为 {}", previous.display());
+    }
+    Ok(())
+}
+
+/// 终端下交互输入两次密码；非终端（如管道）时读取标准输入的第一行
+fn read_new_password() -> anyhow::Result<String> {
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    let password = rpassword::prompt_password("请输入密码: ")?;
+    let confirm = rpassword::prompt_password("请再次输入密码: ")?;
+    if password != confirm {
+        anyhow::bail!("两次输入的密码不一致");
+    }
+    Ok(password)
+}
@@ -0,0 +1,563 @@
+//! 应用配置
+//!
+//! 数据库配置见 `infra::db::DatabaseConfig`，这里放各业务子系统的可选配置。
+//! 与数据库配置不同，这些配置项都有合理的默认值，未设置时不会报错。
+
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use ipnet::IpNet;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use sea_orm::sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+
+use crate::infra::media::VariantFormat;
+use crate::service::backup_service::Compression;
+
+/// 读取可选环境变量，未设置时使用默认值；设置了但无法解析时直接报错
+pub fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{key} 环境变量格式不正确: {value}")),
+        Err(_) => default,
+    }
+}
+
+/// 路径片段中需要转义的字符，保留 RFC 3986 的非保留字符
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// HTTP 服务配置
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// 监听地址，容器中需要设为 `0.0.0.0`
+    pub host: String,
+    pub port: u16,
+    /// 可信反向代理的地址或网段，只有来自这些地址的请求才采信 `X-Forwarded-For` / `X-Real-IP`
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Self {
+        let trusted_proxies = env_or("TRUSTED_PROXIES", "127.0.0.1,::1".to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry
+                    .parse::<IpNet>()
+                    .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                    .unwrap_or_else(|_| panic!("TRUSTED_PROXIES 中的地址无效: {entry}"))
+            })
+            .collect();
+        Self {
+            host: env_or("ROWAN_SERVER_HOST", "127.0.0.1".to_string()),
+            port: env_or("ROWAN_SERVER_PORT", 8000),
+            trusted_proxies,
+        }
+    }
+
+    /// `host:port` 形式的监听地址，IPv6 地址加上方括号
+    pub fn address(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// 对端地址是否是可信反向代理；IPv4 映射的 IPv6 地址按 IPv4 处理
+    pub fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+}
+
+/// 站点信息，用于生成订阅源、站点地图等对外链接
+#[derive(Debug, Clone)]
+pub struct SiteConfig {
+    /// 站点对外地址（前端地址），不带末尾斜杠
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    pub author: String,
+    /// 站点语言，如 `zh-CN`
+    pub language: String,
+    /// 笔记 Markdown 正文所在目录
+    pub notes_dir: String,
+    /// 订阅源默认输出的条目数
+    pub feed_limit: u64,
+    /// 订阅源默认输出全文还是只输出摘要
+    pub feed_full_content: bool,
+    /// robots.txt 中禁止抓取的路径
+    pub robots_disallow: Vec<String>,
+    /// 自定义 robots.txt 文件，设置后替代自动生成的内容
+    pub robots_file: Option<String>,
+}
+
+impl SiteConfig {
+    pub fn from_env() -> Self {
+        let url: String = env_or("SITE_URL", "http://localhost:3000".to_string());
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            title: env_or("SITE_TITLE", "RowanWeb".to_string()),
+            description: env_or("SITE_DESCRIPTION", "Rowan 的笔记与随笔".to_string()),
+            author: env_or("SITE_AUTHOR", "Rowan".to_string()),
+            language: env_or("SITE_LANGUAGE", "zh-CN".to_string()),
+            notes_dir: env_or("NOTES_DIR", "notes".to_string()),
+            feed_limit: env_or("FEED_ITEM_LIMIT", 20),
+            feed_full_content: env_or("FEED_FULL_CONTENT", true),
+            robots_disallow: env_or("ROBOTS_DISALLOW", "/api/".to_string())
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect(),
+            robots_file: std::env::var("ROBOTS_FILE")
+                .ok()
+                .filter(|path| !path.trim().is_empty()),
+        }
+    }
+
+    /// 拼接站内链接，每个路径片段都会做 URL 转义
+    pub fn page_url(&self, segments: &[&str]) -> String {
+        let mut url = self.url.clone();
+        for segment in segments {
+            url.push('/');
+            url.extend(utf8_percent_encode(segment, PATH_SEGMENT));
+        }
+        url
+    }
+
+    /// 笔记页面地址
+    pub fn note_url(&self, slug: &str) -> String {
+        self.page_url(&["notes", slug])
+    }
+
+    /// 随笔页面地址
+    pub fn essay_url(&self, id: i32) -> String {
+        self.page_url(&["essays", &id.to_string()])
+    }
+
+    /// 标签页面地址
+    pub fn tag_url(&self, tag: &str) -> String {
+        self.page_url(&["tags", tag])
+    }
+
+    /// 分类页面地址
+    pub fn category_url(&self, category: &str) -> String {
+        self.page_url(&["categories", category])
+    }
+
+    /// 笔记分享卡片地址
+    pub fn og_image_url(&self, slug: &str) -> String {
+        self.page_url(&["og", &format!("{slug}.png")])
+    }
+
+    /// 媒体文件地址
+    pub fn media_url(&self, file: &str) -> String {
+        self.page_url(&["media", file])
+    }
+
+    /// 站点地图地址
+    pub fn sitemap_url(&self) -> String {
+        self.page_url(&["sitemap.xml"])
+    }
+}
+
+/// 社交分享卡片（OpenGraph 图片）配置
+#[derive(Debug, Clone)]
+pub struct OgImageConfig {
+    /// 生成的 PNG 缓存目录
+    pub cache_dir: String,
+    /// 额外字体目录（如中文字体），其中的 `.ttf` / `.otf` / `.ttc` 都会被加载
+    pub font_dir: Option<String>,
+    /// 是否加载系统字体作为后备
+    pub system_fonts: bool,
+}
+
+impl OgImageConfig {
+    pub fn from_env() -> Self {
+        Self {
+            cache_dir: env_or("OG_CACHE_DIR", "cache/og".to_string()),
+            font_dir: std::env::var("OG_FONT_DIR")
+                .ok()
+                .filter(|dir| !dir.trim().is_empty()),
+            system_fonts: env_or("OG_SYSTEM_FONTS", true),
+        }
+    }
+}
+
+/// 上传文件存储位置
+#[derive(Debug, Clone)]
+pub enum StorageBackend {
+    /// 本地磁盘（`AssetConfig::dir`）
+    Local,
+    /// S3 兼容的对象存储
+    S3(S3Config),
+}
+
+/// S3 兼容对象存储的连接信息
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    /// 服务地址，如 `https://s3.us-east-1.amazonaws.com`、`http://127.0.0.1:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// 使用 `{endpoint}/{bucket}/{key}` 形式的地址，否则使用 `{bucket}.{host}/{key}`
+    pub path_style: bool,
+}
+
+/// 媒体文件上传配置
+#[derive(Debug, Clone)]
+pub struct AssetConfig {
+    pub backend: StorageBackend,
+    /// 本地存储目录
+    pub dir: String,
+    /// 单个文件的大小上限（字节）
+    pub max_bytes: usize,
+    pub variants: ImageVariantConfig,
+}
+
+/// 响应式图片变体配置
+#[derive(Debug, Clone)]
+pub struct ImageVariantConfig {
+    /// 生成的宽度（像素），只生成小于原图宽度的；为空时不生成变体
+    pub widths: Vec<u32>,
+    /// 编码格式，按优先顺序排列
+    pub formats: Vec<VariantFormat>,
+    /// 编码质量（1-100）
+    pub quality: u8,
+}
+
+impl ImageVariantConfig {
+    pub fn from_env() -> Self {
+        let mut widths: Vec<u32> = env_or("IMAGE_VARIANT_WIDTHS", "480,960,1600".to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|width| !width.is_empty())
+            .map(|width| {
+                width
+                    .parse()
+                    .unwrap_or_else(|_| panic!("IMAGE_VARIANT_WIDTHS 中的宽度无效: {width}"))
+            })
+            .filter(|width| *width > 0)
+            .collect();
+        widths.sort_unstable();
+        widths.dedup();
+        let mut formats = Vec::new();
+        for format in env_or("IMAGE_VARIANT_FORMATS", "avif,webp".to_string())
+            .split(',')
+            .filter(|format| !format.trim().is_empty())
+        {
+            let format = format.parse().unwrap_or_else(|e| panic!("{e}"));
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        Self {
+            widths,
+            formats,
+            quality: env_or("IMAGE_VARIANT_QUALITY", 75u8).clamp(1, 100),
+        }
+    }
+
+    /// 是否启用变体
+    pub fn enabled(&self) -> bool {
+        !self.widths.is_empty() && !self.formats.is_empty()
+    }
+}
+
+impl AssetConfig {
+    pub fn from_env() -> Self {
+        let required = |key: &str| {
+            std::env::var(key).unwrap_or_else(|_| panic!("使用 S3 存储时必须配置 {key} 环境变量"))
+        };
+        let backend = match env_or("ASSET_STORAGE", "local".to_string()).as_str() {
+            "local" => StorageBackend::Local,
+            "s3" => StorageBackend::S3(S3Config {
+                endpoint: required("S3_ENDPOINT"),
+                bucket: required("S3_BUCKET"),
+                region: env_or("S3_REGION", "us-east-1".to_string()),
+                access_key: required("S3_ACCESS_KEY_ID"),
+                secret_key: required("S3_SECRET_ACCESS_KEY"),
+                path_style: env_or("S3_PATH_STYLE", true),
+            }),
+            other => panic!("ASSET_STORAGE 只能是 local 或 s3: {other}"),
+        };
+        Self {
+            backend,
+            dir: env_or("ASSET_DIR", "uploads".to_string()),
+            max_bytes: env_or("ASSET_MAX_BYTES", 20 * 1024 * 1024),
+            variants: ImageVariantConfig::from_env(),
+        }
+    }
+}
+
+/// 浏览量统计配置
+#[derive(Debug, Clone)]
+pub struct ViewConfig {
+    /// 同一访客对同一篇笔记的去重窗口（秒）
+    pub dedup_window_secs: u64,
+    /// 内存中的计数批量写回数据库的间隔（秒）
+    pub flush_interval_secs: u64,
+    /// 内存中最多记住的（笔记, 访客）去重记录数，防止大量伪造的访客标识耗尽内存
+    pub max_tracked: usize,
+}
+
+impl ViewConfig {
+    pub fn from_env() -> Self {
+        Self {
+            dedup_window_secs: env_or("VIEW_DEDUP_WINDOW_SECS", 30 * 60),
+            flush_interval_secs: env_or("VIEW_FLUSH_INTERVAL_SECS", 30),
+            max_tracked: env_or("VIEW_MAX_TRACKED", 100_000),
+        }
+    }
+
+    /// 获取去重窗口
+    pub fn dedup_window(&self) -> Duration {
+        Duration::from_secs(self.dedup_window_secs)
+    }
+
+    /// 获取写回间隔
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_secs(self.flush_interval_secs)
+    }
+}
+
+/// 统计汇总配置
+#[derive(Debug, Clone)]
+pub struct StatsConfig {
+    /// 评论数汇总任务的执行间隔（秒）
+    pub rollup_interval_secs: u64,
+}
+
+impl StatsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            rollup_interval_secs: env_or("STATS_ROLLUP_INTERVAL_SECS", 10 * 60),
+        }
+    }
+
+    /// 获取汇总间隔
+    pub fn rollup_interval(&self) -> Duration {
+        Duration::from_secs(self.rollup_interval_secs)
+    }
+}
+
+/// 数据库迁移配置
+#[derive(Debug, Clone)]
+pub struct MigrationConfig {
+    /// 启动服务时是否自动执行未执行的迁移
+    pub auto_migrate: bool,
+    /// 等待其他实例释放迁移锁的最长时间（秒）
+    pub lock_timeout_secs: u64,
+}
+
+impl MigrationConfig {
+    pub fn from_env() -> Self {
+        Self {
+            auto_migrate: env_or("DB_AUTO_MIGRATE", true),
+            lock_timeout_secs: env_or("DB_MIGRATE_LOCK_TIMEOUT_SECS", 60),
+        }
+    }
+
+    /// 获取等待迁移锁的超时时间
+    pub fn lock_timeout(&self) -> Duration {
+        Duration::from_secs(self.lock_timeout_secs)
+    }
+}
+
+/// SQLite 连接配置，每个新连接建立时执行对应的 PRAGMA；使用 PostgreSQL 时忽略
+#[derive(Debug, Clone)]
+pub struct SqliteConfig {
+    /// 是否启用外键约束，关闭后评论、点赞等的级联删除不会生效
+    pub foreign_keys: bool,
+    /// 日志模式，WAL 下读写互不阻塞
+    pub journal_mode: SqliteJournalMode,
+    /// 遇到锁时等待的最长时间（毫秒）
+    pub busy_timeout_ms: u64,
+    /// 同步级别，WAL 下 normal 只在断电时可能丢失最后几个事务
+    pub synchronous: SqliteSynchronous,
+    /// 每个连接的页缓存大小（KiB）
+    pub cache_size_kib: u32,
+    /// 是否为写操作单独建立只有一个连接的连接池，避免多个连接争抢写锁
+    pub separate_writer: bool,
+}
+
+impl SqliteConfig {
+    pub fn from_env() -> Self {
+        Self {
+            foreign_keys: env_or("DB_SQLITE_FOREIGN_KEYS", true),
+            journal_mode: env_or("DB_SQLITE_JOURNAL_MODE", SqliteJournalMode::Wal),
+            busy_timeout_ms: env_or("DB_SQLITE_BUSY_TIMEOUT_MS", 5000),
+            synchronous: env_or("DB_SQLITE_SYNCHRONOUS", SqliteSynchronous::Normal),
+            cache_size_kib: env_or("DB_SQLITE_CACHE_SIZE_KIB", 16 * 1024),
+            separate_writer: env_or("DB_SQLITE_SEPARATE_WRITER", true),
+        }
+    }
+
+    /// 获取等待锁的超时时间
+    pub fn busy_timeout(&self) -> Duration {
+        Duration::from_millis(self.busy_timeout_ms)
+    }
+}
+
+/// 数据库备份配置，只对 SQLite 生效
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    /// 备份目录
+    pub dir: String,
+    /// 定时备份的间隔（秒），0 表示不定时备份
+    pub interval_secs: u64,
+    /// 最多保留的备份份数
+    pub keep: usize,
+    /// 备份最长保留天数，0 表示不按时间清理
+    pub max_age_days: u64,
+    /// 压缩方式：none、gzip 或 zstd
+    pub compression: Compression,
+}
+
+impl BackupConfig {
+    pub fn from_env() -> Self {
+        Self {
+            dir: env_or("BACKUP_DIR", "backups".to_string()),
+            interval_secs: env_or("BACKUP_INTERVAL_SECS", 24 * 60 * 60),
+            keep: env_or("BACKUP_KEEP", 7),
+            max_age_days: env_or("BACKUP_MAX_AGE_DAYS", 30),
+            compression: env_or("BACKUP_COMPRESSION", Compression::Zstd),
+        }
+    }
+
+    /// 是否开启定时备份
+    pub fn scheduled(&self) -> bool {
+        self.interval_secs > 0
+    }
+
+    /// 获取定时备份的间隔
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    /// 获取最长保留时间，不按时间清理时为空
+    pub fn max_age(&self) -> Option<Duration> {
+        (self.max_age_days > 0).then(|| Duration::from_secs(self.max_age_days * 24 * 60 * 60))
+    }
+}
+
+/// 随笔配置
+#[derive(Debug, Clone)]
+pub struct EssayConfig {
+    /// 检查定时发布随笔的间隔（秒）
+    pub publish_interval_secs: u64,
+}
+
+impl EssayConfig {
+    pub fn from_env() -> Self {
+        Self {
+            publish_interval_secs: env_or("ESSAY_PUBLISH_INTERVAL_SECS", 60),
+        }
+    }
+
+    /// 获取检查间隔
+    pub fn publish_interval(&self) -> Duration {
+        Duration::from_secs(self.publish_interval_secs)
+    }
+}
+
+/// 友链健康检查配置
+#[derive(Debug, Clone)]
+pub struct FriendLinkConfig {
+    /// 检查全部友链的间隔（秒）
+    pub check_interval_secs: u64,
+    /// 单个友链的请求超时（秒）
+    pub check_timeout_secs: u64,
+}
+
+impl FriendLinkConfig {
+    pub fn from_env() -> Self {
+        Self {
+            check_interval_secs: env_or("FRIEND_LINK_CHECK_INTERVAL_SECS", 6 * 60 * 60),
+            check_timeout_secs: env_or("FRIEND_LINK_CHECK_TIMEOUT_SECS", 10),
+        }
+    }
+
+    /// 获取检查间隔
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_secs)
+    }
+
+    /// 获取请求超时
+    pub fn check_timeout(&self) -> Duration {
+        Duration::from_secs(self.check_timeout_secs)
+    }
+}
+
+/// Prometheus 指标配置
+#[derive(Debug, Clone, Default)]
+pub struct MetricsConfig {
+    /// 设置后抓取 `/metrics` 需要带 `Authorization: Bearer <token>`
+    pub token: Option<String>,
+}
+
+impl MetricsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            token: std::env::var("METRICS_TOKEN")
+                .ok()
+                .filter(|token| !token.trim().is_empty()),
+        }
+    }
+}
+
+/// JWT 配置
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    /// 签名密钥，必须在 .env 文件中配置
+    pub secret: String,
+    /// 访问令牌有效期（秒），应尽量短
+    pub expires_in: i64,
+    /// 刷新令牌有效期（秒）
+    pub refresh_expires_in: i64,
+}
+
+impl JwtConfig {
+    pub fn from_env() -> Self {
+        Self {
+            secret: std::env::var("JWT_SECRET")
+                .expect("JWT_SECRET 环境变量未设置，请在 .env 文件中配置"),
+            expires_in: env_or("ROWAN_JWT_EXPIRES_IN", 15 * 60),
+            refresh_expires_in: env_or("ROWAN_JWT_REFRESH_EXPIRES_IN", 30 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// 两步验证配置
+#[derive(Debug, Clone)]
+pub struct TotpConfig {
+    /// 显示在验证器应用中的发行方名称
+    pub issuer: String,
+    /// 密码验证通过后，等待输入验证码的有效期（秒）
+    pub challenge_ttl_secs: i64,
+    /// 连续输错验证码或恢复码多少次后锁定两步验证
+    pub max_attempts: i32,
+    /// 锁定时长（秒）
+    pub lockout_secs: i64,
+}
+
+impl TotpConfig {
+    pub fn from_env() -> Self {
+        Self {
+            issuer: env_or("TOTP_ISSUER", "RowanWeb".to_string()),
+            challenge_ttl_secs: env_or("TOTP_CHALLENGE_TTL_SECS", 5 * 60),
+            max_attempts: env_or("TOTP_MAX_ATTEMPTS", 5),
+            lockout_secs: env_or("TOTP_LOCKOUT_SECS", 15 * 60),
+        }
+    }
+}
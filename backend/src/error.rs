@@ -0,0 +1,65 @@
+//! 统一错误处理
+//!
+//! 所有服务层函数都返回 `AppResult<T>`，处理器直接把 `AppError`
+//! 交给 Axum，由 `IntoResponse` 转换成带状态码的 JSON 错误响应。
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use sea_orm::DbErr;
+use serde_json::json;
+
+/// 应用错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("资源不存在")]
+    NotFound,
+    #[error("未认证")]
+    Unauthorized,
+    #[error("无权限")]
+    Forbidden,
+    #[error("请求参数错误: {0}")]
+    BadRequest(String),
+    #[error("资源冲突: {0}")]
+    Conflict(String),
+    #[error("请求过于频繁: {0}")]
+    TooManyRequests(String),
+    #[error("数据库错误: {0}")]
+    Database(#[from] DbErr),
+    #[error("内部错误: {0}")]
+    Internal(String),
+}
+
+/// 服务层统一返回类型
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// 错误对应的 HTTP 状态码
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden => StatusCode::FORBIDDEN,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        // 内部错误只记录日志，不把细节暴露给客户端
+        let message = if status.is_server_error() {
+            log::error!("❌ {self}");
+            "服务器内部错误".to_string()
+        } else {
+            self.to_string()
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
@@ -1,2 +1,7 @@
+pub mod blob;
+pub mod content;
 pub mod db;
+pub mod media;
+pub mod metrics;
 pub mod repositories;
+pub mod unit_of_work;
@@ -0,0 +1,444 @@
+//! 文件存储
+//!
+//! 上传的文件按内容寻址保存：键由内容的 SHA-256 决定，同一内容只存一份，写入后不再修改。
+//! `BlobStore` 屏蔽具体存储位置，默认存本地磁盘，也可以换成任意 S3 兼容的对象存储
+//! （AWS S3、Cloudflare R2、MinIO 等）。
+
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::{AssetConfig, S3Config, StorageBackend},
+    error::{AppError, AppResult},
+};
+
+/// 文件存储
+#[async_trait::async_trait]
+pub trait BlobStore: Send + Sync {
+    /// 写入文件，键已存在时覆盖
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> AppResult<()>;
+    /// 读取文件，不存在时返回 `None`
+    async fn get(&self, key: &str) -> AppResult<Option<Bytes>>;
+    /// 文件是否存在
+    async fn exists(&self, key: &str) -> AppResult<bool>;
+    /// 删除文件，不存在时什么也不做
+    async fn delete(&self, key: &str) -> AppResult<()>;
+}
+
+/// 按配置创建文件存储
+pub fn from_config(config: &AssetConfig) -> AppResult<Box<dyn BlobStore>> {
+    Ok(match &config.backend {
+        StorageBackend::Local => Box::new(LocalBlobStore::new(&config.dir)),
+        StorageBackend::S3(s3) => Box::new(S3BlobStore::new(s3.clone())?),
+    })
+}
+
+/// 键只允许小写字母、数字、`/`、`.`、`-`，且不能包含 `..`，防止越出存储目录
+fn check_key(key: &str) -> AppResult<()> {
+    let valid = !key.is_empty()
+        && !key.starts_with('/')
+        && !key.contains("..")
+        && key.bytes().all(|b| {
+            b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'/' | b'.' | b'-')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::Internal(format!("非法的存储键: {key}")))
+    }
+}
+
+/// 本地磁盘存储
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> AppResult<PathBuf> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> AppError {
+    AppError::Internal(format!("访问文件 {} 失败: {err}", path.display()))
+}
+
+#[async_trait::async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, data: Bytes, _content_type: &str) -> AppResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| io_error(parent, e))?;
+        }
+        // 先写临时文件再改名，读取方不会看到写了一半的文件
+        let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        if let Err(err) = tokio::fs::write(&tmp, &data).await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(io_error(&tmp, err));
+        }
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| io_error(&path, e))
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Option<Bytes>> {
+        let path = self.path(key)?;
+        match tokio::fs::read(&path).await {
+            Ok(data) => Ok(Some(Bytes::from(data))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(io_error(&path, err)),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> AppResult<bool> {
+        let path = self.path(key)?;
+        tokio::fs::try_exists(&path)
+            .await
+            .map_err(|e| io_error(&path, e))
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        let path = self.path(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(io_error(&path, err)),
+        }
+    }
+}
+
+/// S3 路径中需要转义的字符（AWS 签名要求只保留非保留字符）
+const S3_PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// S3 兼容对象存储，请求使用 AWS Signature Version 4 签名
+pub struct S3BlobStore {
+    client: reqwest::Client,
+    config: S3Config,
+    /// 签名用的 Host 头
+    host: String,
+    /// 请求地址前缀（含存储桶），后面直接拼对象键
+    base_url: String,
+    /// 签名用的路径前缀
+    base_path: String,
+}
+
+impl S3BlobStore {
+    pub fn new(config: S3Config) -> AppResult<Self> {
+        let endpoint = config.endpoint.trim_end_matches('/');
+        let (scheme, authority) = endpoint
+            .split_once("://")
+            .ok_or_else(|| AppError::Internal(format!("S3 地址格式不正确: {endpoint}")))?;
+        let (host, base_url, base_path) = if config.path_style {
+            (
+                authority.to_string(),
+                format!("{endpoint}/{}", config.bucket),
+                format!("/{}", config.bucket),
+            )
+        } else {
+            let host = format!("{}.{authority}", config.bucket);
+            (host.clone(), format!("{scheme}://{host}"), String::new())
+        };
+
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .build()
+                .map_err(|e| AppError::Internal(format!("创建 S3 客户端失败: {e}")))?,
+            config,
+            host,
+            base_url,
+            base_path,
+        })
+    }
+
+    /// 发送签名后的请求
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        body: Bytes,
+        content_type: Option<&str>,
+    ) -> AppResult<reqwest::Response> {
+        check_key(key)?;
+        let path: String = key
+            .split('/')
+            .map(|segment| utf8_percent_encode(segment, S3_PATH_SEGMENT).to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        let canonical_uri = format!("{}/{path}", self.base_path);
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = sigv4_authorization(
+            &self.config,
+            method.as_str(),
+            &canonical_uri,
+            &self.host,
+            &amz_date,
+            &payload_hash,
+        );
+
+        let mut request = self
+            .client
+            .request(method, format!("{}/{path}", self.base_url))
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header("authorization", authorization)
+            .body(body);
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("S3 请求失败: {e}")))
+    }
+}
+
+/// 非 2xx 响应转成错误
+async fn s3_error(action: &str, key: &str, response: reqwest::Response) -> AppError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    AppError::Internal(format!("S3 {action} {key} 失败（{status}）: {body}"))
+}
+
+#[async_trait::async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> AppResult<()> {
+        let response = self
+            .send(reqwest::Method::PUT, key, data, Some(content_type))
+            .await?;
+        if !response.status().is_success() {
+            return Err(s3_error("上传", key, response).await);
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Option<Bytes>> {
+        let response = self
+            .send(reqwest::Method::GET, key, Bytes::new(), None)
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => response
+                .bytes()
+                .await
+                .map(Some)
+                .map_err(|e| AppError::Internal(format!("S3 读取 {key} 失败: {e}"))),
+            _ => Err(s3_error("读取", key, response).await),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> AppResult<bool> {
+        let response = self
+            .send(reqwest::Method::HEAD, key, Bytes::new(), None)
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            _ => Err(s3_error("查询", key, response).await),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        let response = self
+            .send(reqwest::Method::DELETE, key, Bytes::new(), None)
+            .await?;
+        // S3 删除不存在的对象也返回 204
+        if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            Err(s3_error("删除", key, response).await)
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 签名密钥：由密钥、日期、区域和服务逐级 HMAC 派生
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{secret_key}").as_bytes(), date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    hmac_sha256(&key, "aws4_request")
+}
+
+/// 计算 SigV4 的 `Authorization` 头，签名 `host`、`x-amz-content-sha256`、`x-amz-date` 三个头
+fn sigv4_authorization(
+    config: &S3Config,
+    method: &str,
+    canonical_uri: &str,
+    host: &str,
+    amz_date: &str,
+    payload_hash: &str,
+) -> String {
+    const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{}/s3/aws4_request", config.region);
+
+    let canonical_request = format!(
+        "{method}\n{canonical_uri}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{SIGNED_HEADERS}\n{payload_hash}"
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = signing_key(&config.secret_key, date, &config.region, "s3");
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={SIGNED_HEADERS}, Signature={signature}",
+        config.access_key
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, sync::Arc};
+
+    use axum::{
+        Router,
+        extract::{Path as UrlPath, State},
+        http::{HeaderMap, Method},
+        response::IntoResponse,
+        routing::any,
+    };
+    use tokio::sync::Mutex;
+
+    #[test]
+    fn test_sigv4_signing_key() {
+        // AWS 文档 "Examples of how to derive a signing key" 中的示例
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_check_key() {
+        assert!(check_key("ab/abcdef.png").is_ok());
+        assert!(check_key("../etc/passwd").is_err());
+        assert!(check_key("/abs").is_err());
+        assert!(check_key("UPPER.png").is_err());
+    }
+
+    type Objects = Arc<Mutex<HashMap<String, Bytes>>>;
+
+    /// 本地的 S3 替身：只实现按路径读写对象，并检查请求带了签名和正确的内容哈希
+    async fn fake_s3(
+        State(objects): State<Objects>,
+        method: Method,
+        UrlPath(path): UrlPath<String>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> axum::response::Response {
+        let signed = headers
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("AWS4-HMAC-SHA256 Credential=test/"));
+        let hash_ok = headers
+            .get("x-amz-content-sha256")
+            .is_some_and(|v| v.as_bytes() == hex::encode(Sha256::digest(&body)).as_bytes());
+        if !signed || !hash_ok {
+            return StatusCode::FORBIDDEN.into_response();
+        }
+
+        let mut objects = objects.lock().await;
+        match method {
+            Method::PUT => {
+                objects.insert(path, body);
+                StatusCode::OK.into_response()
+            }
+            Method::GET | Method::HEAD => match objects.get(&path) {
+                Some(data) => data.clone().into_response(),
+                None => StatusCode::NOT_FOUND.into_response(),
+            },
+            Method::DELETE => {
+                objects.remove(&path);
+                StatusCode::NO_CONTENT.into_response()
+            }
+            _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+        }
+    }
+
+    async fn exercise(store: &dyn BlobStore) {
+        let key = "ab/abcdef.png";
+        assert!(!store.exists(key).await.unwrap());
+        assert_eq!(store.get(key).await.unwrap(), None);
+        store
+            .put(key, Bytes::from_static(b"data"), "image/png")
+            .await
+            .unwrap();
+        assert!(store.exists(key).await.unwrap());
+        assert_eq!(
+            store.get(key).await.unwrap(),
+            Some(Bytes::from_static(b"data"))
+        );
+        store.delete(key).await.unwrap();
+        store.delete(key).await.unwrap();
+        assert!(!store.exists(key).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_local_store() {
+        let dir = std::env::temp_dir().join(format!("rowan-blob-{}", uuid::Uuid::new_v4()));
+        exercise(&LocalBlobStore::new(&dir)).await;
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn test_s3_store_against_stand_in() {
+        let objects = Objects::default();
+        let app = Router::new()
+            .route("/{*path}", any(fake_s3))
+            .with_state(Arc::clone(&objects));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let store = S3BlobStore::new(S3Config {
+            endpoint: format!("http://{addr}"),
+            bucket: "media".to_string(),
+            region: "auto".to_string(),
+            access_key: "test".to_string(),
+            secret_key: "secret".to_string(),
+            path_style: true,
+        })
+        .unwrap();
+        exercise(&store).await;
+
+        store
+            .put("cd/x.jpg", Bytes::from_static(b"x"), "image/jpeg")
+            .await
+            .unwrap();
+        assert!(objects.lock().await.contains_key("media/cd/x.jpg"));
+    }
+}
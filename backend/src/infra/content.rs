@@ -0,0 +1,398 @@
+//! 笔记正文存储
+//!
+//! 数据库只保存笔记的元数据，正文以 Markdown 文件的形式放在
+//! `{notes_dir}/{file_id}.md`，这里负责读取并渲染成 HTML。
+
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, TagEnd, html};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// 笔记正文所在目录
+#[derive(Debug, Clone)]
+pub struct ContentStore {
+    notes_dir: PathBuf,
+    /// 测试中模拟临时文件改名失败
+    #[cfg(test)]
+    fail_commits: bool,
+}
+
+impl ContentStore {
+    pub fn new(notes_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            notes_dir: notes_dir.into(),
+            #[cfg(test)]
+            fail_commits: false,
+        }
+    }
+
+    /// 之后的 [`Self::commit_staged`] 全部失败，用于测试提交后的处理
+    #[cfg(test)]
+    pub fn failing_commits(mut self) -> Self {
+        self.fail_commits = true;
+        self
+    }
+
+    /// 某篇笔记的正文文件路径
+    pub fn note_path(&self, file_id: Uuid) -> PathBuf {
+        self.notes_dir.join(format!("{file_id}.md"))
+    }
+
+    /// 读取笔记的 Markdown 正文，文件不存在时返回 `None`
+    pub async fn read_note(&self, file_id: Uuid) -> AppResult<Option<String>> {
+        match tokio::fs::read_to_string(self.note_path(file_id)).await {
+            Ok(markdown) => Ok(Some(markdown)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(AppError::Internal(format!(
+                "读取笔记 {file_id} 失败: {err}"
+            ))),
+        }
+    }
+
+    /// 写入笔记的 Markdown 正文，目录不存在时自动创建；服务代码使用 [`Self::stage_note`]
+    #[cfg(test)]
+    pub async fn write_note(&self, file_id: Uuid, markdown: &str) -> AppResult<()> {
+        tokio::fs::create_dir_all(&self.notes_dir)
+            .await
+            .and(tokio::fs::write(self.note_path(file_id), markdown).await)
+            .map_err(|err| AppError::Internal(format!("写入笔记 {file_id} 失败: {err}")))
+    }
+
+    /// 把正文先写到正文目录下的临时文件，返回其路径
+    ///
+    /// 临时文件不以 `.md` 结尾，不会被当成笔记；调用 [`Self::commit_staged`]
+    /// 才会替换正式文件，放弃时调用 [`Self::discard_staged`]。
+    pub async fn stage_note(&self, markdown: &str) -> AppResult<PathBuf> {
+        let staged = self
+            .notes_dir
+            .join(format!(".staged-{}.tmp", Uuid::new_v4().simple()));
+        tokio::fs::create_dir_all(&self.notes_dir)
+            .await
+            .and(tokio::fs::write(&staged, markdown).await)
+            .map_err(|err| AppError::Internal(format!("写入临时正文失败: {err}")))?;
+        Ok(staged)
+    }
+
+    /// 暂存一批正文，返回各自的文件 ID 和临时文件；中途失败时删除已经暂存的文件
+    pub async fn stage_notes(&self, notes: &[(Uuid, &str)]) -> AppResult<Vec<(Uuid, PathBuf)>> {
+        let mut staged = Vec::with_capacity(notes.len());
+        for &(file_id, markdown) in notes {
+            match self.stage_note(markdown).await {
+                Ok(path) => staged.push((file_id, path)),
+                Err(err) => {
+                    for (_, path) in &staged {
+                        self.discard_staged(path).await;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(staged)
+    }
+
+    /// 把临时文件重命名为笔记的正文文件；同一目录内的重命名是原子的
+    pub async fn commit_staged(&self, file_id: Uuid, staged: &Path) -> AppResult<()> {
+        #[cfg(test)]
+        if self.fail_commits {
+            return Err(AppError::Internal(format!(
+                "写入笔记 {file_id} 失败: 模拟失败"
+            )));
+        }
+        tokio::fs::rename(staged, self.note_path(file_id))
+            .await
+            .map_err(|err| AppError::Internal(format!("写入笔记 {file_id} 失败: {err}")))
+    }
+
+    /// 删除不再需要的临时文件，失败只记日志
+    pub async fn discard_staged(&self, staged: &Path) {
+        if let Err(err) = tokio::fs::remove_file(staged).await {
+            log::warn!("⚠️ 删除临时正文 {} 失败: {err}", staged.display());
+        }
+    }
+
+    /// 事务结束后处理暂存的正文：事务成功时逐个替换为正式文件，失败时删除全部临时文件
+    ///
+    /// 事务已经提交时写入是持久的，改名失败也不能再报告失败（客户端重试会重复创建），
+    /// 只记录错误并保留临时文件，由站长按日志手动恢复。
+    pub async fn settle_staged<T>(
+        &self,
+        result: AppResult<T>,
+        staged: Vec<(Uuid, PathBuf)>,
+    ) -> AppResult<T> {
+        let value = match result {
+            Ok(value) => value,
+            Err(err) => {
+                for (_, path) in &staged {
+                    self.discard_staged(path).await;
+                }
+                return Err(err);
+            }
+        };
+        for (file_id, path) in staged {
+            if let Err(err) = self.commit_staged(file_id, &path).await {
+                log::error!(
+                    "❌ 元数据已提交但正文未能就位: {err}，正文保留在 {}，请手动移动到 {}",
+                    path.display(),
+                    self.note_path(file_id).display()
+                );
+            }
+        }
+        Ok(value)
+    }
+
+    /// 删除笔记的正文文件，文件不存在时什么也不做
+    pub async fn delete_note(&self, file_id: Uuid) -> AppResult<()> {
+        match tokio::fs::remove_file(self.note_path(file_id)).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(AppError::Internal(
+                format!("删除笔记 {file_id} 失败: {err}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// 检查正文目录是否可读，返回其中的正文文件数；目录还没创建时（尚未写过笔记）返回 0
+    pub async fn count_notes(&self) -> AppResult<usize> {
+        let mut entries = match tokio::fs::read_dir(&self.notes_dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => {
+                return Err(AppError::Internal(format!(
+                    "读取笔记目录 {} 失败: {err}",
+                    self.notes_dir.display()
+                )));
+            }
+        };
+        let mut count = 0;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|err| AppError::Internal(format!("读取笔记目录失败: {err}")))?
+        {
+            if entry.path().extension().is_some_and(|ext| ext == "md") {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// 读取并渲染笔记正文
+    pub async fn render_note(&self, file_id: Uuid) -> AppResult<Option<String>> {
+        Ok(self
+            .read_note(file_id)
+            .await?
+            .map(|markdown| render_markdown(&markdown)))
+    }
+}
+
+/// 支持表格、删除线、脚注和任务列表
+const MARKDOWN_OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_FOOTNOTES)
+    .union(Options::ENABLE_TASKLISTS);
+
+/// 把 Markdown 渲染成 HTML
+pub fn render_markdown(markdown: &str) -> String {
+    render_markdown_with(markdown, |_, _, _| None)
+}
+
+/// 正在收集替代文字的图片
+struct PendingImage<'a> {
+    url: CowStr<'a>,
+    title: CowStr<'a>,
+    alt: String,
+    /// 替代文字中嵌套的图片层数
+    depth: usize,
+    /// 不替换时原样输出的事件
+    events: Vec<Event<'a>>,
+}
+
+/// 把 Markdown 渲染成 HTML，图片交给 `image_html(地址, 替代文字, 标题)` 处理，
+/// 返回 `Some` 时用它替换默认的 `<img>` 标签
+pub fn render_markdown_with(
+    markdown: &str,
+    mut image_html: impl FnMut(&str, &str, &str) -> Option<String>,
+) -> String {
+    let mut events = Vec::new();
+    let mut pending: Option<PendingImage> = None;
+    for event in Parser::new_ext(markdown, MARKDOWN_OPTIONS) {
+        let Some(image) = pending.as_mut() else {
+            if let Event::Start(Tag::Image {
+                dest_url, title, ..
+            }) = &event
+            {
+                pending = Some(PendingImage {
+                    url: dest_url.clone(),
+                    title: title.clone(),
+                    alt: String::new(),
+                    depth: 0,
+                    events: vec![event],
+                });
+            } else {
+                events.push(event);
+            }
+            continue;
+        };
+
+        match &event {
+            Event::Start(Tag::Image { .. }) => image.depth += 1,
+            Event::End(TagEnd::Image) if image.depth > 0 => image.depth -= 1,
+            Event::End(TagEnd::Image) => {
+                image.events.push(event);
+                let image = pending.take().unwrap();
+                match image_html(&image.url, &image.alt, &image.title) {
+                    Some(html) => events.push(Event::InlineHtml(html.into())),
+                    None => events.extend(image.events),
+                }
+                continue;
+            }
+            Event::Text(text) | Event::Code(text) => image.alt.push_str(text),
+            _ => {}
+        }
+        image.events.push(event);
+    }
+
+    let mut output = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut output, events.into_iter());
+    output
+}
+
+/// Markdown 中引用的图片地址
+pub fn image_urls(markdown: &str) -> Vec<String> {
+    Parser::new_ext(markdown, MARKDOWN_OPTIONS)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Image { dest_url, .. }) => Some(dest_url.into_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 中文阅读速度（字/分钟）
+const CJK_CHARS_PER_MINUTE: usize = 400;
+/// 英文阅读速度（词/分钟）
+const WORDS_PER_MINUTE: usize = 200;
+
+/// 提取 Markdown 中的纯文本，块级元素之间以空格分隔
+fn plain_text(markdown: &str) -> String {
+    let mut text = String::with_capacity(markdown.len());
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item) => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// 从 Markdown 中提取纯文本摘要，超过 `max_chars` 个字符时截断并加省略号
+pub fn markdown_excerpt(markdown: &str, max_chars: usize) -> String {
+    let text = plain_text(markdown);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let mut excerpt: String = text.chars().take(max_chars).collect();
+    excerpt.push('…');
+    excerpt
+}
+
+/// 估算阅读时间（分钟，至少 1 分钟）：汉字按字计，其余按词计
+pub fn reading_minutes(markdown: &str) -> usize {
+    let text = plain_text(markdown);
+    let cjk = text.chars().filter(|c| is_cjk(*c)).count();
+    let words = text
+        .split(|c: char| c.is_whitespace() || is_cjk(c))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count();
+    let minutes = cjk as f64 / CJK_CHARS_PER_MINUTE as f64 + words as f64 / WORDS_PER_MINUTE as f64;
+    (minutes.ceil() as usize).max(1)
+}
+
+/// 是否为中日韩文字
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{ac00}'..='\u{d7af}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{20000}'..='\u{2fa1f}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_settle_staged() {
+        let dir = std::env::temp_dir().join(format!("rowan-content-{}", Uuid::new_v4()));
+        let content = ContentStore::new(&dir);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // 事务失败时删除全部临时文件
+        let staged = content.stage_notes(&[(a, "a"), (b, "b")]).await.unwrap();
+        let result: AppResult<()> = Err(AppError::NotFound);
+        assert!(content.settle_staged(result, staged).await.is_err());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        // 事务成功后改名失败仍然返回成功，临时文件留作恢复
+        let failing = content.clone().failing_commits();
+        let staged = failing.stage_notes(&[(a, "a")]).await.unwrap();
+        assert_eq!(
+            failing.settle_staged(Ok(1), staged.clone()).await.unwrap(),
+            1
+        );
+        assert!(staged[0].1.is_file());
+        assert_eq!(content.read_note(a).await.unwrap(), None);
+
+        content.settle_staged(Ok(()), staged).await.unwrap();
+        assert_eq!(content.read_note(a).await.unwrap().as_deref(), Some("a"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let html = render_markdown("# 标题\n\n~~删除~~ 和 <b>&</b>\n\n| a |\n|---|\n| 1 |\n");
+        assert!(html.contains("<h1>标题</h1>"));
+        assert!(html.contains("<del>删除</del>"));
+        assert!(html.contains("<table>"));
+    }
+
+    #[test]
+    fn test_render_markdown_with_images() {
+        let markdown = "![猫 `cat`](/media/a.jpg \"标题\") 和 ![外部](https://example.com/b.png)";
+        let html = render_markdown_with(markdown, |url, alt, title| {
+            (url == "/media/a.jpg").then(|| format!("<picture alt=\"{alt}\" title=\"{title}\">"))
+        });
+        assert!(html.contains("<picture alt=\"猫 cat\" title=\"标题\">"));
+        assert!(html.contains("<img src=\"https://example.com/b.png\" alt=\"外部\" />"));
+        assert_eq!(
+            image_urls(markdown),
+            ["/media/a.jpg", "https://example.com/b.png"]
+        );
+    }
+
+    #[test]
+    fn test_markdown_excerpt() {
+        assert_eq!(
+            markdown_excerpt("# 标题\n\n**加粗** 和 `code`\n\n- 一\n- 二", 100),
+            "标题 加粗 和 code 一 二"
+        );
+        assert_eq!(markdown_excerpt("一二三四五", 3), "一二三…");
+    }
+
+    #[test]
+    fn test_reading_minutes() {
+        assert_eq!(reading_minutes(""), 1);
+        assert_eq!(reading_minutes(&"字".repeat(800)), 2);
+        assert_eq!(reading_minutes(&"word ".repeat(300)), 2);
+        assert_eq!(
+            reading_minutes(&format!("{} {}", "字".repeat(400), "word ".repeat(200))),
+            2
+        );
+    }
+}
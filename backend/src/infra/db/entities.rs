@@ -2,9 +2,20 @@
 
 pub mod prelude;
 
+pub mod admins;
+pub mod asset_variants;
+pub mod assets;
 pub mod comments;
+pub mod essay_revisions;
 pub mod essays;
+pub mod friend_link_applications;
 pub mod friends_links;
 pub mod likes;
+pub mod note_daily_stats;
+pub mod note_referrer_stats;
 pub mod notes_metadata;
+pub mod recovery_codes;
+pub mod sessions;
+pub mod site_daily_stats;
+pub mod slug_redirects;
 pub mod visitor_profiles;
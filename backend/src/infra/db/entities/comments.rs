@@ -15,7 +15,6 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub content: String,
     pub parent_id: Option<i32>,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub created_at: DateTime<Utc>,
     pub is_approved: bool,
 }
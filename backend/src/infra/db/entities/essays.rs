@@ -12,16 +12,18 @@ pub struct Model {
     pub title: String,
     #[sea_orm(column_type = "Text")]
     pub content: String,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub created_at: DateTime<Utc>,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub updated_at: DateTime<Utc>,
+    pub status: String,
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::comments::Entity")]
     Comments,
+    #[sea_orm(has_many = "super::essay_revisions::Entity")]
+    EssayRevisions,
 }
 
 impl Related<super::comments::Entity> for Entity {
@@ -30,4 +32,10 @@ impl Related<super::comments::Entity> for Entity {
     }
 }
 
+impl Related<super::essay_revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::EssayRevisions.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -15,10 +15,12 @@ pub struct Model {
     pub description: Option<String>,
     pub logo_url: Option<String>,
     pub sort_order: i32,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub created_at: DateTime<Utc>,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub updated_at: DateTime<Utc>,
+    pub last_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub last_seen_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -16,9 +16,7 @@ pub struct Model {
     pub title: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub summary: Option<String>,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub published_at: DateTime<Utc>,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub updated_at: DateTime<Utc>,
     pub views: i32,
     pub likes_count: i32,
@@ -34,6 +32,12 @@ pub enum Relation {
     Comments,
     #[sea_orm(has_many = "super::likes::Entity")]
     Likes,
+    #[sea_orm(has_many = "super::note_daily_stats::Entity")]
+    NoteDailyStats,
+    #[sea_orm(has_many = "super::note_referrer_stats::Entity")]
+    NoteReferrerStats,
+    #[sea_orm(has_many = "super::slug_redirects::Entity")]
+    SlugRedirects,
 }
 
 impl Related<super::comments::Entity> for Entity {
@@ -48,4 +52,22 @@ impl Related<super::likes::Entity> for Entity {
     }
 }
 
+impl Related<super::note_daily_stats::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NoteDailyStats.def()
+    }
+}
+
+impl Related<super::note_referrer_stats::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NoteReferrerStats.def()
+    }
+}
+
+impl Related<super::slug_redirects::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SlugRedirects.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
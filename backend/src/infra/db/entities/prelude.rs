@@ -1,14 +1,36 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
+#[allow(unused_imports)]
+pub use super::admins::Entity as Admins;
+#[allow(unused_imports)]
+pub use super::asset_variants::Entity as AssetVariants;
+#[allow(unused_imports)]
+pub use super::assets::Entity as Assets;
 #[allow(unused_imports)]
 pub use super::comments::Entity as Comments;
 #[allow(unused_imports)]
+pub use super::essay_revisions::Entity as EssayRevisions;
+#[allow(unused_imports)]
 pub use super::essays::Entity as Essays;
 #[allow(unused_imports)]
+pub use super::friend_link_applications::Entity as FriendLinkApplications;
+#[allow(unused_imports)]
 pub use super::friends_links::Entity as FriendsLinks;
 #[allow(unused_imports)]
 pub use super::likes::Entity as Likes;
 #[allow(unused_imports)]
+pub use super::note_daily_stats::Entity as NoteDailyStats;
+#[allow(unused_imports)]
+pub use super::note_referrer_stats::Entity as NoteReferrerStats;
+#[allow(unused_imports)]
 pub use super::notes_metadata::Entity as NotesMetadata;
 #[allow(unused_imports)]
+pub use super::recovery_codes::Entity as RecoveryCodes;
+#[allow(unused_imports)]
+pub use super::sessions::Entity as Sessions;
+#[allow(unused_imports)]
+pub use super::site_daily_stats::Entity as SiteDailyStats;
+#[allow(unused_imports)]
+pub use super::slug_redirects::Entity as SlugRedirects;
+#[allow(unused_imports)]
 pub use super::visitor_profiles::Entity as VisitorProfiles;
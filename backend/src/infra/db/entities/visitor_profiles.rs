@@ -14,9 +14,7 @@ pub struct Model {
     #[sea_orm(unique)]
     pub name: String,
     pub ip: String,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub created_at: DateTime<Utc>,
-    #[sea_orm(column_type = "custom(\"DATETIME\")")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -0,0 +1,354 @@
+//! 数据库迁移
+//!
+//! 服务启动时可以自动执行未执行的迁移。多个实例同时启动时用迁移锁排队，只有拿到锁的实例执行迁移；
+//! 数据库中存在程序不认识的迁移（数据库比程序新）时拒绝启动，避免旧程序读写新结构的数据。
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{
+    ConnectionTrait, DatabaseBackend, DatabaseConnection,
+    sea_query::{Alias, ColumnDef, Expr, OnConflict, Query, Table},
+    sqlx::{self, Connection, PgConnection},
+};
+
+use crate::{
+    config::MigrationConfig,
+    error::{AppError, AppResult},
+};
+
+/// PostgreSQL advisory lock 的键（"RowanMig" 的 ASCII）
+const ADVISORY_LOCK_KEY: i64 = 0x526f_7761_6e4d_6967;
+/// 不支持 advisory lock 的数据库（SQLite）使用的锁表
+const LOCK_TABLE: &str = "seaql_migrations_lock";
+/// 锁表中的锁超过这个时间仍未释放，视为持有者已经崩溃
+const STALE_LOCK_SECS: i64 = 600;
+/// 等待迁移锁时的重试间隔
+const RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 单个迁移的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationEntry {
+    pub name: String,
+    pub applied: bool,
+}
+
+/// 数据库与当前程序的迁移对比
+#[derive(Debug, Clone, Default)]
+pub struct SchemaStatus {
+    /// 程序中的全部迁移，按执行顺序排列
+    pub migrations: Vec<MigrationEntry>,
+    /// 数据库中已执行、但程序中没有的迁移
+    pub unknown: Vec<String>,
+}
+
+impl SchemaStatus {
+    /// 已执行的迁移数
+    pub fn applied_count(&self) -> usize {
+        self.migrations.iter().filter(|entry| entry.applied).count()
+    }
+
+    /// 未执行的迁移数
+    pub fn pending_count(&self) -> usize {
+        self.migrations.len() - self.applied_count()
+    }
+
+    /// 数据库是否比程序新
+    pub fn is_ahead(&self) -> bool {
+        !self.unknown.is_empty()
+    }
+
+    fn ensure_not_ahead(&self) -> AppResult<()> {
+        if self.is_ahead() {
+            return Err(AppError::Internal(format!(
+                "数据库中有当前程序不认识的迁移（{}），数据库结构比程序新，请升级程序",
+                self.unknown.join(", ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 对比数据库中已执行的迁移和程序中的迁移
+pub async fn status(db: &DatabaseConnection) -> AppResult<SchemaStatus> {
+    let mut applied: HashSet<String> = Migrator::get_migration_models(db)
+        .await?
+        .into_iter()
+        .map(|model| model.version)
+        .collect();
+
+    let migrations = Migrator::migrations()
+        .iter()
+        .map(|migration| MigrationEntry {
+            name: migration.name().to_string(),
+            applied: applied.remove(migration.name()),
+        })
+        .collect();
+    let mut unknown: Vec<String> = applied.into_iter().collect();
+    unknown.sort();
+
+    Ok(SchemaStatus {
+        migrations,
+        unknown,
+    })
+}
+
+/// 在迁移锁保护下执行未执行的迁移（`steps` 为空时全部执行），返回执行的数量
+pub async fn up(
+    db: &DatabaseConnection,
+    steps: Option<u32>,
+    lock_timeout: Duration,
+) -> AppResult<usize> {
+    let lock = MigrationLock::acquire(db, lock_timeout).await?;
+    let result = async {
+        // 拿到锁后再检查：等待期间其他实例可能已经执行完
+        let status = status(db).await?;
+        status.ensure_not_ahead()?;
+        let count = match steps {
+            Some(steps) => status.pending_count().min(steps as usize),
+            None => status.pending_count(),
+        };
+        if count > 0 {
+            Migrator::up(db, Some(count as u32)).await?;
+        }
+        Ok(count)
+    }
+    .await;
+    lock.release().await;
+    result
+}
+
+/// 在迁移锁保护下回滚最近执行的 `steps` 个迁移，返回回滚的数量
+pub async fn down(db: &DatabaseConnection, steps: u32, lock_timeout: Duration) -> AppResult<usize> {
+    let lock = MigrationLock::acquire(db, lock_timeout).await?;
+    let result = async {
+        let status = status(db).await?;
+        status.ensure_not_ahead()?;
+        let count = status.applied_count().min(steps as usize);
+        if count > 0 {
+            Migrator::down(db, Some(count as u32)).await?;
+        }
+        Ok(count)
+    }
+    .await;
+    lock.release().await;
+    result
+}
+
+/// 删除所有表后重新执行全部迁移
+///
+/// 锁表也会被删除，所以不加锁，只用于开发环境或确认没有其他实例运行时。
+pub async fn fresh(db: &DatabaseConnection) -> AppResult<()> {
+    Migrator::fresh(db).await?;
+    Ok(())
+}
+
+/// 启动服务前检查数据库结构：按配置执行未执行的迁移，数据库比程序新时拒绝启动
+pub async fn prepare(db: &DatabaseConnection, config: &MigrationConfig) -> AppResult<()> {
+    if config.auto_migrate {
+        let count = up(db, None, config.lock_timeout()).await?;
+        if count > 0 {
+            log::info!("🗃️ 已执行 {count} 个数据库迁移");
+        }
+        return Ok(());
+    }
+
+    let status = status(db).await?;
+    status.ensure_not_ahead()?;
+    if status.pending_count() > 0 {
+        log::warn!(
+            "⚠️ 有 {} 个数据库迁移未执行，可运行 migrate up 执行",
+            status.pending_count()
+        );
+    }
+    Ok(())
+}
+
+/// 迁移锁，持有期间其他实例不会执行迁移
+enum MigrationLock<'a> {
+    /// PostgreSQL：在连接池之外单独建立一条连接，持有会话级 advisory lock。
+    /// 迁移使用连接池，连接池只有一个连接时也不会和锁互相等待；连接断开（包括进程崩溃）时锁自动释放
+    Advisory(PgConnection),
+    /// 其他数据库：锁表中的一行
+    Row {
+        db: &'a DatabaseConnection,
+        owner: String,
+    },
+}
+
+impl<'a> MigrationLock<'a> {
+    async fn acquire(db: &'a DatabaseConnection, timeout: Duration) -> AppResult<Self> {
+        let deadline = Instant::now() + timeout;
+        let backend = db.get_database_backend();
+
+        if backend == DatabaseBackend::Postgres {
+            let options = db.get_postgres_connection_pool().connect_options();
+            let mut conn = PgConnection::connect_with(&options)
+                .await
+                .map_err(|e| AppError::Internal(format!("建立迁移锁连接失败: {e}")))?;
+            loop {
+                let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+                    .bind(ADVISORY_LOCK_KEY)
+                    .fetch_one(&mut conn)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("获取迁移锁失败: {e}")))?;
+                if locked {
+                    return Ok(Self::Advisory(conn));
+                }
+                if let Err(err) = wait_for_lock(deadline).await {
+                    let _ = conn.close().await;
+                    return Err(err);
+                }
+            }
+        }
+
+        let table = Alias::new(LOCK_TABLE);
+        db.execute(
+            backend.build(
+                Table::create()
+                    .table(table.clone())
+                    .if_not_exists()
+                    .col(ColumnDef::new(Alias::new("id")).integer().primary_key())
+                    .col(ColumnDef::new(Alias::new("owner")).string().not_null())
+                    .col(
+                        ColumnDef::new(Alias::new("expires_at"))
+                            .big_integer()
+                            .not_null(),
+                    ),
+            ),
+        )
+        .await?;
+
+        let owner = uuid::Uuid::new_v4().to_string();
+        loop {
+            let now = Utc::now().timestamp();
+            db.execute(
+                backend.build(
+                    Query::delete()
+                        .from_table(table.clone())
+                        .and_where(Expr::col(Alias::new("expires_at")).lt(now)),
+                ),
+            )
+            .await?;
+            let inserted = db
+                .execute(
+                    backend.build(
+                        Query::insert()
+                            .into_table(table.clone())
+                            .columns([
+                                Alias::new("id"),
+                                Alias::new("owner"),
+                                Alias::new("expires_at"),
+                            ])
+                            .values_panic([
+                                1.into(),
+                                owner.clone().into(),
+                                (now + STALE_LOCK_SECS).into(),
+                            ])
+                            .on_conflict(
+                                OnConflict::column(Alias::new("id")).do_nothing().to_owned(),
+                            ),
+                    ),
+                )
+                .await?;
+            if inserted.rows_affected() == 1 {
+                return Ok(Self::Row { db, owner });
+            }
+            wait_for_lock(deadline).await?;
+        }
+    }
+
+    /// 释放锁；失败时只记录日志，锁会在过期后自动失效
+    async fn release(self) {
+        let result = match self {
+            // 关闭连接即结束会话，会话持有的 advisory lock 随之释放
+            Self::Advisory(conn) => conn.close().await.map_err(|e| e.to_string()),
+            Self::Row { db, owner } => db
+                .execute(
+                    db.get_database_backend().build(
+                        Query::delete()
+                            .from_table(Alias::new(LOCK_TABLE))
+                            .and_where(Expr::col(Alias::new("owner")).eq(owner)),
+                    ),
+                )
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        };
+        if let Err(err) = result {
+            log::warn!("⚠️ 释放迁移锁失败: {err}");
+        }
+    }
+}
+
+async fn wait_for_lock(deadline: Instant) -> AppResult<()> {
+    if Instant::now() >= deadline {
+        return Err(AppError::Internal(
+            "等待迁移锁超时，可能有其他实例正在执行迁移".to_string(),
+        ));
+    }
+    log::info!("⏳ 其他实例正在执行迁移，等待迁移锁......");
+    tokio::time::sleep(RETRY_INTERVAL).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::test_db;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[tokio::test]
+    async fn test_up_down_and_status() {
+        let db = test_db().await;
+        let total = Migrator::migrations().len();
+        assert_eq!(status(&db).await.unwrap().pending_count(), 0);
+
+        assert_eq!(down(&db, 2, TIMEOUT).await.unwrap(), 2);
+        let current = status(&db).await.unwrap();
+        assert_eq!(current.applied_count(), total - 2);
+        assert!(!current.migrations[total - 1].applied);
+
+        assert_eq!(up(&db, Some(1), TIMEOUT).await.unwrap(), 1);
+        assert_eq!(up(&db, None, TIMEOUT).await.unwrap(), 1);
+        assert_eq!(up(&db, None, TIMEOUT).await.unwrap(), 0);
+        assert_eq!(status(&db).await.unwrap().pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_newer_schema() {
+        let db = test_db().await;
+        db.execute_unprepared(
+            "INSERT INTO seaql_migrations (version, applied_at) \
+             VALUES ('m29990101_000000_from_the_future', 0)",
+        )
+        .await
+        .unwrap();
+
+        let current = status(&db).await.unwrap();
+        assert!(current.is_ahead());
+        assert_eq!(current.unknown, ["m29990101_000000_from_the_future"]);
+        assert!(up(&db, None, TIMEOUT).await.is_err());
+        let config = MigrationConfig {
+            auto_migrate: false,
+            lock_timeout_secs: 1,
+        };
+        assert!(prepare(&db, &config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lock_is_exclusive() {
+        let db = test_db().await;
+        let lock = MigrationLock::acquire(&db, TIMEOUT).await.unwrap();
+        assert!(MigrationLock::acquire(&db, Duration::ZERO).await.is_err());
+        lock.release().await;
+        MigrationLock::acquire(&db, TIMEOUT)
+            .await
+            .unwrap()
+            .release()
+            .await;
+    }
+}
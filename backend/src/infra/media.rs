@@ -0,0 +1,412 @@
+//! 上传文件的类型识别与元数据清理
+//!
+//! 文件类型只看文件头（magic bytes），不信任客户端声明的 `Content-Type` 和扩展名。
+//! 图片在保存前去掉 EXIF、XMP、IPTC 和文本注释等元数据，避免泄露拍摄地点、设备等信息；
+//! 只保留 JPEG 的方向标记，否则手机拍的照片会显示成横的。清理是在容器层面删除数据块，
+//! 不会重新编码图片，画质不受影响。
+//!
+//! 响应式图片的变体（缩小尺寸并转成 WebP / AVIF）也在这里生成，这部分是 CPU 密集的同步代码，
+//! 调用方需要放进 `spawn_blocking`。
+
+use std::{io::Cursor, str::FromStr};
+
+use bytes::Bytes;
+use image::{
+    DynamicImage, ExtendedColorType, ImageDecoder, ImageEncoder, ImageReader,
+    codecs::avif::AvifEncoder, imageops::FilterType, metadata::Orientation,
+};
+use img_parts::{
+    ImageEXIF,
+    jpeg::{Jpeg, JpegSegment},
+    png::Png,
+    webp::WebP,
+};
+
+use crate::error::{AppError, AppResult};
+
+/// 允许上传的文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Jpeg,
+    Png,
+    Gif,
+    WebP,
+    Pdf,
+    Zip,
+}
+
+impl MediaType {
+    /// 按文件头识别类型，不在白名单中的返回 `None`
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\xFF\xD8\xFF") {
+            Some(Self::Jpeg)
+        } else if data.starts_with(b"\x89PNG\r\n\x1A\n") {
+            Some(Self::Png)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+            Some(Self::WebP)
+        } else if data.starts_with(b"%PDF-") {
+            Some(Self::Pdf)
+        } else if data.starts_with(b"PK\x03\x04") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+
+    /// 按保存的 MIME 类型还原
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        [
+            Self::Jpeg,
+            Self::Png,
+            Self::Gif,
+            Self::WebP,
+            Self::Pdf,
+            Self::Zip,
+        ]
+        .into_iter()
+        .find(|kind| kind.mime_type() == mime_type)
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Gif => "image/gif",
+            Self::WebP => "image/webp",
+            Self::Pdf => "application/pdf",
+            Self::Zip => "application/zip",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Gif => "gif",
+            Self::WebP => "webp",
+            Self::Pdf => "pdf",
+            Self::Zip => "zip",
+        }
+    }
+
+    pub fn is_image(self) -> bool {
+        matches!(self, Self::Jpeg | Self::Png | Self::Gif | Self::WebP)
+    }
+
+    /// 是否生成响应式变体；GIF 缩放后会丢失动画，保持原样
+    pub fn has_variants(self) -> bool {
+        matches!(self, Self::Jpeg | Self::Png | Self::WebP)
+    }
+}
+
+/// 响应式图片变体的编码格式，按浏览器应优先选择的顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VariantFormat {
+    Avif,
+    WebP,
+}
+
+impl VariantFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Avif => "image/avif",
+            Self::WebP => "image/webp",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Avif => "avif",
+            Self::WebP => "webp",
+        }
+    }
+}
+
+impl FromStr for VariantFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "avif" => Ok(Self::Avif),
+            "webp" => Ok(Self::WebP),
+            other => Err(format!("不支持的图片变体格式: {other}")),
+        }
+    }
+}
+
+/// JPEG 中 XMP 数据段的前缀
+const XMP_PREFIX: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// JPEG APP0 标记（JFIF）
+const APP0: u8 = 0xE0;
+/// JPEG APP1 标记（EXIF、XMP）
+const APP1: u8 = 0xE1;
+/// JPEG APP13 标记（Photoshop / IPTC）
+const APP13: u8 = 0xED;
+/// JPEG 注释段
+const COM: u8 = 0xFE;
+/// PNG 中会携带元数据的块
+const PNG_METADATA_CHUNKS: [[u8; 4]; 5] = [*b"eXIf", *b"tEXt", *b"zTXt", *b"iTXt", *b"tIME"];
+/// WebP 中的 XMP 块
+const WEBP_XMP: [u8; 4] = *b"XMP ";
+
+/// 去掉图片中的元数据，非图片或 GIF 原样返回
+pub fn strip_metadata(kind: MediaType, data: Bytes) -> AppResult<Bytes> {
+    let invalid = |e: img_parts::Error| AppError::BadRequest(format!("图片文件已损坏: {e}"));
+    match kind {
+        MediaType::Jpeg => {
+            let mut jpeg = Jpeg::from_bytes(data).map_err(invalid)?;
+            let orientation = jpeg.exif().as_deref().and_then(exif_orientation);
+            jpeg.set_exif(None);
+            jpeg.segments_mut().retain(|segment| {
+                let contents = segment.contents();
+                !(segment.marker() == APP1 && contents.starts_with(XMP_PREFIX)
+                    || segment.marker() == APP13
+                    || segment.marker() == COM)
+            });
+            if let Some(orientation) = orientation.filter(|o| *o != 1) {
+                // EXIF 段放在 JFIF（APP0）之后，没有 JFIF 时紧跟文件头
+                let segments = jpeg.segments_mut();
+                let pos = usize::from(segments.first().is_some_and(|s| s.marker() == APP0));
+                segments.insert(pos, exif_segment(orientation_exif(orientation)));
+            }
+            Ok(jpeg.encoder().bytes())
+        }
+        MediaType::Png => {
+            let mut png = Png::from_bytes(data).map_err(invalid)?;
+            for kind in PNG_METADATA_CHUNKS {
+                png.remove_chunks_by_type(kind);
+            }
+            Ok(png.encoder().bytes())
+        }
+        MediaType::WebP => {
+            let mut webp = WebP::from_bytes(data).map_err(invalid)?;
+            webp.remove_chunks_by_id(WEBP_XMP);
+            // set_exif 会顺带更新 VP8X 头中的元数据标志位
+            webp.set_exif(None);
+            Ok(webp.encoder().bytes())
+        }
+        MediaType::Gif | MediaType::Pdf | MediaType::Zip => Ok(data),
+    }
+}
+
+/// AVIF 编码速度（1 最慢、压缩率最高，10 最快）
+const AVIF_SPEED: u8 = 6;
+
+fn image_error(err: image::ImageError) -> AppError {
+    AppError::BadRequest(format!("无法解析图片: {err}"))
+}
+
+/// 是否需要交换宽高（旋转 90° 或 270°）
+fn swaps_dimensions(orientation: Orientation) -> bool {
+    matches!(
+        orientation,
+        Orientation::Rotate90
+            | Orientation::Rotate270
+            | Orientation::Rotate90FlipH
+            | Orientation::Rotate270FlipH
+    )
+}
+
+/// 图片的显示尺寸（已按方向标记旋转），只读文件头，不解码像素
+pub fn image_dimensions(data: &[u8]) -> AppResult<(u32, u32)> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| AppError::BadRequest(format!("无法识别图片格式: {e}")))?
+        .into_decoder()
+        .map_err(image_error)?;
+    let (width, height) = decoder.dimensions();
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    Ok(if swaps_dimensions(orientation) {
+        (height, width)
+    } else {
+        (width, height)
+    })
+}
+
+/// 解码图片并按方向标记旋转；变体不带 EXIF，所以要把方向直接体现在像素上
+pub fn decode_image(data: &[u8]) -> AppResult<DynamicImage> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| AppError::BadRequest(format!("无法识别图片格式: {e}")))?
+        .into_decoder()
+        .map_err(image_error)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).map_err(image_error)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// 按宽度等比缩放（不放大）后编码成指定格式，返回编码结果和实际尺寸
+pub fn encode_variant(
+    image: &DynamicImage,
+    width: u32,
+    format: VariantFormat,
+    quality: u8,
+) -> AppResult<(Bytes, u32, u32)> {
+    let resized;
+    let image = if width < image.width() {
+        let height =
+            (u64::from(image.height()) * u64::from(width) / u64::from(image.width())).max(1) as u32;
+        resized = image.resize_exact(width, height, FilterType::Lanczos3);
+        &resized
+    } else {
+        image
+    };
+    let (width, height) = (image.width(), image.height());
+
+    let has_alpha = image.color().has_alpha();
+    let (pixels, color) = if has_alpha {
+        (image.to_rgba8().into_raw(), ExtendedColorType::Rgba8)
+    } else {
+        (image.to_rgb8().into_raw(), ExtendedColorType::Rgb8)
+    };
+
+    let encoded = match format {
+        VariantFormat::WebP => {
+            let encoder = if has_alpha {
+                webp::Encoder::from_rgba(&pixels, width, height)
+            } else {
+                webp::Encoder::from_rgb(&pixels, width, height)
+            };
+            encoder.encode(f32::from(quality)).to_vec()
+        }
+        VariantFormat::Avif => {
+            let mut out = Vec::new();
+            AvifEncoder::new_with_speed_quality(&mut out, AVIF_SPEED, quality)
+                .write_image(&pixels, width, height, color)
+                .map_err(|e| AppError::Internal(format!("AVIF 编码失败: {e}")))?;
+            out
+        }
+    };
+    Ok((Bytes::from(encoded), width, height))
+}
+
+/// 从 EXIF（TIFF 结构）的第一个 IFD 中读取方向标记（0x0112）
+fn exif_orientation(exif: &[u8]) -> Option<u16> {
+    let big_endian = match exif.get(..4)? {
+        b"MM\0*" => true,
+        b"II*\0" => false,
+        _ => return None,
+    };
+    let u16_at = |pos: usize| {
+        let bytes: [u8; 2] = exif.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |pos: usize| {
+        let bytes: [u8; 4] = exif.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let ifd = u32_at(4)? as usize;
+    let count = u16_at(ifd)? as usize;
+    (0..count)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(0x0112))
+        .and_then(|entry| u16_at(entry + 8))
+        .filter(|orientation| (1..=8).contains(orientation))
+}
+
+/// JPEG 的 EXIF 数据段
+fn exif_segment(exif: Bytes) -> JpegSegment {
+    let mut contents = b"Exif\0\0".to_vec();
+    contents.extend_from_slice(&exif);
+    JpegSegment::new_with_contents(APP1, Bytes::from(contents))
+}
+
+/// 只包含方向标记的最小 EXIF
+fn orientation_exif(orientation: u16) -> Bytes {
+    let mut exif = Vec::with_capacity(26);
+    exif.extend_from_slice(b"MM\0*");
+    exif.extend_from_slice(&8u32.to_be_bytes()); // IFD0 偏移
+    exif.extend_from_slice(&1u16.to_be_bytes()); // 条目数
+    exif.extend_from_slice(&0x0112u16.to_be_bytes()); // Orientation
+    exif.extend_from_slice(&3u16.to_be_bytes()); // SHORT
+    exif.extend_from_slice(&1u32.to_be_bytes()); // 数量
+    exif.extend_from_slice(&orientation.to_be_bytes());
+    exif.extend_from_slice(&[0, 0]); // 值不足 4 字节时补齐
+    exif.extend_from_slice(&0u32.to_be_bytes()); // 没有下一个 IFD
+    Bytes::from(exif)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use img_parts::jpeg::markers;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(
+            MediaType::sniff(b"\x89PNG\r\n\x1A\nrest"),
+            Some(MediaType::Png)
+        );
+        assert_eq!(
+            MediaType::sniff(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(MediaType::WebP)
+        );
+        assert_eq!(MediaType::sniff(b"%PDF-1.7"), Some(MediaType::Pdf));
+        assert_eq!(MediaType::sniff(b"<svg xmlns=\"...\">"), None);
+        assert_eq!(MediaType::sniff(b"MZ\x90\0"), None);
+    }
+
+    #[test]
+    fn test_strip_jpeg_keeps_orientation() {
+        // 带 GPS 等信息的 EXIF（方向为 6）、XMP 和注释
+        let mut exif = orientation_exif(6).to_vec();
+        exif.extend_from_slice(b"GPS 31.2304N 121.4737E");
+        let mut jpeg = Jpeg::from_bytes(Bytes::from_static(b"\xFF\xD8\xFF\xD9")).unwrap();
+        jpeg.segments_mut().push(exif_segment(Bytes::from(exif)));
+        let mut xmp = XMP_PREFIX.to_vec();
+        xmp.extend_from_slice(b"<x:xmpmeta>secret</x:xmpmeta>");
+        jpeg.segments_mut()
+            .push(JpegSegment::new_with_contents(APP1, Bytes::from(xmp)));
+        jpeg.segments_mut().push(JpegSegment::new_with_contents(
+            COM,
+            Bytes::from_static(b"camera serial"),
+        ));
+        // 真实文件中 EOI 跟在扫描段的图像数据之后
+        jpeg.segments_mut().push(JpegSegment::new_with_entropy(
+            markers::SOS,
+            Bytes::from_static(b"\x01\x01\x00\x00\x3F\x00"),
+            Bytes::from_static(b"\x12\x34\xFF\xD9"),
+        ));
+
+        let stripped = strip_metadata(MediaType::Jpeg, jpeg.encoder().bytes()).unwrap();
+        let text = String::from_utf8_lossy(&stripped);
+        assert!(!text.contains("GPS"));
+        assert!(!text.contains("secret"));
+        assert!(!text.contains("camera serial"));
+
+        let jpeg = Jpeg::from_bytes(stripped).unwrap();
+        assert_eq!(jpeg.exif().as_deref().and_then(exif_orientation), Some(6));
+    }
+
+    #[test]
+    fn test_encode_variant() {
+        let image = DynamicImage::new_rgb8(64, 48);
+        let (webp, width, height) = encode_variant(&image, 32, VariantFormat::WebP, 75).unwrap();
+        assert_eq!((width, height), (32, 24));
+        assert_eq!(MediaType::sniff(&webp), Some(MediaType::WebP));
+
+        // 不放大
+        let (avif, width, _) = encode_variant(&image, 100, VariantFormat::Avif, 75).unwrap();
+        assert_eq!(width, 64);
+        assert_eq!(&avif[4..12], b"ftypavif");
+    }
+
+    #[test]
+    fn test_exif_orientation_little_endian() {
+        let exif = b"II*\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0\x03\0\0\0\0\0\0\0";
+        assert_eq!(exif_orientation(exif), Some(3));
+        assert_eq!(exif_orientation(b"garbage"), None);
+    }
+}
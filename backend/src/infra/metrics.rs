@@ -0,0 +1,237 @@
+//! Prometheus 指标
+//!
+//! 所有指标注册在进程内唯一的 `Registry` 上，各处直接调用这里的函数记录：
+//! - HTTP 请求数和耗时，按 `AnnotatedRouter` 上注册的路由模板（如 `/api/notes/{slug}`）分组，
+//!   没有匹配到路由的请求统一记为 `unmatched`，避免任意路径撑爆标签
+//! - 数据库连接池的连接数，抓取时从连接池读取
+//! - 浏览量、点赞、评论的计数
+//! - 后台任务每次执行的耗时和结果
+
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection};
+
+/// 没有匹配到路由的请求使用的路由标签
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Prometheus 文本格式的 Content-Type
+pub const CONTENT_TYPE: &str = prometheus::TEXT_FORMAT;
+
+/// 后台任务耗时的分桶（秒），友链检查和备份可能持续几分钟
+const JOB_BUCKETS: &[f64] = &[
+    0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0,
+];
+
+struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    db_connections: IntGaugeVec,
+    db_max_connections: IntGaugeVec,
+    note_views: IntCounterVec,
+    likes: IntCounterVec,
+    comments: IntCounter,
+    job_duration: HistogramVec,
+    job_last_success: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("rowan".to_string()), None).expect("指标前缀无效");
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP 请求数"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_duration: HistogramVec::new(
+                HistogramOpts::new("http_request_duration_seconds", "HTTP 请求处理耗时"),
+                &["method", "route"],
+            )
+            .unwrap(),
+            db_connections: IntGaugeVec::new(
+                Opts::new("db_pool_connections", "连接池中的连接数"),
+                &["pool", "state"],
+            )
+            .unwrap(),
+            db_max_connections: IntGaugeVec::new(
+                Opts::new("db_pool_max_connections", "连接池的最大连接数"),
+                &["pool"],
+            )
+            .unwrap(),
+            note_views: IntCounterVec::new(
+                Opts::new(
+                    "note_views_total",
+                    "笔记访问数，result 为 counted / duplicate / bot",
+                ),
+                &["result"],
+            )
+            .unwrap(),
+            likes: IntCounterVec::new(Opts::new("likes_total", "点赞和取消点赞次数"), &["action"])
+                .unwrap(),
+            comments: IntCounter::new("comments_created_total", "新增评论数（含导入）").unwrap(),
+            job_duration: HistogramVec::new(
+                HistogramOpts::new("job_duration_seconds", "后台任务单次执行耗时")
+                    .buckets(JOB_BUCKETS.to_vec()),
+                &["job", "outcome"],
+            )
+            .unwrap(),
+            job_last_success: IntGaugeVec::new(
+                Opts::new(
+                    "job_last_success_timestamp_seconds",
+                    "后台任务最近一次成功完成的时间",
+                ),
+                &["job"],
+            )
+            .unwrap(),
+            registry,
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 9] = [
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_duration.clone()),
+            Box::new(metrics.db_connections.clone()),
+            Box::new(metrics.db_max_connections.clone()),
+            Box::new(metrics.note_views.clone()),
+            Box::new(metrics.likes.clone()),
+            Box::new(metrics.comments.clone()),
+            Box::new(metrics.job_duration.clone()),
+            Box::new(metrics.job_last_success.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).expect("指标重复注册");
+        }
+        metrics
+    }
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// 记录一次 HTTP 请求
+pub fn observe_request(method: &str, route: &str, status: u16, elapsed: Duration) {
+    METRICS
+        .http_requests
+        .with_label_values(&[method, route, &status.to_string()])
+        .inc();
+    METRICS
+        .http_duration
+        .with_label_values(&[method, route])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// 记录一次笔记访问：`counted` 计入了浏览量，`duplicate` 在去重窗口内重复访问，`bot` 是爬虫
+pub fn record_view(result: &'static str) {
+    METRICS.note_views.with_label_values(&[result]).inc();
+}
+
+/// 记录点赞（`delta` 为正）或取消点赞（`delta` 为负）
+pub fn record_like(delta: i32) {
+    let action = if delta > 0 { "like" } else { "unlike" };
+    METRICS
+        .likes
+        .with_label_values(&[action])
+        .inc_by(u64::from(delta.unsigned_abs()));
+}
+
+/// 记录新增的评论
+pub fn record_comments(count: usize) {
+    METRICS.comments.inc_by(count as u64);
+}
+
+/// 执行一次后台任务并记录耗时，返回 `Err` 时结果记为 `error`
+pub async fn time_job<T, E>(
+    job: &'static str,
+    task: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let result = task.await;
+    let outcome = if result.is_ok() { "success" } else { "error" };
+    METRICS
+        .job_duration
+        .with_label_values(&[job, outcome])
+        .observe(started.elapsed().as_secs_f64());
+    if result.is_ok() {
+        METRICS
+            .job_last_success
+            .with_label_values(&[job])
+            .set(Utc::now().timestamp());
+    }
+    result
+}
+
+/// 连接池当前的（总连接数，空闲连接数，最大连接数）
+fn pool_stats(db: &DatabaseConnection) -> Option<(u32, usize, u32)> {
+    match db.get_database_backend() {
+        DatabaseBackend::Sqlite => {
+            let pool = db.get_sqlite_connection_pool();
+            Some((
+                pool.size(),
+                pool.num_idle(),
+                pool.options().get_max_connections(),
+            ))
+        }
+        DatabaseBackend::Postgres => {
+            let pool = db.get_postgres_connection_pool();
+            Some((
+                pool.size(),
+                pool.num_idle(),
+                pool.options().get_max_connections(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// 读写是否共用同一个连接池（PostgreSQL、内存 SQLite 或关闭了 `separate_writer`）
+fn same_pool(reader: &DatabaseConnection, writer: &DatabaseConnection) -> bool {
+    match (reader.get_database_backend(), writer.get_database_backend()) {
+        (DatabaseBackend::Sqlite, DatabaseBackend::Sqlite) => std::ptr::eq(
+            reader.get_sqlite_connection_pool().options(),
+            writer.get_sqlite_connection_pool().options(),
+        ),
+        (DatabaseBackend::Postgres, DatabaseBackend::Postgres) => std::ptr::eq(
+            reader.get_postgres_connection_pool().options(),
+            writer.get_postgres_connection_pool().options(),
+        ),
+        _ => false,
+    }
+}
+
+/// 刷新连接池指标后按 Prometheus 文本格式输出全部指标；读写共用连接池时只输出 `reader`
+pub fn render(reader: &DatabaseConnection, writer: &DatabaseConnection) -> String {
+    let mut pools = vec![("reader", reader)];
+    if !same_pool(reader, writer) {
+        pools.push(("writer", writer));
+    }
+    for (name, db) in pools {
+        let Some((size, idle, max)) = pool_stats(db) else {
+            continue;
+        };
+        let idle = idle as i64;
+        METRICS
+            .db_connections
+            .with_label_values(&[name, "idle"])
+            .set(idle);
+        METRICS
+            .db_connections
+            .with_label_values(&[name, "in_use"])
+            .set((i64::from(size) - idle).max(0));
+        METRICS
+            .db_max_connections
+            .with_label_values(&[name])
+            .set(i64::from(max));
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&METRICS.registry.gather(), &mut buffer)
+        .expect("指标编码失败");
+    String::from_utf8(buffer).expect("指标不是 UTF-8")
+}
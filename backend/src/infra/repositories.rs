@@ -0,0 +1,46 @@
+//! 仓储层
+//!
+//! 每个聚合一个仓储 trait（笔记、随笔、评论、点赞、访客、友链），服务只通过 trait 读写数据，
+//! 不直接拼 sea-orm 查询：
+//!
+//! - `SeaOrmRepository` 是基于 sea-orm 的实现，同一个类型可以包装连接池或事务，
+//!   在事务中构造时所有操作都属于该事务
+//! - 测试中的 `memory::InMemoryRepository` 把数据放在内存里，服务逻辑的单元测试不需要数据库
+//!
+//! 写入方法接收完整的实体 `Model`，插入时忽略其中的 `id`，由存储分配后返回。
+
+use sea_orm::ConnectionTrait;
+
+mod comment;
+mod essay;
+mod friend_link;
+mod like;
+#[cfg(test)]
+pub mod memory;
+mod note;
+mod visitor;
+
+pub use comment::CommentRepository;
+pub use essay::EssayRepository;
+pub use friend_link::FriendLinkRepository;
+pub use like::LikeRepository;
+pub use note::NoteRepository;
+pub use visitor::VisitorRepository;
+
+/// 分页参数，页码从 1 开始，由调用方保证在合理范围内
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub number: u64,
+    pub size: u64,
+}
+
+/// 基于 sea-orm 的仓储实现，`C` 为连接池 `DatabaseConnection` 或事务 `DatabaseTransaction`
+pub struct SeaOrmRepository<'a, C> {
+    conn: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> SeaOrmRepository<'a, C> {
+    pub fn new(conn: &'a C) -> Self {
+        Self { conn }
+    }
+}
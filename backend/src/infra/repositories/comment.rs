@@ -0,0 +1,95 @@
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, NotSet,
+    QueryFilter, QueryOrder,
+};
+
+use super::SeaOrmRepository;
+use crate::{
+    error::AppResult,
+    infra::db::entities::comments::{self, Entity as Comment},
+};
+
+/// 评论，属于一篇笔记或一篇随笔，`parent_id` 指向回复的评论
+#[async_trait::async_trait]
+pub trait CommentRepository: Send + Sync {
+    async fn find_comment(&self, id: i32) -> AppResult<Option<comments::Model>>;
+    /// 笔记下的全部评论，按时间正序
+    async fn list_note_comments(&self, note_id: i32) -> AppResult<Vec<comments::Model>>;
+    /// 随笔下的全部评论，按时间正序
+    async fn list_essay_comments(&self, essay_id: i32) -> AppResult<Vec<comments::Model>>;
+    async fn insert_comment(&self, comment: comments::Model) -> AppResult<comments::Model>;
+    async fn update_comment(&self, comment: comments::Model) -> AppResult<comments::Model>;
+    /// 删除评论，其回复的 `parent_id` 置空，返回是否存在
+    async fn delete_comment(&self, id: i32) -> AppResult<bool>;
+    /// 删除笔记下的全部评论，返回删除的数量
+    async fn delete_note_comments(&self, note_id: i32) -> AppResult<u64>;
+}
+
+#[async_trait::async_trait]
+impl<C: ConnectionTrait + Sync> CommentRepository for SeaOrmRepository<'_, C> {
+    async fn find_comment(&self, id: i32) -> AppResult<Option<comments::Model>> {
+        Ok(Comment::find_by_id(id).one(self.conn).await?)
+    }
+
+    async fn list_note_comments(&self, note_id: i32) -> AppResult<Vec<comments::Model>> {
+        Ok(Comment::find()
+            .filter(comments::Column::NoteMetadataId.eq(note_id))
+            .order_by_asc(comments::Column::CreatedAt)
+            .order_by_asc(comments::Column::Id)
+            .all(self.conn)
+            .await?)
+    }
+
+    async fn list_essay_comments(&self, essay_id: i32) -> AppResult<Vec<comments::Model>> {
+        Ok(Comment::find()
+            .filter(comments::Column::EssayId.eq(essay_id))
+            .order_by_asc(comments::Column::CreatedAt)
+            .order_by_asc(comments::Column::Id)
+            .all(self.conn)
+            .await?)
+    }
+
+    async fn insert_comment(&self, comment: comments::Model) -> AppResult<comments::Model> {
+        let mut active = comment.into_active_model().reset_all();
+        active.id = NotSet;
+        Ok(active.insert(self.conn).await?)
+    }
+
+    async fn update_comment(&self, comment: comments::Model) -> AppResult<comments::Model> {
+        Ok(comment
+            .into_active_model()
+            .reset_all()
+            .update(self.conn)
+            .await?)
+    }
+
+    async fn delete_comment(&self, id: i32) -> AppResult<bool> {
+        Comment::update_many()
+            .col_expr(
+                comments::Column::ParentId,
+                sea_orm::sea_query::Expr::value(Option::<i32>::None),
+            )
+            .filter(comments::Column::ParentId.eq(id))
+            .exec(self.conn)
+            .await?;
+        let result = Comment::delete_by_id(id).exec(self.conn).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn delete_note_comments(&self, note_id: i32) -> AppResult<u64> {
+        // 先断开回复关系，避免删除顺序受自引用外键影响
+        Comment::update_many()
+            .col_expr(
+                comments::Column::ParentId,
+                sea_orm::sea_query::Expr::value(Option::<i32>::None),
+            )
+            .filter(comments::Column::NoteMetadataId.eq(note_id))
+            .exec(self.conn)
+            .await?;
+        let result = Comment::delete_many()
+            .filter(comments::Column::NoteMetadataId.eq(note_id))
+            .exec(self.conn)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
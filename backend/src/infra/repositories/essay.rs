@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, NotSet,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, sea_query::Expr,
+};
+
+use super::{Page, SeaOrmRepository};
+use crate::{
+    error::AppResult,
+    infra::db::entities::{
+        essay_revisions::{self, Entity as EssayRevision},
+        essays::{self, Entity as Essay},
+    },
+};
+
+/// 随笔及其历史版本
+#[async_trait::async_trait]
+pub trait EssayRepository: Send + Sync {
+    async fn find_essay(&self, id: i32) -> AppResult<Option<essays::Model>>;
+    /// 随笔列表，可按状态筛选，按发布时间、更新时间倒序，返回（当前页，总数）
+    async fn list_essays(
+        &self,
+        status: Option<&str>,
+        page: Page,
+    ) -> AppResult<(Vec<essays::Model>, u64)>;
+    async fn insert_essay(&self, essay: essays::Model) -> AppResult<essays::Model>;
+    async fn update_essay(&self, essay: essays::Model) -> AppResult<essays::Model>;
+    /// 删除随笔，历史版本和评论随外键级联删除，返回是否存在
+    async fn delete_essay(&self, id: i32) -> AppResult<bool>;
+    /// 把状态为 `from` 且 `publish_at` 不晚于 `now` 的随笔改为 `to`，返回修改的数量
+    async fn transition_due_essays(
+        &self,
+        from: &str,
+        to: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<u64>;
+
+    /// 历史版本，按版本号倒序
+    async fn list_revisions(&self, essay_id: i32) -> AppResult<Vec<essay_revisions::Model>>;
+    async fn find_revision(
+        &self,
+        essay_id: i32,
+        revision: i32,
+    ) -> AppResult<Option<essay_revisions::Model>>;
+    /// 最新的版本号，没有历史版本时为空
+    async fn latest_revision(&self, essay_id: i32) -> AppResult<Option<i32>>;
+    async fn insert_revision(
+        &self,
+        revision: essay_revisions::Model,
+    ) -> AppResult<essay_revisions::Model>;
+}
+
+#[async_trait::async_trait]
+impl<C: ConnectionTrait + Sync> EssayRepository for SeaOrmRepository<'_, C> {
+    async fn find_essay(&self, id: i32) -> AppResult<Option<essays::Model>> {
+        Ok(Essay::find_by_id(id).one(self.conn).await?)
+    }
+
+    async fn list_essays(
+        &self,
+        status: Option<&str>,
+        page: Page,
+    ) -> AppResult<(Vec<essays::Model>, u64)> {
+        let mut select = Essay::find();
+        if let Some(status) = status {
+            select = select.filter(essays::Column::Status.eq(status));
+        }
+        let paginator = select
+            .order_by_desc(essays::Column::PublishAt)
+            .order_by_desc(essays::Column::UpdatedAt)
+            .paginate(self.conn, page.size);
+        let total = paginator.num_items().await?;
+        let essays = paginator.fetch_page(page.number - 1).await?;
+        Ok((essays, total))
+    }
+
+    async fn insert_essay(&self, essay: essays::Model) -> AppResult<essays::Model> {
+        let mut active = essay.into_active_model().reset_all();
+        active.id = NotSet;
+        Ok(active.insert(self.conn).await?)
+    }
+
+    async fn update_essay(&self, essay: essays::Model) -> AppResult<essays::Model> {
+        Ok(essay
+            .into_active_model()
+            .reset_all()
+            .update(self.conn)
+            .await?)
+    }
+
+    async fn delete_essay(&self, id: i32) -> AppResult<bool> {
+        let result = Essay::delete_by_id(id).exec(self.conn).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn transition_due_essays(
+        &self,
+        from: &str,
+        to: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<u64> {
+        let result = Essay::update_many()
+            .col_expr(essays::Column::Status, Expr::value(to))
+            .filter(essays::Column::Status.eq(from))
+            .filter(essays::Column::PublishAt.lte(now))
+            .exec(self.conn)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn list_revisions(&self, essay_id: i32) -> AppResult<Vec<essay_revisions::Model>> {
+        Ok(EssayRevision::find()
+            .filter(essay_revisions::Column::EssayId.eq(essay_id))
+            .order_by_desc(essay_revisions::Column::Revision)
+            .all(self.conn)
+            .await?)
+    }
+
+    async fn find_revision(
+        &self,
+        essay_id: i32,
+        revision: i32,
+    ) -> AppResult<Option<essay_revisions::Model>> {
+        Ok(EssayRevision::find()
+            .filter(essay_revisions::Column::EssayId.eq(essay_id))
+            .filter(essay_revisions::Column::Revision.eq(revision))
+            .one(self.conn)
+            .await?)
+    }
+
+    async fn latest_revision(&self, essay_id: i32) -> AppResult<Option<i32>> {
+        Ok(EssayRevision::find()
+            .select_only()
+            .column_as(essay_revisions::Column::Revision.max(), "latest")
+            .filter(essay_revisions::Column::EssayId.eq(essay_id))
+            .into_tuple()
+            .one(self.conn)
+            .await?
+            .flatten())
+    }
+
+    async fn insert_revision(
+        &self,
+        revision: essay_revisions::Model,
+    ) -> AppResult<essay_revisions::Model> {
+        let mut active = revision.into_active_model().reset_all();
+        active.id = NotSet;
+        Ok(active.insert(self.conn).await?)
+    }
+}
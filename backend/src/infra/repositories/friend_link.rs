@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, NotSet,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, sea_query::Expr,
+};
+
+use super::SeaOrmRepository;
+use crate::{
+    error::AppResult,
+    infra::db::entities::{
+        friend_link_applications::{self, Entity as FriendLinkApplication},
+        friends_links::{self, Entity as FriendsLink},
+    },
+};
+
+/// 友链及访客提交的友链申请
+#[async_trait::async_trait]
+pub trait FriendLinkRepository: Send + Sync {
+    /// 全部友链，按 `sort_order`、`id` 升序
+    async fn list_links(&self) -> AppResult<Vec<friends_links::Model>>;
+    async fn find_link(&self, id: i32) -> AppResult<Option<friends_links::Model>>;
+    /// 链接是否已经在友链中，`except` 为正在修改的友链
+    async fn is_link_url_taken(&self, url: &str, except: Option<i32>) -> AppResult<bool>;
+    /// 最大的 `sort_order`，没有友链时为空
+    async fn max_link_sort_order(&self) -> AppResult<Option<i32>>;
+    async fn insert_link(&self, link: friends_links::Model) -> AppResult<friends_links::Model>;
+    async fn update_link(&self, link: friends_links::Model) -> AppResult<friends_links::Model>;
+    /// 删除友链，返回是否存在
+    async fn delete_link(&self, id: i32) -> AppResult<bool>;
+    async fn set_link_order(&self, id: i32, sort_order: i32, now: DateTime<Utc>) -> AppResult<()>;
+    /// 记录一次健康检查的结果，`alive` 时同时更新 `last_seen_at`
+    async fn record_link_check(
+        &self,
+        id: i32,
+        status: Option<i32>,
+        error: Option<String>,
+        checked_at: DateTime<Utc>,
+        alive: bool,
+    ) -> AppResult<()>;
+
+    /// 友链申请，可按状态筛选，按提交时间倒序
+    async fn list_applications(
+        &self,
+        status: Option<&str>,
+    ) -> AppResult<Vec<friend_link_applications::Model>>;
+    async fn find_application(&self, id: i32)
+    -> AppResult<Option<friend_link_applications::Model>>;
+    /// 链接为 `url` 或来自 `ip` 的待审核申请
+    async fn pending_applications(
+        &self,
+        url: &str,
+        ip: &str,
+    ) -> AppResult<Vec<friend_link_applications::Model>>;
+    async fn insert_application(
+        &self,
+        application: friend_link_applications::Model,
+    ) -> AppResult<friend_link_applications::Model>;
+    async fn update_application(
+        &self,
+        application: friend_link_applications::Model,
+    ) -> AppResult<friend_link_applications::Model>;
+}
+
+/// 待审核申请的状态值，与 `friend_link_service::ApplicationStatus::Pending` 一致
+pub(super) const PENDING: &str = "pending";
+
+#[async_trait::async_trait]
+impl<C: ConnectionTrait + Sync> FriendLinkRepository for SeaOrmRepository<'_, C> {
+    async fn list_links(&self) -> AppResult<Vec<friends_links::Model>> {
+        Ok(FriendsLink::find()
+            .order_by_asc(friends_links::Column::SortOrder)
+            .order_by_asc(friends_links::Column::Id)
+            .all(self.conn)
+            .await?)
+    }
+
+    async fn find_link(&self, id: i32) -> AppResult<Option<friends_links::Model>> {
+        Ok(FriendsLink::find_by_id(id).one(self.conn).await?)
+    }
+
+    async fn is_link_url_taken(&self, url: &str, except: Option<i32>) -> AppResult<bool> {
+        let mut query = FriendsLink::find().filter(friends_links::Column::Url.eq(url));
+        if let Some(id) = except {
+            query = query.filter(friends_links::Column::Id.ne(id));
+        }
+        Ok(query.count(self.conn).await? > 0)
+    }
+
+    async fn max_link_sort_order(&self) -> AppResult<Option<i32>> {
+        Ok(FriendsLink::find()
+            .select_only()
+            .column_as(friends_links::Column::SortOrder.max(), "max_order")
+            .into_tuple()
+            .one(self.conn)
+            .await?
+            .flatten())
+    }
+
+    async fn insert_link(&self, link: friends_links::Model) -> AppResult<friends_links::Model> {
+        let mut active = link.into_active_model().reset_all();
+        active.id = NotSet;
+        Ok(active.insert(self.conn).await?)
+    }
+
+    async fn update_link(&self, link: friends_links::Model) -> AppResult<friends_links::Model> {
+        Ok(link
+            .into_active_model()
+            .reset_all()
+            .update(self.conn)
+            .await?)
+    }
+
+    async fn delete_link(&self, id: i32) -> AppResult<bool> {
+        let result = FriendsLink::delete_by_id(id).exec(self.conn).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn set_link_order(&self, id: i32, sort_order: i32, now: DateTime<Utc>) -> AppResult<()> {
+        FriendsLink::update_many()
+            .col_expr(friends_links::Column::SortOrder, Expr::value(sort_order))
+            .col_expr(friends_links::Column::UpdatedAt, Expr::value(now))
+            .filter(friends_links::Column::Id.eq(id))
+            .exec(self.conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn record_link_check(
+        &self,
+        id: i32,
+        status: Option<i32>,
+        error: Option<String>,
+        checked_at: DateTime<Utc>,
+        alive: bool,
+    ) -> AppResult<()> {
+        let mut update = FriendsLink::update_many()
+            .col_expr(friends_links::Column::LastStatus, Expr::value(status))
+            .col_expr(friends_links::Column::LastError, Expr::value(error))
+            .col_expr(
+                friends_links::Column::LastCheckedAt,
+                Expr::value(checked_at),
+            );
+        if alive {
+            update = update.col_expr(friends_links::Column::LastSeenAt, Expr::value(checked_at));
+        }
+        update
+            .filter(friends_links::Column::Id.eq(id))
+            .exec(self.conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_applications(
+        &self,
+        status: Option<&str>,
+    ) -> AppResult<Vec<friend_link_applications::Model>> {
+        let mut query = FriendLinkApplication::find();
+        if let Some(status) = status {
+            query = query.filter(friend_link_applications::Column::Status.eq(status));
+        }
+        Ok(query
+            .order_by_desc(friend_link_applications::Column::CreatedAt)
+            .all(self.conn)
+            .await?)
+    }
+
+    async fn find_application(
+        &self,
+        id: i32,
+    ) -> AppResult<Option<friend_link_applications::Model>> {
+        Ok(FriendLinkApplication::find_by_id(id).one(self.conn).await?)
+    }
+
+    async fn pending_applications(
+        &self,
+        url: &str,
+        ip: &str,
+    ) -> AppResult<Vec<friend_link_applications::Model>> {
+        Ok(FriendLinkApplication::find()
+            .filter(friend_link_applications::Column::Status.eq(PENDING))
+            .filter(
+                friend_link_applications::Column::Url
+                    .eq(url)
+                    .or(friend_link_applications::Column::Ip.eq(ip)),
+            )
+            .all(self.conn)
+            .await?)
+    }
+
+    async fn insert_application(
+        &self,
+        application: friend_link_applications::Model,
+    ) -> AppResult<friend_link_applications::Model> {
+        let mut active = application.into_active_model().reset_all();
+        active.id = NotSet;
+        Ok(active.insert(self.conn).await?)
+    }
+
+    async fn update_application(
+        &self,
+        application: friend_link_applications::Model,
+    ) -> AppResult<friend_link_applications::Model> {
+        Ok(application
+            .into_active_model()
+            .reset_all()
+            .update(self.conn)
+            .await?)
+    }
+}
@@ -0,0 +1,55 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set};
+
+use super::SeaOrmRepository;
+use crate::{
+    error::AppResult,
+    infra::db::entities::likes::{self, Entity as Like},
+};
+
+/// 点赞记录，同一 IP 对同一笔记只有一条
+#[async_trait::async_trait]
+pub trait LikeRepository: Send + Sync {
+    async fn find_like(&self, note_id: i32, ip: &str) -> AppResult<Option<likes::Model>>;
+    async fn insert_like(&self, note_id: i32, ip: &str) -> AppResult<likes::Model>;
+    /// 删除点赞记录，返回是否存在
+    async fn delete_like(&self, note_id: i32, ip: &str) -> AppResult<bool>;
+    /// 笔记的全部点赞记录
+    async fn list_likes(&self, note_id: i32) -> AppResult<Vec<likes::Model>>;
+}
+
+#[async_trait::async_trait]
+impl<C: ConnectionTrait + Sync> LikeRepository for SeaOrmRepository<'_, C> {
+    async fn find_like(&self, note_id: i32, ip: &str) -> AppResult<Option<likes::Model>> {
+        Ok(Like::find()
+            .filter(likes::Column::NoteMetadataId.eq(note_id))
+            .filter(likes::Column::IpAddress.eq(ip))
+            .one(self.conn)
+            .await?)
+    }
+
+    async fn insert_like(&self, note_id: i32, ip: &str) -> AppResult<likes::Model> {
+        Ok(likes::ActiveModel {
+            note_metadata_id: Set(note_id),
+            ip_address: Set(ip.to_string()),
+            ..Default::default()
+        }
+        .insert(self.conn)
+        .await?)
+    }
+
+    async fn delete_like(&self, note_id: i32, ip: &str) -> AppResult<bool> {
+        let result = Like::delete_many()
+            .filter(likes::Column::NoteMetadataId.eq(note_id))
+            .filter(likes::Column::IpAddress.eq(ip))
+            .exec(self.conn)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn list_likes(&self, note_id: i32) -> AppResult<Vec<likes::Model>> {
+        Ok(Like::find()
+            .filter(likes::Column::NoteMetadataId.eq(note_id))
+            .all(self.conn)
+            .await?)
+    }
+}
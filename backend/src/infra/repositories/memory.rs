@@ -0,0 +1,667 @@
+//! 内存中的仓储实现，只用于测试
+//!
+//! 行为尽量与数据库一致：插入时分配自增 id，唯一约束冲突和更新不存在的行返回数据库错误，
+//! 列表的排序与 sea-orm 实现相同。
+
+use std::{cmp::Reverse, sync::Mutex};
+
+use chrono::{DateTime, Utc};
+use sea_orm::DbErr;
+
+use super::{
+    CommentRepository, EssayRepository, FriendLinkRepository, LikeRepository, NoteRepository, Page,
+    VisitorRepository, friend_link::PENDING, note::has_tag,
+};
+use crate::{
+    error::AppResult,
+    infra::db::entities::{
+        comments, essay_revisions, essays, friend_link_applications, friends_links, likes,
+        notes_metadata, slug_redirects, visitor_profiles,
+    },
+};
+
+#[derive(Default)]
+struct State {
+    next_id: i32,
+    notes: Vec<notes_metadata::Model>,
+    slug_redirects: Vec<slug_redirects::Model>,
+    likes: Vec<likes::Model>,
+    essays: Vec<essays::Model>,
+    revisions: Vec<essay_revisions::Model>,
+    comments: Vec<comments::Model>,
+    visitors: Vec<visitor_profiles::Model>,
+    links: Vec<friends_links::Model>,
+    applications: Vec<friend_link_applications::Model>,
+}
+
+impl State {
+    fn next_id(&mut self) -> i32 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+/// 所有表共用一个 id 序列，方便在断言里区分不同的行
+#[derive(Default)]
+pub struct InMemoryRepository {
+    state: Mutex<State>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut State) -> T) -> T {
+        f(&mut self.state.lock().unwrap())
+    }
+}
+
+fn unique_violation(column: &str) -> DbErr {
+    DbErr::Custom(format!("UNIQUE constraint failed: {column}"))
+}
+
+fn not_updated() -> DbErr {
+    DbErr::RecordNotUpdated
+}
+
+fn paginate<T: Clone>(rows: Vec<T>, page: Page) -> (Vec<T>, u64) {
+    let total = rows.len() as u64;
+    let rows = rows
+        .into_iter()
+        .skip(((page.number - 1) * page.size) as usize)
+        .take(page.size as usize)
+        .collect();
+    (rows, total)
+}
+
+/// 用 `model` 替换 `rows` 中 id 相同的行
+fn replace<T: Clone>(rows: &mut [T], model: &T, id: impl Fn(&T) -> i32) -> AppResult<T> {
+    let row = rows
+        .iter_mut()
+        .find(|row| id(row) == id(model))
+        .ok_or_else(not_updated)?;
+    *row = model.clone();
+    Ok(model.clone())
+}
+
+#[async_trait::async_trait]
+impl NoteRepository for InMemoryRepository {
+    async fn find_note(&self, id: i32) -> AppResult<Option<notes_metadata::Model>> {
+        Ok(self.with(|s| s.notes.iter().find(|n| n.id == id).cloned()))
+    }
+
+    async fn find_note_by_slug(&self, slug: &str) -> AppResult<Option<notes_metadata::Model>> {
+        Ok(self.with(|s| s.notes.iter().find(|n| n.slug == slug).cloned()))
+    }
+
+    async fn list_notes(&self, page: Page) -> AppResult<(Vec<notes_metadata::Model>, u64)> {
+        let mut notes = self.with(|s| s.notes.clone());
+        notes.sort_by_key(|row| Reverse(row.published_at));
+        Ok(paginate(notes, page))
+    }
+
+    async fn list_published_notes_by_tag(
+        &self,
+        tag: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<notes_metadata::Model>> {
+        let mut notes: Vec<_> = self.with(|s| {
+            s.notes
+                .iter()
+                .filter(|n| n.published_at <= now && has_tag(n, tag))
+                .cloned()
+                .collect()
+        });
+        notes.sort_by_key(|row| Reverse(row.published_at));
+        Ok(notes)
+    }
+
+    async fn list_published_notes_by_category(
+        &self,
+        category: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<notes_metadata::Model>> {
+        let mut notes: Vec<_> = self.with(|s| {
+            s.notes
+                .iter()
+                .filter(|n| n.published_at <= now && n.category.as_deref() == Some(category))
+                .cloned()
+                .collect()
+        });
+        notes.sort_by_key(|row| Reverse(row.published_at));
+        Ok(notes)
+    }
+
+    async fn insert_note(&self, note: notes_metadata::Model) -> AppResult<notes_metadata::Model> {
+        self.with(|s| {
+            if s.notes.iter().any(|n| n.slug == note.slug) {
+                return Err(unique_violation("notes_metadata.slug").into());
+            }
+            if s.notes.iter().any(|n| n.file_id == note.file_id) {
+                return Err(unique_violation("notes_metadata.file_id").into());
+            }
+            let note = notes_metadata::Model {
+                id: s.next_id(),
+                ..note
+            };
+            s.notes.push(note.clone());
+            Ok(note)
+        })
+    }
+
+    async fn update_note(&self, note: notes_metadata::Model) -> AppResult<notes_metadata::Model> {
+        self.with(|s| {
+            if s.notes
+                .iter()
+                .any(|n| n.id != note.id && n.slug == note.slug)
+            {
+                return Err(unique_violation("notes_metadata.slug").into());
+            }
+            replace(&mut s.notes, &note, |n| n.id)
+        })
+    }
+
+    async fn delete_note(&self, id: i32) -> AppResult<bool> {
+        Ok(self.with(|s| {
+            s.likes.retain(|l| l.note_metadata_id != id);
+            s.comments.retain(|c| c.note_metadata_id != Some(id));
+            s.slug_redirects.retain(|r| r.note_id != id);
+            let before = s.notes.len();
+            s.notes.retain(|n| n.id != id);
+            s.notes.len() < before
+        }))
+    }
+
+    async fn add_note_likes(&self, id: i32, delta: i32) -> AppResult<()> {
+        self.with(|s| {
+            if let Some(note) = s.notes.iter_mut().find(|n| n.id == id)
+                && note.likes_count + delta >= 0
+            {
+                note.likes_count += delta;
+            }
+        });
+        Ok(())
+    }
+
+    async fn is_slug_taken(&self, slug: &str, except: Option<i32>) -> AppResult<bool> {
+        Ok(self.with(|s| {
+            s.notes
+                .iter()
+                .any(|n| n.slug == slug && Some(n.id) != except)
+                || s.slug_redirects
+                    .iter()
+                    .any(|r| r.old_slug == slug && Some(r.note_id) != except)
+        }))
+    }
+
+    async fn record_slug_change(
+        &self,
+        note_id: i32,
+        old_slug: &str,
+        new_slug: &str,
+    ) -> AppResult<()> {
+        self.with(|s| {
+            s.slug_redirects
+                .retain(|r| !(r.old_slug == new_slug && r.note_id == note_id));
+            if s.slug_redirects.iter().any(|r| r.old_slug == old_slug) {
+                return Err(unique_violation("slug_redirects.old_slug").into());
+            }
+            let redirect = slug_redirects::Model {
+                id: s.next_id(),
+                old_slug: old_slug.to_string(),
+                note_id,
+                created_at: Utc::now(),
+            };
+            s.slug_redirects.push(redirect);
+            Ok(())
+        })
+    }
+
+    async fn find_note_by_old_slug(
+        &self,
+        old_slug: &str,
+    ) -> AppResult<Option<notes_metadata::Model>> {
+        Ok(self.with(|s| {
+            let redirect = s.slug_redirects.iter().find(|r| r.old_slug == old_slug)?;
+            s.notes.iter().find(|n| n.id == redirect.note_id).cloned()
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl LikeRepository for InMemoryRepository {
+    async fn find_like(&self, note_id: i32, ip: &str) -> AppResult<Option<likes::Model>> {
+        Ok(self.with(|s| {
+            s.likes
+                .iter()
+                .find(|l| l.note_metadata_id == note_id && l.ip_address == ip)
+                .cloned()
+        }))
+    }
+
+    async fn insert_like(&self, note_id: i32, ip: &str) -> AppResult<likes::Model> {
+        self.with(|s| {
+            if s.likes
+                .iter()
+                .any(|l| l.note_metadata_id == note_id && l.ip_address == ip)
+            {
+                return Err(unique_violation("likes.note_metadata_id, likes.ip_address").into());
+            }
+            let like = likes::Model {
+                id: s.next_id(),
+                note_metadata_id: note_id,
+                ip_address: ip.to_string(),
+            };
+            s.likes.push(like.clone());
+            Ok(like)
+        })
+    }
+
+    async fn delete_like(&self, note_id: i32, ip: &str) -> AppResult<bool> {
+        Ok(self.with(|s| {
+            let before = s.likes.len();
+            s.likes
+                .retain(|l| !(l.note_metadata_id == note_id && l.ip_address == ip));
+            s.likes.len() < before
+        }))
+    }
+
+    async fn list_likes(&self, note_id: i32) -> AppResult<Vec<likes::Model>> {
+        Ok(self.with(|s| {
+            s.likes
+                .iter()
+                .filter(|l| l.note_metadata_id == note_id)
+                .cloned()
+                .collect()
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl EssayRepository for InMemoryRepository {
+    async fn find_essay(&self, id: i32) -> AppResult<Option<essays::Model>> {
+        Ok(self.with(|s| s.essays.iter().find(|e| e.id == id).cloned()))
+    }
+
+    async fn list_essays(
+        &self,
+        status: Option<&str>,
+        page: Page,
+    ) -> AppResult<(Vec<essays::Model>, u64)> {
+        let mut essays: Vec<_> = self.with(|s| {
+            s.essays
+                .iter()
+                .filter(|e| status.is_none_or(|status| e.status == status))
+                .cloned()
+                .collect()
+        });
+        essays.sort_by_key(|row| Reverse((row.publish_at, row.updated_at)));
+        Ok(paginate(essays, page))
+    }
+
+    async fn insert_essay(&self, essay: essays::Model) -> AppResult<essays::Model> {
+        Ok(self.with(|s| {
+            let essay = essays::Model {
+                id: s.next_id(),
+                ..essay
+            };
+            s.essays.push(essay.clone());
+            essay
+        }))
+    }
+
+    async fn update_essay(&self, essay: essays::Model) -> AppResult<essays::Model> {
+        self.with(|s| replace(&mut s.essays, &essay, |e| e.id))
+    }
+
+    async fn delete_essay(&self, id: i32) -> AppResult<bool> {
+        Ok(self.with(|s| {
+            s.revisions.retain(|r| r.essay_id != id);
+            s.comments.retain(|c| c.essay_id != Some(id));
+            let before = s.essays.len();
+            s.essays.retain(|e| e.id != id);
+            s.essays.len() < before
+        }))
+    }
+
+    async fn transition_due_essays(
+        &self,
+        from: &str,
+        to: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<u64> {
+        Ok(self.with(|s| {
+            let mut changed = 0;
+            for essay in s
+                .essays
+                .iter_mut()
+                .filter(|e| e.status == from && e.publish_at.is_some_and(|at| at <= now))
+            {
+                essay.status = to.to_string();
+                changed += 1;
+            }
+            changed
+        }))
+    }
+
+    async fn list_revisions(&self, essay_id: i32) -> AppResult<Vec<essay_revisions::Model>> {
+        let mut revisions: Vec<_> = self.with(|s| {
+            s.revisions
+                .iter()
+                .filter(|r| r.essay_id == essay_id)
+                .cloned()
+                .collect()
+        });
+        revisions.sort_by_key(|row| Reverse(row.revision));
+        Ok(revisions)
+    }
+
+    async fn find_revision(
+        &self,
+        essay_id: i32,
+        revision: i32,
+    ) -> AppResult<Option<essay_revisions::Model>> {
+        Ok(self.with(|s| {
+            s.revisions
+                .iter()
+                .find(|r| r.essay_id == essay_id && r.revision == revision)
+                .cloned()
+        }))
+    }
+
+    async fn latest_revision(&self, essay_id: i32) -> AppResult<Option<i32>> {
+        Ok(self.with(|s| {
+            s.revisions
+                .iter()
+                .filter(|r| r.essay_id == essay_id)
+                .map(|r| r.revision)
+                .max()
+        }))
+    }
+
+    async fn insert_revision(
+        &self,
+        revision: essay_revisions::Model,
+    ) -> AppResult<essay_revisions::Model> {
+        self.with(|s| {
+            if s.revisions
+                .iter()
+                .any(|r| r.essay_id == revision.essay_id && r.revision == revision.revision)
+            {
+                return Err(
+                    unique_violation("essay_revisions.essay_id, essay_revisions.revision").into(),
+                );
+            }
+            let revision = essay_revisions::Model {
+                id: s.next_id(),
+                ..revision
+            };
+            s.revisions.push(revision.clone());
+            Ok(revision)
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl CommentRepository for InMemoryRepository {
+    async fn find_comment(&self, id: i32) -> AppResult<Option<comments::Model>> {
+        Ok(self.with(|s| s.comments.iter().find(|c| c.id == id).cloned()))
+    }
+
+    async fn list_note_comments(&self, note_id: i32) -> AppResult<Vec<comments::Model>> {
+        let mut comments: Vec<_> = self.with(|s| {
+            s.comments
+                .iter()
+                .filter(|c| c.note_metadata_id == Some(note_id))
+                .cloned()
+                .collect()
+        });
+        comments.sort_by_key(|c| (c.created_at, c.id));
+        Ok(comments)
+    }
+
+    async fn list_essay_comments(&self, essay_id: i32) -> AppResult<Vec<comments::Model>> {
+        let mut comments: Vec<_> = self.with(|s| {
+            s.comments
+                .iter()
+                .filter(|c| c.essay_id == Some(essay_id))
+                .cloned()
+                .collect()
+        });
+        comments.sort_by_key(|c| (c.created_at, c.id));
+        Ok(comments)
+    }
+
+    async fn insert_comment(&self, comment: comments::Model) -> AppResult<comments::Model> {
+        Ok(self.with(|s| {
+            let comment = comments::Model {
+                id: s.next_id(),
+                ..comment
+            };
+            s.comments.push(comment.clone());
+            comment
+        }))
+    }
+
+    async fn update_comment(&self, comment: comments::Model) -> AppResult<comments::Model> {
+        self.with(|s| replace(&mut s.comments, &comment, |c| c.id))
+    }
+
+    async fn delete_comment(&self, id: i32) -> AppResult<bool> {
+        Ok(self.with(|s| {
+            for reply in s.comments.iter_mut().filter(|c| c.parent_id == Some(id)) {
+                reply.parent_id = None;
+            }
+            let before = s.comments.len();
+            s.comments.retain(|c| c.id != id);
+            s.comments.len() < before
+        }))
+    }
+
+    async fn delete_note_comments(&self, note_id: i32) -> AppResult<u64> {
+        Ok(self.with(|s| {
+            let before = s.comments.len();
+            s.comments.retain(|c| c.note_metadata_id != Some(note_id));
+            (before - s.comments.len()) as u64
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl VisitorRepository for InMemoryRepository {
+    async fn find_visitor(&self, id: i32) -> AppResult<Option<visitor_profiles::Model>> {
+        Ok(self.with(|s| s.visitors.iter().find(|v| v.id == id).cloned()))
+    }
+
+    async fn find_visitor_by_cookie(
+        &self,
+        cookie_id: &str,
+    ) -> AppResult<Option<visitor_profiles::Model>> {
+        Ok(self.with(|s| {
+            s.visitors
+                .iter()
+                .find(|v| v.cookie_id == cookie_id)
+                .cloned()
+        }))
+    }
+
+    async fn find_visitor_by_name(&self, name: &str) -> AppResult<Option<visitor_profiles::Model>> {
+        Ok(self.with(|s| s.visitors.iter().find(|v| v.name == name).cloned()))
+    }
+
+    async fn insert_visitor(
+        &self,
+        visitor: visitor_profiles::Model,
+    ) -> AppResult<visitor_profiles::Model> {
+        self.with(|s| {
+            if s.visitors.iter().any(|v| v.cookie_id == visitor.cookie_id) {
+                return Err(unique_violation("visitor_profiles.cookie_id").into());
+            }
+            if s.visitors.iter().any(|v| v.name == visitor.name) {
+                return Err(unique_violation("visitor_profiles.name").into());
+            }
+            let visitor = visitor_profiles::Model {
+                id: s.next_id(),
+                ..visitor
+            };
+            s.visitors.push(visitor.clone());
+            Ok(visitor)
+        })
+    }
+
+    async fn update_visitor(
+        &self,
+        visitor: visitor_profiles::Model,
+    ) -> AppResult<visitor_profiles::Model> {
+        self.with(|s| {
+            if s.visitors
+                .iter()
+                .any(|v| v.id != visitor.id && v.name == visitor.name)
+            {
+                return Err(unique_violation("visitor_profiles.name").into());
+            }
+            replace(&mut s.visitors, &visitor, |v| v.id)
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl FriendLinkRepository for InMemoryRepository {
+    async fn list_links(&self) -> AppResult<Vec<friends_links::Model>> {
+        let mut links = self.with(|s| s.links.clone());
+        links.sort_by_key(|l| (l.sort_order, l.id));
+        Ok(links)
+    }
+
+    async fn find_link(&self, id: i32) -> AppResult<Option<friends_links::Model>> {
+        Ok(self.with(|s| s.links.iter().find(|l| l.id == id).cloned()))
+    }
+
+    async fn is_link_url_taken(&self, url: &str, except: Option<i32>) -> AppResult<bool> {
+        Ok(self.with(|s| s.links.iter().any(|l| l.url == url && Some(l.id) != except)))
+    }
+
+    async fn max_link_sort_order(&self) -> AppResult<Option<i32>> {
+        Ok(self.with(|s| s.links.iter().map(|l| l.sort_order).max()))
+    }
+
+    async fn insert_link(&self, link: friends_links::Model) -> AppResult<friends_links::Model> {
+        self.with(|s| {
+            if s.links.iter().any(|l| l.url == link.url) {
+                return Err(unique_violation("friends_links.url").into());
+            }
+            let link = friends_links::Model {
+                id: s.next_id(),
+                ..link
+            };
+            s.links.push(link.clone());
+            Ok(link)
+        })
+    }
+
+    async fn update_link(&self, link: friends_links::Model) -> AppResult<friends_links::Model> {
+        self.with(|s| {
+            if s.links.iter().any(|l| l.id != link.id && l.url == link.url) {
+                return Err(unique_violation("friends_links.url").into());
+            }
+            replace(&mut s.links, &link, |l| l.id)
+        })
+    }
+
+    async fn delete_link(&self, id: i32) -> AppResult<bool> {
+        Ok(self.with(|s| {
+            let before = s.links.len();
+            s.links.retain(|l| l.id != id);
+            s.links.len() < before
+        }))
+    }
+
+    async fn set_link_order(&self, id: i32, sort_order: i32, now: DateTime<Utc>) -> AppResult<()> {
+        self.with(|s| {
+            if let Some(link) = s.links.iter_mut().find(|l| l.id == id) {
+                link.sort_order = sort_order;
+                link.updated_at = now;
+            }
+        });
+        Ok(())
+    }
+
+    async fn record_link_check(
+        &self,
+        id: i32,
+        status: Option<i32>,
+        error: Option<String>,
+        checked_at: DateTime<Utc>,
+        alive: bool,
+    ) -> AppResult<()> {
+        self.with(|s| {
+            if let Some(link) = s.links.iter_mut().find(|l| l.id == id) {
+                link.last_status = status;
+                link.last_error = error;
+                link.last_checked_at = Some(checked_at);
+                if alive {
+                    link.last_seen_at = Some(checked_at);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn list_applications(
+        &self,
+        status: Option<&str>,
+    ) -> AppResult<Vec<friend_link_applications::Model>> {
+        let mut applications: Vec<_> = self.with(|s| {
+            s.applications
+                .iter()
+                .filter(|a| status.is_none_or(|status| a.status == status))
+                .cloned()
+                .collect()
+        });
+        applications.sort_by_key(|row| Reverse(row.created_at));
+        Ok(applications)
+    }
+
+    async fn find_application(
+        &self,
+        id: i32,
+    ) -> AppResult<Option<friend_link_applications::Model>> {
+        Ok(self.with(|s| s.applications.iter().find(|a| a.id == id).cloned()))
+    }
+
+    async fn pending_applications(
+        &self,
+        url: &str,
+        ip: &str,
+    ) -> AppResult<Vec<friend_link_applications::Model>> {
+        Ok(self.with(|s| {
+            s.applications
+                .iter()
+                .filter(|a| a.status == PENDING && (a.url == url || a.ip == ip))
+                .cloned()
+                .collect()
+        }))
+    }
+
+    async fn insert_application(
+        &self,
+        application: friend_link_applications::Model,
+    ) -> AppResult<friend_link_applications::Model> {
+        Ok(self.with(|s| {
+            let application = friend_link_applications::Model {
+                id: s.next_id(),
+                ..application
+            };
+            s.applications.push(application.clone());
+            application
+        }))
+    }
+
+    async fn update_application(
+        &self,
+        application: friend_link_applications::Model,
+    ) -> AppResult<friend_link_applications::Model> {
+        self.with(|s| replace(&mut s.applications, &application, |a| a.id))
+    }
+}
@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, NotSet,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, sea_query::Expr,
+};
+
+use super::{Page, SeaOrmRepository};
+use crate::{
+    error::AppResult,
+    infra::db::entities::{
+        notes_metadata::{self, Entity as NotesMetadata},
+        slug_redirects::{self, Entity as SlugRedirect},
+    },
+};
+
+/// 笔记元数据及其 slug 跳转记录
+#[async_trait::async_trait]
+pub trait NoteRepository: Send + Sync {
+    async fn find_note(&self, id: i32) -> AppResult<Option<notes_metadata::Model>>;
+    async fn find_note_by_slug(&self, slug: &str) -> AppResult<Option<notes_metadata::Model>>;
+    /// 全部笔记（包括未到发布时间的），按发布时间倒序，返回（当前页，总数）
+    async fn list_notes(&self, page: Page) -> AppResult<(Vec<notes_metadata::Model>, u64)>;
+    /// `now` 之前发布、带有标签 `tag` 的笔记，按发布时间倒序
+    async fn list_published_notes_by_tag(
+        &self,
+        tag: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<notes_metadata::Model>>;
+    /// `now` 之前发布、属于分类 `category` 的笔记，按发布时间倒序
+    async fn list_published_notes_by_category(
+        &self,
+        category: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<notes_metadata::Model>>;
+    async fn insert_note(&self, note: notes_metadata::Model) -> AppResult<notes_metadata::Model>;
+    async fn update_note(&self, note: notes_metadata::Model) -> AppResult<notes_metadata::Model>;
+    /// 删除笔记，点赞、评论、统计和 slug 跳转记录随外键级联删除，返回是否存在
+    async fn delete_note(&self, id: i32) -> AppResult<bool>;
+    /// 点赞数加上 `delta`，不会减到负数
+    async fn add_note_likes(&self, id: i32, delta: i32) -> AppResult<()>;
+
+    /// slug 是否被其他笔记占用（当前 slug 或历史 slug），`except` 为正在修改的笔记
+    async fn is_slug_taken(&self, slug: &str, except: Option<i32>) -> AppResult<bool>;
+    /// 记录笔记的旧 slug；如果改回了以前用过的 slug，删除对应的跳转记录
+    async fn record_slug_change(
+        &self,
+        note_id: i32,
+        old_slug: &str,
+        new_slug: &str,
+    ) -> AppResult<()>;
+    /// 旧 slug 对应的笔记
+    async fn find_note_by_old_slug(
+        &self,
+        old_slug: &str,
+    ) -> AppResult<Option<notes_metadata::Model>>;
+}
+
+/// 标签是逗号分隔的文本，判断是否包含某个完整的标签
+pub(super) fn has_tag(note: &notes_metadata::Model, tag: &str) -> bool {
+    note.tags
+        .as_deref()
+        .is_some_and(|tags| tags.split(',').any(|t| t.trim() == tag))
+}
+
+#[async_trait::async_trait]
+impl<C: ConnectionTrait + Sync> NoteRepository for SeaOrmRepository<'_, C> {
+    async fn find_note(&self, id: i32) -> AppResult<Option<notes_metadata::Model>> {
+        Ok(NotesMetadata::find_by_id(id).one(self.conn).await?)
+    }
+
+    async fn find_note_by_slug(&self, slug: &str) -> AppResult<Option<notes_metadata::Model>> {
+        Ok(NotesMetadata::find()
+            .filter(notes_metadata::Column::Slug.eq(slug))
+            .one(self.conn)
+            .await?)
+    }
+
+    async fn list_notes(&self, page: Page) -> AppResult<(Vec<notes_metadata::Model>, u64)> {
+        let paginator = NotesMetadata::find()
+            .order_by_desc(notes_metadata::Column::PublishedAt)
+            .paginate(self.conn, page.size);
+        let total = paginator.num_items().await?;
+        let notes = paginator.fetch_page(page.number - 1).await?;
+        Ok((notes, total))
+    }
+
+    async fn list_published_notes_by_tag(
+        &self,
+        tag: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<notes_metadata::Model>> {
+        // 先用 LIKE 粗筛，再在内存中精确匹配
+        let notes = NotesMetadata::find()
+            .filter(notes_metadata::Column::PublishedAt.lte(now))
+            .filter(notes_metadata::Column::Tags.contains(tag))
+            .order_by_desc(notes_metadata::Column::PublishedAt)
+            .all(self.conn)
+            .await?;
+        Ok(notes
+            .into_iter()
+            .filter(|note| has_tag(note, tag))
+            .collect())
+    }
+
+    async fn list_published_notes_by_category(
+        &self,
+        category: &str,
+        now: DateTime<Utc>,
+    ) -> AppResult<Vec<notes_metadata::Model>> {
+        Ok(NotesMetadata::find()
+            .filter(notes_metadata::Column::PublishedAt.lte(now))
+            .filter(notes_metadata::Column::Category.eq(category))
+            .order_by_desc(notes_metadata::Column::PublishedAt)
+            .all(self.conn)
+            .await?)
+    }
+
+    async fn insert_note(&self, note: notes_metadata::Model) -> AppResult<notes_metadata::Model> {
+        let mut active = note.into_active_model().reset_all();
+        active.id = NotSet;
+        Ok(active.insert(self.conn).await?)
+    }
+
+    async fn update_note(&self, note: notes_metadata::Model) -> AppResult<notes_metadata::Model> {
+        Ok(note
+            .into_active_model()
+            .reset_all()
+            .update(self.conn)
+            .await?)
+    }
+
+    async fn delete_note(&self, id: i32) -> AppResult<bool> {
+        let result = NotesMetadata::delete_by_id(id).exec(self.conn).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn add_note_likes(&self, id: i32, delta: i32) -> AppResult<()> {
+        let mut update = NotesMetadata::update_many()
+            .col_expr(
+                notes_metadata::Column::LikesCount,
+                Expr::col(notes_metadata::Column::LikesCount).add(delta),
+            )
+            .filter(notes_metadata::Column::Id.eq(id));
+        if delta < 0 {
+            update = update.filter(notes_metadata::Column::LikesCount.gte(-delta));
+        }
+        update.exec(self.conn).await?;
+        Ok(())
+    }
+
+    async fn is_slug_taken(&self, slug: &str, except: Option<i32>) -> AppResult<bool> {
+        let mut current = NotesMetadata::find().filter(notes_metadata::Column::Slug.eq(slug));
+        let mut redirected = SlugRedirect::find().filter(slug_redirects::Column::OldSlug.eq(slug));
+        if let Some(note_id) = except {
+            current = current.filter(notes_metadata::Column::Id.ne(note_id));
+            redirected = redirected.filter(slug_redirects::Column::NoteId.ne(note_id));
+        }
+        Ok(current.count(self.conn).await? > 0 || redirected.count(self.conn).await? > 0)
+    }
+
+    async fn record_slug_change(
+        &self,
+        note_id: i32,
+        old_slug: &str,
+        new_slug: &str,
+    ) -> AppResult<()> {
+        SlugRedirect::delete_many()
+            .filter(slug_redirects::Column::OldSlug.eq(new_slug))
+            .filter(slug_redirects::Column::NoteId.eq(note_id))
+            .exec(self.conn)
+            .await?;
+
+        slug_redirects::ActiveModel {
+            old_slug: Set(old_slug.to_string()),
+            note_id: Set(note_id),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(self.conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_note_by_old_slug(
+        &self,
+        old_slug: &str,
+    ) -> AppResult<Option<notes_metadata::Model>> {
+        let Some(redirect) = SlugRedirect::find()
+            .filter(slug_redirects::Column::OldSlug.eq(old_slug))
+            .one(self.conn)
+            .await?
+        else {
+            return Ok(None);
+        };
+        self.find_note(redirect.note_id).await
+    }
+}
@@ -0,0 +1,73 @@
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, NotSet,
+    QueryFilter,
+};
+
+use super::SeaOrmRepository;
+use crate::{
+    error::AppResult,
+    infra::db::entities::visitor_profiles::{self, Entity as VisitorProfile},
+};
+
+/// 评论访客的资料，按浏览器 cookie 识别，昵称全站唯一
+#[async_trait::async_trait]
+pub trait VisitorRepository: Send + Sync {
+    async fn find_visitor(&self, id: i32) -> AppResult<Option<visitor_profiles::Model>>;
+    async fn find_visitor_by_cookie(
+        &self,
+        cookie_id: &str,
+    ) -> AppResult<Option<visitor_profiles::Model>>;
+    async fn find_visitor_by_name(&self, name: &str) -> AppResult<Option<visitor_profiles::Model>>;
+    async fn insert_visitor(
+        &self,
+        visitor: visitor_profiles::Model,
+    ) -> AppResult<visitor_profiles::Model>;
+    async fn update_visitor(
+        &self,
+        visitor: visitor_profiles::Model,
+    ) -> AppResult<visitor_profiles::Model>;
+}
+
+#[async_trait::async_trait]
+impl<C: ConnectionTrait + Sync> VisitorRepository for SeaOrmRepository<'_, C> {
+    async fn find_visitor(&self, id: i32) -> AppResult<Option<visitor_profiles::Model>> {
+        Ok(VisitorProfile::find_by_id(id).one(self.conn).await?)
+    }
+
+    async fn find_visitor_by_cookie(
+        &self,
+        cookie_id: &str,
+    ) -> AppResult<Option<visitor_profiles::Model>> {
+        Ok(VisitorProfile::find()
+            .filter(visitor_profiles::Column::CookieId.eq(cookie_id))
+            .one(self.conn)
+            .await?)
+    }
+
+    async fn find_visitor_by_name(&self, name: &str) -> AppResult<Option<visitor_profiles::Model>> {
+        Ok(VisitorProfile::find()
+            .filter(visitor_profiles::Column::Name.eq(name))
+            .one(self.conn)
+            .await?)
+    }
+
+    async fn insert_visitor(
+        &self,
+        visitor: visitor_profiles::Model,
+    ) -> AppResult<visitor_profiles::Model> {
+        let mut active = visitor.into_active_model().reset_all();
+        active.id = NotSet;
+        Ok(active.insert(self.conn).await?)
+    }
+
+    async fn update_visitor(
+        &self,
+        visitor: visitor_profiles::Model,
+    ) -> AppResult<visitor_profiles::Model> {
+        Ok(visitor
+            .into_active_model()
+            .reset_all()
+            .update(self.conn)
+            .await?)
+    }
+}
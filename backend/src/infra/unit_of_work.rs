@@ -0,0 +1,275 @@
+//! 工作单元
+//!
+//! 把跨表的一组仓储调用放进同一个数据库事务：
+//!
+//! - 闭包返回 `Err` 时整体回滚，返回 `Ok` 时提交
+//! - SQLite 并发写入时可能返回 `SQLITE_BUSY`，此时回滚并稍等片刻后重新执行整个闭包，
+//!   因此闭包中数据库以外的副作用（例如写笔记正文）必须可以重复执行
+//! - `nested` 在当前事务中建立保存点，内层失败只撤销保存点之后的修改，外层可以继续
+//!
+//! ```text
+//! unit_of_work::run(db, |uow| {
+//!     Box::pin(async move {
+//!         let repo = uow.repo();
+//!         repo.insert_like(note_id, ip).await?;
+//!         repo.add_note_likes(note_id, 1).await
+//!     })
+//! })
+//! .await
+//! ```
+
+use std::{future::Future, marker::PhantomData, pin::Pin, time::Duration};
+
+use sea_orm::{
+    DatabaseConnection, DatabaseTransaction, DbErr, RuntimeErr, TransactionTrait,
+    sqlx::{self, error::DatabaseError, sqlite::SqliteError},
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    infra::repositories::SeaOrmRepository,
+};
+
+/// 工作单元闭包返回的 future
+pub type UowFuture<'c, T> = Pin<Box<dyn Future<Output = AppResult<T>> + Send + 'c>>;
+
+/// 遇到 `SQLITE_BUSY` 时最多执行的次数
+const MAX_ATTEMPTS: u32 = 5;
+/// 第一次重试前的等待时间，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(20);
+
+/// SQLite 的主错误码，扩展错误码的低 8 位
+const SQLITE_BUSY: i32 = 5;
+const SQLITE_LOCKED: i32 = 6;
+
+/// 一个进行中的事务；`'a` 是闭包可以借用的外部数据的生命周期
+pub struct UnitOfWork<'a> {
+    txn: DatabaseTransaction,
+    _borrows: PhantomData<&'a ()>,
+}
+
+impl<'a> UnitOfWork<'a> {
+    fn new(txn: DatabaseTransaction) -> Self {
+        Self {
+            txn,
+            _borrows: PhantomData,
+        }
+    }
+
+    /// 事务内的仓储
+    pub fn repo(&self) -> SeaOrmRepository<'_, DatabaseTransaction> {
+        SeaOrmRepository::new(&self.txn)
+    }
+
+    /// 事务本身，供还没有仓储的表（例如统计）直接使用
+    pub fn conn(&self) -> &DatabaseTransaction {
+        &self.txn
+    }
+
+    /// 在保存点中执行 `f`：成功时释放保存点，失败时回滚到保存点并返回错误，外层事务不受影响
+    pub async fn nested<T, F>(&self, f: F) -> AppResult<T>
+    where
+        F: for<'c> FnOnce(&'c UnitOfWork<'a>) -> UowFuture<'c, T>,
+    {
+        let inner = UnitOfWork::new(self.txn.begin().await?);
+        let result = f(&inner).await;
+        inner.finish(result).await
+    }
+
+    async fn finish<T>(self, result: AppResult<T>) -> AppResult<T> {
+        match result {
+            Ok(value) => {
+                self.txn.commit().await?;
+                Ok(value)
+            }
+            Err(err) => {
+                if let Err(rollback_err) = self.txn.rollback().await {
+                    log::warn!("⚠️ 回滚事务失败: {rollback_err}");
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+/// 在事务中执行 `f`，数据库繁忙时自动重试
+pub async fn run<'a, T, F>(db: &DatabaseConnection, f: F) -> AppResult<T>
+where
+    F: for<'c> Fn(&'c UnitOfWork<'a>) -> UowFuture<'c, T>,
+{
+    let mut attempt = 1;
+    loop {
+        let uow = UnitOfWork::new(db.begin().await?);
+        let result = f(&uow).await;
+        match uow.finish(result).await {
+            Err(err) if attempt < MAX_ATTEMPTS && is_busy(&err) => {
+                let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+                log::warn!(
+                    "⚠️ 数据库繁忙，{}ms 后重试事务（第 {attempt} 次）",
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// 是否是 SQLite 的 `SQLITE_BUSY` / `SQLITE_LOCKED`，回滚后重试可能成功
+fn is_busy(err: &AppError) -> bool {
+    let AppError::Database(DbErr::Conn(runtime) | DbErr::Exec(runtime) | DbErr::Query(runtime)) =
+        err
+    else {
+        return false;
+    };
+    let RuntimeErr::SqlxError(sqlx::Error::Database(db_err)) = runtime else {
+        return false;
+    };
+    db_err
+        .try_downcast_ref::<SqliteError>()
+        .and_then(|err| err.code())
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use sea_orm::{ConnectOptions, ConnectionTrait, Database, EntityTrait, PaginatorTrait};
+
+    use super::*;
+    use crate::infra::{
+        db::{entities::friends_links, test_db},
+        repositories::FriendLinkRepository,
+    };
+
+    fn link(url: &str) -> friends_links::Model {
+        let now = chrono::Utc::now();
+        friends_links::Model {
+            id: 0,
+            name: "链接".to_string(),
+            url: url.to_string(),
+            description: None,
+            logo_url: None,
+            sort_order: 0,
+            created_at: now,
+            updated_at: now,
+            last_status: None,
+            last_error: None,
+            last_checked_at: None,
+            last_seen_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollback_and_savepoints() {
+        let db = test_db().await;
+
+        let result: AppResult<()> = run(&db, |uow| {
+            Box::pin(async move {
+                uow.repo().insert_link(link("https://a.example/")).await?;
+                Err(AppError::Conflict("放弃".to_string()))
+            })
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+        assert_eq!(friends_links::Entity::find().count(&db).await.unwrap(), 0);
+
+        let url = "https://b.example/";
+        run(&db, |uow| {
+            Box::pin(async move {
+                uow.repo().insert_link(link(url)).await?;
+                let nested: AppResult<()> = uow
+                    .nested(|inner| {
+                        Box::pin(async move {
+                            inner.repo().insert_link(link("https://c.example/")).await?;
+                            Err(AppError::BadRequest("内层失败".to_string()))
+                        })
+                    })
+                    .await;
+                assert!(nested.is_err());
+                uow.nested(|inner| {
+                    Box::pin(async move {
+                        inner.repo().insert_link(link("https://d.example/")).await?;
+                        Ok(())
+                    })
+                })
+                .await
+            })
+        })
+        .await
+        .unwrap();
+
+        let urls: Vec<String> = db
+            .query_all(sea_orm::Statement::from_string(
+                db.get_database_backend(),
+                "SELECT url FROM friends_links ORDER BY url",
+            ))
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.try_get("", "url").unwrap())
+            .collect();
+        assert_eq!(urls, ["https://b.example/", "https://d.example/"]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_when_sqlite_is_busy() {
+        let path = std::env::temp_dir().join(format!("rowan-uow-{}.db", uuid::Uuid::new_v4()));
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let connect = |url: &str| {
+            let mut opt = ConnectOptions::new(url);
+            opt.max_connections(1).sqlx_logging(false);
+            Database::connect(opt)
+        };
+        let holder = connect(&url).await.unwrap();
+        let db = connect(&url).await.unwrap();
+        holder
+            .execute_unprepared("CREATE TABLE t (id INTEGER)")
+            .await
+            .unwrap();
+        // 不等待锁，立即返回 SQLITE_BUSY
+        db.execute_unprepared("PRAGMA busy_timeout = 0")
+            .await
+            .unwrap();
+
+        let lock = holder.begin().await.unwrap();
+        lock.execute_unprepared("INSERT INTO t VALUES (0)")
+            .await
+            .unwrap();
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            lock.commit().await.unwrap();
+        });
+
+        let attempts = AtomicU32::new(0);
+        let attempts_ref = &attempts;
+        run(&db, |uow| {
+            Box::pin(async move {
+                attempts_ref.fetch_add(1, Ordering::SeqCst);
+                uow.conn()
+                    .execute_unprepared("INSERT INTO t VALUES (1)")
+                    .await?;
+                Ok(())
+            })
+        })
+        .await
+        .unwrap();
+        release.await.unwrap();
+
+        assert!(attempts.load(Ordering::SeqCst) > 1);
+        let rows = db
+            .query_all(sea_orm::Statement::from_string(
+                db.get_database_backend(),
+                "SELECT id FROM t",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+
+        drop((db, holder));
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,2 +1,7 @@
+pub mod api;
+pub mod cli;
+pub mod config;
+pub mod error;
 pub mod infra;
 pub mod schema;
+pub mod service;
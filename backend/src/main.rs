@@ -1,67 +1,255 @@
-use axum::{Extension, Json, routing::get};
+use axum::{Extension, Json, middleware, routing::get};
+use clap::Parser;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
-//mod api;
-mod infra;
-mod schema;
-
-use infra::db::{AppState, create_db_pool};
+use rowan_web_backend::{
+    api::{self, metrics_handler},
+    cli::{self, ArchiveAction, Cli, Command},
+    config::{
+        AssetConfig, BackupConfig, EssayConfig, FriendLinkConfig, JwtConfig, MetricsConfig,
+        MigrationConfig, OgImageConfig, ServerConfig, SiteConfig, StatsConfig, TotpConfig,
+        ViewConfig,
+    },
+    infra::{
+        blob,
+        db::{AppState, DatabaseLock, DbPools, create_db_pool, migrate, sqlite_path},
+    },
+    service::{
+        backup_service, essay_service, friend_link_service,
+        og_service::OgRenderer,
+        sitemap_service::{self, SitemapCache},
+        stats_service,
+        view_service::{self, ViewTracker},
+    },
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
     // 初始化日志
     env_logger::init();
 
-    // 加载环境变量
-    dotenvy::dotenv().expect("无法加载 .env 文件，请确保 .env 文件存在且格式正确");
+    // 加载环境变量；容器中通常直接通过环境变量配置，没有 .env 文件
+    match dotenvy::dotenv() {
+        Ok(_) => {}
+        Err(err) if err.not_found() => log::info!("📄 未找到 .env 文件，仅使用环境变量"),
+        Err(err) => panic!("无法加载 .env 文件，请确保格式正确: {err}"),
+    }
+
+    // 获取数据库URL，在环境变量或 .env 文件中配置
+    let database_url = std::env::var("DATABASE_URL")
+        .expect("DATABASE_URL 环境变量未设置，请在环境变量或 .env 文件中配置");
+
+    let command = cli.command.unwrap_or(Command::Serve);
+    // 恢复备份要替换的正是数据库文件本身，必须在连接数据库之前执行
+    if let Command::Restore { file, yes } = &command {
+        return cli::restore(&database_url, file, *yes).await;
+    }
 
-    // 获取数据库URL，必须在 .env 文件中配置
-    let database_url =
-        std::env::var("DATABASE_URL").expect("DATABASE_URL 环境变量未设置，请在 .env 文件中配置");
+    // 会写数据库的命令在执行期间锁定 SQLite 文件，防止同时恢复备份；
+    // 服务在 serve 中自行加锁，只读的导出和备份不加锁，可以在服务运行时执行
+    let writes = matches!(
+        command,
+        Command::CreateAdmin { .. }
+            | Command::Migrate { .. }
+            | Command::Import { .. }
+            | Command::Archive {
+                action: ArchiveAction::Import { .. }
+            }
+    );
+    let _database_lock = match sqlite_path(&database_url) {
+        Some(database) if writes => Some(DatabaseLock::acquire(&database)?),
+        _ => None,
+    };
 
     // 创建数据库连接池
     log::info!("🔗 正在连接数据库: {database_url}......");
-    let db = create_db_pool(&database_url).await?;
+    let pools = create_db_pool(&database_url).await?;
     log::info!("✅ 数据库连接池创建成功！");
 
+    match command {
+        Command::Serve => serve(pools, &database_url).await,
+        Command::CreateAdmin { username } => cli::create_admin(&pools.writer, &username).await,
+        Command::Export { out_dir, force } => cli::export(&pools.reader, &out_dir, force).await,
+        Command::Migrate { action } => cli::migrate(&pools.writer, action).await,
+        Command::Archive { action } => match action {
+            ArchiveAction::Export { .. } => cli::archive(&pools.reader, action).await,
+            ArchiveAction::Import { .. } => cli::archive(&pools.writer, action).await,
+        },
+        Command::Import { source } => cli::import(&pools.writer, source).await,
+        Command::Backup { action } => cli::backup(&pools.reader, action).await,
+        Command::Restore { .. } => unreachable!("恢复备份在连接数据库之前已经处理"),
+    }
+}
+
+/// 启动 HTTP 服务及后台任务，直到收到关闭信号
+async fn serve(pools: DbPools, database_url: &str) -> anyhow::Result<()> {
+    // SQLite 文件数据库在服务运行期间一直锁定，防止同时恢复备份
+    let database_file = sqlite_path(database_url);
+    let _database_lock = database_file
+        .as_deref()
+        .map(DatabaseLock::acquire)
+        .transpose()?;
+
+    // 按配置执行未执行的迁移；数据库结构比程序新时拒绝启动
+    migrate::prepare(&pools.writer, &MigrationConfig::from_env()).await?;
+
+    // 后台任务都会写数据库，统一使用写连接池
+    let db = &pools.writer;
+
+    // 后台任务共用的关闭信号
+    let shutdown = CancellationToken::new();
+
+    // 浏览量聚合器及其定时写回任务
+    let view_config = ViewConfig::from_env();
+    let views = Arc::new(ViewTracker::new(&view_config));
+    let view_flusher = tokio::spawn(view_service::run_flusher(
+        Arc::clone(&views),
+        db.clone(),
+        view_config.flush_interval(),
+        shutdown.clone(),
+    ));
+
+    // 每日统计中的评论数汇总任务
+    let stats_config = StatsConfig::from_env();
+    let stats_rollup = tokio::spawn(stats_service::run_rollup(
+        db.clone(),
+        stats_config.rollup_interval(),
+        shutdown.clone(),
+    ));
+
+    // 定时随笔的发布任务
+    let essay_config = EssayConfig::from_env();
+    let essay_publisher = tokio::spawn(essay_service::run_publisher(
+        db.clone(),
+        essay_config.publish_interval(),
+        shutdown.clone(),
+    ));
+
+    // 友链健康检查任务
+    let link_config = FriendLinkConfig::from_env();
+    let link_checker = tokio::spawn(friend_link_service::run_checker(
+        db.clone(),
+        friend_link_service::checker_client(link_config.check_timeout())?,
+        link_config.check_interval(),
+        shutdown.clone(),
+    ));
+
+    // SQLite 数据库的定时备份任务
+    let backup_config = BackupConfig::from_env();
+    let backup_scheduler = (database_file.is_some() && backup_config.scheduled()).then(|| {
+        tokio::spawn(backup_service::run_scheduler(
+            pools.reader.clone(),
+            backup_config,
+            shutdown.clone(),
+        ))
+    });
+
     // 创建应用状态
-    let app_state = AppState::new(db);
-
-    //let annotated_router = api::create_api_router();
-    //let api_docs = Arc::new(annotated_router.annotations().clone());
-    //let app_router = annotated_router.build();
-    //let api_docs_for_handler = Arc::clone(&api_docs);
-
-    // let app = app_router
-    //     .route(
-    //         "/api/docs",
-    //         get(move || {
-    //             let docs = Arc::clone(&api_docs_for_handler);
-    //             async move { Json((*docs).clone()) }
-    //         }),
-    //     )
-    //     .layer(Extension(app_state)); // 添加应用状态作为扩展
-
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:5000")
+    let server = ServerConfig::from_env();
+    let address = server.address();
+    let site = SiteConfig::from_env();
+    let sitemap = Arc::new(SitemapCache::new(sitemap_service::robots_txt(&site)?));
+    let og = Arc::new(OgRenderer::new(&OgImageConfig::from_env()));
+    let asset_config = AssetConfig::from_env();
+    let blobs = Arc::from(blob::from_config(&asset_config)?);
+    let app_state = AppState::new(
+        pools,
+        views,
+        JwtConfig::from_env(),
+        TotpConfig::from_env(),
+        site,
+        sitemap,
+        og,
+        blobs,
+        asset_config,
+        MetricsConfig::from_env(),
+        server,
+    );
+
+    let annotated_router = api::create_api_router();
+    let api_docs = Arc::new(annotated_router.annotations().clone());
+    let app_router = annotated_router.build();
+    let api_docs_for_handler = Arc::clone(&api_docs);
+
+    let app = app_router
+        .route(
+            "/api/docs",
+            get(move || {
+                let docs = Arc::clone(&api_docs_for_handler);
+                async move { Json((*docs).clone()) }
+            }),
+        )
+        // 按路由模板记录请求指标，必须在所有路由注册之后添加
+        .layer(middleware::from_fn(metrics_handler::track_requests))
+        .layer(Extension(app_state)); // 添加应用状态作为扩展
+
+    let listener = tokio::net::TcpListener::bind(&address)
         .await
-        .expect("Failed to bind to address 127.0.0.1:5000. Is the port already in use?");
+        .unwrap_or_else(|err| {
+            panic!("Failed to bind to address {address}: {err}. Is the port already in use?")
+        });
 
     println!("🚀 服务器已启动!");
-    println!("📍 服务地址: http://127.0.0.1:5000");
+    println!("📍 服务地址: http://{address}");
     println!("📝 API 端点:");
-    // for endpoint in api_docs.iter() {
-    //     println!(
-    //         "   {:?} {} - {}",
-    //         endpoint.method, endpoint.path, endpoint.description
-    //     );
-    // }
-    println!("📚 API 文档: http://127.0.0.1:5000/api/docs");
+    for endpoint in api_docs.iter() {
+        println!(
+            "   {:?} {} - {}",
+            endpoint.method, endpoint.path, endpoint.description
+        );
+    }
+    println!("📚 API 文档: http://{address}/api/docs");
     println!("💾 数据库: {database_url}");
     println!();
 
-    // axum::serve(listener, app)
-    //     .await
-    //     .expect("Axum server failed to start or encountered a fatal error");
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(shutdown.clone()))
+    .await
+    .expect("Axum server failed to start or encountered a fatal error");
+
+    // 等待后台任务完成最后一次写回
+    shutdown.cancel();
+    let _ = view_flusher.await;
+    let _ = stats_rollup.await;
+    let _ = essay_publisher.await;
+    let _ = link_checker.await;
+    if let Some(backup_scheduler) = backup_scheduler {
+        let _ = backup_scheduler.await;
+    }
+    log::info!("👋 服务器已关闭");
 
     Ok(())
 }
+
+/// 等待 Ctrl+C 或 SIGTERM，收到后通知所有后台任务
+async fn shutdown_signal(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("无法监听 Ctrl+C 信号");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("无法监听 SIGTERM 信号")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    log::info!("🛑 收到关闭信号，正在停止服务......");
+    shutdown.cancel();
+}
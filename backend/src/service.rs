@@ -1,3 +1,63 @@
+pub mod archive_service;
+pub mod asset_service;
 pub mod auth_service;
-pub mod comment_service;
+pub mod backup_service;
+// 评论服务仍沿用旧的用户模型，等对应的实体落地后再接入
+// pub mod comment_service;
+pub mod essay_service;
+pub mod export_service;
+pub mod feed_service;
+pub mod friend_link_service;
+pub mod health_service;
+pub mod image_service;
+pub mod import_service;
 pub mod note_service;
+pub mod og_service;
+#[cfg(feature = "html")]
+pub mod page_service;
+pub mod session_service;
+pub mod sitemap_service;
+pub mod slug_service;
+pub mod stats_service;
+pub mod totp_service;
+pub mod view_service;
+
+use serde::Deserialize;
+
+use crate::infra::repositories::Page;
+
+/// 通用分页参数，页码从 1 开始
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaginationQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_per_page")]
+    pub per_page: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_per_page() -> u64 {
+    20
+}
+
+impl Default for PaginationQuery {
+    fn default() -> Self {
+        Self {
+            page: default_page(),
+            per_page: default_per_page(),
+        }
+    }
+}
+
+impl PaginationQuery {
+    /// 转换为仓储的分页参数，页码至少为 1，每页 1 到 100 条
+    pub fn page(&self) -> Page {
+        Page {
+            number: self.page.max(1),
+            size: self.per_page.clamp(1, 100),
+        }
+    }
+}
@@ -0,0 +1,922 @@
+//! 站点内容归档
+//!
+//! 把笔记（元数据、Markdown 正文、旧 slug、点赞）、随笔（连同历史版本）、访客、评论和友链
+//! 导出成一个带格式版本号的 JSON 文件，用于迁移到另一个实例或长期存档。
+//!
+//! 导入时所有整数 id 都由目标数据库重新分配，归档中的 id 只用来还原彼此的引用
+//! （评论所属的笔记 / 随笔、访客和回复关系）；创建、更新、发布时间按原样保留。
+//! 导入在一个事务中完成，任何一条失败都会整体回滚。与已有内容冲突时：
+//!
+//! - 笔记 slug 已被占用时改用不冲突的 slug，旧 slug 已被占用时不再记录跳转
+//! - 访客按 cookie 识别，已存在的访客直接复用；昵称被其他访客占用时加数字后缀
+//! - 链接已经存在的友链跳过
+//!
+//! 媒体文件、统计数据和管理员账号不在归档范围内。
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use sea_orm::{DatabaseConnection, EntityTrait, QueryOrder};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, AppResult},
+    infra::{
+        content::ContentStore,
+        db::entities::{
+            comments::{self, Entity as Comment},
+            essay_revisions::{self, Entity as EssayRevision},
+            essays::{self, Entity as Essay},
+            friends_links::{self, Entity as FriendsLink},
+            likes::{self, Entity as Like},
+            notes_metadata::{self, Entity as NotesMetadata},
+            slug_redirects::{self, Entity as SlugRedirect},
+            visitor_profiles::{self, Entity as VisitorProfile},
+        },
+        metrics,
+        repositories::{
+            CommentRepository, EssayRepository, FriendLinkRepository, LikeRepository,
+            NoteRepository, VisitorRepository,
+        },
+        unit_of_work,
+    },
+    service::{essay_service::EssayStatus, import_service, note_service, slug_service},
+};
+
+/// 归档文件的格式标识
+pub const ARCHIVE_FORMAT: &str = "rowan-archive";
+/// 当前的归档格式版本，格式不兼容地变化时加一
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// 归档文件
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Archive {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub notes: Vec<ArchivedNote>,
+    pub essays: Vec<ArchivedEssay>,
+    pub visitors: Vec<ArchivedVisitor>,
+    pub comments: Vec<ArchivedComment>,
+    pub friend_links: Vec<ArchivedFriendLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedNote {
+    pub id: i32,
+    pub slug: String,
+    pub title: String,
+    pub summary: Option<String>,
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+    /// Markdown 正文，导出时正文文件丢失则为空
+    pub content: Option<String>,
+    pub published_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub views: i32,
+    /// 改名前的 slug，导入后继续跳转到这篇笔记
+    #[serde(default)]
+    pub old_slugs: Vec<String>,
+    /// 点过赞的 IP
+    #[serde(default)]
+    pub likes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedEssay {
+    pub id: i32,
+    pub title: String,
+    pub content: String,
+    pub status: String,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub revisions: Vec<ArchivedRevision>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedRevision {
+    pub revision: i32,
+    pub title: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedVisitor {
+    pub id: i32,
+    pub cookie_id: String,
+    pub name: String,
+    pub ip: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 评论；`note_id` 和 `essay_id` 恰好有一个，`parent_id` 指向同一归档中的另一条评论
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedComment {
+    pub id: i32,
+    pub note_id: Option<i32>,
+    pub essay_id: Option<i32>,
+    pub visitor_id: i32,
+    pub parent_id: Option<i32>,
+    pub content: String,
+    pub is_approved: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedFriendLink {
+    pub name: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub logo_url: Option<String>,
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 导入结果
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub notes: usize,
+    pub essays: usize,
+    /// 新建的访客，不含按 cookie 复用的
+    pub visitors: usize,
+    pub comments: usize,
+    pub likes: usize,
+    pub friend_links: usize,
+    /// slug 冲突而改名的笔记（原 slug，新 slug）
+    pub renamed_slugs: Vec<(String, String)>,
+    /// 链接已存在而跳过的友链
+    pub skipped_links: Vec<String>,
+}
+
+/// 导出全部内容
+pub async fn export_archive(db: &DatabaseConnection, content: &ContentStore) -> AppResult<Archive> {
+    let mut redirects: HashMap<i32, Vec<String>> = HashMap::new();
+    for redirect in SlugRedirect::find()
+        .order_by_asc(slug_redirects::Column::Id)
+        .all(db)
+        .await?
+    {
+        redirects
+            .entry(redirect.note_id)
+            .or_default()
+            .push(redirect.old_slug);
+    }
+    let mut likes: HashMap<i32, Vec<String>> = HashMap::new();
+    for like in Like::find().order_by_asc(likes::Column::Id).all(db).await? {
+        likes
+            .entry(like.note_metadata_id)
+            .or_default()
+            .push(like.ip_address);
+    }
+
+    let mut notes = Vec::new();
+    for note in NotesMetadata::find()
+        .order_by_asc(notes_metadata::Column::Id)
+        .all(db)
+        .await?
+    {
+        let markdown = content.read_note(note.file_id).await?;
+        if markdown.is_none() {
+            log::warn!("⚠️ 笔记 {} 的正文文件不存在，只导出元数据", note.slug);
+        }
+        notes.push(ArchivedNote {
+            id: note.id,
+            tags: note_service::parse_tags(note.tags.as_deref()),
+            old_slugs: redirects.remove(&note.id).unwrap_or_default(),
+            likes: likes.remove(&note.id).unwrap_or_default(),
+            slug: note.slug,
+            title: note.title,
+            summary: note.summary,
+            category: note.category,
+            content: markdown,
+            published_at: note.published_at,
+            updated_at: note.updated_at,
+            views: note.views,
+        });
+    }
+
+    let mut revisions: HashMap<i32, Vec<ArchivedRevision>> = HashMap::new();
+    for revision in EssayRevision::find()
+        .order_by_asc(essay_revisions::Column::EssayId)
+        .order_by_asc(essay_revisions::Column::Revision)
+        .all(db)
+        .await?
+    {
+        revisions
+            .entry(revision.essay_id)
+            .or_default()
+            .push(ArchivedRevision {
+                revision: revision.revision,
+                title: revision.title,
+                content: revision.content,
+                created_at: revision.created_at,
+            });
+    }
+    let essays = Essay::find()
+        .order_by_asc(essays::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|essay| ArchivedEssay {
+            revisions: revisions.remove(&essay.id).unwrap_or_default(),
+            id: essay.id,
+            title: essay.title,
+            content: essay.content,
+            status: essay.status,
+            publish_at: essay.publish_at,
+            created_at: essay.created_at,
+            updated_at: essay.updated_at,
+        })
+        .collect();
+
+    let visitors = VisitorProfile::find()
+        .order_by_asc(visitor_profiles::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|visitor| ArchivedVisitor {
+            id: visitor.id,
+            cookie_id: visitor.cookie_id,
+            name: visitor.name,
+            ip: visitor.ip,
+            created_at: visitor.created_at,
+            updated_at: visitor.updated_at,
+        })
+        .collect();
+
+    let comments = Comment::find()
+        .order_by_asc(comments::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|comment| ArchivedComment {
+            id: comment.id,
+            note_id: comment.note_metadata_id,
+            essay_id: comment.essay_id,
+            visitor_id: comment.visitor_profile_id,
+            parent_id: comment.parent_id,
+            content: comment.content,
+            is_approved: comment.is_approved,
+            created_at: comment.created_at,
+        })
+        .collect();
+
+    let friend_links = FriendsLink::find()
+        .order_by_asc(friends_links::Column::SortOrder)
+        .order_by_asc(friends_links::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|link| ArchivedFriendLink {
+            name: link.name,
+            url: link.url,
+            description: link.description,
+            logo_url: link.logo_url,
+            sort_order: link.sort_order,
+            created_at: link.created_at,
+            updated_at: link.updated_at,
+        })
+        .collect();
+
+    Ok(Archive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        notes,
+        essays,
+        visitors,
+        comments,
+        friend_links,
+    })
+}
+
+/// 解析归档文件，格式或版本不对时报错
+pub fn parse_archive(json: &str) -> AppResult<Archive> {
+    let archive: Archive = serde_json::from_str(json)
+        .map_err(|e| AppError::BadRequest(format!("归档文件格式错误: {e}")))?;
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(AppError::BadRequest(format!(
+            "不是站点归档文件（format = {}）",
+            archive.format
+        )));
+    }
+    if archive.version == 0 || archive.version > ARCHIVE_VERSION {
+        return Err(AppError::BadRequest(format!(
+            "不支持的归档版本 {}，当前程序支持到 {ARCHIVE_VERSION}",
+            archive.version
+        )));
+    }
+    Ok(archive)
+}
+
+/// 按归档中的 id 查找导入后的 id
+fn remap(ids: &HashMap<i32, i32>, kind: &str, id: i32) -> AppResult<i32> {
+    ids.get(&id)
+        .copied()
+        .ok_or_else(|| AppError::BadRequest(format!("归档中的评论引用了不存在的{kind} #{id}")))
+}
+
+/// 检查归档中的 id 是否存在，错误信息与 `remap` 一致
+fn check_ref(ids: &HashSet<i32>, kind: &str, id: i32) -> AppResult<()> {
+    if ids.contains(&id) {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!(
+            "归档中的评论引用了不存在的{kind} #{id}"
+        )))
+    }
+}
+
+/// 检查评论对笔记、随笔、访客和父评论的引用，在写入任何内容之前拒绝残缺的归档
+fn check_references(archive: &Archive) -> AppResult<()> {
+    let notes: HashSet<i32> = archive.notes.iter().map(|note| note.id).collect();
+    let essays: HashSet<i32> = archive.essays.iter().map(|essay| essay.id).collect();
+    let visitors: HashSet<i32> = archive.visitors.iter().map(|visitor| visitor.id).collect();
+    let comments: HashSet<i32> = archive.comments.iter().map(|comment| comment.id).collect();
+    for comment in &archive.comments {
+        match (comment.note_id, comment.essay_id) {
+            (Some(note_id), None) => check_ref(&notes, "笔记", note_id)?,
+            (None, Some(essay_id)) => check_ref(&essays, "随笔", essay_id)?,
+            _ => {
+                return Err(AppError::BadRequest(format!(
+                    "评论 #{} 必须且只能属于一篇笔记或随笔",
+                    comment.id
+                )));
+            }
+        }
+        check_ref(&visitors, "访客", comment.visitor_id)?;
+        if let Some(parent_id) = comment.parent_id {
+            check_ref(&comments, "评论", parent_id)?;
+        }
+    }
+    Ok(())
+}
+
+/// 导入归档，返回导入结果
+pub async fn import_archive(
+    db: &DatabaseConnection,
+    content: &ContentStore,
+    archive: &Archive,
+) -> AppResult<ImportReport> {
+    let statuses = [
+        EssayStatus::Draft,
+        EssayStatus::Published,
+        EssayStatus::Scheduled,
+    ];
+    if let Some(essay) = archive.essays.iter().find(|essay| {
+        !statuses
+            .iter()
+            .any(|status| status.as_str() == essay.status)
+    }) {
+        return Err(AppError::BadRequest(format!(
+            "随笔 #{} 的状态 {} 无效",
+            essay.id, essay.status
+        )));
+    }
+    check_references(archive)?;
+
+    // 正文先暂存，事务提交后才成为正式文件：事务回滚或重试时不会留下没有元数据的正文
+    let file_ids: Vec<Uuid> = archive.notes.iter().map(|_| Uuid::new_v4()).collect();
+    let contents: Vec<(Uuid, &str)> = archive
+        .notes
+        .iter()
+        .zip(&file_ids)
+        .filter_map(|(note, &file_id)| Some((file_id, note.content.as_deref()?)))
+        .collect();
+    let staged = content.stage_notes(&contents).await?;
+    let file_ids = &file_ids;
+
+    let result = unit_of_work::run(db, |uow| {
+        Box::pin(async move {
+            let repo = uow.repo();
+            let mut report = ImportReport::default();
+
+            let mut note_ids = HashMap::new();
+            for (note, &file_id) in archive.notes.iter().zip(file_ids) {
+                let slug = if repo.is_slug_taken(&note.slug, None).await?
+                    || repo.find_note_by_old_slug(&note.slug).await?.is_some()
+                {
+                    let slug = slug_service::unique_slug(&repo, &note.slug, None).await?;
+                    report.renamed_slugs.push((note.slug.clone(), slug.clone()));
+                    slug
+                } else {
+                    note.slug.clone()
+                };
+                let inserted = repo
+                    .insert_note(notes_metadata::Model {
+                        id: 0,
+                        file_id,
+                        slug,
+                        title: note.title.clone(),
+                        summary: note.summary.clone(),
+                        published_at: note.published_at,
+                        updated_at: note.updated_at,
+                        views: note.views,
+                        likes_count: note.likes.len() as i32,
+                        tags: (!note.tags.is_empty()).then(|| note.tags.join(",")),
+                        category: note.category.clone(),
+                    })
+                    .await?;
+                for old_slug in &note.old_slugs {
+                    if !repo.is_slug_taken(old_slug, None).await?
+                        && repo.find_note_by_old_slug(old_slug).await?.is_none()
+                    {
+                        repo.record_slug_change(inserted.id, old_slug, &inserted.slug)
+                            .await?;
+                    }
+                }
+                for ip in &note.likes {
+                    if repo.find_like(inserted.id, ip).await?.is_none() {
+                        repo.insert_like(inserted.id, ip).await?;
+                        report.likes += 1;
+                    }
+                }
+                note_ids.insert(note.id, inserted.id);
+                report.notes += 1;
+            }
+
+            let mut essay_ids = HashMap::new();
+            for essay in &archive.essays {
+                let inserted = repo
+                    .insert_essay(essays::Model {
+                        id: 0,
+                        title: essay.title.clone(),
+                        content: essay.content.clone(),
+                        created_at: essay.created_at,
+                        updated_at: essay.updated_at,
+                        status: essay.status.clone(),
+                        publish_at: essay.publish_at,
+                    })
+                    .await?;
+                for revision in &essay.revisions {
+                    repo.insert_revision(essay_revisions::Model {
+                        id: 0,
+                        essay_id: inserted.id,
+                        revision: revision.revision,
+                        title: revision.title.clone(),
+                        content: revision.content.clone(),
+                        created_at: revision.created_at,
+                    })
+                    .await?;
+                }
+                essay_ids.insert(essay.id, inserted.id);
+                report.essays += 1;
+            }
+
+            let mut visitor_ids = HashMap::new();
+            for visitor in &archive.visitors {
+                if let Some(existing) = repo.find_visitor_by_cookie(&visitor.cookie_id).await? {
+                    visitor_ids.insert(visitor.id, existing.id);
+                    continue;
+                }
+                let name = import_service::unique_visitor_name(&repo, &visitor.name).await?;
+                let inserted = repo
+                    .insert_visitor(visitor_profiles::Model {
+                        id: 0,
+                        cookie_id: visitor.cookie_id.clone(),
+                        name,
+                        ip: visitor.ip.clone(),
+                        created_at: visitor.created_at,
+                        updated_at: visitor.updated_at,
+                    })
+                    .await?;
+                visitor_ids.insert(visitor.id, inserted.id);
+                report.visitors += 1;
+            }
+
+            // 先插入全部评论，再补上回复关系，不依赖评论在归档中的顺序
+            let (mut comment_ids, mut inserted_comments) = (HashMap::new(), HashMap::new());
+            for comment in &archive.comments {
+                let (note_metadata_id, essay_id) = match (comment.note_id, comment.essay_id) {
+                    (Some(note_id), None) => (Some(remap(&note_ids, "笔记", note_id)?), None),
+                    (None, Some(essay_id)) => (None, Some(remap(&essay_ids, "随笔", essay_id)?)),
+                    _ => {
+                        return Err(AppError::BadRequest(format!(
+                            "评论 #{} 必须且只能属于一篇笔记或随笔",
+                            comment.id
+                        )));
+                    }
+                };
+                let inserted = repo
+                    .insert_comment(comments::Model {
+                        id: 0,
+                        note_metadata_id,
+                        essay_id,
+                        visitor_profile_id: remap(&visitor_ids, "访客", comment.visitor_id)?,
+                        content: comment.content.clone(),
+                        parent_id: None,
+                        created_at: comment.created_at,
+                        is_approved: comment.is_approved,
+                    })
+                    .await?;
+                comment_ids.insert(comment.id, inserted.id);
+                inserted_comments.insert(comment.id, inserted);
+                report.comments += 1;
+            }
+            for comment in &archive.comments {
+                let Some(parent_id) = comment.parent_id else {
+                    continue;
+                };
+                let mut inserted = inserted_comments[&comment.id].clone();
+                inserted.parent_id = Some(remap(&comment_ids, "评论", parent_id)?);
+                repo.update_comment(inserted).await?;
+            }
+
+            for link in &archive.friend_links {
+                if repo.is_link_url_taken(&link.url, None).await? {
+                    report.skipped_links.push(link.url.clone());
+                    continue;
+                }
+                repo.insert_link(friends_links::Model {
+                    id: 0,
+                    name: link.name.clone(),
+                    url: link.url.clone(),
+                    description: link.description.clone(),
+                    logo_url: link.logo_url.clone(),
+                    sort_order: link.sort_order,
+                    created_at: link.created_at,
+                    updated_at: link.updated_at,
+                    last_status: None,
+                    last_error: None,
+                    last_checked_at: None,
+                    last_seen_at: None,
+                })
+                .await?;
+                report.friend_links += 1;
+            }
+
+            Ok(report)
+        })
+    })
+    .await;
+    let report = content.settle_staged(result, staged).await?;
+    metrics::record_comments(report.comments);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        infra::{
+            db::test_db,
+            repositories::{Page, SeaOrmRepository},
+        },
+        service::note_service::{CreateNoteRequest, UpdateNoteRequest},
+    };
+
+    fn at(days_ago: i64) -> DateTime<Utc> {
+        // 截到整秒，避免不同数据库的时间精度影响比较
+        let time = Utc::now() - chrono::Duration::days(days_ago);
+        DateTime::from_timestamp(time.timestamp(), 0).unwrap()
+    }
+
+    /// 一篇改过 slug、有点赞的笔记，一篇带历史版本的随笔，两位访客，一段评论回复和一条友链
+    async fn seed(db: &DatabaseConnection, content: &ContentStore) {
+        let note = note_service::create_note(
+            db,
+            content,
+            CreateNoteRequest {
+                title: "Hello".to_string(),
+                slug: None,
+                summary: Some("摘要".to_string()),
+                tags: vec!["rust".to_string(), "web".to_string()],
+                category: Some("技术".to_string()),
+                content: "# Hello\n".to_string(),
+                published_at: Some(at(10)),
+            },
+        )
+        .await
+        .unwrap();
+        note_service::update_note(
+            db,
+            content,
+            note.id,
+            UpdateNoteRequest {
+                slug: Some("hello-rust".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        note_service::like_note(db, note.id, "203.0.113.1")
+            .await
+            .unwrap();
+
+        let repo = SeaOrmRepository::new(db);
+        let essay = repo
+            .insert_essay(essays::Model {
+                id: 0,
+                title: "随笔".to_string(),
+                content: "第二版".to_string(),
+                created_at: at(5),
+                updated_at: at(4),
+                status: EssayStatus::Published.as_str().to_string(),
+                publish_at: Some(at(5)),
+            })
+            .await
+            .unwrap();
+        repo.insert_revision(essay_revisions::Model {
+            id: 0,
+            essay_id: essay.id,
+            revision: 1,
+            title: "随笔".to_string(),
+            content: "第一版".to_string(),
+            created_at: at(5),
+        })
+        .await
+        .unwrap();
+
+        let mut visitors = Vec::new();
+        for name in ["小明", "小红"] {
+            visitors.push(
+                repo.insert_visitor(visitor_profiles::Model {
+                    id: 0,
+                    cookie_id: format!("cookie-{name}"),
+                    name: name.to_string(),
+                    ip: "198.51.100.1".to_string(),
+                    created_at: at(3),
+                    updated_at: at(3),
+                })
+                .await
+                .unwrap(),
+            );
+        }
+        let comment = |visitor: i32, parent_id, note, essay| comments::Model {
+            id: 0,
+            note_metadata_id: note,
+            essay_id: essay,
+            visitor_profile_id: visitor,
+            content: "评论".to_string(),
+            parent_id,
+            created_at: at(2),
+            is_approved: true,
+        };
+        let root = repo
+            .insert_comment(comment(visitors[0].id, None, Some(note.id), None))
+            .await
+            .unwrap();
+        repo.insert_comment(comment(visitors[1].id, Some(root.id), Some(note.id), None))
+            .await
+            .unwrap();
+        repo.insert_comment(comment(visitors[1].id, None, None, Some(essay.id)))
+            .await
+            .unwrap();
+
+        repo.insert_link(friends_links::Model {
+            id: 0,
+            name: "朋友".to_string(),
+            url: "https://friend.example/".to_string(),
+            description: None,
+            logo_url: None,
+            sort_order: 3,
+            created_at: at(1),
+            updated_at: at(1),
+            last_status: Some(200),
+            last_error: None,
+            last_checked_at: None,
+            last_seen_at: None,
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let dir = std::env::temp_dir().join(format!("rowan-archive-{}", Uuid::new_v4()));
+        let (source, target) = (test_db().await, test_db().await);
+        let source_content = ContentStore::new(dir.join("source"));
+        let target_content = ContentStore::new(dir.join("target"));
+        seed(&source, &source_content).await;
+
+        let archive = export_archive(&source, &source_content).await.unwrap();
+        let json = serde_json::to_string(&archive).unwrap();
+        let archive = parse_archive(&json).unwrap();
+        assert_eq!(archive.notes[0].old_slugs, ["hello"]);
+
+        // 目标库中先有一个同名访客，让 id 与源库错开
+        let repo = SeaOrmRepository::new(&target);
+        repo.insert_visitor(visitor_profiles::Model {
+            id: 0,
+            cookie_id: "someone-else".to_string(),
+            name: "小明".to_string(),
+            ip: "192.0.2.1".to_string(),
+            created_at: at(0),
+            updated_at: at(0),
+        })
+        .await
+        .unwrap();
+
+        let report = import_archive(&target, &target_content, &archive)
+            .await
+            .unwrap();
+        assert_eq!(
+            (
+                report.notes,
+                report.essays,
+                report.visitors,
+                report.comments
+            ),
+            (1, 1, 2, 3)
+        );
+        assert!(report.renamed_slugs.is_empty());
+
+        let note = repo.find_note_by_slug("hello-rust").await.unwrap().unwrap();
+        assert_eq!(note.published_at, at(10));
+        assert_eq!(note.likes_count, 1);
+        assert_eq!(note.tags.as_deref(), Some("rust,web"));
+        assert_eq!(
+            target_content
+                .read_note(note.file_id)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("# Hello\n")
+        );
+        assert_eq!(
+            slug_service::resolve_redirect(&repo, "hello")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("hello-rust")
+        );
+
+        // 回复关系和访客都指向导入后的新 id
+        let comments = repo.list_note_comments(note.id).await.unwrap();
+        let root = comments.iter().find(|c| c.parent_id.is_none()).unwrap();
+        let reply = comments.iter().find(|c| c.parent_id.is_some()).unwrap();
+        assert_eq!(reply.parent_id, Some(root.id));
+        let author = repo
+            .find_visitor(root.visitor_profile_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(author.cookie_id, "cookie-小明");
+        assert_eq!(author.name, "小明-2");
+        assert_eq!(root.created_at, at(2));
+
+        let (essays, _) = repo
+            .list_essays(
+                None,
+                Page {
+                    number: 1,
+                    size: 10,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(essays[0].updated_at, at(4));
+        assert_eq!(
+            repo.list_revisions(essays[0].id).await.unwrap()[0].content,
+            "第一版"
+        );
+        assert_eq!(
+            repo.list_essay_comments(essays[0].id).await.unwrap().len(),
+            1
+        );
+
+        // 再导入一次：slug 改名，访客复用，友链跳过
+        let again = import_archive(&target, &target_content, &archive)
+            .await
+            .unwrap();
+        assert_eq!(again.visitors, 0);
+        assert_eq!(again.renamed_slugs.len(), 1);
+        assert_eq!(again.skipped_links, ["https://friend.example/"]);
+
+        let mut wrong = archive;
+        wrong.version = ARCHIVE_VERSION + 1;
+        assert!(parse_archive(&serde_json::to_string(&wrong).unwrap()).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_long_visitor_name_keeps_suffix_within_limit() {
+        let dir = std::env::temp_dir().join(format!("rowan-archive-{}", Uuid::new_v4()));
+        let db = test_db().await;
+        let content = ContentStore::new(&dir);
+        let name = "abcdefghijklm";
+        let visitor = |id, cookie: &str| ArchivedVisitor {
+            id,
+            cookie_id: cookie.to_string(),
+            name: name.to_string(),
+            ip: "192.0.2.1".to_string(),
+            created_at: at(0),
+            updated_at: at(0),
+        };
+        let mut archive = export_archive(&db, &content).await.unwrap();
+        archive.visitors = vec![visitor(1, "first"), visitor(2, "second")];
+        import_archive(&db, &content, &archive).await.unwrap();
+
+        let repo = SeaOrmRepository::new(&db);
+        let second = repo
+            .find_visitor_by_cookie("second")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.name, "abcdefghijk-2");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_failed_content_rename_keeps_import() {
+        let dir = std::env::temp_dir().join(format!("rowan-archive-{}", Uuid::new_v4()));
+        let (source, target) = (test_db().await, test_db().await);
+        let source_content = ContentStore::new(dir.join("source"));
+        let target_content = ContentStore::new(dir.join("target")).failing_commits();
+        seed(&source, &source_content).await;
+        let archive = export_archive(&source, &source_content).await.unwrap();
+
+        // 事务已经提交，正文改名失败不算导入失败，重试也不会重复导入
+        let report = import_archive(&target, &target_content, &archive)
+            .await
+            .unwrap();
+        assert_eq!(report.notes, 1);
+        let note = SeaOrmRepository::new(&target)
+            .find_note_by_slug("hello-rust")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(target_content.read_note(note.file_id).await.unwrap(), None);
+        // 正文留在临时文件中，可以按日志手动恢复
+        let staged: Vec<String> = std::fs::read_dir(dir.join("target"))
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        assert_eq!(staged, ["# Hello\n"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_dangling_reference_rolls_back() {
+        let dir = std::env::temp_dir().join(format!("rowan-archive-{}", Uuid::new_v4()));
+        let (source, target) = (test_db().await, test_db().await);
+        let source_content = ContentStore::new(dir.join("source"));
+        let target_content = ContentStore::new(dir.join("target"));
+        seed(&source, &source_content).await;
+        let archive = export_archive(&source, &source_content).await.unwrap();
+        let repo = SeaOrmRepository::new(&target);
+
+        let json = serde_json::to_string(&archive).unwrap();
+        let mut dangling_parent = parse_archive(&json).unwrap();
+        let reply = dangling_parent
+            .comments
+            .iter_mut()
+            .find(|comment| comment.parent_id.is_some())
+            .unwrap();
+        reply.parent_id = Some(9999);
+        let mut dangling_note = archive;
+        let comment = dangling_note
+            .comments
+            .iter_mut()
+            .find(|comment| comment.note_id.is_some())
+            .unwrap();
+        comment.note_id = Some(9999);
+
+        for archive in [dangling_parent, dangling_note] {
+            assert!(matches!(
+                import_archive(&target, &target_content, &archive).await,
+                Err(AppError::BadRequest(_))
+            ));
+            // 之前插入的笔记、随笔和访客随事务一起回滚
+            assert!(
+                repo.find_note_by_slug("hello-rust")
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+            assert!(
+                repo.find_visitor_by_cookie("cookie-小明")
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+            let (essays, total) = repo
+                .list_essays(
+                    None,
+                    Page {
+                        number: 1,
+                        size: 10,
+                    },
+                )
+                .await
+                .unwrap();
+            assert!(essays.is_empty() && total == 0);
+            // 残缺的归档在写入正文之前就被拒绝，正文目录里没有留下任何文件
+            assert_eq!(
+                std::fs::read_dir(dir.join("target")).map_or(0, |entries| entries.count()),
+                0
+            );
+        }
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
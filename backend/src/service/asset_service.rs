@@ -0,0 +1,249 @@
+//! 媒体文件（笔记和随笔中的图片、附件）
+//!
+//! 上传的文件按文件头识别类型，图片去掉元数据后按内容的 SHA-256 寻址保存，
+//! 同一文件重复上传只保存一份。对外地址为 `/media/{hash}.{ext}`，内容永不改变，可以长期缓存。
+
+use bytes::Bytes;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{AppError, AppResult},
+    infra::{
+        blob::BlobStore,
+        db::entities::{
+            asset_variants::{self, Entity as AssetVariant},
+            assets::{self, Entity as Asset},
+        },
+        media::{self, MediaType},
+        unit_of_work,
+    },
+    service::{PaginationQuery, image_service},
+};
+
+/// 原始文件名的最大长度（字符数）
+const MAX_NAME_LEN: usize = 255;
+
+/// 对外的文件名：`{hash}.{ext}`
+pub fn file_name(asset: &assets::Model) -> String {
+    format!("{}.{}", asset.hash, asset.extension)
+}
+
+/// 存储键：按哈希前两位分目录，避免单个目录下文件过多
+fn object_key(hash: &str, extension: &str) -> String {
+    format!("{}/{hash}.{extension}", &hash[..2])
+}
+
+/// 文件在存储中的键
+pub fn storage_key(asset: &assets::Model) -> String {
+    object_key(&asset.hash, &asset.extension)
+}
+
+/// 只保留客户端文件名的最后一段，去掉控制字符
+fn clean_name(name: &str) -> Option<String> {
+    let name: String = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_LEN)
+        .collect();
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// 保存上传的文件，返回文件记录以及是否为新文件（内容相同的文件已存在时返回已有记录）
+pub async fn upload(
+    db: &DatabaseConnection,
+    blobs: &dyn BlobStore,
+    data: Bytes,
+    original_name: Option<&str>,
+) -> AppResult<(assets::Model, bool)> {
+    let kind = MediaType::sniff(&data).ok_or_else(|| {
+        AppError::BadRequest(
+            "不支持的文件类型，仅支持 JPEG、PNG、GIF、WebP、PDF 和 ZIP".to_string(),
+        )
+    })?;
+    let data = media::strip_metadata(kind, data)?;
+    let hash = hex::encode(Sha256::digest(&data));
+    // 记录显示尺寸，渲染时写进 <img> 避免页面跳动
+    let dimensions = kind
+        .is_image()
+        .then(|| media::image_dimensions(&data).ok())
+        .flatten();
+
+    if let Some(existing) = Asset::find()
+        .filter(assets::Column::Hash.eq(&hash))
+        .one(db)
+        .await?
+    {
+        // 存储中的文件丢失时（如迁移存储后）顺便补上
+        let key = storage_key(&existing);
+        if !blobs.exists(&key).await? {
+            blobs.put(&key, data, &existing.mime_type).await?;
+        }
+        return Ok((existing, false));
+    }
+
+    // 先写文件再写记录：记录存在时文件一定存在
+    blobs
+        .put(
+            &object_key(&hash, kind.extension()),
+            data.clone(),
+            kind.mime_type(),
+        )
+        .await?;
+
+    let asset = assets::ActiveModel {
+        hash: Set(hash.clone()),
+        mime_type: Set(kind.mime_type().to_string()),
+        extension: Set(kind.extension().to_string()),
+        size: Set(data.len() as i64),
+        original_name: Set(original_name.and_then(clean_name)),
+        width: Set(dimensions.map(|(width, _)| width as i32)),
+        height: Set(dimensions.map(|(_, height)| height as i32)),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    match asset.insert(db).await {
+        Ok(asset) => Ok((asset, true)),
+        // 并发上传同一文件时，另一个请求已经写入了记录
+        Err(err) => match Asset::find()
+            .filter(assets::Column::Hash.eq(&hash))
+            .one(db)
+            .await?
+        {
+            Some(existing) => Ok((existing, false)),
+            None => Err(err.into()),
+        },
+    }
+}
+
+/// 文件列表，最新的在前
+pub async fn list(
+    db: &DatabaseConnection,
+    query: &PaginationQuery,
+) -> AppResult<(Vec<assets::Model>, u64)> {
+    let page = query.page.max(1);
+    let per_page = query.per_page.clamp(1, 100);
+
+    let paginator = Asset::find()
+        .order_by_desc(assets::Column::CreatedAt)
+        .order_by_desc(assets::Column::Id)
+        .paginate(db, per_page);
+    let total = paginator.num_items().await?;
+    let assets = paginator.fetch_page(page - 1).await?;
+    Ok((assets, total))
+}
+
+/// 按 ID 查找
+pub async fn get(db: &DatabaseConnection, id: i32) -> AppResult<assets::Model> {
+    Asset::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// 按对外文件名（`{hash}.{ext}`）查找
+pub async fn find_by_file_name(db: &DatabaseConnection, file: &str) -> AppResult<assets::Model> {
+    let (hash, extension) = file.split_once('.').ok_or(AppError::NotFound)?;
+    Asset::find()
+        .filter(assets::Column::Hash.eq(hash))
+        .filter(assets::Column::Extension.eq(extension))
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// 删除文件记录和存储中的文件（包括图片变体）
+///
+/// 记录在一个事务中删除，提交后才删除存储中的文件：事务失败时文件都还在；
+/// 提交后个别文件删除失败只会留下没有记录引用的文件，记录日志后忽略。
+pub async fn delete(db: &DatabaseConnection, blobs: &dyn BlobStore, id: i32) -> AppResult<()> {
+    let (asset, variants) = unit_of_work::run(db, |uow| {
+        Box::pin(async move {
+            let txn = uow.conn();
+            let asset = Asset::find_by_id(id)
+                .one(txn)
+                .await?
+                .ok_or(AppError::NotFound)?;
+            let variants = AssetVariant::find()
+                .filter(asset_variants::Column::AssetId.eq(id))
+                .all(txn)
+                .await?;
+            AssetVariant::delete_many()
+                .filter(asset_variants::Column::AssetId.eq(id))
+                .exec(txn)
+                .await?;
+            Asset::delete_by_id(id).exec(txn).await?;
+            Ok((asset, variants))
+        })
+    })
+    .await?;
+
+    let keys = std::iter::once(storage_key(&asset)).chain(
+        variants
+            .iter()
+            .map(|variant| image_service::variant_storage_key(&asset, variant)),
+    );
+    for key in keys {
+        if let Err(err) = blobs.delete(&key).await {
+            log::warn!("⚠️ 删除媒体文件 {key} 失败，需要手动清理: {err}");
+        }
+    }
+    log::info!("🗑️ 已删除媒体文件 {}", file_name(&asset));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_name() {
+        assert_eq!(clean_name("C:\\photos\\猫.jpg").as_deref(), Some("猫.jpg"));
+        assert_eq!(clean_name("../../etc/passwd").as_deref(), Some("passwd"));
+        assert_eq!(clean_name("a\nb.png").as_deref(), Some("ab.png"));
+        assert_eq!(clean_name("dir/"), None);
+    }
+
+    #[tokio::test]
+    async fn test_upload_deduplicates() {
+        let db = crate::infra::db::test_db().await;
+        let dir = std::env::temp_dir().join(format!("rowan-assets-{}", uuid::Uuid::new_v4()));
+        let blobs = crate::infra::blob::LocalBlobStore::new(&dir);
+
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(3, 2)
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        let png = Bytes::from(png.into_inner());
+
+        let (asset, created) = upload(&db, &blobs, png.clone(), Some("a.png"))
+            .await
+            .unwrap();
+        assert!(created);
+        assert_eq!((asset.width, asset.height), (Some(3), Some(2)));
+        let (again, created) = upload(&db, &blobs, png, Some("b.png")).await.unwrap();
+        assert!(!created);
+        assert_eq!(again.id, asset.id);
+        assert_eq!(
+            find_by_file_name(&db, &file_name(&asset)).await.unwrap().id,
+            asset.id
+        );
+
+        delete(&db, &blobs, asset.id).await.unwrap();
+        assert!(!blobs.exists(&storage_key(&asset)).await.unwrap());
+        assert!(matches!(get(&db, asset.id).await, Err(AppError::NotFound)));
+        assert!(matches!(
+            delete(&db, &blobs, asset.id).await,
+            Err(AppError::NotFound)
+        ));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,112 +1,363 @@
+//! 站长（管理员）认证
+//!
+//! - 密码使用 Argon2id 哈希，以 PHC 字符串存入 `admins.password_hash`
+//! - 登录成功后签发一对令牌：短期的访问令牌（JWT）和长期的刷新令牌
+//! - 刷新令牌由 `session_service` 管理，每使用一次就换发新的一对（轮换）
+//! - 访问令牌携带会话ID，会话被吊销后立即失效
+//! - 启用两步验证后，密码正确只会拿到短期的 MFA 令牌，验证码通过后才开启会话
+
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+};
 use chrono::{Duration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    sea_query::Expr,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
     config::JwtConfig,
     error::{AppError, AppResult},
-    models::{
-        dtos::{LoginRequest, RegisterRequest},
-        user::{self, Entity as User},
-    },
+    infra::db::entities::admins::{self, Entity as Admin},
+    service::session_service::{self, DeviceInfo},
 };
 
+/// 密码最短长度
+pub const MIN_PASSWORD_LEN: usize = 12;
+
+/// JWT 令牌类型，防止把一种令牌当作另一种使用
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenType {
+    Access,
+    /// 密码已验证、等待两步验证码的临时令牌
+    Mfa,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
-    pub sub: String, // 用户ID
-    pub email: String,
-    pub exp: usize, // 过期时间
-    pub iat: usize, // 签发时间
+    pub sub: String, // 管理员ID
+    pub username: String,
+    pub typ: TokenType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>, // 会话ID（刷新令牌的会话族），MFA 令牌没有
+    pub jti: String, // 令牌唯一ID
+    pub exp: usize,  // 过期时间
+    pub iat: usize,  // 签发时间
 }
 
-/// 用户注册
-pub async fn register(db: &DatabaseConnection, req: RegisterRequest) -> AppResult<user::Model> {
-    // 检查邮箱是否已存在
-    let existing_user = User::find()
-        .filter(user::Column::Email.eq(&req.email))
-        .one(db)
-        .await?;
+impl Claims {
+    /// 令牌所属的管理员ID
+    pub fn admin_id(&self) -> AppResult<i32> {
+        self.sub.parse().map_err(|_| AppError::Unauthorized)
+    }
+}
+
+/// 登录请求
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
 
-    if existing_user.is_some() {
-        return Err(AppError::Conflict("Email already exists".to_string()));
+/// 登录/刷新后返回的令牌对
+#[derive(Debug, Clone)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+/// 使用 Argon2id 哈希密码（CPU 密集，放到阻塞线程池中执行）
+pub async fn hash_password(password: String) -> AppResult<String> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|err| AppError::Internal(format!("密码哈希失败: {err}")))
+    })
+    .await
+    .map_err(|err| AppError::Internal(err.to_string()))?
+}
+
+/// 校验密码是否与哈希匹配
+pub async fn verify_password(password: String, password_hash: String) -> AppResult<bool> {
+    tokio::task::spawn_blocking(move || {
+        let parsed = PasswordHash::new(&password_hash)
+            .map_err(|err| AppError::Internal(format!("密码哈希格式错误: {err}")))?;
+        Ok(Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok())
+    })
+    .await
+    .map_err(|err| AppError::Internal(err.to_string()))?
+}
+
+/// 创建管理员账号（用于命令行初始化）
+pub async fn create_admin(
+    db: &DatabaseConnection,
+    username: &str,
+    password: &str,
+) -> AppResult<admins::Model> {
+    let username = username.trim();
+    if username.is_empty() || username.len() > 32 {
+        return Err(AppError::BadRequest(
+            "用户名长度必须在 1 到 32 之间".to_string(),
+        ));
+    }
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(AppError::BadRequest(format!(
+            "密码至少需要 {MIN_PASSWORD_LEN} 个字符"
+        )));
     }
 
-    // 加密密码
-    let password_hash = bcrypt::hash(&req.password, bcrypt::DEFAULT_COST)?;
+    let existing = Admin::find()
+        .filter(admins::Column::Username.eq(username))
+        .one(db)
+        .await?;
+    if existing.is_some() {
+        return Err(AppError::Conflict("管理员用户名已存在".to_string()));
+    }
 
-    // 创建新用户
-    let new_user = user::ActiveModel {
-        id: Set(Uuid::new_v4()),
-        username: Set(req.username),
-        email: Set(req.email),
+    let password_hash = hash_password(password.to_string()).await?;
+    let now = Utc::now();
+    let admin = admins::ActiveModel {
+        username: Set(username.to_string()),
         password_hash: Set(password_hash),
-        created_at: Set(Utc::now()),
-        updated_at: Set(Utc::now()),
+        last_login_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
         ..Default::default()
-    };
+    }
+    .insert(db)
+    .await?;
 
-    let user = new_user.insert(db).await?;
-    Ok(user)
+    Ok(admin)
 }
 
-/// 用户登录
-pub async fn login(db: &DatabaseConnection, req: LoginRequest) -> AppResult<user::Model> {
-    // 查找用户
-    let user = User::find()
-        .filter(user::Column::Email.eq(&req.email))
+/// 管理员登录，校验用户名和密码（不含两步验证）
+pub async fn login(db: &DatabaseConnection, req: LoginRequest) -> AppResult<admins::Model> {
+    let admin = Admin::find()
+        .filter(admins::Column::Username.eq(req.username.trim()))
         .one(db)
-        .await?
-        .ok_or(AppError::Unauthorized)?;
+        .await?;
 
-    // 验证密码
-    let is_valid = bcrypt::verify(&req.password, &user.password_hash)?;
-    if !is_valid {
+    let Some(admin) = admin else {
+        // 用户不存在时也做一次哈希，避免通过响应时间探测用户名
+        let _ = hash_password(req.password).await;
         return Err(AppError::Unauthorized);
-    }
+    };
 
-    Ok(user)
+    if !verify_password(req.password, admin.password_hash.clone()).await? {
+        return Err(AppError::Unauthorized);
+    }
+    Ok(admin)
 }
 
-/// 生成JWT令牌
-pub fn generate_token(user: &user::Model, config: &JwtConfig) -> AppResult<String> {
+fn encode_token(
+    admin: &admins::Model,
+    typ: TokenType,
+    session_id: Option<&str>,
+    jti: String,
+    ttl_secs: i64,
+    config: &JwtConfig,
+) -> AppResult<String> {
     let now = Utc::now();
-    let exp = now + Duration::seconds(config.expires_in);
+    let exp = now + Duration::seconds(ttl_secs);
 
     let claims = Claims {
-        sub: user.id.to_string(),
-        email: user.email.clone(),
+        sub: admin.id.to_string(),
+        username: admin.username.clone(),
+        typ,
+        sid: session_id.map(str::to_string),
+        jti,
         exp: exp.timestamp() as usize,
         iat: now.timestamp() as usize,
     };
 
-    let token = encode(
+    encode(
         &Header::default(),
         &claims,
         &EncodingKey::from_secret(config.secret.as_ref()),
-    )?;
+    )
+    .map_err(|err| AppError::Internal(format!("签发令牌失败: {err}")))
+}
 
-    Ok(token)
+/// 为某个会话签发访问令牌
+pub fn issue_access_token(
+    admin: &admins::Model,
+    session_id: &str,
+    config: &JwtConfig,
+) -> AppResult<String> {
+    encode_token(
+        admin,
+        TokenType::Access,
+        Some(session_id),
+        Uuid::new_v4().to_string(),
+        config.expires_in,
+        config,
+    )
 }
 
-/// 验证JWT令牌
-pub fn verify_token(token: &str, config: &JwtConfig) -> AppResult<Claims> {
+/// 密码验证通过但还需要两步验证时，签发短期的 MFA 令牌
+///
+/// `jti` 由 `totp_service::begin_challenge` 记录在管理员记录上，令牌只能提交一次。
+pub fn issue_mfa_token(
+    admin: &admins::Model,
+    jti: &str,
+    ttl_secs: i64,
+    config: &JwtConfig,
+) -> AppResult<String> {
+    encode_token(
+        admin,
+        TokenType::Mfa,
+        None,
+        jti.to_string(),
+        ttl_secs,
+        config,
+    )
+}
+
+/// 登录成功后开启新会话，签发访问令牌和刷新令牌
+pub async fn start_session(
+    db: &DatabaseConnection,
+    admin: &admins::Model,
+    device: DeviceInfo,
+    config: &JwtConfig,
+) -> AppResult<TokenPair> {
+    // 顺带清理过期的会话记录，避免表无限增长
+    session_service::prune_expired(db).await?;
+    Admin::update_many()
+        .col_expr(admins::Column::LastLoginAt, Expr::value(Utc::now()))
+        .filter(admins::Column::Id.eq(admin.id))
+        .exec(db)
+        .await?;
+    let issued =
+        session_service::create_session(db, admin.id, device, config.refresh_expires_in).await?;
+    Ok(TokenPair {
+        access_token: issue_access_token(admin, &issued.family_id, config)?,
+        refresh_token: issued.token,
+        expires_in: config.expires_in,
+    })
+}
+
+/// 验证JWT令牌，并检查令牌类型
+pub fn verify_token(token: &str, expected: TokenType, config: &JwtConfig) -> AppResult<Claims> {
     let token_data = decode::<Claims>(
         token,
         &DecodingKey::from_secret(config.secret.as_ref()),
         &Validation::default(),
-    )?;
+    )
+    .map_err(|_| AppError::Unauthorized)?;
 
+    if token_data.claims.typ != expected {
+        return Err(AppError::Unauthorized);
+    }
     Ok(token_data.claims)
 }
 
-/// 根据用户ID获取用户
-pub async fn get_user_by_id(db: &DatabaseConnection, user_id: Uuid) -> AppResult<user::Model> {
-    let user = User::find_by_id(user_id)
+/// 使用刷新令牌换发新的令牌对
+pub async fn refresh(
+    db: &DatabaseConnection,
+    refresh_token: &str,
+    device: DeviceInfo,
+    config: &JwtConfig,
+) -> AppResult<TokenPair> {
+    let issued =
+        session_service::rotate(db, refresh_token, device, config.refresh_expires_in).await?;
+    // 管理员被删除后，会话随外键级联删除，这里只是兜底
+    let admin = Admin::find_by_id(issued.admin_id)
+        .one(db)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    Ok(TokenPair {
+        access_token: issue_access_token(&admin, &issued.family_id, config)?,
+        refresh_token: issued.token,
+        expires_in: config.expires_in,
+    })
+}
+
+/// 根据管理员ID获取管理员
+pub async fn get_admin_by_id(db: &DatabaseConnection, admin_id: i32) -> AppResult<admins::Model> {
+    let admin = Admin::find_by_id(admin_id)
         .one(db)
         .await?
         .ok_or(AppError::NotFound)?;
 
-    Ok(user)
+    Ok(admin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> JwtConfig {
+        JwtConfig {
+            secret: "test-secret".to_string(),
+            expires_in: 60,
+            refresh_expires_in: 3600,
+        }
+    }
+
+    fn admin() -> admins::Model {
+        admins::Model {
+            id: 7,
+            username: "rowan".to_string(),
+            password_hash: String::new(),
+            last_login_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            totp_secret: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
+            mfa_challenge_jti: None,
+            mfa_failed_attempts: 0,
+            mfa_locked_until: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_password_hash_roundtrip() {
+        let hash = hash_password("correct horse battery".to_string())
+            .await
+            .unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(
+            verify_password("correct horse battery".to_string(), hash.clone())
+                .await
+                .unwrap()
+        );
+        assert!(!verify_password("wrong".to_string(), hash).await.unwrap());
+    }
+
+    #[test]
+    fn test_token_types_are_not_interchangeable() {
+        let config = config();
+        let token = issue_access_token(&admin(), "family-1", &config).unwrap();
+
+        let claims = verify_token(&token, TokenType::Access, &config).unwrap();
+        assert_eq!(claims.admin_id().unwrap(), 7);
+        assert_eq!(claims.sid.as_deref(), Some("family-1"));
+        assert!(verify_token("not-a-jwt", TokenType::Access, &config).is_err());
+
+        let mfa = issue_mfa_token(&admin(), "challenge-1", 60, &config).unwrap();
+        assert!(verify_token(&mfa, TokenType::Access, &config).is_err());
+        assert!(
+            verify_token(&mfa, TokenType::Mfa, &config)
+                .unwrap()
+                .sid
+                .is_none()
+        );
+
+        let other = JwtConfig {
+            secret: "other".to_string(),
+            ..config
+        };
+        assert!(verify_token(&token, TokenType::Access, &other).is_err());
+    }
 }
@@ -0,0 +1,571 @@
+//! SQLite 数据库备份与恢复
+//!
+//! 备份用 `VACUUM INTO` 在线生成一致的快照，不阻塞正在运行的服务；快照先做一次
+//! `PRAGMA integrity_check`，再按配置压缩，压缩后重新解压一遍核对 SHA-256，
+//! 确认无误才以 `rowan-YYYYMMDD-HHMMSS.db[.gz|.zst]` 的名字放进备份目录，
+//! 旁边的 `.sha256` 文件记录备份文件本身的 SHA-256，可以直接用 `sha256sum -c` 核对。
+//! 每次备份后按保留份数和最长保留天数清理旧备份，最新的一份总会保留。
+//!
+//! 恢复只能在服务停止时进行（见 `infra::db::DatabaseLock`）：先核对备份文件的校验和，再解压到临时文件检查完整性，
+//! 原数据库连同 `-wal`、`-shm` 改名保留，再把临时文件换上去。
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend,
+    sqlx::{Connection, sqlite::SqliteConnectOptions, sqlite::SqliteConnection},
+};
+use sha2::{Digest, Sha256};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::BackupConfig,
+    error::{AppError, AppResult},
+    infra::{db::DatabaseLock, metrics},
+};
+
+/// 备份文件名前缀
+const PREFIX: &str = "rowan-";
+/// 备份文件名中的时间格式（UTC）
+const TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+/// 校验文件的扩展名
+const CHECKSUM_EXT: &str = "sha256";
+
+/// 备份文件的压缩方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// 备份文件名的后缀
+    fn extension(self) -> &'static str {
+        match self {
+            Self::None => ".db",
+            Self::Gzip => ".db.gz",
+            Self::Zstd => ".db.zst",
+        }
+    }
+
+    /// 根据文件名判断压缩方式
+    fn from_file_name(name: &str) -> Option<Self> {
+        [Self::Zstd, Self::Gzip, Self::None]
+            .into_iter()
+            .find(|compression| name.ends_with(compression.extension()))
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "gzip" | "gz" => Ok(Self::Gzip),
+            "zstd" | "zst" => Ok(Self::Zstd),
+            other => Err(format!("不支持的压缩方式: {other}")),
+        }
+    }
+}
+
+/// 备份目录中的一份备份
+#[derive(Debug, Clone)]
+pub struct BackupFile {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub compression: Compression,
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+impl BackupFile {
+    /// 从文件名解析，不是备份文件时返回 `None`
+    fn parse(path: PathBuf, size: u64) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let compression = Compression::from_file_name(name)?;
+        let time = name
+            .strip_prefix(PREFIX)?
+            .strip_suffix(compression.extension())?;
+        let created_at = NaiveDateTime::parse_from_str(time, TIME_FORMAT)
+            .ok()?
+            .and_utc();
+        Some(Self {
+            path,
+            created_at,
+            compression,
+            size,
+        })
+    }
+}
+
+fn io_error(action: &str, path: &Path, err: impl std::fmt::Display) -> AppError {
+    AppError::Internal(format!("{action} {} 失败: {err}", path.display()))
+}
+
+/// 在路径后面追加后缀，例如 `myweb.db` → `myweb.db-wal`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// 生成一份备份，返回备份文件
+pub async fn create_backup(
+    db: &DatabaseConnection,
+    config: &BackupConfig,
+) -> AppResult<BackupFile> {
+    if db.get_database_backend() != DbBackend::Sqlite {
+        return Err(AppError::BadRequest(
+            "在线备份只支持 SQLite，PostgreSQL 请使用 pg_dump".to_string(),
+        ));
+    }
+    let dir = Path::new(&config.dir);
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|err| io_error("创建备份目录", dir, err))?;
+
+    let now = Utc::now();
+    let name = format!(
+        "{PREFIX}{}{}",
+        now.format(TIME_FORMAT),
+        config.compression.extension()
+    );
+    let path = dir.join(&name);
+    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        return Err(AppError::Conflict(format!("备份 {name} 已存在")));
+    }
+
+    let snapshot = with_suffix(&path, ".snapshot");
+    let result = write_backup(db, &snapshot, &path, config.compression).await;
+    let _ = tokio::fs::remove_file(&snapshot).await;
+    let hash = match result {
+        Ok(hash) => hash,
+        Err(err) => {
+            let _ = tokio::fs::remove_file(with_suffix(&path, ".partial")).await;
+            return Err(err);
+        }
+    };
+
+    let checksum = with_suffix(&path, &format!(".{CHECKSUM_EXT}"));
+    tokio::fs::write(&checksum, format!("{hash}  {name}\n"))
+        .await
+        .map_err(|err| io_error("写入", &checksum, err))?;
+
+    let size = tokio::fs::metadata(&path)
+        .await
+        .map_err(|err| io_error("读取", &path, err))?
+        .len();
+    log::info!("💾 已备份数据库到 {}（{size} 字节）", path.display());
+    Ok(BackupFile {
+        path,
+        created_at: now,
+        compression: config.compression,
+        size,
+    })
+}
+
+/// 生成快照、校验、压缩并核对，返回备份文件的 SHA-256
+async fn write_backup(
+    db: &DatabaseConnection,
+    snapshot: &Path,
+    path: &Path,
+    compression: Compression,
+) -> AppResult<String> {
+    let target = snapshot.to_string_lossy().replace('\'', "''");
+    db.execute_unprepared(&format!("VACUUM INTO '{target}'"))
+        .await?;
+    check_integrity(snapshot).await?;
+
+    let (snapshot, path) = (snapshot.to_path_buf(), path.to_path_buf());
+    tokio::task::spawn_blocking(move || {
+        let hash = hash_file(&snapshot, Compression::None)?;
+        let partial = with_suffix(&path, ".partial");
+        compress(&snapshot, &partial, compression)?;
+        if hash_file(&partial, compression)? != hash {
+            return Err(AppError::Internal(format!(
+                "备份 {} 解压后与快照不一致",
+                path.display()
+            )));
+        }
+        let checksum = hash_file(&partial, Compression::None)?;
+        std::fs::rename(&partial, &path).map_err(|err| io_error("重命名", &partial, err))?;
+        Ok(checksum)
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("备份任务异常退出: {err}")))?
+}
+
+/// 打开一个独立的连接检查数据库文件是否完好，同时改回普通的回滚日志模式，
+/// 这样单个文件就是完整的数据库
+async fn check_integrity(path: &Path) -> AppResult<()> {
+    let options = SqliteConnectOptions::new().filename(path);
+    let mut conn = SqliteConnection::connect_with(&options)
+        .await
+        .map_err(|err| io_error("打开", path, err))?;
+    let result: AppResult<()> = async {
+        sea_orm::sqlx::query("PRAGMA journal_mode = DELETE")
+            .execute(&mut conn)
+            .await
+            .map_err(|err| io_error("检查", path, err))?;
+        let messages: Vec<String> = sea_orm::sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|err| io_error("检查", path, err))?;
+        if messages != ["ok"] {
+            return Err(AppError::Internal(format!(
+                "{} 未通过完整性检查: {}",
+                path.display(),
+                messages.join("; ")
+            )));
+        }
+        Ok(())
+    }
+    .await;
+    let _ = conn.close().await;
+    result
+}
+
+fn compress(source: &Path, target: &Path, compression: Compression) -> AppResult<()> {
+    let mut reader =
+        BufReader::new(File::open(source).map_err(|err| io_error("读取", source, err))?);
+    let file = File::create(target).map_err(|err| io_error("创建", target, err))?;
+    let write = |err| io_error("写入", target, err);
+    let mut writer = BufWriter::new(file);
+    match compression {
+        Compression::None => {
+            io::copy(&mut reader, &mut writer).map_err(write)?;
+        }
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+            io::copy(&mut reader, &mut encoder).map_err(write)?;
+            writer = encoder.finish().map_err(write)?;
+        }
+        Compression::Zstd => {
+            zstd::stream::copy_encode(&mut reader, &mut writer, 0).map_err(write)?;
+        }
+    }
+    let file = writer.into_inner().map_err(|err| write(err.into_error()))?;
+    file.sync_all().map_err(write)
+}
+
+/// 解压后的内容
+fn decoder(path: &Path, compression: Compression) -> AppResult<Box<dyn Read>> {
+    let file = BufReader::new(File::open(path).map_err(|err| io_error("读取", path, err))?);
+    Ok(match compression {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(flate2::read::GzDecoder::new(file)),
+        Compression::Zstd => Box::new(
+            zstd::stream::read::Decoder::with_buffer(file)
+                .map_err(|err| io_error("解压", path, err))?,
+        ),
+    })
+}
+
+/// 文件解压后内容的 SHA-256
+fn hash_file(path: &Path, compression: Compression) -> AppResult<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut decoder(path, compression)?, &mut hasher)
+        .map_err(|err| io_error("解压", path, err))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 备份目录中的全部备份，按时间从新到旧
+pub async fn list_backups(dir: &Path) -> AppResult<Vec<BackupFile>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(io_error("读取备份目录", dir, err)),
+    };
+    let mut backups = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|err| io_error("读取备份目录", dir, err))?
+    {
+        let size = entry.metadata().await.map(|meta| meta.len()).unwrap_or(0);
+        backups.extend(BackupFile::parse(entry.path(), size));
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    Ok(backups)
+}
+
+/// 按保留策略需要删除的备份：超出 `keep` 份的，以及早于 `max_age` 的；最新的一份总会保留
+fn expired(
+    backups: &[BackupFile],
+    keep: usize,
+    max_age: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Vec<&BackupFile> {
+    let cutoff = max_age
+        .and_then(|age| chrono::Duration::from_std(age).ok())
+        .map(|age| now - age);
+    backups
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(index, backup)| {
+            *index >= keep.max(1) || cutoff.is_some_and(|cutoff| backup.created_at < cutoff)
+        })
+        .map(|(_, backup)| backup)
+        .collect()
+}
+
+/// 按保留策略清理旧备份，返回删除的份数
+pub async fn prune(config: &BackupConfig) -> AppResult<usize> {
+    let backups = list_backups(Path::new(&config.dir)).await?;
+    let expired = expired(&backups, config.keep, config.max_age(), Utc::now());
+    for backup in &expired {
+        tokio::fs::remove_file(&backup.path)
+            .await
+            .map_err(|err| io_error("删除", &backup.path, err))?;
+        let _ =
+            tokio::fs::remove_file(with_suffix(&backup.path, &format!(".{CHECKSUM_EXT}"))).await;
+        log::info!("🧹 已删除过期备份 {}", backup.path.display());
+    }
+    Ok(expired.len())
+}
+
+/// 定时备份并清理旧备份，直到收到关闭信号；启动时不会立即备份
+pub async fn run_scheduler(
+    db: DatabaseConnection,
+    config: BackupConfig,
+    shutdown: CancellationToken,
+) {
+    let interval = config.interval();
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+        if let Err(err) = metrics::time_job("backup", create_backup(&db, &config)).await {
+            log::warn!("⚠️ 定时备份数据库失败: {err}");
+            continue;
+        }
+        if let Err(err) = prune(&config).await {
+            log::warn!("⚠️ 清理旧备份失败: {err}");
+        }
+    }
+}
+
+/// 用备份替换数据库文件，返回原数据库改名后的路径（原来没有数据库时为空）
+///
+/// 服务或其他会写数据库的命令（迁移、导入、创建管理员）正在运行时拒绝执行
+pub async fn restore(backup: &Path, database: &Path) -> AppResult<Option<PathBuf>> {
+    let _lock = DatabaseLock::acquire(database)?;
+
+    let name = backup
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let compression = Compression::from_file_name(name)
+        .ok_or_else(|| AppError::BadRequest(format!("无法识别的备份文件 {}", backup.display())))?;
+
+    let staging = with_suffix(database, ".restoring");
+    let result = stage(backup, &staging, compression).await;
+    if let Err(err) = result {
+        let _ = tokio::fs::remove_file(&staging).await;
+        return Err(err);
+    }
+
+    let previous = if tokio::fs::try_exists(database).await.unwrap_or(false) {
+        let previous = with_suffix(
+            database,
+            &format!(".pre-restore-{}", Utc::now().format(TIME_FORMAT)),
+        );
+        for suffix in ["", "-wal", "-shm"] {
+            let from = with_suffix(database, suffix);
+            if tokio::fs::try_exists(&from).await.unwrap_or(false) {
+                let to = with_suffix(&previous, suffix);
+                tokio::fs::rename(&from, &to)
+                    .await
+                    .map_err(|err| io_error("重命名", &from, err))?;
+            }
+        }
+        Some(previous)
+    } else {
+        None
+    };
+    tokio::fs::rename(&staging, database)
+        .await
+        .map_err(|err| io_error("重命名", &staging, err))?;
+    log::info!("♻️ 已从 {} 恢复数据库", backup.display());
+    Ok(previous)
+}
+
+/// 核对校验文件（如果有），解压备份到临时文件并检查完整性
+async fn stage(backup: &Path, staging: &Path, compression: Compression) -> AppResult<()> {
+    let checksum = with_suffix(backup, &format!(".{CHECKSUM_EXT}"));
+    let expected = match tokio::fs::read_to_string(&checksum).await {
+        Ok(line) => line.split_whitespace().next().map(str::to_string),
+        Err(_) => {
+            log::warn!("⚠️ 没有找到 {}，跳过校验和核对", checksum.display());
+            None
+        }
+    };
+
+    let (backup, staging_path) = (backup.to_path_buf(), staging.to_path_buf());
+    tokio::task::spawn_blocking(move || {
+        if let Some(expected) = expected
+            && expected != hash_file(&backup, Compression::None)?
+        {
+            return Err(AppError::BadRequest(format!(
+                "备份 {} 的校验和不一致，文件可能已损坏",
+                backup.display()
+            )));
+        }
+
+        let mut reader = decoder(&backup, compression)?;
+        let file =
+            File::create(&staging_path).map_err(|err| io_error("创建", &staging_path, err))?;
+        let mut writer = BufWriter::new(file);
+        io::copy(&mut reader, &mut writer).map_err(|err| io_error("解压", &backup, err))?;
+        writer
+            .into_inner()
+            .map_err(|err| io_error("写入", &staging_path, err.into_error()))?
+            .sync_all()
+            .map_err(|err| io_error("写入", &staging_path, err))
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("恢复任务异常退出: {err}")))??;
+
+    check_integrity(staging).await
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{Database, Statement};
+
+    use super::*;
+
+    fn backup_at(minutes_ago: i64, now: DateTime<Utc>) -> BackupFile {
+        BackupFile {
+            path: PathBuf::from(format!("{minutes_ago}")),
+            created_at: now - chrono::Duration::minutes(minutes_ago),
+            compression: Compression::Zstd,
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_and_retention() {
+        let parsed = BackupFile::parse(PathBuf::from("rowan-20261019-180000.db.zst"), 1).unwrap();
+        assert_eq!(parsed.compression, Compression::Zstd);
+        assert_eq!(parsed.created_at.to_rfc3339(), "2026-10-19T18:00:00+00:00");
+        assert!(
+            BackupFile::parse(PathBuf::from("rowan-20261019-180000.db.zst.sha256"), 1).is_none()
+        );
+        assert!(BackupFile::parse(PathBuf::from("other.db"), 1).is_none());
+
+        let now = Utc::now();
+        let backups: Vec<_> = [0, 60, 120, 180].map(|ago| backup_at(ago, now)).into();
+        let names = |expired: Vec<&BackupFile>| {
+            expired
+                .iter()
+                .map(|backup| backup.path.display().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(expired(&backups, 2, None, now)), ["120", "180"]);
+        assert_eq!(
+            names(expired(
+                &backups,
+                10,
+                Some(Duration::from_secs(90 * 60)),
+                now
+            )),
+            ["120", "180"]
+        );
+        // 最新的一份即使过期也保留
+        let old: Vec<_> = [600, 700].map(|ago| backup_at(ago, now)).into();
+        assert_eq!(
+            names(expired(&old, 0, Some(Duration::from_secs(60)), now)),
+            ["700"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let dir = std::env::temp_dir().join(format!("rowan-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let database = dir.join("site.db");
+        let url = format!("sqlite://{}?mode=rwc", database.display());
+        let db = Database::connect(&url).await.unwrap();
+        db.execute_unprepared(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);
+             INSERT INTO t (name) VALUES ('before');",
+        )
+        .await
+        .unwrap();
+
+        let mut config = BackupConfig {
+            dir: dir.join("backups").display().to_string(),
+            interval_secs: 0,
+            keep: 7,
+            max_age_days: 0,
+            compression: Compression::Gzip,
+        };
+        let gzip = create_backup(&db, &config).await.unwrap();
+        assert_eq!(gzip.compression, Compression::Gzip);
+        // 校验文件记录的是备份文件本身的哈希，与 `sha256sum` 的输出一致
+        let line = std::fs::read_to_string(with_suffix(&gzip.path, ".sha256")).unwrap();
+        let digest = hex::encode(Sha256::digest(std::fs::read(&gzip.path).unwrap()));
+        assert_eq!(
+            line,
+            format!(
+                "{digest}  {}\n",
+                gzip.path.file_name().unwrap().to_string_lossy()
+            )
+        );
+
+        // 同一秒内的第二份备份会重名，换一种压缩方式
+        config.compression = Compression::Zstd;
+        create_backup(&db, &config).await.unwrap();
+        assert_eq!(list_backups(Path::new(&config.dir)).await.unwrap().len(), 2);
+
+        db.execute_unprepared("INSERT INTO t (name) VALUES ('after')")
+            .await
+            .unwrap();
+        // 数据库仍在使用时不能恢复
+        let lock = DatabaseLock::acquire(&database).unwrap();
+        assert!(matches!(
+            restore(&gzip.path, &database).await,
+            Err(AppError::Conflict(_))
+        ));
+        drop(lock);
+        db.close().await.unwrap();
+
+        let previous = restore(&gzip.path, &database).await.unwrap().unwrap();
+        assert!(previous.exists());
+        let db = Database::connect(&url).await.unwrap();
+        let rows = db
+            .query_all(Statement::from_string(
+                DbBackend::Sqlite,
+                "SELECT name FROM t",
+            ))
+            .await
+            .unwrap();
+        let names: Vec<String> = rows
+            .iter()
+            .map(|row| row.try_get("", "name").unwrap())
+            .collect();
+        assert_eq!(names, ["before"]);
+        db.close().await.unwrap();
+
+        // 校验和不一致时拒绝恢复
+        std::fs::write(with_suffix(&gzip.path, ".sha256"), "00  x\n").unwrap();
+        assert!(matches!(
+            restore(&gzip.path, &database).await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,538 @@
+//! 随笔服务
+//!
+//! 随笔有三种状态：
+//! - `draft`：草稿，只有站长能看到
+//! - `scheduled`：定时发布，到 `publish_at` 后由后台任务自动转为已发布
+//! - `published`：已发布，`publish_at` 为实际发布时间
+//!
+//! 每次修改标题或正文前，都会把旧版本存进 `essay_revisions`。
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::{AppError, AppResult},
+    infra::{
+        db::entities::{essay_revisions, essays},
+        metrics,
+        repositories::{EssayRepository, SeaOrmRepository},
+        unit_of_work,
+    },
+    service::PaginationQuery,
+};
+
+/// 标题最大长度，与 `essays.title` 列一致
+pub const MAX_TITLE_LEN: usize = 20;
+
+/// 随笔状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EssayStatus {
+    Draft,
+    Published,
+    Scheduled,
+}
+
+impl EssayStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Published => "published",
+            Self::Scheduled => "scheduled",
+        }
+    }
+}
+
+/// 创建随笔请求
+#[derive(Debug, Deserialize)]
+pub struct CreateEssayRequest {
+    pub title: String,
+    pub content: String,
+    #[serde(default = "default_status")]
+    pub status: EssayStatus,
+    /// 定时发布时必填；直接发布时可选，用于补录过去的发布时间
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+fn default_status() -> EssayStatus {
+    EssayStatus::Draft
+}
+
+/// 更新随笔请求，未提供的字段保持不变
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateEssayRequest {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub status: Option<EssayStatus>,
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+/// 两个版本之间的差异
+#[derive(Debug, Clone)]
+pub struct EssayDiff {
+    pub from: i32,
+    /// 为 `None` 时表示当前版本
+    pub to: Option<i32>,
+    /// unified diff 格式的正文差异
+    pub unified: String,
+    pub title_changed: bool,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+fn validate(title: &str, content: &str) -> AppResult<()> {
+    let len = title.trim().chars().count();
+    if len == 0 || len > MAX_TITLE_LEN {
+        return Err(AppError::BadRequest(format!(
+            "标题长度必须在 1 到 {MAX_TITLE_LEN} 之间"
+        )));
+    }
+    if content.trim().is_empty() {
+        return Err(AppError::BadRequest("正文不能为空".to_string()));
+    }
+    Ok(())
+}
+
+/// 根据目标状态计算 `publish_at`
+fn resolve_publish_at(
+    status: EssayStatus,
+    requested: Option<DateTime<Utc>>,
+    current: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> AppResult<Option<DateTime<Utc>>> {
+    match status {
+        EssayStatus::Draft => Ok(None),
+        EssayStatus::Scheduled => match requested {
+            Some(at) if at > now => Ok(Some(at)),
+            Some(_) => Err(AppError::BadRequest(
+                "定时发布时间必须晚于当前时间".to_string(),
+            )),
+            None => Err(AppError::BadRequest(
+                "定时发布需要指定 publish_at".to_string(),
+            )),
+        },
+        EssayStatus::Published => match requested {
+            Some(at) if at > now => Err(AppError::BadRequest(
+                "发布时间不能晚于当前时间，请使用定时发布".to_string(),
+            )),
+            Some(at) => Ok(Some(at)),
+            // 已发布的随笔保留原发布时间
+            None => Ok(Some(current.filter(|at| *at <= now).unwrap_or(now))),
+        },
+    }
+}
+
+/// 获取已发布的随笔列表，按发布时间倒序
+pub async fn list_published(
+    repo: &dyn EssayRepository,
+    query: &PaginationQuery,
+) -> AppResult<(Vec<essays::Model>, u64)> {
+    list(repo, Some(EssayStatus::Published), query).await
+}
+
+/// 获取随笔列表（站长），可按状态筛选
+pub async fn list(
+    repo: &dyn EssayRepository,
+    status: Option<EssayStatus>,
+    query: &PaginationQuery,
+) -> AppResult<(Vec<essays::Model>, u64)> {
+    repo.list_essays(status.map(EssayStatus::as_str), query.page())
+        .await
+}
+
+/// 获取任意状态的随笔（站长）
+pub async fn get_essay(repo: &dyn EssayRepository, id: i32) -> AppResult<essays::Model> {
+    repo.find_essay(id).await?.ok_or(AppError::NotFound)
+}
+
+/// 获取已发布的随笔，草稿和未到时间的定时随笔视为不存在
+pub async fn get_published(repo: &dyn EssayRepository, id: i32) -> AppResult<essays::Model> {
+    repo.find_essay(id)
+        .await?
+        .filter(|essay| essay.status == EssayStatus::Published.as_str())
+        .ok_or(AppError::NotFound)
+}
+
+/// 创建随笔
+pub async fn create_essay(
+    repo: &dyn EssayRepository,
+    req: CreateEssayRequest,
+) -> AppResult<essays::Model> {
+    validate(&req.title, &req.content)?;
+    let now = Utc::now();
+    let publish_at = resolve_publish_at(req.status, req.publish_at, None, now)?;
+
+    repo.insert_essay(essays::Model {
+        id: 0,
+        title: req.title.trim().to_string(),
+        content: req.content,
+        created_at: now,
+        updated_at: now,
+        status: req.status.as_str().to_string(),
+        publish_at,
+    })
+    .await
+}
+
+/// 更新随笔；标题或正文有变化时先保存旧版本
+pub async fn update_essay(
+    db: &DatabaseConnection,
+    id: i32,
+    req: UpdateEssayRequest,
+) -> AppResult<essays::Model> {
+    let req = &req;
+    unit_of_work::run(db, |uow| {
+        Box::pin(async move { apply_update(&uow.repo(), id, req).await })
+    })
+    .await
+}
+
+async fn apply_update(
+    repo: &dyn EssayRepository,
+    id: i32,
+    req: &UpdateEssayRequest,
+) -> AppResult<essays::Model> {
+    let essay = get_essay(repo, id).await?;
+
+    let title = req
+        .title
+        .as_deref()
+        .map_or_else(|| essay.title.clone(), |title| title.trim().to_string());
+    let content = req.content.clone().unwrap_or_else(|| essay.content.clone());
+    validate(&title, &content)?;
+
+    let now = Utc::now();
+    let current_status = parse_status(&essay.status);
+    let status = req.status.unwrap_or(current_status);
+    let publish_at = if req.status.is_some() || req.publish_at.is_some() {
+        resolve_publish_at(status, req.publish_at, essay.publish_at, now)?
+    } else {
+        essay.publish_at
+    };
+
+    if title != essay.title || content != essay.content {
+        save_revision(repo, &essay).await?;
+    }
+
+    repo.update_essay(essays::Model {
+        title,
+        content,
+        status: status.as_str().to_string(),
+        publish_at,
+        updated_at: now,
+        ..essay
+    })
+    .await
+}
+
+/// 删除随笔，历史版本随外键级联删除
+pub async fn delete_essay(repo: &dyn EssayRepository, id: i32) -> AppResult<()> {
+    if !repo.delete_essay(id).await? {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// 数据库中的状态字符串，未知值按草稿处理，避免误发布
+pub fn parse_status(status: &str) -> EssayStatus {
+    match status {
+        "published" => EssayStatus::Published,
+        "scheduled" => EssayStatus::Scheduled,
+        _ => EssayStatus::Draft,
+    }
+}
+
+async fn save_revision(repo: &dyn EssayRepository, essay: &essays::Model) -> AppResult<()> {
+    let latest = repo.latest_revision(essay.id).await?;
+    repo.insert_revision(essay_revisions::Model {
+        id: 0,
+        essay_id: essay.id,
+        revision: latest.unwrap_or(0) + 1,
+        title: essay.title.clone(),
+        content: essay.content.clone(),
+        // 旧版本的内容定稿于上次更新时
+        created_at: essay.updated_at,
+    })
+    .await?;
+    Ok(())
+}
+
+/// 获取随笔的历史版本，按版本号倒序
+pub async fn list_revisions(
+    repo: &dyn EssayRepository,
+    essay_id: i32,
+) -> AppResult<Vec<essay_revisions::Model>> {
+    get_essay(repo, essay_id).await?;
+    repo.list_revisions(essay_id).await
+}
+
+async fn get_revision(
+    repo: &dyn EssayRepository,
+    essay_id: i32,
+    revision: i32,
+) -> AppResult<essay_revisions::Model> {
+    repo.find_revision(essay_id, revision)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// 比较两个版本；`to` 为空时与当前版本比较
+pub async fn diff(
+    repo: &dyn EssayRepository,
+    essay_id: i32,
+    from: i32,
+    to: Option<i32>,
+) -> AppResult<EssayDiff> {
+    let old = get_revision(repo, essay_id, from).await?;
+    let (new_title, new_content) = match to {
+        Some(to) => {
+            let new = get_revision(repo, essay_id, to).await?;
+            (new.title, new.content)
+        }
+        None => {
+            let essay = get_essay(repo, essay_id).await?;
+            (essay.title, essay.content)
+        }
+    };
+
+    Ok(diff_texts(
+        from,
+        to,
+        &old.title,
+        &old.content,
+        &new_title,
+        &new_content,
+    ))
+}
+
+fn diff_texts(
+    from: i32,
+    to: Option<i32>,
+    old_title: &str,
+    old_content: &str,
+    new_title: &str,
+    new_content: &str,
+) -> EssayDiff {
+    let text_diff = TextDiff::from_lines(old_content, new_content);
+    let (mut additions, mut deletions) = (0, 0);
+    for change in text_diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => additions += 1,
+            ChangeTag::Delete => deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+
+    let to_label = to.map_or_else(|| "current".to_string(), |to| format!("r{to}"));
+    let unified = text_diff
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("r{from}"), &to_label)
+        .to_string();
+
+    EssayDiff {
+        from,
+        to,
+        unified,
+        title_changed: old_title != new_title,
+        additions,
+        deletions,
+    }
+}
+
+/// 发布所有已到时间的定时随笔，返回发布的数量
+pub async fn publish_due(repo: &dyn EssayRepository, now: DateTime<Utc>) -> AppResult<u64> {
+    repo.transition_due_essays(
+        EssayStatus::Scheduled.as_str(),
+        EssayStatus::Published.as_str(),
+        now,
+    )
+    .await
+}
+
+/// 定时发布任务，直到收到关闭信号
+pub async fn run_publisher(
+    db: DatabaseConnection,
+    interval: Duration,
+    shutdown: CancellationToken,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+        let repo = SeaOrmRepository::new(&db);
+        match metrics::time_job("essay_publish", publish_due(&repo, Utc::now())).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("📰 已自动发布 {count} 篇定时随笔"),
+            Err(err) => log::warn!("⚠️ 定时发布随笔失败: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::repositories::memory::InMemoryRepository;
+
+    #[test]
+    fn test_resolve_publish_at() {
+        let now = Utc::now();
+        let later = now + chrono::Duration::hours(1);
+        let earlier = now - chrono::Duration::hours(1);
+
+        assert_eq!(
+            resolve_publish_at(EssayStatus::Draft, Some(later), None, now).unwrap(),
+            None
+        );
+        assert_eq!(
+            resolve_publish_at(EssayStatus::Scheduled, Some(later), None, now).unwrap(),
+            Some(later)
+        );
+        assert!(resolve_publish_at(EssayStatus::Scheduled, Some(earlier), None, now).is_err());
+        assert!(resolve_publish_at(EssayStatus::Scheduled, None, None, now).is_err());
+        assert!(resolve_publish_at(EssayStatus::Published, Some(later), None, now).is_err());
+        assert_eq!(
+            resolve_publish_at(EssayStatus::Published, None, Some(earlier), now).unwrap(),
+            Some(earlier)
+        );
+        // 定时随笔提前发布时使用当前时间
+        assert_eq!(
+            resolve_publish_at(EssayStatus::Published, None, Some(later), now).unwrap(),
+            Some(now)
+        );
+    }
+
+    #[test]
+    fn test_diff_counts_lines() {
+        let diff = diff_texts(1, None, "标题", "a\nb\nc\n", "标题", "a\nB\nc\nd\n");
+        assert_eq!((diff.additions, diff.deletions), (2, 1));
+        assert!(!diff.title_changed);
+        assert!(diff.unified.contains("--- r1"));
+        assert!(diff.unified.contains("+++ current"));
+        assert!(diff.unified.contains("-b"));
+        assert!(diff.unified.contains("+B"));
+    }
+
+    #[tokio::test]
+    async fn test_revisions_and_publishing_in_memory() {
+        let repo = InMemoryRepository::new();
+        let now = Utc::now();
+        let essay = create_essay(
+            &repo,
+            CreateEssayRequest {
+                title: "草稿".to_string(),
+                content: "a\n".to_string(),
+                status: EssayStatus::Draft,
+                publish_at: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        for content in ["b\n", "c\n"] {
+            apply_update(
+                &repo,
+                essay.id,
+                &UpdateEssayRequest {
+                    content: Some(content.to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        }
+        // 只改状态不产生新版本
+        apply_update(
+            &repo,
+            essay.id,
+            &UpdateEssayRequest {
+                status: Some(EssayStatus::Scheduled),
+                publish_at: Some(now + chrono::Duration::minutes(5)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let revisions = list_revisions(&repo, essay.id).await.unwrap();
+        assert_eq!(
+            revisions.iter().map(|r| r.revision).collect::<Vec<_>>(),
+            [2, 1]
+        );
+        let diff = diff(&repo, essay.id, 1, None).await.unwrap();
+        assert_eq!((diff.additions, diff.deletions), (1, 1));
+
+        assert_eq!(
+            list_published(&repo, &PaginationQuery::default())
+                .await
+                .unwrap()
+                .1,
+            0
+        );
+        assert_eq!(
+            publish_due(&repo, now + chrono::Duration::minutes(10))
+                .await
+                .unwrap(),
+            1
+        );
+        let (published, total) = list_published(&repo, &PaginationQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(published[0].content, "c\n");
+
+        delete_essay(&repo, essay.id).await.unwrap();
+        assert!(matches!(
+            list_revisions(&repo, essay.id).await,
+            Err(AppError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_essay_lifecycle() {
+        let db = crate::infra::db::test_db().await;
+        let repo = SeaOrmRepository::new(&db);
+        let now = Utc::now();
+        let essay = create_essay(
+            &repo,
+            CreateEssayRequest {
+                title: "定时随笔".to_string(),
+                content: "第一版\n".to_string(),
+                status: EssayStatus::Scheduled,
+                publish_at: Some(now + chrono::Duration::hours(1)),
+            },
+        )
+        .await
+        .unwrap();
+        assert!(get_published(&repo, essay.id).await.is_err());
+
+        update_essay(
+            &db,
+            essay.id,
+            UpdateEssayRequest {
+                content: Some("第二版\n".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(list_revisions(&repo, essay.id).await.unwrap().len(), 1);
+
+        assert_eq!(publish_due(&repo, now).await.unwrap(), 0);
+        assert_eq!(
+            publish_due(&repo, now + chrono::Duration::hours(2))
+                .await
+                .unwrap(),
+            1
+        );
+        let published = get_published(&repo, essay.id).await.unwrap();
+        assert_eq!(published.content, "第二版\n");
+    }
+}
@@ -0,0 +1,636 @@
+//! 静态站点导出
+//!
+//! 把笔记、随笔、标签页、分类页、笔记分享卡片、订阅源和站点地图导出到一个目录，
+//! 可以直接部署到静态托管。
+//! 导出复用服务端的查询和渲染逻辑：页面数据以 JSON 保存，启用 `html` 特性时
+//! 同时用服务端的模板输出 `index.html`。
+//!
+//! 增量导出：目录下的 `.export-manifest.json` 记录每个文件的数据来源版本（`updated_at` 等）
+//! 和内容哈希。来源版本没变的文件不再渲染；重新渲染后内容哈希没变的文件也不会重写，
+//! 这样静态托管按修改时间同步时只会上传真正变化的文件。上次导出过、这次已不存在的文件会被删除。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "html")]
+use crate::service::page_service;
+use crate::{
+    config::SiteConfig,
+    error::{AppError, AppResult},
+    infra::{
+        content::ContentStore,
+        db::entities::{
+            asset_variants::{self, Entity as AssetVariant},
+            assets::{self, Entity as Asset},
+            essays::{self, Entity as Essay},
+            notes_metadata::{self, Entity as NotesMetadata},
+            slug_redirects::Entity as SlugRedirect,
+        },
+    },
+    service::{
+        essay_service::EssayStatus,
+        feed_service::{self, FeedFormat, FeedMode, FeedScope},
+        image_service::MediaImages,
+        note_service,
+        og_service::{OgCard, OgRenderer},
+        sitemap_service::{self, Sitemap},
+    },
+};
+
+/// 增量导出的清单文件名
+pub const MANIFEST_FILE: &str = ".export-manifest.json";
+
+/// 清单中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ManifestEntry {
+    /// 数据来源版本，变化时需要重新渲染
+    source: String,
+    /// 文件内容的 SHA-256
+    hash: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    files: BTreeMap<String, ManifestEntry>,
+}
+
+/// 导出统计
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExportStats {
+    /// 新写入或内容有变化的文件
+    pub written: usize,
+    /// 重新渲染但内容没变的文件
+    pub unchanged: usize,
+    /// 来源没变、跳过渲染的文件
+    pub skipped: usize,
+    /// 已不存在而被删除的文件
+    pub removed: usize,
+}
+
+/// 增量写出文件
+struct Exporter {
+    out_dir: PathBuf,
+    force: bool,
+    previous: Manifest,
+    next: Manifest,
+    stats: ExportStats,
+}
+
+impl Exporter {
+    async fn open(out_dir: &Path, force: bool) -> AppResult<Self> {
+        tokio::fs::create_dir_all(out_dir)
+            .await
+            .map_err(|e| io_error(out_dir, e))?;
+        let manifest_path = out_dir.join(MANIFEST_FILE);
+        let previous = match tokio::fs::read(&manifest_path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log::warn!("⚠️ 导出清单无法解析，将全量导出: {e}");
+                Manifest::default()
+            }),
+            Err(_) => Manifest::default(),
+        };
+
+        Ok(Self {
+            out_dir: out_dir.to_path_buf(),
+            force,
+            previous,
+            next: Manifest::default(),
+            stats: ExportStats::default(),
+        })
+    }
+
+    /// 输出一个文件；来源版本没变且文件还在时跳过 `render`
+    async fn emit<B: AsRef<[u8]>>(
+        &mut self,
+        path: String,
+        source: String,
+        render: impl AsyncFnOnce() -> AppResult<B>,
+    ) -> AppResult<()> {
+        let target = self.out_dir.join(&path);
+        let previous = self.previous.files.get(&path).cloned();
+
+        if !self.force
+            && let Some(entry) = &previous
+            && entry.source == source
+            && tokio::fs::try_exists(&target).await.unwrap_or(false)
+        {
+            self.next.files.insert(path, entry.clone());
+            self.stats.skipped += 1;
+            return Ok(());
+        }
+
+        let body = render().await?;
+        let hash = hex::encode(Sha256::digest(body.as_ref()));
+        let unchanged = previous.is_some_and(|entry| entry.hash == hash)
+            && tokio::fs::try_exists(&target).await.unwrap_or(false);
+        if unchanged {
+            self.stats.unchanged += 1;
+        } else {
+            if let Some(parent) = target.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| io_error(parent, e))?;
+            }
+            tokio::fs::write(&target, body)
+                .await
+                .map_err(|e| io_error(&target, e))?;
+            self.stats.written += 1;
+        }
+
+        self.next.files.insert(path, ManifestEntry { source, hash });
+        Ok(())
+    }
+
+    /// 删除这次没有输出的旧文件并保存清单
+    async fn finish(mut self) -> AppResult<ExportStats> {
+        let current: BTreeSet<&String> = self.next.files.keys().collect();
+        for path in self.previous.files.keys() {
+            if current.contains(path) {
+                continue;
+            }
+            let target = self.out_dir.join(path);
+            match tokio::fs::remove_file(&target).await {
+                Ok(()) => self.stats.removed += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(io_error(&target, e)),
+            }
+        }
+
+        let manifest_path = self.out_dir.join(MANIFEST_FILE);
+        let manifest = serde_json::to_vec_pretty(&self.next)
+            .map_err(|e| AppError::Internal(format!("序列化导出清单失败: {e}")))?;
+        tokio::fs::write(&manifest_path, manifest)
+            .await
+            .map_err(|e| io_error(&manifest_path, e))?;
+        Ok(self.stats)
+    }
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> AppError {
+    AppError::Internal(format!("写入 {} 失败: {err}", path.display()))
+}
+
+fn to_json<T: Serialize>(value: &T) -> AppResult<String> {
+    serde_json::to_string(value).map_err(|e| AppError::Internal(format!("序列化失败: {e}")))
+}
+
+/// 标签、分类等用户输入的名字作为文件名时去掉路径分隔符
+fn file_segment(name: &str) -> String {
+    let segment: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\') || c.is_control() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    match segment.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => segment,
+    }
+}
+
+/// 笔记页面数据
+#[derive(Debug, Serialize)]
+struct NotePage {
+    id: i32,
+    slug: String,
+    title: String,
+    summary: Option<String>,
+    published_at: String,
+    updated_at: String,
+    tags: Vec<String>,
+    category: Option<String>,
+    content_html: Option<String>,
+}
+
+/// 列表页中的笔记
+#[derive(Debug, Serialize)]
+struct NoteSummary<'a> {
+    slug: &'a str,
+    title: &'a str,
+    summary: Option<&'a str>,
+    published_at: String,
+    tags: Vec<String>,
+    category: Option<&'a str>,
+}
+
+impl<'a> From<&'a notes_metadata::Model> for NoteSummary<'a> {
+    fn from(note: &'a notes_metadata::Model) -> Self {
+        Self {
+            slug: &note.slug,
+            title: &note.title,
+            summary: note.summary.as_deref(),
+            published_at: note.published_at.to_rfc3339(),
+            tags: note_service::parse_tags(note.tags.as_deref()),
+            category: note.category.as_deref(),
+        }
+    }
+}
+
+/// 随笔页面数据
+#[derive(Debug, Serialize)]
+struct EssayPage<'a> {
+    id: i32,
+    title: &'a str,
+    content_html: String,
+    published_at: String,
+    updated_at: String,
+}
+
+/// 列表中的随笔
+#[derive(Debug, Serialize)]
+struct EssaySummary<'a> {
+    id: i32,
+    title: &'a str,
+    published_at: String,
+}
+
+/// 标签页、分类页
+#[derive(Debug, Serialize)]
+struct ListPage<'a> {
+    name: &'a str,
+    notes: Vec<NoteSummary<'a>>,
+}
+
+/// 一组内容的来源版本：数量加最近更新时间
+fn version<'a>(updated: impl Iterator<Item = &'a DateTime<Utc>>) -> String {
+    let (count, latest) = updated.fold((0usize, None), |(count, latest), at| {
+        (count + 1, latest.max(Some(*at)))
+    });
+    match latest {
+        Some(latest) => format!("{count}@{}", latest.timestamp_millis()),
+        None => "0".to_string(),
+    }
+}
+
+/// 读取笔记正文及其中引用的媒体图片
+async fn note_content(
+    db: &DatabaseConnection,
+    content: &ContentStore,
+    site: &SiteConfig,
+    note: &notes_metadata::Model,
+) -> AppResult<(Option<String>, MediaImages)> {
+    let markdown = content.read_note(note.file_id).await?;
+    let images = match &markdown {
+        Some(markdown) => MediaImages::load(db, site, markdown).await?,
+        None => MediaImages::default(),
+    };
+    Ok((markdown, images))
+}
+
+/// 媒体文件的版本：上传图片或生成变体后，引用它们的正文渲染结果会变
+async fn media_version(db: &DatabaseConnection) -> AppResult<String> {
+    let uploaded: Vec<DateTime<Utc>> = Asset::find()
+        .select_only()
+        .column(assets::Column::CreatedAt)
+        .into_tuple()
+        .all(db)
+        .await?;
+    let generated: Vec<DateTime<Utc>> = AssetVariant::find()
+        .select_only()
+        .column(asset_variants::Column::CreatedAt)
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(format!(
+        "{}:{}",
+        version(uploaded.iter()),
+        version(generated.iter())
+    ))
+}
+
+/// 导出整个站点
+pub async fn export_site(
+    db: &DatabaseConnection,
+    content: &ContentStore,
+    site: &SiteConfig,
+    og: &OgRenderer,
+    out_dir: &Path,
+    force: bool,
+) -> AppResult<ExportStats> {
+    let mut exporter = Exporter::open(out_dir, force).await?;
+
+    let notes = NotesMetadata::find()
+        .filter(notes_metadata::Column::PublishedAt.lte(Utc::now()))
+        .order_by_desc(notes_metadata::Column::PublishedAt)
+        .all(db)
+        .await?;
+    let essays = Essay::find()
+        .filter(essays::Column::Status.eq(EssayStatus::Published.as_str()))
+        .order_by_desc(essays::Column::PublishAt)
+        .all(db)
+        .await?;
+
+    // 站点配置变化（地址、标题等）时所有文件都要重新生成
+    let config = hex::encode(&Sha256::digest(format!("{site:?}").as_bytes())[..8]);
+    let global = format!(
+        "{config}:{}:{}",
+        version(notes.iter().map(|note| &note.updated_at)),
+        version(essays.iter().map(|essay| &essay.updated_at)),
+    );
+    let media = media_version(db).await?;
+
+    for note in &notes {
+        // 正文文件直接被修改时 updated_at 不会变，所以同时参考文件的修改时间
+        let modified = tokio::fs::metadata(content.note_path(note.file_id))
+            .await
+            .and_then(|meta| meta.modified())
+            .map(|at| DateTime::<Utc>::from(at).timestamp_millis())
+            .unwrap_or_default();
+        let source = format!(
+            "{config}:{media}:{}:{modified}",
+            note.updated_at.timestamp_millis()
+        );
+        exporter
+            .emit(
+                format!("notes/{}.json", file_segment(&note.slug)),
+                source.clone(),
+                async || {
+                    let (markdown, images) = note_content(db, content, site, note).await?;
+                    to_json(&NotePage {
+                        id: note.id,
+                        slug: note.slug.clone(),
+                        title: note.title.clone(),
+                        summary: note.summary.clone(),
+                        published_at: note.published_at.to_rfc3339(),
+                        updated_at: note.updated_at.to_rfc3339(),
+                        tags: note_service::parse_tags(note.tags.as_deref()),
+                        category: note.category.clone(),
+                        content_html: markdown.map(|md| images.render_markdown(site, &md)),
+                    })
+                },
+            )
+            .await?;
+        exporter
+            .emit(
+                format!("og/{}.png", file_segment(&note.slug)),
+                source.clone(),
+                async || {
+                    let markdown = content.read_note(note.file_id).await?;
+                    og.render(&OgCard::for_note(site, note, markdown.as_deref()))
+                        .await
+                },
+            )
+            .await?;
+        #[cfg(feature = "html")]
+        exporter
+            .emit(
+                format!("notes/{}/index.html", file_segment(&note.slug)),
+                source,
+                async || {
+                    let (markdown, images) = note_content(db, content, site, note).await?;
+                    page_service::note_page(site, note, markdown.as_deref(), &images)
+                },
+            )
+            .await?;
+    }
+
+    for essay in &essays {
+        let source = format!("{config}:{media}:{}", essay.updated_at.timestamp_millis());
+        exporter
+            .emit(
+                format!("essays/{}.json", essay.id),
+                source.clone(),
+                async || {
+                    let images = MediaImages::load(db, site, &essay.content).await?;
+                    to_json(&EssayPage {
+                        id: essay.id,
+                        title: &essay.title,
+                        content_html: images.render_markdown(site, &essay.content),
+                        published_at: essay.publish_at.unwrap_or(essay.created_at).to_rfc3339(),
+                        updated_at: essay.updated_at.to_rfc3339(),
+                    })
+                },
+            )
+            .await?;
+        #[cfg(feature = "html")]
+        exporter
+            .emit(
+                format!("essays/{}/index.html", essay.id),
+                source,
+                async || {
+                    let images = MediaImages::load(db, site, &essay.content).await?;
+                    page_service::essay_page(site, essay, &images)
+                },
+            )
+            .await?;
+    }
+
+    // 标签页和分类页
+    let mut tags: BTreeMap<String, Vec<&notes_metadata::Model>> = BTreeMap::new();
+    let mut categories: BTreeMap<String, Vec<&notes_metadata::Model>> = BTreeMap::new();
+    for note in &notes {
+        for tag in note_service::parse_tags(note.tags.as_deref()) {
+            tags.entry(tag).or_default().push(note);
+        }
+        if let Some(category) = note.category.as_deref().map(str::trim)
+            && !category.is_empty()
+        {
+            categories
+                .entry(category.to_string())
+                .or_default()
+                .push(note);
+        }
+    }
+    for (dir, groups) in [("tags", &tags), ("categories", &categories)] {
+        for (name, members) in groups {
+            let source = format!(
+                "{config}:{}",
+                version(members.iter().map(|note| &note.updated_at))
+            );
+            exporter
+                .emit(
+                    format!("{dir}/{}.json", file_segment(name)),
+                    source.clone(),
+                    async || {
+                        to_json(&ListPage {
+                            name,
+                            notes: members
+                                .iter()
+                                .map(|note| NoteSummary::from(*note))
+                                .collect(),
+                        })
+                    },
+                )
+                .await?;
+            #[cfg(feature = "html")]
+            exporter
+                .emit(
+                    format!("{dir}/{}/index.html", file_segment(name)),
+                    source,
+                    async || match dir {
+                        "tags" => page_service::tag_page(site, name, members.iter().copied()),
+                        _ => page_service::category_page(site, name, members.iter().copied()),
+                    },
+                )
+                .await?;
+        }
+    }
+
+    // 列表页
+    exporter
+        .emit("notes.json".to_string(), global.clone(), async || {
+            to_json(&notes.iter().map(NoteSummary::from).collect::<Vec<_>>())
+        })
+        .await?;
+    exporter
+        .emit("essays.json".to_string(), global.clone(), async || {
+            to_json(
+                &essays
+                    .iter()
+                    .map(|essay| EssaySummary {
+                        id: essay.id,
+                        title: &essay.title,
+                        published_at: essay.publish_at.unwrap_or(essay.created_at).to_rfc3339(),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .await?;
+
+    #[cfg(feature = "html")]
+    exporter
+        .emit("index.html".to_string(), global.clone(), async || {
+            let recent_notes = &notes[..notes.len().min(page_service::HOME_NOTES as usize)];
+            let recent_essays = &essays[..essays.len().min(page_service::HOME_ESSAYS as usize)];
+            page_service::home_page(site, recent_notes, recent_essays)
+        })
+        .await?;
+
+    // 订阅源使用与服务端相同的默认参数
+    let mode = if site.feed_full_content {
+        FeedMode::Full
+    } else {
+        FeedMode::Summary
+    };
+    for format in [FeedFormat::Rss, FeedFormat::Atom, FeedFormat::Json] {
+        exporter
+            .emit(format.path().to_string(), global.clone(), async || {
+                let feed = feed_service::build_feed(
+                    db,
+                    content,
+                    site,
+                    &FeedScope::All,
+                    format,
+                    mode,
+                    site.feed_limit,
+                )
+                .await?;
+                Ok(feed_service::render(&feed, format))
+            })
+            .await?;
+    }
+
+    // 站点地图只有在内容变化时才需要重新生成
+    let sitemap = Sitemap::build(site, &sitemap_service::collect_urls(db, site).await?);
+    exporter
+        .emit("sitemap.xml".to_string(), global.clone(), async || {
+            Ok(sitemap.root.clone())
+        })
+        .await?;
+    for (i, part) in sitemap.parts.iter().enumerate() {
+        exporter
+            .emit(
+                format!("sitemaps/{}.xml", i + 1),
+                global.clone(),
+                async || Ok(part.clone()),
+            )
+            .await?;
+    }
+    exporter
+        .emit("robots.txt".to_string(), config.clone(), async || {
+            sitemap_service::robots_txt(site)
+        })
+        .await?;
+
+    // 改过 slug 的笔记：生成 Netlify / Cloudflare Pages 格式的 301 跳转规则
+    let redirects = SlugRedirect::find().all(db).await?;
+    let slugs: BTreeMap<i32, &str> = notes
+        .iter()
+        .map(|note| (note.id, note.slug.as_str()))
+        .collect();
+    let mut rules = String::new();
+    for redirect in &redirects {
+        if let Some(slug) = slugs.get(&redirect.note_id) {
+            rules.push_str(&format!(
+                "/notes/{} /notes/{slug} 301\n",
+                file_segment(&redirect.old_slug)
+            ));
+        }
+    }
+    exporter
+        .emit(
+            "_redirects".to_string(),
+            format!(
+                "{config}:{}",
+                version(redirects.iter().map(|r| &r.created_at))
+            ),
+            async || Ok(rules),
+        )
+        .await?;
+
+    exporter.finish().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_incremental_emit() {
+        let dir = std::env::temp_dir().join(format!("rowan-export-{}", uuid::Uuid::new_v4()));
+
+        let mut exporter = Exporter::open(&dir, false).await.unwrap();
+        exporter
+            .emit("a.json".into(), "v1".into(), async || Ok("1"))
+            .await
+            .unwrap();
+        exporter
+            .emit("b/c.json".into(), "v1".into(), async || Ok("2"))
+            .await
+            .unwrap();
+        assert_eq!(exporter.finish().await.unwrap().written, 2);
+
+        // 来源没变的跳过渲染，来源变了但内容没变的不重写，没有输出的旧文件被删除
+        let mut exporter = Exporter::open(&dir, false).await.unwrap();
+        exporter
+            .emit("a.json".into(), "v1".into(), async || -> AppResult<&str> {
+                panic!("不应重新渲染")
+            })
+            .await
+            .unwrap();
+        exporter
+            .emit("d.json".into(), "v2".into(), async || Ok("3"))
+            .await
+            .unwrap();
+        let stats = exporter.finish().await.unwrap();
+        assert_eq!(
+            stats,
+            ExportStats {
+                written: 1,
+                unchanged: 0,
+                skipped: 1,
+                removed: 1,
+            }
+        );
+        assert!(!dir.join("b/c.json").exists());
+
+        let mut exporter = Exporter::open(&dir, false).await.unwrap();
+        exporter
+            .emit("d.json".into(), "v3".into(), async || Ok("3"))
+            .await
+            .unwrap();
+        assert_eq!(exporter.finish().await.unwrap().unchanged, 1);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,475 @@
+//! 订阅源（RSS 2.0 / Atom / JSON Feed）
+//!
+//! 条目来自已发布的笔记和随笔，按发布时间倒序。按标签或分类订阅时只包含笔记，
+//! 因为随笔没有标签和分类。
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    config::SiteConfig,
+    error::AppResult,
+    infra::{
+        content::{ContentStore, markdown_excerpt, render_markdown},
+        db::entities::{
+            essays::{self, Entity as Essay},
+            notes_metadata::{self, Entity as NotesMetadata},
+        },
+        repositories::SeaOrmRepository,
+    },
+    service::{essay_service::EssayStatus, note_service},
+};
+
+/// 单次请求最多输出的条目数
+pub const MAX_FEED_LIMIT: u64 = 100;
+
+/// 订阅源格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl FeedFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Rss => "application/rss+xml; charset=utf-8",
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+
+    /// 订阅源自身的路径
+    pub fn path(self) -> &'static str {
+        match self {
+            Self::Rss => "feed.xml",
+            Self::Atom => "atom.xml",
+            Self::Json => "feed.json",
+        }
+    }
+}
+
+/// 输出全文还是只输出摘要
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedMode {
+    Full,
+    Summary,
+}
+
+/// 订阅范围
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedScope {
+    All,
+    Tag(String),
+    Category(String),
+}
+
+impl FeedScope {
+    /// 标签优先于分类
+    pub fn from_query(tag: Option<String>, category: Option<String>) -> Self {
+        match (tag, category) {
+            (Some(tag), _) if !tag.trim().is_empty() => Self::Tag(tag.trim().to_string()),
+            (_, Some(category)) if !category.trim().is_empty() => {
+                Self::Category(category.trim().to_string())
+            }
+            _ => Self::All,
+        }
+    }
+}
+
+/// 订阅源中的一个条目
+#[derive(Debug, Clone)]
+pub struct FeedItem {
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    pub summary: Option<String>,
+    /// 全文模式下渲染好的 HTML
+    pub content_html: Option<String>,
+    pub published: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+}
+
+/// 完整的订阅源
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub title: String,
+    pub description: String,
+    pub home_url: String,
+    /// 订阅源自身的地址（含筛选参数）
+    pub self_url: String,
+    pub language: String,
+    pub author: String,
+    pub items: Vec<FeedItem>,
+}
+
+impl Feed {
+    /// 订阅源最后更新时间，用于 `Last-Modified`
+    pub fn updated(&self) -> Option<DateTime<Utc>> {
+        self.items.iter().map(|item| item.updated).max()
+    }
+}
+
+/// 汇总订阅条目
+pub async fn build_feed(
+    db: &DatabaseConnection,
+    content: &ContentStore,
+    site: &SiteConfig,
+    scope: &FeedScope,
+    format: FeedFormat,
+    mode: FeedMode,
+    limit: u64,
+) -> AppResult<Feed> {
+    let limit = limit.clamp(1, MAX_FEED_LIMIT);
+    let mut items = note_items(db, content, site, scope, mode, limit).await?;
+    if *scope == FeedScope::All {
+        items.extend(essay_items(db, site, mode, limit).await?);
+    }
+    items.sort_by_key(|item| std::cmp::Reverse(item.published));
+    items.truncate(limit as usize);
+
+    let (title, home_url, query) = match scope {
+        FeedScope::All => (site.title.clone(), site.url.clone(), String::new()),
+        FeedScope::Tag(tag) => (
+            format!("{} · #{tag}", site.title),
+            site.tag_url(tag),
+            format!("?tag={}", encode_query(tag)),
+        ),
+        FeedScope::Category(category) => (
+            format!("{} · {category}", site.title),
+            site.category_url(category),
+            format!("?category={}", encode_query(category)),
+        ),
+    };
+
+    Ok(Feed {
+        title,
+        description: site.description.clone(),
+        home_url,
+        self_url: format!("{}{query}", site.page_url(&[format.path()])),
+        language: site.language.clone(),
+        author: site.author.clone(),
+        items,
+    })
+}
+
+fn encode_query(value: &str) -> String {
+    percent_encoding::utf8_percent_encode(value, percent_encoding::NON_ALPHANUMERIC).to_string()
+}
+
+async fn note_items(
+    db: &DatabaseConnection,
+    content: &ContentStore,
+    site: &SiteConfig,
+    scope: &FeedScope,
+    mode: FeedMode,
+    limit: u64,
+) -> AppResult<Vec<FeedItem>> {
+    let notes: Vec<notes_metadata::Model> = match scope {
+        FeedScope::All => {
+            NotesMetadata::find()
+                .filter(notes_metadata::Column::PublishedAt.lte(Utc::now()))
+                .order_by_desc(notes_metadata::Column::PublishedAt)
+                .limit(limit)
+                .all(db)
+                .await?
+        }
+        FeedScope::Tag(tag) => {
+            note_service::list_published_by_tag(&SeaOrmRepository::new(db), tag).await?
+        }
+        FeedScope::Category(category) => {
+            note_service::list_published_by_category(&SeaOrmRepository::new(db), category).await?
+        }
+    };
+    let notes = notes.into_iter().take(limit as usize);
+
+    let mut items = Vec::with_capacity(limit as usize);
+    for note in notes {
+        let content_html = match mode {
+            FeedMode::Full => content.render_note(note.file_id).await?,
+            FeedMode::Summary => None,
+        };
+        items.push(FeedItem {
+            id: format!("urn:uuid:{}", note.file_id),
+            url: site.note_url(&note.slug),
+            tags: note_service::parse_tags(note.tags.as_deref()),
+            title: note.title,
+            summary: note.summary,
+            content_html,
+            published: note.published_at,
+            updated: note.updated_at,
+            category: note.category,
+        });
+    }
+    Ok(items)
+}
+
+async fn essay_items(
+    db: &DatabaseConnection,
+    site: &SiteConfig,
+    mode: FeedMode,
+    limit: u64,
+) -> AppResult<Vec<FeedItem>> {
+    let essays = Essay::find()
+        .filter(essays::Column::Status.eq(EssayStatus::Published.as_str()))
+        .order_by_desc(essays::Column::PublishAt)
+        .limit(limit)
+        .all(db)
+        .await?;
+
+    Ok(essays
+        .into_iter()
+        .map(|essay| {
+            let url = site.essay_url(essay.id);
+            FeedItem {
+                id: url.clone(),
+                url,
+                summary: Some(markdown_excerpt(&essay.content, 140)),
+                content_html: (mode == FeedMode::Full).then(|| render_markdown(&essay.content)),
+                published: essay.publish_at.unwrap_or(essay.created_at),
+                updated: essay.updated_at,
+                title: essay.title,
+                tags: Vec::new(),
+                category: None,
+            }
+        })
+        .collect())
+}
+
+/// 转义 XML 文本和属性值
+pub fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // XML 1.0 不允许的控制字符直接丢弃
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn rfc3339(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 按指定格式输出
+pub fn render(feed: &Feed, format: FeedFormat) -> String {
+    match format {
+        FeedFormat::Rss => render_rss(feed),
+        FeedFormat::Atom => render_atom(feed),
+        FeedFormat::Json => render_json(feed),
+    }
+}
+
+fn render_rss(feed: &Feed) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str(r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:content="http://purl.org/rss/1.0/modules/content/"><channel>"#);
+    xml.push_str(&format!(
+        "<title>{}</title><link>{}</link><description>{}</description><language>{}</language>",
+        xml_escape(&feed.title),
+        xml_escape(&feed.home_url),
+        xml_escape(&feed.description),
+        xml_escape(&feed.language),
+    ));
+    xml.push_str(&format!(
+        r#"<atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+        xml_escape(&feed.self_url)
+    ));
+    if let Some(updated) = feed.updated() {
+        xml.push_str(&format!(
+            "<lastBuildDate>{}</lastBuildDate>",
+            updated.to_rfc2822()
+        ));
+    }
+
+    for item in &feed.items {
+        xml.push_str("<item>");
+        xml.push_str(&format!(
+            "<title>{}</title><link>{}</link>",
+            xml_escape(&item.title),
+            xml_escape(&item.url)
+        ));
+        xml.push_str(&format!(
+            r#"<guid isPermaLink="false">{}</guid><pubDate>{}</pubDate>"#,
+            xml_escape(&item.id),
+            item.published.to_rfc2822()
+        ));
+        if let Some(summary) = &item.summary {
+            xml.push_str(&format!(
+                "<description>{}</description>",
+                xml_escape(summary)
+            ));
+        }
+        if let Some(html) = &item.content_html {
+            xml.push_str(&format!(
+                "<content:encoded>{}</content:encoded>",
+                xml_escape(html)
+            ));
+        }
+        for category in item.category.iter().chain(&item.tags) {
+            xml.push_str(&format!("<category>{}</category>", xml_escape(category)));
+        }
+        xml.push_str("</item>");
+    }
+
+    xml.push_str("</channel></rss>");
+    xml
+}
+
+fn render_atom(feed: &Feed) -> String {
+    let updated = feed.updated().unwrap_or_else(Utc::now);
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str(&format!(
+        r#"<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="{}">"#,
+        xml_escape(&feed.language)
+    ));
+    xml.push_str(&format!(
+        r#"<id>{}</id><title>{}</title><subtitle>{}</subtitle><updated>{}</updated>"#,
+        xml_escape(&feed.self_url),
+        xml_escape(&feed.title),
+        xml_escape(&feed.description),
+        rfc3339(updated),
+    ));
+    xml.push_str(&format!(
+        r#"<link href="{}"/><link href="{}" rel="self"/><author><name>{}</name></author>"#,
+        xml_escape(&feed.home_url),
+        xml_escape(&feed.self_url),
+        xml_escape(&feed.author),
+    ));
+
+    for item in &feed.items {
+        xml.push_str("<entry>");
+        xml.push_str(&format!(
+            r#"<id>{}</id><title>{}</title><link href="{}"/><published>{}</published><updated>{}</updated>"#,
+            xml_escape(&item.id),
+            xml_escape(&item.title),
+            xml_escape(&item.url),
+            rfc3339(item.published),
+            rfc3339(item.updated),
+        ));
+        if let Some(summary) = &item.summary {
+            xml.push_str(&format!("<summary>{}</summary>", xml_escape(summary)));
+        }
+        if let Some(html) = &item.content_html {
+            xml.push_str(&format!(
+                r#"<content type="html">{}</content>"#,
+                xml_escape(html)
+            ));
+        }
+        for term in item.category.iter().chain(&item.tags) {
+            xml.push_str(&format!(r#"<category term="{}"/>"#, xml_escape(term)));
+        }
+        xml.push_str("</entry>");
+    }
+
+    xml.push_str("</feed>");
+    xml
+}
+
+fn render_json(feed: &Feed) -> String {
+    let items: Vec<_> = feed
+        .items
+        .iter()
+        .map(|item| {
+            let mut value = json!({
+                "id": item.id,
+                "url": item.url,
+                "title": item.title,
+                "date_published": rfc3339(item.published),
+                "date_modified": rfc3339(item.updated),
+            });
+            // JSON Feed 要求每个条目至少有 content_html 或 content_text
+            match &item.content_html {
+                Some(html) => value["content_html"] = json!(html),
+                None => {
+                    value["content_text"] = json!(item.summary.as_deref().unwrap_or(&item.title))
+                }
+            }
+            if let Some(summary) = &item.summary {
+                value["summary"] = json!(summary);
+            }
+            let tags: Vec<&String> = item.category.iter().chain(&item.tags).collect();
+            if !tags.is_empty() {
+                value["tags"] = json!(tags);
+            }
+            value
+        })
+        .collect();
+
+    json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": feed.title,
+        "home_page_url": feed.home_url,
+        "feed_url": feed.self_url,
+        "description": feed.description,
+        "language": feed.language,
+        "authors": [{ "name": feed.author }],
+        "items": items,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn feed() -> Feed {
+        let at = Utc.with_ymd_and_hms(2026, 10, 19, 9, 30, 0).unwrap();
+        Feed {
+            title: "Rowan & Co".to_string(),
+            description: "笔记".to_string(),
+            home_url: "https://example.com".to_string(),
+            self_url: "https://example.com/feed.xml".to_string(),
+            language: "zh-CN".to_string(),
+            author: "Rowan".to_string(),
+            items: vec![FeedItem {
+                id: "urn:uuid:1".to_string(),
+                url: "https://example.com/notes/hello".to_string(),
+                title: "<Hello>".to_string(),
+                summary: Some("摘要".to_string()),
+                content_html: Some("<p>正文</p>".to_string()),
+                published: at,
+                updated: at,
+                tags: vec!["rust".to_string()],
+                category: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_rss_and_atom_dates_and_escaping() {
+        let rss = render(&feed(), FeedFormat::Rss);
+        assert!(rss.contains("<pubDate>Mon, 19 Oct 2026 09:30:00 +0000</pubDate>"));
+        assert!(rss.contains("<title>Rowan &amp; Co</title>"));
+        assert!(rss.contains("<title>&lt;Hello&gt;</title>"));
+        assert!(rss.contains("&lt;p&gt;正文&lt;/p&gt;"));
+
+        let atom = render(&feed(), FeedFormat::Atom);
+        assert!(atom.contains("<updated>2026-10-19T09:30:00Z</updated>"));
+        assert!(atom.contains(r#"<category term="rust"/>"#));
+    }
+
+    #[test]
+    fn test_json_feed_summary_mode() {
+        let mut feed = feed();
+        feed.items[0].content_html = None;
+        let value: serde_json::Value =
+            serde_json::from_str(&render(&feed, FeedFormat::Json)).unwrap();
+        assert_eq!(value["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(value["items"][0]["content_text"], "摘要");
+        assert_eq!(value["items"][0]["date_published"], "2026-10-19T09:30:00Z");
+    }
+}
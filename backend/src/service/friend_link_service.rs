@@ -0,0 +1,577 @@
+//! 友链服务
+//!
+//! - 站长直接增删改友链，拖拽排序时一次性提交全部友链的新顺序
+//! - 访客可以提交交换友链的申请，站长审核通过后才会出现在友链列表中
+//! - 后台任务定期访问每个友链，记录 HTTP 状态码和最近一次能访问的时间
+
+use std::{collections::HashSet, time::Duration};
+
+use chrono::Utc;
+use reqwest::{Client, Url, redirect::Policy};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::{AppError, AppResult},
+    infra::{
+        db::entities::{friend_link_applications, friends_links},
+        metrics,
+        repositories::{FriendLinkRepository, SeaOrmRepository},
+        unit_of_work,
+    },
+};
+
+/// 各字段的最大长度，与表结构一致
+const MAX_NAME_LEN: usize = 15;
+const MAX_URL_LEN: usize = 255;
+const MAX_DESCRIPTION_LEN: usize = 100;
+const MAX_CONTACT_LEN: usize = 100;
+const MAX_MESSAGE_LEN: usize = 500;
+/// 同一 IP 同时等待审核的申请数上限
+const MAX_PENDING_PER_IP: usize = 3;
+
+/// 检查友链时使用的 User-Agent
+const CHECKER_USER_AGENT: &str = concat!("RowanWeb-LinkChecker/", env!("CARGO_PKG_VERSION"));
+
+/// 友链内容，创建和更新共用
+#[derive(Debug, Clone, Deserialize)]
+pub struct FriendLinkRequest {
+    pub name: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub logo_url: Option<String>,
+    /// 省略时排到最后
+    pub sort_order: Option<i32>,
+}
+
+/// 访客提交的友链申请
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApplicationRequest {
+    pub name: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub logo_url: Option<String>,
+    pub contact: Option<String>,
+    pub message: Option<String>,
+}
+
+/// 友链申请状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplicationStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ApplicationStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// 一次健康检查的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckOutcome {
+    /// HTTP 状态码，连接失败或超时时为空
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+impl CheckOutcome {
+    /// 2xx 和 3xx 都视为可以访问
+    pub fn is_alive(&self) -> bool {
+        matches!(self.status, Some(status) if status < 400)
+    }
+}
+
+/// 检查 `http(s)` 链接格式
+fn validate_url(url: &str, field: &str) -> AppResult<String> {
+    let url = url.trim();
+    if url.chars().count() > MAX_URL_LEN {
+        return Err(AppError::BadRequest(format!(
+            "{field} 不能超过 {MAX_URL_LEN} 个字符"
+        )));
+    }
+    let parsed =
+        Url::parse(url).map_err(|_| AppError::BadRequest(format!("{field} 不是合法的链接")))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(AppError::BadRequest(format!(
+            "{field} 只支持 http 或 https 链接"
+        )));
+    }
+    Ok(url.to_string())
+}
+
+fn validate_text(value: &str, field: &str, max: usize) -> AppResult<String> {
+    let value = value.trim();
+    if value.is_empty() || value.chars().count() > max {
+        return Err(AppError::BadRequest(format!(
+            "{field} 长度必须在 1 到 {max} 之间"
+        )));
+    }
+    Ok(value.to_string())
+}
+
+fn validate_optional(value: Option<String>, field: &str, max: usize) -> AppResult<Option<String>> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .map(|value| validate_text(&value, field, max))
+        .transpose()
+}
+
+fn validate_optional_url(value: Option<String>, field: &str) -> AppResult<Option<String>> {
+    value
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| validate_url(&value, field))
+        .transpose()
+}
+
+/// 获取全部友链，按 `sort_order` 升序
+pub async fn list_links(repo: &dyn FriendLinkRepository) -> AppResult<Vec<friends_links::Model>> {
+    repo.list_links().await
+}
+
+async fn ensure_url_free(
+    repo: &dyn FriendLinkRepository,
+    url: &str,
+    except: Option<i32>,
+) -> AppResult<()> {
+    if repo.is_link_url_taken(url, except).await? {
+        return Err(AppError::Conflict("该链接已经在友链中".to_string()));
+    }
+    Ok(())
+}
+
+/// 添加友链
+pub async fn create_link(
+    repo: &dyn FriendLinkRepository,
+    req: FriendLinkRequest,
+) -> AppResult<friends_links::Model> {
+    let name = validate_text(&req.name, "名称", MAX_NAME_LEN)?;
+    let url = validate_url(&req.url, "链接")?;
+    let description = validate_optional(req.description, "描述", MAX_DESCRIPTION_LEN)?;
+    let logo_url = validate_optional_url(req.logo_url, "Logo")?;
+    ensure_url_free(repo, &url, None).await?;
+
+    let sort_order = match req.sort_order {
+        Some(order) => order,
+        None => repo.max_link_sort_order().await?.map_or(0, |max| max + 1),
+    };
+    let now = Utc::now();
+    repo.insert_link(friends_links::Model {
+        id: 0,
+        name,
+        url,
+        description,
+        logo_url,
+        sort_order,
+        created_at: now,
+        updated_at: now,
+        last_status: None,
+        last_error: None,
+        last_checked_at: None,
+        last_seen_at: None,
+    })
+    .await
+}
+
+/// 修改友链；链接地址变了就清空旧的检查结果
+pub async fn update_link(
+    repo: &dyn FriendLinkRepository,
+    id: i32,
+    req: FriendLinkRequest,
+) -> AppResult<friends_links::Model> {
+    let mut link = repo.find_link(id).await?.ok_or(AppError::NotFound)?;
+
+    let name = validate_text(&req.name, "名称", MAX_NAME_LEN)?;
+    let url = validate_url(&req.url, "链接")?;
+    let description = validate_optional(req.description, "描述", MAX_DESCRIPTION_LEN)?;
+    let logo_url = validate_optional_url(req.logo_url, "Logo")?;
+    ensure_url_free(repo, &url, Some(id)).await?;
+
+    if url != link.url {
+        link.last_status = None;
+        link.last_error = None;
+        link.last_checked_at = None;
+        link.last_seen_at = None;
+    }
+    link.name = name;
+    link.url = url;
+    link.description = description;
+    link.logo_url = logo_url;
+    if let Some(order) = req.sort_order {
+        link.sort_order = order;
+    }
+    link.updated_at = Utc::now();
+    repo.update_link(link).await
+}
+
+/// 删除友链
+pub async fn delete_link(repo: &dyn FriendLinkRepository, id: i32) -> AppResult<()> {
+    if !repo.delete_link(id).await? {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// 按给定顺序重排全部友链，`ids` 必须恰好包含每个友链一次
+pub async fn reorder(db: &DatabaseConnection, ids: &[i32]) -> AppResult<()> {
+    unit_of_work::run(db, |uow| {
+        Box::pin(async move { apply_order(&uow.repo(), ids).await })
+    })
+    .await
+}
+
+async fn apply_order(repo: &dyn FriendLinkRepository, ids: &[i32]) -> AppResult<()> {
+    let existing: HashSet<i32> = repo.list_links().await?.iter().map(|l| l.id).collect();
+    let requested: HashSet<i32> = ids.iter().copied().collect();
+    if requested.len() != ids.len() || requested != existing {
+        return Err(AppError::BadRequest(
+            "排序列表必须恰好包含全部友链各一次".to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+    for (order, id) in ids.iter().enumerate() {
+        repo.set_link_order(*id, order as i32, now).await?;
+    }
+    Ok(())
+}
+
+/// 访客提交友链申请
+pub async fn submit_application(
+    repo: &dyn FriendLinkRepository,
+    req: ApplicationRequest,
+    ip: &str,
+) -> AppResult<friend_link_applications::Model> {
+    let name = validate_text(&req.name, "名称", MAX_NAME_LEN)?;
+    let url = validate_url(&req.url, "链接")?;
+    let description = validate_optional(req.description, "描述", MAX_DESCRIPTION_LEN)?;
+    let logo_url = validate_optional_url(req.logo_url, "Logo")?;
+    let contact = validate_optional(req.contact, "联系方式", MAX_CONTACT_LEN)?;
+    let message = validate_optional(req.message, "留言", MAX_MESSAGE_LEN)?;
+
+    ensure_url_free(repo, &url, None).await?;
+    let pending = repo.pending_applications(&url, ip).await?;
+    if pending.iter().any(|application| application.url == url) {
+        return Err(AppError::Conflict("该链接的申请正在审核中".to_string()));
+    }
+    if pending.len() >= MAX_PENDING_PER_IP {
+        return Err(AppError::Conflict(
+            "待审核的申请太多了，请耐心等待".to_string(),
+        ));
+    }
+
+    repo.insert_application(friend_link_applications::Model {
+        id: 0,
+        name,
+        url,
+        description,
+        logo_url,
+        contact,
+        message,
+        status: ApplicationStatus::Pending.as_str().to_string(),
+        ip: ip.to_string(),
+        created_at: Utc::now(),
+        reviewed_at: None,
+    })
+    .await
+}
+
+/// 获取友链申请，默认按提交时间倒序
+pub async fn list_applications(
+    repo: &dyn FriendLinkRepository,
+    status: Option<ApplicationStatus>,
+) -> AppResult<Vec<friend_link_applications::Model>> {
+    repo.list_applications(status.map(ApplicationStatus::as_str))
+        .await
+}
+
+/// 审核申请；通过时在同一事务中创建友链
+pub async fn review_application(
+    db: &DatabaseConnection,
+    id: i32,
+    approve: bool,
+) -> AppResult<Option<friends_links::Model>> {
+    unit_of_work::run(db, |uow| {
+        Box::pin(async move { apply_review(&uow.repo(), id, approve).await })
+    })
+    .await
+}
+
+async fn apply_review(
+    repo: &dyn FriendLinkRepository,
+    id: i32,
+    approve: bool,
+) -> AppResult<Option<friends_links::Model>> {
+    let mut application = repo.find_application(id).await?.ok_or(AppError::NotFound)?;
+    if application.status != ApplicationStatus::Pending.as_str() {
+        return Err(AppError::Conflict("该申请已经审核过了".to_string()));
+    }
+
+    let link = if approve {
+        Some(
+            create_link(
+                repo,
+                FriendLinkRequest {
+                    name: application.name.clone(),
+                    url: application.url.clone(),
+                    description: application.description.clone(),
+                    logo_url: application.logo_url.clone(),
+                    sort_order: None,
+                },
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    let status = if approve {
+        ApplicationStatus::Approved
+    } else {
+        ApplicationStatus::Rejected
+    };
+    application.status = status.as_str().to_string();
+    application.reviewed_at = Some(Utc::now());
+    repo.update_application(application).await?;
+    Ok(link)
+}
+
+/// 创建检查友链用的 HTTP 客户端
+pub fn checker_client(timeout: Duration) -> AppResult<Client> {
+    Client::builder()
+        .timeout(timeout)
+        .redirect(Policy::limited(5))
+        .user_agent(CHECKER_USER_AGENT)
+        .build()
+        .map_err(|err| AppError::Internal(format!("创建 HTTP 客户端失败: {err}")))
+}
+
+/// 访问一个链接并记录结果，不读取响应正文
+pub async fn check_url(client: &Client, url: &str) -> CheckOutcome {
+    match client.get(url).send().await {
+        Ok(response) => CheckOutcome {
+            status: Some(response.status().as_u16()),
+            error: None,
+        },
+        Err(err) => CheckOutcome {
+            status: None,
+            error: Some(if err.is_timeout() {
+                "请求超时".to_string()
+            } else if err.is_connect() {
+                "无法连接".to_string()
+            } else {
+                err.to_string().chars().take(255).collect()
+            }),
+        },
+    }
+}
+
+/// 检查全部友链，返回（检查数，可访问数）
+pub async fn check_all(
+    repo: &dyn FriendLinkRepository,
+    client: &Client,
+) -> AppResult<(usize, usize)> {
+    let links = repo.list_links().await?;
+    let mut alive = 0;
+    for link in &links {
+        let outcome = check_url(client, &link.url).await;
+        let is_alive = outcome.is_alive();
+        if is_alive {
+            alive += 1;
+        } else {
+            log::debug!("🔗 友链 {} 无法访问: {:?}", link.url, outcome);
+        }
+        repo.record_link_check(
+            link.id,
+            outcome.status.map(i32::from),
+            outcome.error,
+            Utc::now(),
+            is_alive,
+        )
+        .await?;
+    }
+    Ok((links.len(), alive))
+}
+
+/// 定期检查友链，直到收到关闭信号
+pub async fn run_checker(
+    db: DatabaseConnection,
+    client: Client,
+    interval: Duration,
+    shutdown: CancellationToken,
+) {
+    let repo = SeaOrmRepository::new(&db);
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+        // 检查可能持续较久，关闭时直接放弃本轮
+        tokio::select! {
+            result = metrics::time_job("link_check", check_all(&repo, &client)) => match result {
+                Ok((total, alive)) => log::info!("🔗 友链检查完成: {alive}/{total} 可以访问"),
+                Err(err) => log::warn!("⚠️ 友链检查失败: {err}"),
+            },
+            _ = shutdown.cancelled() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::repositories::memory::InMemoryRepository;
+    use axum::{Router, http::StatusCode, routing::get};
+
+    fn application(url: &str) -> ApplicationRequest {
+        ApplicationRequest {
+            name: "朋友".to_string(),
+            url: url.to_string(),
+            description: None,
+            logo_url: None,
+            contact: None,
+            message: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_application_limits_and_review() {
+        let repo = InMemoryRepository::new();
+        let ip = "198.51.100.7";
+
+        let first = submit_application(&repo, application("https://a.example/"), ip)
+            .await
+            .unwrap();
+        assert!(matches!(
+            submit_application(&repo, application("https://a.example/"), "198.51.100.8").await,
+            Err(AppError::Conflict(_))
+        ));
+        for url in ["https://b.example/", "https://c.example/"] {
+            submit_application(&repo, application(url), ip)
+                .await
+                .unwrap();
+        }
+        assert!(matches!(
+            submit_application(&repo, application("https://d.example/"), ip).await,
+            Err(AppError::Conflict(_))
+        ));
+
+        let link = apply_review(&repo, first.id, true).await.unwrap().unwrap();
+        assert_eq!(link.url, "https://a.example/");
+        assert!(matches!(
+            apply_review(&repo, first.id, false).await,
+            Err(AppError::Conflict(_))
+        ));
+        // 审核通过后该 IP 的待审核申请减少，可以再提交
+        submit_application(&repo, application("https://d.example/"), ip)
+            .await
+            .unwrap();
+        // 已经在友链中的链接不能再申请
+        assert!(matches!(
+            submit_application(&repo, application("https://a.example/"), "203.0.113.9").await,
+            Err(AppError::Conflict(_))
+        ));
+        assert_eq!(
+            list_applications(&repo, Some(ApplicationStatus::Pending))
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reorder_requires_every_link_once() {
+        let repo = InMemoryRepository::new();
+        let mut ids = Vec::new();
+        for url in [
+            "https://a.example/",
+            "https://b.example/",
+            "https://c.example/",
+        ] {
+            let link = create_link(
+                &repo,
+                FriendLinkRequest {
+                    name: "链接".to_string(),
+                    url: url.to_string(),
+                    description: None,
+                    logo_url: None,
+                    sort_order: None,
+                },
+            )
+            .await
+            .unwrap();
+            ids.push(link.id);
+        }
+
+        assert!(apply_order(&repo, &[ids[0], ids[1]]).await.is_err());
+        assert!(apply_order(&repo, &[ids[0], ids[0], ids[1]]).await.is_err());
+
+        let reversed: Vec<i32> = ids.iter().rev().copied().collect();
+        apply_order(&repo, &reversed).await.unwrap();
+        let links = list_links(&repo).await.unwrap();
+        assert_eq!(links.iter().map(|l| l.id).collect::<Vec<_>>(), reversed);
+    }
+
+    /// 在本地随机端口启动一个桩服务器
+    async fn stub_server() -> String {
+        let app = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/gone", get(|| async { StatusCode::NOT_FOUND }))
+            .route(
+                "/moved",
+                get(|| async { (StatusCode::FOUND, [("location", "/ok")]) }),
+            )
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "late"
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_check_url_against_stub_server() {
+        let base = stub_server().await;
+        let client = checker_client(Duration::from_millis(500)).unwrap();
+
+        let ok = check_url(&client, &format!("{base}/ok")).await;
+        assert_eq!(ok.status, Some(200));
+        assert!(ok.is_alive());
+
+        let moved = check_url(&client, &format!("{base}/moved")).await;
+        assert_eq!(moved.status, Some(200));
+
+        let gone = check_url(&client, &format!("{base}/gone")).await;
+        assert_eq!(gone.status, Some(404));
+        assert!(!gone.is_alive());
+
+        let slow = check_url(&client, &format!("{base}/slow")).await;
+        assert_eq!(slow.status, None);
+        assert_eq!(slow.error.as_deref(), Some("请求超时"));
+    }
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("https://example.com/", "链接").is_ok());
+        assert!(validate_url(" http://example.com ", "链接").is_ok());
+        assert!(validate_url("ftp://example.com", "链接").is_err());
+        assert!(validate_url("javascript:alert(1)", "链接").is_err());
+        assert!(validate_url("not a url", "链接").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! 健康检查与构建信息
+//!
+//! - 存活检查：进程能响应请求即可，不访问任何依赖
+//! - 就绪检查：读写连接池都能连上数据库、迁移都已执行且数据库不比程序新、笔记正文目录可读，
+//!   任何一项失败或超时都算未就绪，负载均衡应暂停转发流量
+//! - 构建信息：版本号、git 提交、构建时间和启用的 feature，由 `build.rs` 在编译时写入
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    error::AppResult,
+    infra::{content::ContentStore, db::migrate},
+};
+
+/// 单项检查的超时时间，避免数据库卡住时探针一直挂起
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 单项就绪检查的结果
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    /// 成功时的补充信息或失败原因
+    pub detail: String,
+    pub elapsed: Duration,
+}
+
+/// 就绪检查结果
+#[derive(Debug, Clone)]
+pub struct Readiness {
+    pub checks: Vec<Check>,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.checks.iter().all(|check| check.ok)
+    }
+}
+
+/// 执行一项检查，超时或出错都记为失败；失败的具体错误只写日志
+async fn run_check<F>(name: &'static str, check: F) -> Check
+where
+    F: Future<Output = AppResult<Result<String, String>>>,
+{
+    let started = Instant::now();
+    let (ok, detail) = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(Ok(detail))) => (true, detail),
+        Ok(Ok(Err(reason))) => (false, reason),
+        Ok(Err(err)) => {
+            log::warn!("⚠️ 就绪检查 {name} 失败: {err}");
+            (false, "检查出错，详见服务日志".to_string())
+        }
+        Err(_) => (false, format!("超过 {} 秒未完成", CHECK_TIMEOUT.as_secs())),
+    };
+    Check {
+        name,
+        ok,
+        detail,
+        elapsed: started.elapsed(),
+    }
+}
+
+/// 检查各项依赖是否就绪
+pub async fn readiness(
+    reader: &DatabaseConnection,
+    writer: &DatabaseConnection,
+    content: &ContentStore,
+) -> Readiness {
+    let (database, database_writer, migrations, content_dir) = tokio::join!(
+        run_check("database", async {
+            reader.ping().await?;
+            Ok(Ok("可以连接".to_string()))
+        }),
+        // SQLite 的写连接池只有一个连接，写入繁忙时这一项可能超时
+        run_check("database_writer", async {
+            writer.ping().await?;
+            Ok(Ok("可以连接".to_string()))
+        }),
+        run_check("migrations", async {
+            let status = migrate::status(reader).await?;
+            Ok(if status.is_ahead() {
+                Err(format!(
+                    "数据库中有程序不认识的迁移: {}",
+                    status.unknown.join(", ")
+                ))
+            } else if status.pending_count() > 0 {
+                Err(format!("有 {} 个迁移未执行", status.pending_count()))
+            } else {
+                Ok(format!("已执行 {} 个迁移", status.applied_count()))
+            })
+        }),
+        run_check("content", async {
+            let count = content.count_notes().await?;
+            Ok(Ok(format!("{count} 篇笔记正文")))
+        }),
+    );
+    Readiness {
+        checks: vec![database, database_writer, migrations, content_dir],
+    }
+}
+
+/// 程序的构建信息
+#[derive(Debug, Clone)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub build_time: Option<DateTime<Utc>>,
+    pub features: Vec<&'static str>,
+    /// debug 或 release
+    pub profile: &'static str,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "html") {
+            features.push("html");
+        }
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: env!("ROWAN_GIT_SHA"),
+            build_time: env!("ROWAN_BUILD_TIME")
+                .parse()
+                .ok()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            features,
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            },
+        }
+    }
+
+    /// 构建时间的 RFC 3339 表示
+    pub fn build_time_rfc3339(&self) -> Option<String> {
+        self.build_time
+            .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::test_db;
+
+    #[tokio::test]
+    async fn test_readiness() {
+        let db = test_db().await;
+        let dir = std::env::temp_dir().join(format!("rowan-health-{}", uuid::Uuid::new_v4()));
+        let content = ContentStore::new(&dir);
+
+        // 正文目录还没创建时也算就绪
+        let result = readiness(&db, &db, &content).await;
+        assert!(result.is_ready(), "{result:?}");
+        content
+            .write_note(uuid::Uuid::new_v4(), "# hi\n")
+            .await
+            .unwrap();
+        let result = readiness(&db, &db, &content).await;
+        assert_eq!(result.checks[3].detail, "1 篇笔记正文");
+
+        // 正文目录不可读（这里是一个普通文件）
+        let file = dir.join("not-a-dir");
+        std::fs::write(&file, "").unwrap();
+        let result = readiness(&db, &db, &ContentStore::new(&file)).await;
+        assert!(!result.is_ready());
+        assert!(result.checks[..3].iter().all(|check| check.ok));
+
+        // 新建的空数据库没有执行迁移
+        let empty = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let result = readiness(&empty, &empty, &content).await;
+        let migrations = &result.checks[2];
+        assert!(!migrations.ok);
+        assert!(migrations.detail.contains("未执行"), "{migrations:?}");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(BuildInfo::current().version, env!("CARGO_PKG_VERSION"));
+    }
+}
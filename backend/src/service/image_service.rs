@@ -0,0 +1,525 @@
+//! 响应式图片
+//!
+//! 图片上传后在后台按配置的宽度生成 WebP / AVIF 变体，保存在原图旁边，
+//! 对外地址为 `/media/{hash}/{width}w.{ext}`。渲染 Markdown 时，指向媒体文件的图片会改写成
+//! 带 `srcset` 的 `<picture>`，并写上原图宽高，避免图片加载时页面跳动；
+//! 变体还没生成好时只补上宽高。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set, sea_query::OnConflict,
+};
+
+use crate::{
+    config::{ImageVariantConfig, SiteConfig},
+    error::{AppError, AppResult},
+    infra::{
+        blob::BlobStore,
+        content,
+        db::entities::{
+            asset_variants::{self, Entity as AssetVariant},
+            assets::{self, Entity as Asset},
+        },
+        media::{self, MediaType, VariantFormat},
+    },
+    service::{asset_service, feed_service::xml_escape},
+};
+
+/// 变体的文件名：`{width}w.{ext}`
+pub fn variant_file_name(variant: &asset_variants::Model) -> String {
+    format!("{}w.{}", variant.width, variant.format)
+}
+
+/// 从变体文件名解析宽度和格式
+pub fn parse_variant_file_name(file: &str) -> Option<(i32, VariantFormat)> {
+    let (width, extension) = file.split_once('.')?;
+    let width = width.strip_suffix('w')?.parse().ok()?;
+    Some((width, extension.parse().ok()?))
+}
+
+/// 变体在存储中的键，和原图放在同一个目录下
+fn variant_key(hash: &str, file: &str) -> String {
+    format!("{}/{hash}/{file}", &hash[..2])
+}
+
+/// 变体在存储中的键
+pub fn variant_storage_key(asset: &assets::Model, variant: &asset_variants::Model) -> String {
+    variant_key(&asset.hash, &variant_file_name(variant))
+}
+
+/// 变体的访问地址
+pub fn variant_url(
+    site: &SiteConfig,
+    asset: &assets::Model,
+    variant: &asset_variants::Model,
+) -> String {
+    site.page_url(&["media", &asset.hash, &variant_file_name(variant)])
+}
+
+/// 需要生成的宽度：配置中小于原图的宽度；原图不超过最大宽度时再加上原图宽度（只转格式）
+fn target_widths(widths: &[u32], original: u32) -> Vec<u32> {
+    let mut targets: Vec<u32> = widths.iter().copied().filter(|w| *w < original).collect();
+    if widths.last().is_some_and(|max| original <= *max) {
+        targets.push(original);
+    }
+    targets
+}
+
+/// 读取原图
+async fn load_original(blobs: &dyn BlobStore, asset: &assets::Model) -> AppResult<bytes::Bytes> {
+    blobs
+        .get(&asset_service::storage_key(asset))
+        .await?
+        .ok_or_else(|| {
+            AppError::Internal(format!(
+                "媒体文件 {} 在存储中丢失",
+                asset_service::file_name(asset)
+            ))
+        })
+}
+
+/// 按当前配置补齐图片的变体，删掉配置中已不再需要的变体；
+/// 返回（可能补上了宽高的）图片记录和现有的全部变体
+pub async fn sync_variants(
+    db: &DatabaseConnection,
+    blobs: &dyn BlobStore,
+    config: &ImageVariantConfig,
+    asset: &assets::Model,
+) -> AppResult<(assets::Model, Vec<asset_variants::Model>)> {
+    let Some(kind) = MediaType::from_mime_type(&asset.mime_type).filter(|kind| kind.is_image())
+    else {
+        return Ok((asset.clone(), Vec::new()));
+    };
+
+    // 早于变体功能上传的图片没有记录宽高，顺便补上
+    let mut original = None;
+    let mut asset = asset.clone();
+    if asset.width.is_none() || asset.height.is_none() {
+        let data = load_original(blobs, &asset).await?;
+        let (width, height) = media::image_dimensions(&data)?;
+        let mut active = asset.into_active_model();
+        active.width = Set(Some(width as i32));
+        active.height = Set(Some(height as i32));
+        asset = active.update(db).await?;
+        original = Some(data);
+    }
+
+    let wanted: Vec<(i32, VariantFormat)> = match asset.width {
+        Some(width) if kind.has_variants() && config.enabled() => {
+            target_widths(&config.widths, width as u32)
+                .into_iter()
+                .flat_map(|width| {
+                    config
+                        .formats
+                        .iter()
+                        .map(move |format| (width as i32, *format))
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    let is_wanted = |variant: &asset_variants::Model| {
+        wanted
+            .iter()
+            .any(|(width, format)| variant.width == *width && variant.format == format.extension())
+    };
+
+    let existing = AssetVariant::find()
+        .filter(asset_variants::Column::AssetId.eq(asset.id))
+        .all(db)
+        .await?;
+    for variant in existing.iter().filter(|variant| !is_wanted(variant)) {
+        AssetVariant::delete_by_id(variant.id).exec(db).await?;
+        blobs.delete(&variant_storage_key(&asset, variant)).await?;
+    }
+
+    let missing: Vec<(u32, VariantFormat)> = wanted
+        .iter()
+        .filter(|(width, format)| {
+            !existing
+                .iter()
+                .any(|variant| variant.width == *width && variant.format == format.extension())
+        })
+        .map(|(width, format)| (*width as u32, *format))
+        .collect();
+    if !missing.is_empty() {
+        let data = match original {
+            Some(data) => data,
+            None => load_original(blobs, &asset).await?,
+        };
+        // 解码和编码是 CPU 密集型操作，放到阻塞线程池里执行
+        let quality = config.quality;
+        let encoded = tokio::task::spawn_blocking(move || {
+            let image = media::decode_image(&data)?;
+            missing
+                .into_iter()
+                .map(|(width, format)| {
+                    media::encode_variant(&image, width, format, quality)
+                        .map(|(bytes, width, height)| (format, bytes, width, height))
+                })
+                .collect::<AppResult<Vec<_>>>()
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("生成图片变体失败: {e}")))??;
+
+        for (format, bytes, width, height) in encoded {
+            let file = format!("{width}w.{}", format.extension());
+            let size = bytes.len() as i64;
+            // 先写文件再写记录：记录存在时文件一定存在
+            blobs
+                .put(&variant_key(&asset.hash, &file), bytes, format.mime_type())
+                .await?;
+            let variant = asset_variants::ActiveModel {
+                asset_id: Set(asset.id),
+                width: Set(width as i32),
+                height: Set(height as i32),
+                format: Set(format.extension().to_string()),
+                size: Set(size),
+                created_at: Set(Utc::now()),
+                ..Default::default()
+            };
+            // 并发生成同一变体时以后写入的为准
+            AssetVariant::insert(variant)
+                .on_conflict(
+                    OnConflict::columns([
+                        asset_variants::Column::AssetId,
+                        asset_variants::Column::Width,
+                        asset_variants::Column::Format,
+                    ])
+                    .update_columns([
+                        asset_variants::Column::Height,
+                        asset_variants::Column::Size,
+                        asset_variants::Column::CreatedAt,
+                    ])
+                    .to_owned(),
+                )
+                .exec_without_returning(db)
+                .await?;
+        }
+    }
+
+    let variants = AssetVariant::find()
+        .filter(asset_variants::Column::AssetId.eq(asset.id))
+        .order_by_asc(asset_variants::Column::Width)
+        .order_by_asc(asset_variants::Column::Format)
+        .all(db)
+        .await?;
+    Ok((asset, variants))
+}
+
+/// 在后台生成变体，上传请求不用等编码完成
+pub fn spawn_sync(
+    db: DatabaseConnection,
+    blobs: Arc<dyn BlobStore>,
+    config: ImageVariantConfig,
+    asset: assets::Model,
+) {
+    tokio::spawn(async move {
+        match sync_variants(&db, blobs.as_ref(), &config, &asset).await {
+            Ok((_, variants)) if !variants.is_empty() => log::info!(
+                "🖼️ 已生成 {} 的 {} 个图片变体",
+                asset_service::file_name(&asset),
+                variants.len()
+            ),
+            Ok(_) => {}
+            Err(err) => log::warn!(
+                "⚠️ 生成 {} 的图片变体失败: {err}",
+                asset_service::file_name(&asset)
+            ),
+        }
+    });
+}
+
+/// 一张媒体图片及其变体
+#[derive(Debug, Clone)]
+pub struct ResponsiveImage {
+    pub asset: assets::Model,
+    /// 按宽度从小到大排列
+    pub variants: Vec<asset_variants::Model>,
+}
+
+/// 同一格式的一组变体
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageSource {
+    pub format: VariantFormat,
+    /// 如 `https://…/480w.avif 480w, https://…/960w.avif 960w`
+    pub srcset: String,
+}
+
+impl ResponsiveImage {
+    /// 原图地址
+    pub fn src(&self, site: &SiteConfig) -> String {
+        site.media_url(&asset_service::file_name(&self.asset))
+    }
+
+    /// 按格式分组的 srcset，浏览器应优先选择的格式在前
+    pub fn sources(&self, site: &SiteConfig) -> Vec<ImageSource> {
+        let mut sources: Vec<ImageSource> = Vec::new();
+        let mut variants: Vec<(VariantFormat, &asset_variants::Model)> = self
+            .variants
+            .iter()
+            .filter_map(|variant| Some((variant.format.parse().ok()?, variant)))
+            .collect();
+        variants.sort_by_key(|(format, variant)| (*format, variant.width));
+        for (format, variant) in variants {
+            let candidate = format!(
+                "{} {}w",
+                variant_url(site, &self.asset, variant),
+                variant.width
+            );
+            match sources.last_mut() {
+                Some(source) if source.format == format => {
+                    source.srcset.push_str(", ");
+                    source.srcset.push_str(&candidate);
+                }
+                _ => sources.push(ImageSource {
+                    format,
+                    srcset: candidate,
+                }),
+            }
+        }
+        sources
+    }
+
+    /// `sizes` 属性：图片最多按原始宽度显示，窄屏时占满视口
+    pub fn sizes(&self) -> Option<String> {
+        let width = self
+            .asset
+            .width
+            .or_else(|| self.variants.iter().map(|variant| variant.width).max())?;
+        Some(format!("(max-width: {width}px) 100vw, {width}px"))
+    }
+
+    /// 替换 Markdown 默认的 `<img>` 标签；既没有宽高也没有变体时返回 `None`
+    pub fn to_html(&self, site: &SiteConfig, alt: &str, title: &str) -> Option<String> {
+        let sources = self.sources(site);
+        if sources.is_empty() && self.asset.width.is_none() {
+            return None;
+        }
+
+        let mut img = format!(
+            "<img src=\"{}\" alt=\"{}\"",
+            xml_escape(&self.src(site)),
+            xml_escape(alt)
+        );
+        if !title.is_empty() {
+            img.push_str(&format!(" title=\"{}\"", xml_escape(title)));
+        }
+        if let (Some(width), Some(height)) = (self.asset.width, self.asset.height) {
+            img.push_str(&format!(" width=\"{width}\" height=\"{height}\""));
+        }
+        img.push_str(" loading=\"lazy\" decoding=\"async\" />");
+        if sources.is_empty() {
+            return Some(img);
+        }
+
+        let sizes = self.sizes().unwrap_or_else(|| "100vw".to_string());
+        let mut html = String::from("<picture>");
+        for source in sources {
+            html.push_str(&format!(
+                "<source type=\"{}\" srcset=\"{}\" sizes=\"{sizes}\" />",
+                source.format.mime_type(),
+                xml_escape(&source.srcset)
+            ));
+        }
+        html.push_str(&img);
+        html.push_str("</picture>");
+        Some(html)
+    }
+}
+
+/// 媒体文件地址中的文件名，支持站内地址 `/media/{file}` 和带域名的完整地址
+fn media_file<'a>(site: &SiteConfig, url: &'a str) -> Option<&'a str> {
+    let absolute = site.page_url(&["media", ""]);
+    let file = url
+        .strip_prefix(absolute.as_str())
+        .or_else(|| url.strip_prefix("/media/"))?;
+    (!file.is_empty() && !file.contains(['/', '?', '#'])).then_some(file)
+}
+
+/// 按哈希批量读取图片及其变体，以对外文件名为键
+async fn load_images(
+    db: &DatabaseConnection,
+    hashes: Vec<String>,
+) -> AppResult<HashMap<String, ResponsiveImage>> {
+    if hashes.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let assets = Asset::find()
+        .filter(assets::Column::Hash.is_in(hashes))
+        .all(db)
+        .await?;
+    let mut variants: HashMap<i32, Vec<asset_variants::Model>> = HashMap::new();
+    for variant in AssetVariant::find()
+        .filter(asset_variants::Column::AssetId.is_in(assets.iter().map(|asset| asset.id)))
+        .order_by_asc(asset_variants::Column::Width)
+        .all(db)
+        .await?
+    {
+        variants.entry(variant.asset_id).or_default().push(variant);
+    }
+
+    Ok(assets
+        .into_iter()
+        .map(|asset| {
+            let variants = variants.remove(&asset.id).unwrap_or_default();
+            (
+                asset_service::file_name(&asset),
+                ResponsiveImage { asset, variants },
+            )
+        })
+        .collect())
+}
+
+/// 按对外文件名（`{hash}.{ext}`）读取图片及其变体
+pub async fn find_image(db: &DatabaseConnection, file: &str) -> AppResult<ResponsiveImage> {
+    let asset = asset_service::find_by_file_name(db, file).await?;
+    let variants = AssetVariant::find()
+        .filter(asset_variants::Column::AssetId.eq(asset.id))
+        .order_by_asc(asset_variants::Column::Width)
+        .all(db)
+        .await?;
+    Ok(ResponsiveImage { asset, variants })
+}
+
+/// 按原图哈希和变体文件名查找变体
+pub async fn find_variant(
+    db: &DatabaseConnection,
+    hash: &str,
+    file: &str,
+) -> AppResult<(assets::Model, asset_variants::Model)> {
+    let (width, format) = parse_variant_file_name(file).ok_or(AppError::NotFound)?;
+    let asset = Asset::find()
+        .filter(assets::Column::Hash.eq(hash))
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let variant = AssetVariant::find()
+        .filter(asset_variants::Column::AssetId.eq(asset.id))
+        .filter(asset_variants::Column::Width.eq(width))
+        .filter(asset_variants::Column::Format.eq(format.extension()))
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok((asset, variant))
+}
+
+/// 一篇正文中引用到的媒体图片
+#[derive(Debug, Default)]
+pub struct MediaImages {
+    images: HashMap<String, ResponsiveImage>,
+}
+
+impl MediaImages {
+    /// 读取 Markdown 中引用到的媒体图片，外部图片不处理
+    pub async fn load(
+        db: &DatabaseConnection,
+        site: &SiteConfig,
+        markdown: &str,
+    ) -> AppResult<Self> {
+        let mut hashes: Vec<String> = content::image_urls(markdown)
+            .iter()
+            .filter_map(|url| media_file(site, url))
+            .filter_map(|file| file.split_once('.'))
+            .map(|(hash, _)| hash.to_string())
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        Ok(Self {
+            images: load_images(db, hashes).await?,
+        })
+    }
+
+    /// 渲染 Markdown，媒体图片改写成响应式图片
+    pub fn render_markdown(&self, site: &SiteConfig, markdown: &str) -> String {
+        content::render_markdown_with(markdown, |url, alt, title| {
+            self.images
+                .get(media_file(site, url)?)?
+                .to_html(site, alt, title)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_target_widths() {
+        assert_eq!(target_widths(&[480, 960, 1600], 4000), [480, 960, 1600]);
+        assert_eq!(target_widths(&[480, 960, 1600], 1200), [480, 960, 1200]);
+        assert_eq!(target_widths(&[480, 960, 1600], 960), [480, 960]);
+        assert_eq!(target_widths(&[480], 300), [300]);
+    }
+
+    #[test]
+    fn test_variant_file_name() {
+        assert_eq!(
+            parse_variant_file_name("960w.avif"),
+            Some((960, VariantFormat::Avif))
+        );
+        assert_eq!(parse_variant_file_name("960.avif"), None);
+        assert_eq!(parse_variant_file_name("960w.gif"), None);
+    }
+
+    #[test]
+    fn test_render_responsive_image() {
+        let mut site = SiteConfig::from_env();
+        site.url = "https://example.com".to_string();
+        let at = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let hash = "ab".repeat(32);
+        let asset = assets::Model {
+            id: 1,
+            hash: hash.clone(),
+            mime_type: "image/jpeg".to_string(),
+            extension: "jpg".to_string(),
+            size: 1000,
+            original_name: None,
+            width: Some(1200),
+            height: Some(800),
+            created_at: at,
+        };
+        let variant = |id, width, format: &str| asset_variants::Model {
+            id,
+            asset_id: 1,
+            width,
+            height: width * 2 / 3,
+            format: format.to_string(),
+            size: 100,
+            created_at: at,
+        };
+        let images = MediaImages {
+            images: HashMap::from([(
+                format!("{hash}.jpg"),
+                ResponsiveImage {
+                    asset,
+                    variants: vec![
+                        variant(1, 480, "webp"),
+                        variant(2, 480, "avif"),
+                        variant(3, 960, "webp"),
+                        variant(4, 960, "avif"),
+                    ],
+                },
+            )]),
+        };
+
+        let html = images.render_markdown(
+            &site,
+            &format!("![a \"cat\"](/media/{hash}.jpg) ![b](https://example.com/media/{hash}.jpg) ![c](/other.png)"),
+        );
+        let base = format!("https://example.com/media/{hash}");
+        assert!(html.contains(&format!(
+            "<picture><source type=\"image/avif\" srcset=\"{base}/480w.avif 480w, {base}/960w.avif 960w\" sizes=\"(max-width: 1200px) 100vw, 1200px\" /><source type=\"image/webp\""
+        )));
+        assert!(html.contains(&format!(
+            "<img src=\"{base}.jpg\" alt=\"a &quot;cat&quot;\" width=\"1200\" height=\"800\" loading=\"lazy\" decoding=\"async\" /></picture>"
+        )));
+        assert_eq!(html.matches("<picture>").count(), 2);
+        assert!(html.contains("<img src=\"/other.png\" alt=\"c\" />"));
+    }
+}
@@ -0,0 +1,1256 @@
+//! 从其他博客系统导入
+//!
+//! - Hexo / Hugo / Jekyll：递归读取目录下的 `.md` / `.markdown` 文件，解析 YAML（`---`）或
+//!   TOML（`+++`）front matter，`title`、`slug`（或 `permalink` / `url` 的最后一段、文件名）、
+//!   `date`、`updated` / `lastmod`、`tags`、`categories` / `category`、`description` / `summary`
+//!   对应到笔记的元数据，正文原样保存。`draft: true`、`published: false` 和 `_drafts` 目录下的文件跳过
+//! - WordPress：读取后台「工具 → 导出」生成的 WXR 文件，文章按选择导入为笔记或随笔，
+//!   正文（HTML，Markdown 渲染时原样保留）、摘要、分类、标签和发布时间一并导入；
+//!   评论连同回复关系导入，评论者按邮箱（没有时按昵称）建立访客，pingback、垃圾评论跳过
+//!
+//! 没有时区的时间按 UTC 处理。导入前先生成计划：每篇内容最终使用的 slug、与已有笔记或同批内容的
+//! slug 冲突（按选择改名或跳过）以及跳过的原因；`dry_run` 时只返回计划，否则在一个事务中写入。
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use percent_encoding::percent_decode_str;
+use sea_orm::DatabaseConnection;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, AppResult},
+    infra::{
+        content::ContentStore,
+        db::entities::{comments, essays, notes_metadata, visitor_profiles},
+        metrics,
+        repositories::{
+            CommentRepository, EssayRepository, NoteRepository, SeaOrmRepository, VisitorRepository,
+        },
+        unit_of_work,
+    },
+    service::{
+        essay_service::{self, EssayStatus},
+        note_service, slug_service,
+    },
+};
+
+/// 访客昵称最大长度，与数据库字段一致
+const MAX_VISITOR_NAME_LEN: usize = 13;
+/// 评论者没有昵称时使用的名字
+const ANONYMOUS: &str = "匿名";
+
+/// 待导入的一篇内容
+#[derive(Debug, Clone)]
+pub struct ImportedPost {
+    /// 来源（文件路径或 WordPress 文章 id），用于报告
+    pub source: String,
+    pub title: String,
+    /// 原站的 slug，为空时由标题生成
+    pub slug: Option<String>,
+    pub summary: Option<String>,
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+    pub content: String,
+    pub status: EssayStatus,
+    pub published_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub comments: Vec<ImportedComment>,
+}
+
+/// 待导入的一条评论
+#[derive(Debug, Clone)]
+pub struct ImportedComment {
+    /// 原站的评论 id，只用于还原回复关系
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub author: String,
+    pub email: Option<String>,
+    pub ip: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub approved: bool,
+}
+
+/// 解析结果：要导入为笔记和随笔的内容，以及解析时就跳过的文件
+#[derive(Debug, Default)]
+pub struct ImportBatch {
+    pub notes: Vec<ImportedPost>,
+    pub essays: Vec<ImportedPost>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// 跳过的内容及原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub source: String,
+    pub reason: String,
+}
+
+impl Skipped {
+    fn new(source: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// slug 与已有笔记冲突时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SlugConflict {
+    /// 依次追加 `-2`、`-3`……
+    Rename,
+    /// 跳过这篇内容
+    Skip,
+}
+
+/// WordPress 文章导入为哪种内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PostTarget {
+    Notes,
+    Essays,
+}
+
+/// 计划创建的笔记
+#[derive(Debug, Clone)]
+pub struct PlannedNote {
+    pub source: String,
+    pub title: String,
+    pub slug: String,
+    /// slug 冲突时原来想用的 slug
+    pub renamed_from: Option<String>,
+    pub comments: usize,
+}
+
+/// 计划创建的随笔
+#[derive(Debug, Clone)]
+pub struct PlannedEssay {
+    pub source: String,
+    /// 截断到随笔标题最大长度后的标题
+    pub title: String,
+    /// 标题超长被截断时的原标题
+    pub truncated_from: Option<String>,
+    pub status: EssayStatus,
+    pub comments: usize,
+}
+
+/// 导入报告；`dry_run` 时是将要创建的内容，否则是已经创建的内容
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub notes: Vec<PlannedNote>,
+    pub essays: Vec<PlannedEssay>,
+    /// 需要新建的访客
+    pub visitors: usize,
+    pub skipped: Vec<Skipped>,
+}
+
+impl ImportReport {
+    /// 因 slug 冲突改名的笔记数
+    pub fn renamed(&self) -> usize {
+        self.notes
+            .iter()
+            .filter(|note| note.renamed_from.is_some())
+            .count()
+    }
+
+    /// 标题被截断的随笔数
+    pub fn truncated(&self) -> usize {
+        self.essays
+            .iter()
+            .filter(|essay| essay.truncated_from.is_some())
+            .count()
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    text.chars().take(max).collect()
+}
+
+/// 解析各种常见格式的时间，没有时区时按 UTC
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.to_utc());
+    }
+    for format in [
+        "%Y-%m-%d %H:%M:%S %z",
+        "%Y-%m-%d %H:%M:%S%z",
+        "%Y-%m-%d %H:%M %z",
+    ] {
+        if let Ok(time) = DateTime::parse_from_str(text, format) {
+            return Some(time.to_utc());
+        }
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(time.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+// ---------- Hexo / Hugo / Jekyll ----------
+
+/// 把 front matter 和正文分开，返回（front matter，正文）；没有 front matter 时返回 `None`
+fn split_front_matter(text: &str) -> AppResult<Option<(Map<String, Value>, &str)>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let Some((first, rest)) = text.split_once('\n') else {
+        return Ok(None);
+    };
+    let delimiter = first.trim_end();
+    if delimiter != "---" && delimiter != "+++" {
+        return Ok(None);
+    }
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == delimiter || (delimiter == "---" && trimmed == "...") {
+            let (matter, body) = (&rest[..offset], &rest[offset + line.len()..]);
+            let map = if delimiter == "---" {
+                parse_yaml(matter)?
+            } else {
+                parse_toml(matter)?
+            };
+            return Ok(Some((map, body.trim_start_matches(['\r', '\n']))));
+        }
+        offset += line.len();
+    }
+    Ok(None)
+}
+
+fn parse_yaml(matter: &str) -> AppResult<Map<String, Value>> {
+    if matter.trim().is_empty() {
+        return Ok(Map::new());
+    }
+    serde_yaml::from_str(matter)
+        .map_err(|e| AppError::BadRequest(format!("YAML front matter 格式错误: {e}")))
+}
+
+fn parse_toml(matter: &str) -> AppResult<Map<String, Value>> {
+    let table: toml::Table = matter
+        .parse()
+        .map_err(|e| AppError::BadRequest(format!("TOML front matter 格式错误: {e}")))?;
+    Ok(table
+        .into_iter()
+        .map(|(key, value)| (key, toml_to_json(value)))
+        .collect())
+}
+
+/// TOML 的时间类型在 JSON 中没有对应，转成字符串
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(text) => Value::String(text),
+        toml::Value::Integer(number) => number.into(),
+        toml::Value::Float(number) => number.into(),
+        toml::Value::Boolean(flag) => flag.into(),
+        toml::Value::Datetime(time) => Value::String(time.to_string()),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// 第一个存在的字段，数字和布尔值也转成字符串
+fn text_field(matter: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| match matter.get(*key)? {
+        Value::String(text) => non_empty(Some(text.clone())),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    })
+}
+
+/// 列表字段：YAML 列表（Hexo 的多级分类是嵌套列表，会被展开）或逗号分隔的字符串
+fn list_field(matter: &Map<String, Value>, keys: &[&str]) -> Vec<String> {
+    fn collect(value: &Value, out: &mut Vec<String>) {
+        match value {
+            Value::Array(items) => items.iter().for_each(|item| collect(item, out)),
+            Value::String(text) => out.extend(
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string),
+            ),
+            Value::Number(number) => out.push(number.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut items = Vec::new();
+    for key in keys {
+        if let Some(value) = matter.get(*key) {
+            collect(value, &mut items);
+        }
+    }
+    let mut seen = HashSet::new();
+    items.retain(|item| !item.contains(',') && seen.insert(item.clone()));
+    items
+}
+
+/// Jekyll 的文件名以 `YYYY-MM-DD-` 开头，拆成日期和剩下的部分
+fn split_jekyll_date(stem: &str) -> (Option<DateTime<Utc>>, &str) {
+    if stem.len() > 11
+        && stem.as_bytes()[10] == b'-'
+        && let Some(date) = stem.get(..10).and_then(parse_date)
+    {
+        return (Some(date), &stem[11..]);
+    }
+    (None, stem)
+}
+
+/// 把一个 Markdown 文件解析成待导入的笔记，返回 `Err` 时是跳过的原因
+fn parse_markdown_file(source: &str, path: &Path, text: &str) -> Result<ImportedPost, String> {
+    let (matter, body) = match split_front_matter(text) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => return Err("没有 front matter".to_string()),
+        Err(err) => return Err(err.to_string()),
+    };
+    if matter.get("draft").and_then(Value::as_bool) == Some(true)
+        || matter.get("published").and_then(Value::as_bool) == Some(false)
+    {
+        return Err("草稿".to_string());
+    }
+
+    // Hugo 的 page bundle 正文在 `文章名/index.md`
+    let mut stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    if stem == "index" {
+        stem = path
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or(stem);
+    }
+    let (file_date, stem) = split_jekyll_date(stem);
+
+    let slug = text_field(&matter, &["slug"]).or_else(|| {
+        text_field(&matter, &["permalink", "url"]).and_then(|link| {
+            link.trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .map(|segment| segment.trim_end_matches(".html").to_string())
+                .filter(|segment| !segment.is_empty())
+        })
+    });
+    let published_at = match text_field(&matter, &["date"]) {
+        Some(date) => Some(parse_date(&date).ok_or_else(|| format!("无法识别的日期 {date}"))?),
+        None => file_date,
+    }
+    .ok_or_else(|| "缺少日期".to_string())?;
+    let updated_at = text_field(&matter, &["updated", "lastmod", "last_modified_at"])
+        .and_then(|date| parse_date(&date))
+        .unwrap_or(published_at);
+
+    Ok(ImportedPost {
+        source: source.to_string(),
+        title: truncate_chars(
+            &text_field(&matter, &["title"]).unwrap_or_else(|| stem.to_string()),
+            note_service::MAX_TITLE_LEN,
+        ),
+        slug: slug.or_else(|| Some(stem.to_string())),
+        summary: text_field(&matter, &["description", "summary", "excerpt"]),
+        tags: list_field(&matter, &["tags"]),
+        category: list_field(&matter, &["categories", "category"])
+            .into_iter()
+            .next(),
+        content: body.to_string(),
+        status: EssayStatus::Published,
+        published_at,
+        updated_at,
+        comments: Vec::new(),
+    })
+}
+
+/// 读取 Hexo / Hugo / Jekyll 的 Markdown 目录
+pub fn parse_markdown_tree(dir: &Path) -> AppResult<ImportBatch> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = std::fs::read_dir(&current).map_err(|err| {
+            AppError::BadRequest(format!("读取目录 {} 失败: {err}", current.display()))
+        })?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "node_modules" {
+                    pending.push(path);
+                }
+            } else if name.ends_with(".md") || name.ends_with(".markdown") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    let mut batch = ImportBatch::default();
+    for path in files {
+        let source = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        // Hugo 的 `_index.md` 是栏目页，不是文章
+        if path.file_name().is_some_and(|name| name == "_index.md") {
+            batch.skipped.push(Skipped::new(source, "栏目页"));
+            continue;
+        }
+        if path.components().any(|part| part.as_os_str() == "_drafts") {
+            batch.skipped.push(Skipped::new(source, "草稿"));
+            continue;
+        }
+        let text = std::fs::read_to_string(&path)
+            .map_err(|err| AppError::BadRequest(format!("读取 {} 失败: {err}", path.display())))?;
+        match parse_markdown_file(&source, &path, &text) {
+            Ok(post) => batch.notes.push(post),
+            Err(reason) => batch.skipped.push(Skipped::new(source, reason)),
+        }
+    }
+    Ok(batch)
+}
+
+// ---------- WordPress WXR ----------
+
+/// WXR 中用到的命名空间，各 WordPress 版本的 `wp` 命名空间版本号不同，只比较前缀
+#[derive(Clone, Copy)]
+enum Ns {
+    None,
+    Wp,
+    Content,
+    Excerpt,
+}
+
+impl Ns {
+    fn matches(self, uri: Option<&str>) -> bool {
+        match (self, uri) {
+            (Self::None, None) => true,
+            (Self::Wp, Some(uri)) => {
+                uri.starts_with("http://wordpress.org/export/") && !uri.ends_with("/excerpt/")
+            }
+            (Self::Content, Some(uri)) => {
+                uri.starts_with("http://purl.org/rss/1.0/modules/content")
+            }
+            (Self::Excerpt, Some(uri)) => {
+                uri.starts_with("http://wordpress.org/export/") && uri.ends_with("/excerpt/")
+            }
+            _ => false,
+        }
+    }
+}
+
+fn children<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    ns: Ns,
+    name: &'a str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| {
+        child.is_element()
+            && child.tag_name().name() == name
+            && ns.matches(child.tag_name().namespace())
+    })
+}
+
+/// 子元素的文本（含 CDATA），不存在时为空字符串
+fn child_text(node: roxmltree::Node, ns: Ns, name: &str) -> String {
+    children(node, ns, name)
+        .next()
+        .map(|child| {
+            child
+                .children()
+                .filter_map(|text| text.text())
+                .collect::<String>()
+        })
+        .unwrap_or_default()
+}
+
+/// WordPress 的时间：优先 GMT 时间，草稿的 GMT 时间为全零，此时退回站点本地时间
+fn wp_date(node: roxmltree::Node, gmt: &str, local: &str) -> Option<DateTime<Utc>> {
+    parse_date(&child_text(node, Ns::Wp, gmt))
+        .or_else(|| parse_date(&child_text(node, Ns::Wp, local)))
+}
+
+fn parse_wp_comment(node: roxmltree::Node) -> Option<ImportedComment> {
+    let kind = child_text(node, Ns::Wp, "comment_type");
+    if !kind.is_empty() && kind != "comment" {
+        return None;
+    }
+    let approved = match child_text(node, Ns::Wp, "comment_approved").as_str() {
+        "1" => true,
+        "0" => false,
+        // spam / trash
+        _ => return None,
+    };
+    let parent = child_text(node, Ns::Wp, "comment_parent");
+    Some(ImportedComment {
+        id: child_text(node, Ns::Wp, "comment_id"),
+        parent_id: (!parent.is_empty() && parent != "0").then_some(parent),
+        author: child_text(node, Ns::Wp, "comment_author"),
+        email: non_empty(Some(child_text(node, Ns::Wp, "comment_author_email"))),
+        ip: truncate_chars(child_text(node, Ns::Wp, "comment_author_IP").trim(), 45),
+        content: child_text(node, Ns::Wp, "comment_content"),
+        created_at: wp_date(node, "comment_date_gmt", "comment_date")?,
+        approved,
+    })
+}
+
+/// 读取 WordPress 导出的 WXR 文件，文章按 `target` 导入为笔记或随笔
+pub fn parse_wxr(xml: &str, target: PostTarget) -> AppResult<ImportBatch> {
+    let doc = roxmltree::Document::parse(xml)
+        .map_err(|e| AppError::BadRequest(format!("WXR 文件格式错误: {e}")))?;
+    let channel = children(doc.root_element(), Ns::None, "channel")
+        .next()
+        .ok_or_else(|| AppError::BadRequest("WXR 文件中没有 channel".to_string()))?;
+
+    let mut batch = ImportBatch::default();
+    for item in children(channel, Ns::None, "item") {
+        let id = child_text(item, Ns::Wp, "post_id");
+        let title = child_text(item, Ns::None, "title");
+        let source = format!("wp#{id} {title}");
+        let kind = child_text(item, Ns::Wp, "post_type");
+        if kind != "post" {
+            batch
+                .skipped
+                .push(Skipped::new(source, format!("不导入类型为 {kind} 的内容")));
+            continue;
+        }
+
+        let status = match child_text(item, Ns::Wp, "status").as_str() {
+            "publish" => EssayStatus::Published,
+            "future" => EssayStatus::Scheduled,
+            "draft" | "pending" | "private" => EssayStatus::Draft,
+            other => {
+                batch
+                    .skipped
+                    .push(Skipped::new(source, format!("状态为 {other}")));
+                continue;
+            }
+        };
+        if status == EssayStatus::Draft && target == PostTarget::Notes {
+            batch.skipped.push(Skipped::new(source, "草稿"));
+            continue;
+        }
+        let Some(published_at) = wp_date(item, "post_date_gmt", "post_date") else {
+            batch.skipped.push(Skipped::new(source, "缺少发布时间"));
+            continue;
+        };
+        let updated_at =
+            wp_date(item, "post_modified_gmt", "post_modified").unwrap_or(published_at);
+
+        let category = |domain: &str| {
+            children(item, Ns::None, "category")
+                .filter(|node| node.attribute("domain") == Some(domain))
+                .filter_map(|node| non_empty(node.text().map(str::to_string)))
+                .filter(|name| !name.contains(','))
+                .collect::<Vec<_>>()
+        };
+        // 非 ASCII 的 slug 在 WXR 中是百分号编码的
+        let slug = child_text(item, Ns::Wp, "post_name");
+        let slug = percent_decode_str(&slug).decode_utf8_lossy().to_string();
+        // 随笔的标题限制更短，在计划中截断并报告，这里只按笔记的限制截断
+        let post = ImportedPost {
+            source,
+            title: truncate_chars(
+                non_empty(Some(title)).as_deref().unwrap_or("untitled"),
+                note_service::MAX_TITLE_LEN,
+            ),
+            slug: non_empty(Some(slug)),
+            summary: non_empty(Some(child_text(item, Ns::Excerpt, "encoded"))),
+            tags: category("post_tag"),
+            category: category("category")
+                .into_iter()
+                .find(|name| name != "Uncategorized"),
+            content: child_text(item, Ns::Content, "encoded"),
+            status,
+            published_at,
+            updated_at,
+            comments: children(item, Ns::Wp, "comment")
+                .filter_map(parse_wp_comment)
+                .collect(),
+        };
+        match target {
+            PostTarget::Notes => batch.notes.push(post),
+            PostTarget::Essays => batch.essays.push(post),
+        }
+    }
+    Ok(batch)
+}
+
+// ---------- 写入 ----------
+
+/// 评论者对应的访客 cookie：按邮箱（没有时按昵称）生成，重复导入时复用同一个访客
+fn visitor_cookie(comment: &ImportedComment) -> String {
+    let key = comment
+        .email
+        .as_deref()
+        .map(str::to_lowercase)
+        .unwrap_or_else(|| comment.author.trim().to_string());
+    let hash = hex::encode(Sha256::digest(key.as_bytes()));
+    format!("import-{}", &hash[..32])
+}
+
+/// 生成计划：确定每篇笔记的 slug，统计需要新建的访客
+async fn plan<R>(
+    repo: &R,
+    batch: &ImportBatch,
+    on_conflict: SlugConflict,
+) -> AppResult<ImportReport>
+where
+    R: NoteRepository + VisitorRepository,
+{
+    let mut report = ImportReport {
+        skipped: batch.skipped.clone(),
+        ..Default::default()
+    };
+
+    let mut reserved = HashSet::new();
+    for post in &batch.notes {
+        let base = slug_service::slugify(post.slug.as_deref().unwrap_or(&post.title));
+        // `is_slug_taken` 同时检查历史 slug，已有跳转的旧 slug 也算占用
+        let taken = reserved.contains(&base) || repo.is_slug_taken(&base, None).await?;
+        let slug = match (taken, on_conflict) {
+            (false, _) => base.clone(),
+            (true, SlugConflict::Rename) => {
+                slug_service::unique_slug_reserving(repo, &base, None, &reserved).await?
+            }
+            (true, SlugConflict::Skip) => {
+                report.skipped.push(Skipped::new(
+                    post.source.clone(),
+                    format!("slug {base} 已被占用"),
+                ));
+                continue;
+            }
+        };
+        reserved.insert(slug.clone());
+        report.notes.push(PlannedNote {
+            source: post.source.clone(),
+            title: post.title.clone(),
+            renamed_from: (slug != base).then_some(base),
+            slug,
+            comments: post.comments.len(),
+        });
+    }
+    report.essays = batch
+        .essays
+        .iter()
+        .map(|post| {
+            let title = truncate_chars(&post.title, essay_service::MAX_TITLE_LEN);
+            PlannedEssay {
+                source: post.source.clone(),
+                truncated_from: (title != post.title).then(|| post.title.clone()),
+                title,
+                status: post.status,
+                comments: post.comments.len(),
+            }
+        })
+        .collect();
+
+    let planned: HashSet<&str> = report
+        .notes
+        .iter()
+        .map(|note| note.source.as_str())
+        .collect();
+    let mut cookies = HashSet::new();
+    for comment in batch
+        .notes
+        .iter()
+        .filter(|post| planned.contains(post.source.as_str()))
+        .chain(&batch.essays)
+        .flat_map(|post| &post.comments)
+    {
+        let cookie = visitor_cookie(comment);
+        if cookies.insert(cookie.clone()) && repo.find_visitor_by_cookie(&cookie).await?.is_none() {
+            report.visitors += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// 截断到昵称的最大长度，被其他访客占用时加数字后缀，加上后缀后仍不超过最大长度
+pub(crate) async fn unique_visitor_name<R: VisitorRepository>(
+    repo: &R,
+    author: &str,
+) -> AppResult<String> {
+    let mut name = truncate_chars(author, MAX_VISITOR_NAME_LEN);
+    let mut n = 2;
+    while repo.find_visitor_by_name(&name).await?.is_some() {
+        let suffix = format!("-{n}");
+        name = truncate_chars(author, MAX_VISITOR_NAME_LEN - suffix.len()) + &suffix;
+        n += 1;
+    }
+    Ok(name)
+}
+
+/// 找到或新建评论者对应的访客，昵称被其他访客占用时加数字后缀
+async fn visitor_for<R: VisitorRepository>(repo: &R, comment: &ImportedComment) -> AppResult<i32> {
+    let cookie = visitor_cookie(comment);
+    if let Some(visitor) = repo.find_visitor_by_cookie(&cookie).await? {
+        return Ok(visitor.id);
+    }
+    let author = non_empty(Some(comment.author.clone())).unwrap_or_else(|| ANONYMOUS.to_string());
+    let name = unique_visitor_name(repo, &author).await?;
+    let visitor = repo
+        .insert_visitor(visitor_profiles::Model {
+            id: 0,
+            cookie_id: cookie,
+            name,
+            ip: comment.ip.clone(),
+            created_at: comment.created_at,
+            updated_at: comment.created_at,
+        })
+        .await?;
+    Ok(visitor.id)
+}
+
+/// 写入一篇内容的评论：先全部插入，再补上回复关系；父评论被跳过的回复成为顶层评论
+async fn insert_comments<R>(
+    repo: &R,
+    post: &ImportedPost,
+    note_id: Option<i32>,
+    essay_id: Option<i32>,
+) -> AppResult<()>
+where
+    R: CommentRepository + VisitorRepository,
+{
+    let mut inserted = HashMap::new();
+    for comment in &post.comments {
+        let model = repo
+            .insert_comment(comments::Model {
+                id: 0,
+                note_metadata_id: note_id,
+                essay_id,
+                visitor_profile_id: visitor_for(repo, comment).await?,
+                content: comment.content.clone(),
+                parent_id: None,
+                created_at: comment.created_at,
+                is_approved: comment.approved,
+            })
+            .await?;
+        inserted.insert(comment.id.as_str(), model);
+    }
+    for comment in &post.comments {
+        let Some(parent) = comment
+            .parent_id
+            .as_deref()
+            .and_then(|parent| inserted.get(parent))
+            .map(|parent| parent.id)
+        else {
+            continue;
+        };
+        let mut model = inserted[comment.id.as_str()].clone();
+        model.parent_id = Some(parent);
+        repo.update_comment(model).await?;
+    }
+    Ok(())
+}
+
+/// 按计划导入；`dry_run` 时只返回计划，不做任何修改
+pub async fn import_batch(
+    db: &DatabaseConnection,
+    content: &ContentStore,
+    batch: &ImportBatch,
+    on_conflict: SlugConflict,
+    dry_run: bool,
+) -> AppResult<ImportReport> {
+    if dry_run {
+        let mut report = plan(&SeaOrmRepository::new(db), batch, on_conflict).await?;
+        report.dry_run = true;
+        return Ok(report);
+    }
+
+    // 正文先暂存，事务提交后才成为正式文件：事务回滚或重试时不会留下没有元数据的正文。
+    // 计划在事务里才确定，所以暂存全部笔记，提交后再删掉被跳过的
+    let file_ids: HashMap<&str, Uuid> = batch
+        .notes
+        .iter()
+        .map(|post| (post.source.as_str(), Uuid::new_v4()))
+        .collect();
+    let contents: Vec<(Uuid, &str)> = batch
+        .notes
+        .iter()
+        .map(|post| (file_ids[post.source.as_str()], post.content.as_str()))
+        .collect();
+    let mut staged = content.stage_notes(&contents).await?;
+    let file_ids = &file_ids;
+
+    let result = unit_of_work::run(db, |uow| {
+        Box::pin(async move {
+            let repo = uow.repo();
+            let report = plan(&repo, batch, on_conflict).await?;
+            let posts: HashMap<&str, &ImportedPost> = batch
+                .notes
+                .iter()
+                .map(|post| (post.source.as_str(), post))
+                .collect();
+
+            for planned in &report.notes {
+                let post = posts[planned.source.as_str()];
+                let file_id = file_ids[planned.source.as_str()];
+                let note = repo
+                    .insert_note(notes_metadata::Model {
+                        id: 0,
+                        file_id,
+                        slug: planned.slug.clone(),
+                        title: post.title.clone(),
+                        summary: post.summary.clone(),
+                        published_at: post.published_at,
+                        updated_at: post.updated_at,
+                        views: 0,
+                        likes_count: 0,
+                        tags: (!post.tags.is_empty()).then(|| post.tags.join(",")),
+                        category: post.category.clone(),
+                    })
+                    .await?;
+                insert_comments(&repo, post, Some(note.id), None).await?;
+            }
+
+            for (planned, post) in report.essays.iter().zip(&batch.essays) {
+                let essay = repo
+                    .insert_essay(essays::Model {
+                        id: 0,
+                        title: planned.title.clone(),
+                        content: post.content.clone(),
+                        created_at: post.published_at,
+                        updated_at: post.updated_at,
+                        status: post.status.as_str().to_string(),
+                        publish_at: (post.status != EssayStatus::Draft)
+                            .then_some(post.published_at),
+                    })
+                    .await?;
+                insert_comments(&repo, post, None, Some(essay.id)).await?;
+            }
+
+            log::info!(
+                "📥 已导入 {} 篇笔记、{} 篇随笔，跳过 {} 项",
+                report.notes.len(),
+                report.essays.len(),
+                report.skipped.len()
+            );
+            Ok(report)
+        })
+    })
+    .await;
+    if let Ok(report) = &result {
+        let imported: HashSet<Uuid> = report
+            .notes
+            .iter()
+            .map(|note| file_ids[note.source.as_str()])
+            .collect();
+        let (kept, skipped): (Vec<_>, Vec<_>) = staged
+            .into_iter()
+            .partition(|(file_id, _)| imported.contains(file_id));
+        for (_, path) in &skipped {
+            content.discard_staged(path).await;
+        }
+        staged = kept;
+    }
+    let report = content.settle_staged(result, staged).await?;
+    metrics::record_comments(
+        report.notes.iter().map(|note| note.comments).sum::<usize>()
+            + report
+                .essays
+                .iter()
+                .map(|essay| essay.comments)
+                .sum::<usize>(),
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::test_db;
+
+    const HEXO: &str = "---\ntitle: 你好 Hexo\ndate: 2020-01-02 10:00:00\nupdated: 2020-01-03 08:00:00\ntags:\n  - rust\n  - web\ncategories:\n  - [技术, 后端]\n---\n\n正文\n";
+    const HUGO: &str = "+++\ntitle = \"Hugo Post\"\ndate = 2021-05-06T07:08:09+08:00\nslug = \"hugo-post\"\ntags = [\"go\"]\ndescription = \"摘要\"\n+++\n# Hugo\n";
+    const JEKYLL: &str = "---\ntitle: Jekyll\ncategory: misc\ntags: a, b\n---\nbody\n";
+
+    const WXR: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0"
+    xmlns:excerpt="http://wordpress.org/export/1.2/excerpt/"
+    xmlns:content="http://purl.org/rss/1.0/modules/content/"
+    xmlns:wp="http://wordpress.org/export/1.2/">
+<channel>
+    <item>
+        <title>Hello WordPress</title>
+        <content:encoded><![CDATA[<p>正文</p>]]></content:encoded>
+        <excerpt:encoded><![CDATA[摘要]]></excerpt:encoded>
+        <wp:post_id>7</wp:post_id>
+        <wp:post_date_gmt>2019-03-04 05:06:07</wp:post_date_gmt>
+        <wp:post_name><![CDATA[%e4%bd%a0%e5%a5%bd]]></wp:post_name>
+        <wp:status>publish</wp:status>
+        <wp:post_type>post</wp:post_type>
+        <category domain="category" nicename="tech"><![CDATA[技术]]></category>
+        <category domain="post_tag" nicename="php"><![CDATA[php]]></category>
+        <wp:comment>
+            <wp:comment_id>1</wp:comment_id>
+            <wp:comment_author><![CDATA[小红]]></wp:comment_author>
+            <wp:comment_author_email>red@example.com</wp:comment_author_email>
+            <wp:comment_author_IP>192.0.2.1</wp:comment_author_IP>
+            <wp:comment_date_gmt>2019-03-05 00:00:00</wp:comment_date_gmt>
+            <wp:comment_content><![CDATA[第一]]></wp:comment_content>
+            <wp:comment_approved>1</wp:comment_approved>
+            <wp:comment_type>comment</wp:comment_type>
+            <wp:comment_parent>0</wp:comment_parent>
+        </wp:comment>
+        <wp:comment>
+            <wp:comment_id>2</wp:comment_id>
+            <wp:comment_author><![CDATA[小蓝]]></wp:comment_author>
+            <wp:comment_author_IP>192.0.2.2</wp:comment_author_IP>
+            <wp:comment_date_gmt>2019-03-06 00:00:00</wp:comment_date_gmt>
+            <wp:comment_content><![CDATA[回复]]></wp:comment_content>
+            <wp:comment_approved>0</wp:comment_approved>
+            <wp:comment_type></wp:comment_type>
+            <wp:comment_parent>1</wp:comment_parent>
+        </wp:comment>
+        <wp:comment>
+            <wp:comment_id>3</wp:comment_id>
+            <wp:comment_author><![CDATA[spammer]]></wp:comment_author>
+            <wp:comment_date_gmt>2019-03-06 00:00:00</wp:comment_date_gmt>
+            <wp:comment_content><![CDATA[buy]]></wp:comment_content>
+            <wp:comment_approved>spam</wp:comment_approved>
+            <wp:comment_parent>0</wp:comment_parent>
+        </wp:comment>
+    </item>
+    <item>
+        <title>Draft</title>
+        <wp:post_id>8</wp:post_id>
+        <wp:post_date>2019-04-01 00:00:00</wp:post_date>
+        <wp:post_date_gmt>0000-00-00 00:00:00</wp:post_date_gmt>
+        <wp:status>draft</wp:status>
+        <wp:post_type>post</wp:post_type>
+    </item>
+    <item>
+        <title>About</title>
+        <wp:post_id>9</wp:post_id>
+        <wp:status>publish</wp:status>
+        <wp:post_type>page</wp:post_type>
+    </item>
+</channel>
+</rss>"#;
+
+    fn write(path: &Path, text: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn test_parse_markdown_tree() {
+        let dir = std::env::temp_dir().join(format!("rowan-import-{}", Uuid::new_v4()));
+        write(&dir.join("hexo.md"), HEXO);
+        write(&dir.join("hugo-post/index.md"), HUGO);
+        write(&dir.join("_posts/2022-02-03-jekyll-post.markdown"), JEKYLL);
+        write(&dir.join("_drafts/wip.md"), JEKYLL);
+        write(&dir.join("draft.md"), "---\ntitle: x\ndraft: true\n---\n");
+        write(&dir.join("_index.md"), HUGO);
+        write(&dir.join("README.md"), "# no front matter\n");
+
+        let batch = parse_markdown_tree(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let notes: Vec<_> = batch.notes.iter().map(|n| n.source.as_str()).collect();
+        assert_eq!(
+            notes,
+            [
+                "_posts/2022-02-03-jekyll-post.markdown",
+                "hexo.md",
+                "hugo-post/index.md"
+            ]
+        );
+        let (jekyll, hexo, hugo) = (&batch.notes[0], &batch.notes[1], &batch.notes[2]);
+
+        assert_eq!(hexo.title, "你好 Hexo");
+        assert_eq!(hexo.slug.as_deref(), Some("hexo"));
+        assert_eq!(hexo.tags, ["rust", "web"]);
+        assert_eq!(hexo.category.as_deref(), Some("技术"));
+        assert_eq!(hexo.published_at.to_rfc3339(), "2020-01-02T10:00:00+00:00");
+        assert_eq!(hexo.updated_at.to_rfc3339(), "2020-01-03T08:00:00+00:00");
+        assert_eq!(hexo.content, "正文\n");
+
+        assert_eq!(hugo.slug.as_deref(), Some("hugo-post"));
+        assert_eq!(hugo.summary.as_deref(), Some("摘要"));
+        assert_eq!(hugo.published_at.to_rfc3339(), "2021-05-05T23:08:09+00:00");
+        assert_eq!(hugo.content, "# Hugo\n");
+
+        assert_eq!(jekyll.slug.as_deref(), Some("jekyll-post"));
+        assert_eq!(
+            jekyll.published_at.to_rfc3339(),
+            "2022-02-03T00:00:00+00:00"
+        );
+        assert_eq!(jekyll.tags, ["a", "b"]);
+        assert_eq!(jekyll.category.as_deref(), Some("misc"));
+
+        let mut skipped: Vec<_> = batch
+            .skipped
+            .iter()
+            .map(|s| (s.source.as_str(), s.reason.as_str()))
+            .collect();
+        skipped.sort();
+        assert_eq!(
+            skipped,
+            [
+                ("README.md", "没有 front matter"),
+                ("_drafts/wip.md", "草稿"),
+                ("_index.md", "栏目页"),
+                ("draft.md", "草稿"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_wxr() {
+        let batch = parse_wxr(WXR, PostTarget::Notes).unwrap();
+        assert_eq!(batch.notes.len(), 1);
+        assert_eq!(batch.skipped.len(), 2);
+
+        let post = &batch.notes[0];
+        assert_eq!(post.slug.as_deref(), Some("你好"));
+        assert_eq!(post.content, "<p>正文</p>");
+        assert_eq!(post.summary.as_deref(), Some("摘要"));
+        assert_eq!(post.tags, ["php"]);
+        assert_eq!(post.category.as_deref(), Some("技术"));
+        assert_eq!(post.published_at.to_rfc3339(), "2019-03-04T05:06:07+00:00");
+        let comments: Vec<_> = post
+            .comments
+            .iter()
+            .map(|c| (c.id.as_str(), c.parent_id.as_deref(), c.approved))
+            .collect();
+        assert_eq!(comments, [("1", None, true), ("2", Some("1"), false)]);
+
+        // 导入为随笔时草稿保留，发布时间退回站点本地时间
+        let batch = parse_wxr(WXR, PostTarget::Essays).unwrap();
+        assert_eq!(batch.essays.len(), 2);
+        assert_eq!(batch.essays[1].status, EssayStatus::Draft);
+        assert_eq!(
+            batch.essays[1].published_at.to_rfc3339(),
+            "2019-04-01T00:00:00+00:00"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_content_rename_keeps_import() {
+        let dir = std::env::temp_dir().join(format!("rowan-import-{}", Uuid::new_v4()));
+        let db = test_db().await;
+        let content = ContentStore::new(&dir).failing_commits();
+        let batch = parse_wxr(WXR, PostTarget::Notes).unwrap();
+
+        // 事务已经提交，正文改名失败不算导入失败
+        let report = import_batch(&db, &content, &batch, SlugConflict::Rename, false)
+            .await
+            .unwrap();
+        assert_eq!(report.notes.len(), 1);
+        let note = SeaOrmRepository::new(&db)
+            .find_note_by_slug(&report.notes[0].slug)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(content.read_note(note.file_id).await.unwrap(), None);
+        // 正文留在临时文件中，可以按日志手动恢复
+        let staged: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        assert_eq!(staged, ["<p>正文</p>"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_long_essay_title_is_truncated() {
+        let dir = std::env::temp_dir().join(format!("rowan-import-{}", Uuid::new_v4()));
+        let db = test_db().await;
+        let content = ContentStore::new(&dir);
+        let mut batch = parse_wxr(WXR, PostTarget::Essays).unwrap();
+        let long = "一篇标题很长很长的随笔，长度超过了随笔标题的限制".to_string();
+        assert!(long.chars().count() > essay_service::MAX_TITLE_LEN);
+        batch.essays[0].title = long.clone();
+
+        // 试运行时报告被截断的标题
+        let report = import_batch(&db, &content, &batch, SlugConflict::Rename, true)
+            .await
+            .unwrap();
+        assert_eq!(report.truncated(), 1);
+        assert_eq!(
+            report.essays[0].truncated_from.as_deref(),
+            Some(long.as_str())
+        );
+        let title = report.essays[0].title.clone();
+        assert_eq!(title.chars().count(), essay_service::MAX_TITLE_LEN);
+        assert!(long.starts_with(&title));
+
+        import_batch(&db, &content, &batch, SlugConflict::Rename, false)
+            .await
+            .unwrap();
+        let (essays, _) = SeaOrmRepository::new(&db)
+            .list_essays(
+                None,
+                crate::infra::repositories::Page {
+                    number: 1,
+                    size: 10,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(essays.iter().any(|essay| essay.title == title));
+        assert!(
+            essays
+                .iter()
+                .all(|essay| essay.title.chars().count() <= essay_service::MAX_TITLE_LEN)
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_and_import() {
+        let dir = std::env::temp_dir().join(format!("rowan-import-{}", Uuid::new_v4()));
+        let db = test_db().await;
+        let content = ContentStore::new(&dir);
+        let repo = SeaOrmRepository::new(&db);
+        let now = Utc::now();
+        repo.insert_note(notes_metadata::Model {
+            id: 0,
+            file_id: Uuid::new_v4(),
+            slug: "ni-hao".to_string(),
+            title: "已有".to_string(),
+            summary: None,
+            published_at: now,
+            updated_at: now,
+            views: 0,
+            likes_count: 0,
+            tags: None,
+            category: None,
+        })
+        .await
+        .unwrap();
+
+        let mut batch = parse_wxr(WXR, PostTarget::Notes).unwrap();
+        // 同一批中两篇文章 slug 相同
+        let mut twin = batch.notes[0].clone();
+        twin.source = "twin".to_string();
+        twin.comments.clear();
+        batch.notes.push(twin);
+
+        let report = import_batch(&db, &content, &batch, SlugConflict::Rename, true)
+            .await
+            .unwrap();
+        assert!(report.dry_run);
+        let slugs: Vec<_> = report
+            .notes
+            .iter()
+            .map(|n| (n.slug.as_str(), n.renamed_from.as_deref()))
+            .collect();
+        assert_eq!(
+            slugs,
+            [("ni-hao-2", Some("ni-hao")), ("ni-hao-3", Some("ni-hao"))]
+        );
+        assert_eq!(report.visitors, 2);
+        assert!(repo.find_note_by_slug("ni-hao-2").await.unwrap().is_none());
+
+        let skip = import_batch(&db, &content, &batch, SlugConflict::Skip, true)
+            .await
+            .unwrap();
+        assert!(skip.notes.is_empty());
+        assert_eq!(skip.visitors, 0);
+        assert_eq!(skip.skipped.len(), 4);
+
+        let report = import_batch(&db, &content, &batch, SlugConflict::Rename, false)
+            .await
+            .unwrap();
+        assert_eq!(report.notes.len(), 2);
+        let note = repo.find_note_by_slug("ni-hao-2").await.unwrap().unwrap();
+        assert_eq!(note.tags.as_deref(), Some("php"));
+        assert_eq!(
+            content.read_note(note.file_id).await.unwrap().as_deref(),
+            Some("<p>正文</p>")
+        );
+        let comments = repo.list_note_comments(note.id).await.unwrap();
+        assert_eq!(comments.len(), 2);
+        let first = comments.iter().find(|c| c.parent_id.is_none()).unwrap();
+        let reply = comments.iter().find(|c| c.parent_id.is_some()).unwrap();
+        assert_eq!(reply.parent_id, Some(first.id));
+        assert!(first.is_approved && !reply.is_approved);
+
+        // 全部跳过时暂存的正文被清理，正文目录里只有导入的两篇
+        let skip = import_batch(&db, &content, &batch, SlugConflict::Skip, false)
+            .await
+            .unwrap();
+        assert!(skip.notes.is_empty());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        // 再次导入时复用已有访客
+        let again = import_batch(&db, &content, &batch, SlugConflict::Rename, true)
+            .await
+            .unwrap();
+        assert_eq!(again.visitors, 0);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_redirected_slug_is_taken() {
+        let dir = std::env::temp_dir().join(format!("rowan-import-{}", Uuid::new_v4()));
+        let db = test_db().await;
+        let content = ContentStore::new(&dir);
+        let repo = SeaOrmRepository::new(&db);
+        let now = Utc::now();
+        let existing = repo
+            .insert_note(notes_metadata::Model {
+                id: 0,
+                file_id: Uuid::new_v4(),
+                slug: "hello".to_string(),
+                title: "已有".to_string(),
+                summary: None,
+                published_at: now,
+                updated_at: now,
+                views: 0,
+                likes_count: 0,
+                tags: None,
+                category: None,
+            })
+            .await
+            .unwrap();
+        // ni-hao 只作为旧 slug 存在，仍然跳转到已有的笔记
+        repo.record_slug_change(existing.id, "ni-hao", "hello")
+            .await
+            .unwrap();
+        let batch = parse_wxr(WXR, PostTarget::Notes).unwrap();
+
+        let skip = import_batch(&db, &content, &batch, SlugConflict::Skip, true)
+            .await
+            .unwrap();
+        assert!(skip.notes.is_empty());
+        for dry_run in [true, false] {
+            let report = import_batch(&db, &content, &batch, SlugConflict::Rename, dry_run)
+                .await
+                .unwrap();
+            assert_eq!(report.notes[0].slug, "ni-hao-2");
+            assert_eq!(report.notes[0].renamed_from.as_deref(), Some("ni-hao"));
+        }
+        assert!(repo.find_note_by_slug("ni-hao").await.unwrap().is_none());
+        assert_eq!(
+            slug_service::resolve_redirect(&repo, "ni-hao")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("hello")
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
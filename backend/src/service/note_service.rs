@@ -1,152 +1,454 @@
-use chrono::Utc;
-use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
-    QueryOrder, Set,
-};
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::{
     error::{AppError, AppResult},
-    models::{
-        dtos::{CreateNoteRequest, PaginationQuery, UpdateNoteRequest},
-        note::{self, Entity as Note},
+    infra::{
+        content::ContentStore,
+        db::entities::notes_metadata,
+        metrics,
+        repositories::{CommentRepository, LikeRepository, NoteRepository},
+        unit_of_work,
     },
+    service::{PaginationQuery, slug_service, stats_service},
 };
 
-/// 获取笔记列表
+/// 标题最大长度，与数据库字段一致
+pub const MAX_TITLE_LEN: usize = 255;
+
+/// 上传笔记请求
+#[derive(Debug, Deserialize)]
+pub struct CreateNoteRequest {
+    pub title: String,
+    /// 省略时由标题自动生成
+    pub slug: Option<String>,
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+    /// Markdown 正文
+    pub content: String,
+    /// 省略时为当前时间
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// 修改笔记请求，未提供的字段保持不变
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateNoteRequest {
+    pub title: Option<String>,
+    /// 修改后旧 slug 会 301 跳转到新 slug
+    pub slug: Option<String>,
+    pub summary: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub content: Option<String>,
+}
+
+fn validate_title(title: &str) -> AppResult<String> {
+    let title = title.trim();
+    let len = title.chars().count();
+    if len == 0 || len > MAX_TITLE_LEN {
+        return Err(AppError::BadRequest(format!(
+            "标题长度必须在 1 到 {MAX_TITLE_LEN} 之间"
+        )));
+    }
+    Ok(title.to_string())
+}
+
+/// 把标签列表拼成逗号分隔的字段，去掉空白和重复项
+fn join_tags(tags: &[String]) -> Option<String> {
+    let mut joined: Vec<&str> = Vec::new();
+    for tag in tags.iter().map(|tag| tag.trim()) {
+        if !tag.is_empty() && !tag.contains(',') && !joined.contains(&tag) {
+            joined.push(tag);
+        }
+    }
+    (!joined.is_empty()).then(|| joined.join(","))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 解析逗号分隔的标签字段
+pub fn parse_tags(tags: Option<&str>) -> Vec<String> {
+    tags.map(|tags| {
+        tags.split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// 获取笔记列表，按发布时间倒序
 pub async fn list_notes(
-    db: &DatabaseConnection,
+    repo: &dyn NoteRepository,
     query: &PaginationQuery,
-) -> AppResult<(Vec<note::Model>, u64)> {
-    let page = query.page.max(1);
-    let per_page = query.per_page.min(100).max(1);
+) -> AppResult<(Vec<notes_metadata::Model>, u64)> {
+    repo.list_notes(query.page()).await
+}
+
+/// 某个标签下已发布的笔记，按发布时间倒序
+pub async fn list_published_by_tag(
+    repo: &dyn NoteRepository,
+    tag: &str,
+) -> AppResult<Vec<notes_metadata::Model>> {
+    repo.list_published_notes_by_tag(tag, Utc::now()).await
+}
 
-    let paginator = Note::find()
-        .order_by_desc(note::Column::CreatedAt)
-        .paginate(db, per_page);
+/// 某个分类下已发布的笔记，按发布时间倒序
+pub async fn list_published_by_category(
+    repo: &dyn NoteRepository,
+    category: &str,
+) -> AppResult<Vec<notes_metadata::Model>> {
+    repo.list_published_notes_by_category(category, Utc::now())
+        .await
+}
 
-    let total = paginator.num_items().await?;
-    let notes = paginator.fetch_page(page - 1).await?;
+/// 根据 slug 获取单个笔记
+pub async fn get_note_by_slug(
+    repo: &dyn NoteRepository,
+    slug: &str,
+) -> AppResult<notes_metadata::Model> {
+    repo.find_note_by_slug(slug)
+        .await?
+        .ok_or(AppError::NotFound)
+}
 
-    Ok((notes, total))
+/// 根据 slug 获取已发布的笔记，供公开页面和接口使用；未到发布时间的笔记视为不存在
+pub async fn get_published_note_by_slug(
+    repo: &dyn NoteRepository,
+    slug: &str,
+) -> AppResult<notes_metadata::Model> {
+    let note = get_note_by_slug(repo, slug).await?;
+    if note.published_at > Utc::now() {
+        return Err(AppError::NotFound);
+    }
+    Ok(note)
 }
 
-/// 创建笔记
+/// 上传笔记：生成不冲突的 slug，写入元数据和 Markdown 正文
 pub async fn create_note(
     db: &DatabaseConnection,
+    content: &ContentStore,
     req: CreateNoteRequest,
-    author_id: Uuid,
-) -> AppResult<note::Model> {
-    let new_note = note::ActiveModel {
-        id: Set(Uuid::new_v4()),
-        title: Set(req.title),
-        content: Set(req.content),
-        summary: Set(req.summary),
-        author_id: Set(author_id),
-        status: Set(req.status.unwrap_or_else(|| "published".to_string())),
-        tags: Set(req.tags),
-        created_at: Set(Utc::now()),
-        updated_at: Set(Utc::now()),
-        ..Default::default()
-    };
-
-    let note = new_note.insert(db).await?;
-    Ok(note)
-}
+) -> AppResult<notes_metadata::Model> {
+    let title = validate_title(&req.title)?;
+    let now = Utc::now();
+    let file_id = Uuid::new_v4();
+    // 与 update_note 相同：正文暂存到临时文件，事务提交后才成为正式文件
+    let staged = content.stage_note(&req.content).await?;
+    let (req, title) = (&req, &title);
 
-/// 获取单个笔记
-pub async fn get_note(db: &DatabaseConnection, note_id: Uuid) -> AppResult<note::Model> {
-    let note = Note::find_by_id(note_id)
-        .one(db)
-        .await?
-        .ok_or(AppError::NotFound)?;
+    let result = unit_of_work::run(db, |uow| {
+        Box::pin(async move {
+            let repo = uow.repo();
+            let base = req.slug.as_deref().unwrap_or(title);
+            let slug = slug_service::unique_slug(&repo, base, None).await?;
 
-    Ok(note)
+            repo.insert_note(notes_metadata::Model {
+                id: 0,
+                file_id,
+                slug,
+                title: title.clone(),
+                summary: non_empty(req.summary.clone()),
+                published_at: req.published_at.unwrap_or(now),
+                updated_at: now,
+                views: 0,
+                likes_count: 0,
+                tags: join_tags(&req.tags),
+                category: non_empty(req.category.clone()),
+            })
+            .await
+        })
+    })
+    .await;
+    content.settle_staged(result, vec![(file_id, staged)]).await
 }
 
-/// 更新笔记
+/// 修改笔记；slug 变化时记录旧 slug 以便跳转
 pub async fn update_note(
     db: &DatabaseConnection,
-    note_id: Uuid,
+    content: &ContentStore,
+    id: i32,
     req: UpdateNoteRequest,
-    author_id: Uuid,
-) -> AppResult<note::Model> {
-    let note = Note::find_by_id(note_id)
-        .one(db)
-        .await?
-        .ok_or(AppError::NotFound)?;
+) -> AppResult<notes_metadata::Model> {
+    // 正文先写到临时文件，事务提交后再替换正式文件：事务可能重试或回滚，
+    // 在事务里直接覆盖正文会让文件和元数据不一致
+    let staged = match &req.content {
+        Some(markdown) => Some(content.stage_note(markdown).await?),
+        None => None,
+    };
+    let req = &req;
+    let result = unit_of_work::run(db, |uow| {
+        Box::pin(async move {
+            let repo = uow.repo();
+            let mut note = repo.find_note(id).await?.ok_or(AppError::NotFound)?;
 
-    // 检查权限
-    if note.author_id != author_id {
-        return Err(AppError::Forbidden);
-    }
+            if let Some(title) = &req.title {
+                note.title = validate_title(title)?;
+            }
+            if let Some(slug) = &req.slug {
+                let slug = slug_service::unique_slug(&repo, slug, Some(id)).await?;
+                if slug != note.slug {
+                    repo.record_slug_change(id, &note.slug, &slug).await?;
+                    log::info!("🔀 笔记 #{id} 的 slug 由 {} 改为 {slug}", note.slug);
+                    note.slug = slug;
+                }
+            }
+            if req.summary.is_some() {
+                note.summary = non_empty(req.summary.clone());
+            }
+            if let Some(tags) = &req.tags {
+                note.tags = join_tags(tags);
+            }
+            if req.category.is_some() {
+                note.category = non_empty(req.category.clone());
+            }
+            note.updated_at = Utc::now();
+            repo.update_note(note).await
+        })
+    })
+    .await;
 
-    let mut active_note: note::ActiveModel = note.into();
+    // 事务失败时临时文件直接删除，用不到文件 ID
+    let file_id = result.as_ref().map_or(Uuid::nil(), |note| note.file_id);
+    let staged = staged.into_iter().map(|path| (file_id, path)).collect();
+    content.settle_staged(result, staged).await
+}
 
-    if let Some(title) = req.title {
-        active_note.title = Set(title);
-    }
-    if let Some(content) = req.content {
-        active_note.content = Set(content);
-    }
-    if let Some(summary) = req.summary {
-        active_note.summary = Set(summary);
-    }
-    if let Some(status) = req.status {
-        active_note.status = Set(status);
-    }
-    if let Some(tags) = req.tags {
-        active_note.tags = Set(tags);
+/// 删除笔记及其评论，点赞、统计和 slug 跳转记录随外键级联删除，最后删除正文文件
+pub async fn delete_note(
+    db: &DatabaseConnection,
+    content: &ContentStore,
+    id: i32,
+) -> AppResult<()> {
+    let note = unit_of_work::run(db, |uow| {
+        Box::pin(async move {
+            let repo = uow.repo();
+            let note = repo.find_note(id).await?.ok_or(AppError::NotFound)?;
+            let comments = repo.delete_note_comments(id).await?;
+            repo.delete_note(id).await?;
+            log::info!("🗑️ 删除笔记 {}，连同 {comments} 条评论", note.slug);
+            Ok(note)
+        })
+    })
+    .await?;
+
+    // 数据库已经提交，正文删除失败只留下一个孤立文件
+    if let Err(err) = content.delete_note(note.file_id).await {
+        log::warn!("⚠️ {err}");
     }
-    active_note.updated_at = Set(Utc::now());
+    Ok(())
+}
 
-    let updated_note = active_note.update(db).await?;
-    Ok(updated_note)
+/// 点赞笔记，同一 IP 只能点赞一次
+pub async fn like_note(db: &DatabaseConnection, note_id: i32, ip: &str) -> AppResult<()> {
+    unit_of_work::run(db, |uow| {
+        Box::pin(async move {
+            let repo = uow.repo();
+            repo.find_note(note_id).await?.ok_or(AppError::NotFound)?;
+            if repo.find_like(note_id, ip).await?.is_some() {
+                return Err(AppError::Conflict("已经点过赞了".to_string()));
+            }
+            repo.insert_like(note_id, ip).await?;
+            repo.add_note_likes(note_id, 1).await?;
+            stats_service::record_like(uow.conn(), note_id, 1).await?;
+            Ok(())
+        })
+    })
+    .await?;
+    metrics::record_like(1);
+    Ok(())
 }
 
-/// 删除笔记
-pub async fn delete_note(db: &DatabaseConnection, note_id: Uuid, author_id: Uuid) -> AppResult<()> {
-    let note = Note::find_by_id(note_id)
-        .one(db)
-        .await?
-        .ok_or(AppError::NotFound)?;
+/// 取消点赞
+pub async fn unlike_note(db: &DatabaseConnection, note_id: i32, ip: &str) -> AppResult<()> {
+    unit_of_work::run(db, |uow| {
+        Box::pin(async move {
+            let repo = uow.repo();
+            if !repo.delete_like(note_id, ip).await? {
+                return Err(AppError::NotFound);
+            }
+            repo.add_note_likes(note_id, -1).await?;
+            stats_service::record_like(uow.conn(), note_id, -1).await?;
+            Ok(())
+        })
+    })
+    .await?;
+    metrics::record_like(-1);
+    Ok(())
+}
 
-    // 检查权限
-    if note.author_id != author_id {
-        return Err(AppError::Forbidden);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::{
+        db::test_db,
+        repositories::{SeaOrmRepository, memory::InMemoryRepository},
+    };
+
+    fn request(title: &str, tags: &[&str]) -> CreateNoteRequest {
+        CreateNoteRequest {
+            title: title.to_string(),
+            slug: None,
+            summary: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            category: None,
+            content: format!("# {title}\n"),
+            published_at: Some(Utc::now() - chrono::Duration::minutes(1)),
+        }
     }
 
-    note.delete(db).await?;
-    Ok(())
-}
+    #[tokio::test]
+    async fn test_scheduled_note_is_hidden() {
+        let repo = InMemoryRepository::new();
+        let now = Utc::now();
+        for (slug, published_at) in [
+            ("published", now - chrono::Duration::minutes(1)),
+            ("scheduled", now + chrono::Duration::days(1)),
+        ] {
+            repo.insert_note(notes_metadata::Model {
+                id: 0,
+                file_id: Uuid::new_v4(),
+                slug: slug.to_string(),
+                title: slug.to_string(),
+                summary: None,
+                published_at,
+                updated_at: now,
+                views: 0,
+                likes_count: 0,
+                tags: None,
+                category: None,
+            })
+            .await
+            .unwrap();
+        }
 
-/// 点赞笔记
-pub async fn like_note(db: &DatabaseConnection, note_id: Uuid, _user_id: Uuid) -> AppResult<()> {
-    let note = Note::find_by_id(note_id)
-        .one(db)
-        .await?
-        .ok_or(AppError::NotFound)?;
+        assert!(get_published_note_by_slug(&repo, "published").await.is_ok());
+        // 管理接口仍然能按 slug 找到，公开接口在发布前返回 404
+        assert!(get_note_by_slug(&repo, "scheduled").await.is_ok());
+        assert!(matches!(
+            get_published_note_by_slug(&repo, "scheduled").await,
+            Err(AppError::NotFound)
+        ));
+    }
 
-    let mut active_note: note::ActiveModel = note.into();
-    active_note.likes_count = Set(active_note.likes_count.unwrap() + 1);
-    active_note.updated_at = Set(Utc::now());
+    #[tokio::test]
+    async fn test_note_lifecycle() {
+        let db = test_db().await;
+        let dir = std::env::temp_dir().join(format!("rowan-notes-{}", Uuid::new_v4()));
+        let content = ContentStore::new(&dir);
+        let repo = SeaOrmRepository::new(&db);
 
-    active_note.update(db).await?;
-    Ok(())
-}
+        let first = create_note(&db, &content, request("Hello World", &["Rust", "web"]))
+            .await
+            .unwrap();
+        let second = create_note(&db, &content, request("Hello World", &["Rusty"]))
+            .await
+            .unwrap();
+        assert_eq!(first.slug, "hello-world");
+        assert_ne!(second.slug, first.slug);
 
-/// 取消点赞
-pub async fn unlike_note(db: &DatabaseConnection, note_id: Uuid, _user_id: Uuid) -> AppResult<()> {
-    let note = Note::find_by_id(note_id)
-        .one(db)
-        .await?
-        .ok_or(AppError::NotFound)?;
+        // LIKE 粗筛会同时命中 Rust 和 Rusty，精确匹配后只剩一篇
+        let tagged = list_published_by_tag(&repo, "Rust").await.unwrap();
+        assert_eq!(
+            tagged.iter().map(|note| note.id).collect::<Vec<_>>(),
+            [first.id]
+        );
 
-    let mut active_note: note::ActiveModel = note.into();
-    let current_likes = active_note.likes_count.unwrap().max(1);
-    active_note.likes_count = Set(current_likes - 1);
-    active_note.updated_at = Set(Utc::now());
+        let renamed = update_note(
+            &db,
+            &content,
+            first.id,
+            UpdateNoteRequest {
+                slug: Some("hello-rust".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(renamed.slug, "hello-rust");
 
-    active_note.update(db).await?;
-    Ok(())
+        // 事务失败时正文保持不变，也不留下临时文件
+        let rejected = update_note(
+            &db,
+            &content,
+            first.id,
+            UpdateNoteRequest {
+                title: Some(" ".to_string()),
+                content: Some("# rejected\n".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(matches!(rejected, Err(AppError::BadRequest(_))));
+        assert_eq!(
+            content.read_note(first.file_id).await.unwrap().as_deref(),
+            Some("# Hello World\n")
+        );
+        update_note(
+            &db,
+            &content,
+            first.id,
+            UpdateNoteRequest {
+                content: Some("# updated\n".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            content.read_note(first.file_id).await.unwrap().as_deref(),
+            Some("# updated\n")
+        );
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        assert_eq!(
+            slug_service::resolve_redirect(&repo, "hello-world")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("hello-rust")
+        );
+
+        like_note(&db, first.id, "203.0.113.1").await.unwrap();
+        like_note(&db, first.id, "203.0.113.2").await.unwrap();
+        assert!(matches!(
+            like_note(&db, first.id, "203.0.113.1").await,
+            Err(AppError::Conflict(_))
+        ));
+        unlike_note(&db, first.id, "203.0.113.2").await.unwrap();
+        let note = get_note_by_slug(&repo, "hello-rust").await.unwrap();
+        assert_eq!(note.likes_count, 1);
+
+        let series =
+            stats_service::note_series(&db, first.id, stats_service::Window::last_days(1).unwrap())
+                .await
+                .unwrap();
+        assert_eq!(series.iter().map(|point| point.likes).sum::<i64>(), 1);
+
+        delete_note(&db, &content, first.id).await.unwrap();
+        assert!(repo.find_note(first.id).await.unwrap().is_none());
+        assert!(repo.list_likes(first.id).await.unwrap().is_empty());
+        assert!(!content.note_path(first.file_id).exists());
+        // 旧 slug 随跳转记录一起释放
+        assert_eq!(
+            slug_service::unique_slug(&repo, "hello-world", None)
+                .await
+                .unwrap(),
+            "hello-world"
+        );
+        assert!(matches!(
+            delete_note(&db, &content, first.id).await,
+            Err(AppError::NotFound)
+        ));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
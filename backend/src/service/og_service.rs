@@ -0,0 +1,437 @@
+//! 社交分享卡片（OpenGraph 图片）
+//!
+//! 为每篇笔记生成 1200×630 的 PNG 卡片：标题、标签、阅读时间和站点信息。先按模板拼出 SVG，
+//! 再用 resvg 光栅化，全程纯 Rust，不依赖浏览器或系统图形库。
+//!
+//! 拉丁字符使用内置的 Noto Sans。构建时 `assets/fonts` 中有 Noto Sans SC 子集（GB2312 汉字）的话，
+//! 中文使用内置的子集；没有子集或子集之外的字符需要 `OG_FONT_DIR` 中的字体或系统已安装的字体，
+//! 找不到字形时该字符不会显示。
+//!
+//! 卡片按输入内容和已加载字体的哈希缓存在磁盘上，标题、标签、阅读时间或字体变化后自然对应新的文件。
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use resvg::{
+    tiny_skia,
+    usvg::{self, fontdb},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::{OgImageConfig, SiteConfig},
+    error::{AppError, AppResult},
+    infra::{
+        content::{is_cjk, reading_minutes},
+        db::entities::notes_metadata,
+    },
+    service::{feed_service::xml_escape, note_service},
+};
+
+/// 卡片宽度
+pub const WIDTH: u32 = 1200;
+/// 卡片高度
+pub const HEIGHT: u32 = 630;
+
+/// 模板版本，修改模板后递增，让旧缓存失效
+const TEMPLATE_VERSION: u32 = 1;
+
+/// 内置字体的家族名
+const EMBEDDED_FAMILY: &str = "Noto Sans";
+
+/// 内置的中文字体子集（常规、粗体），由 `build.rs` 检测到字体文件时开启
+#[cfg(embedded_cjk_font)]
+const CJK_FONTS: [&[u8]; 2] = [
+    include_bytes!("../../assets/fonts/NotoSansSC-Regular.subset.otf"),
+    include_bytes!("../../assets/fonts/NotoSansSC-Bold.subset.otf"),
+];
+/// 按顺序回退的字体，缺字时 usvg 会在字体库中另找能显示的字体
+const FONT_FAMILIES: &str = "'Noto Sans', 'Noto Sans SC', 'Noto Sans CJK SC', 'Source Han Sans SC', 'PingFang SC', 'Microsoft YaHei', sans-serif";
+
+/// 左右边距
+const MARGIN: f64 = 80.0;
+const TITLE_SIZE: f64 = 64.0;
+const TITLE_LINE_HEIGHT: f64 = 84.0;
+const TITLE_MAX_LINES: usize = 3;
+/// 卡片上最多展示的标签数
+const MAX_TAGS: usize = 5;
+
+/// 卡片内容
+#[derive(Debug, Clone)]
+pub struct OgCard {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub reading_minutes: usize,
+    pub site_title: String,
+    /// 站点域名，显示在右下角
+    pub site_host: String,
+}
+
+impl OgCard {
+    /// 笔记的卡片，`markdown` 为正文（用于估算阅读时间）
+    pub fn for_note(
+        site: &SiteConfig,
+        note: &notes_metadata::Model,
+        markdown: Option<&str>,
+    ) -> Self {
+        let site_host = site
+            .url
+            .split_once("://")
+            .map_or(site.url.as_str(), |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Self {
+            title: note.title.clone(),
+            tags: note_service::parse_tags(note.tags.as_deref()),
+            reading_minutes: markdown.map_or(1, reading_minutes),
+            site_title: site.title.clone(),
+            site_host,
+        }
+    }
+
+    /// 缓存键：模板版本、字体库指纹和全部输入的哈希
+    ///
+    /// `fonts` 为 `OgRenderer::font_fingerprint`，字体增减后旧卡片（可能缺字）随之失效。
+    pub fn cache_key(&self, fonts: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(TEMPLATE_VERSION.to_be_bytes());
+        hasher.update(fonts.as_bytes());
+        hasher.update([0]);
+        for part in [&self.title, &self.site_title, &self.site_host] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        for tag in &self.tags {
+            hasher.update(tag.as_bytes());
+            hasher.update([0]);
+        }
+        hasher.update(self.reading_minutes.to_be_bytes());
+        hex::encode(&hasher.finalize()[..16])
+    }
+
+    /// 生成卡片的 SVG
+    pub fn to_svg(&self) -> String {
+        let max_width = f64::from(WIDTH) - MARGIN * 2.0;
+        let lines = wrap_text(&self.title, TITLE_SIZE, max_width, TITLE_MAX_LINES);
+        let title: String = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                format!(
+                    r#"<tspan x="{MARGIN}" y="{}">{}</tspan>"#,
+                    230.0 + TITLE_LINE_HEIGHT * i as f64,
+                    xml_escape(line)
+                )
+            })
+            .collect();
+        let tags = self
+            .tags
+            .iter()
+            .take(MAX_TAGS)
+            .map(|tag| format!("#{tag}"))
+            .collect::<Vec<_>>()
+            .join("   ");
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}">
+  <defs>
+    <linearGradient id="bg" x1="0" y1="0" x2="1" y2="1">
+      <stop offset="0" stop-color="#1e293b"/>
+      <stop offset="1" stop-color="#0f172a"/>
+    </linearGradient>
+  </defs>
+  <rect width="{WIDTH}" height="{HEIGHT}" fill="url(#bg)"/>
+  <rect width="16" height="{HEIGHT}" fill="#10b981"/>
+  <g font-family="{FONT_FAMILIES}">
+    <text x="{MARGIN}" y="120" font-size="34" font-weight="700" fill="#10b981">{site_title}</text>
+    <text font-size="{TITLE_SIZE}" font-weight="700" fill="#f8fafc">{title}</text>
+    <text x="{MARGIN}" y="500" font-size="30" fill="#94a3b8" xml:space="preserve">{tags}</text>
+    <text x="{MARGIN}" y="565" font-size="28" fill="#cbd5e1">约 {minutes} 分钟阅读</text>
+    <text x="{right}" y="565" font-size="28" fill="#64748b" text-anchor="end">{host}</text>
+  </g>
+</svg>"##,
+            site_title = xml_escape(&self.site_title),
+            tags = xml_escape(&tags),
+            minutes = self.reading_minutes,
+            right = f64::from(WIDTH) - MARGIN,
+            host = xml_escape(&self.site_host),
+        )
+    }
+}
+
+/// 估算字符宽度（以字号为单位）：中日韩文字和全角字符占满一个字号，其余按常见比例估算
+fn char_width(c: char) -> f64 {
+    if is_cjk(c) || ('\u{ff00}'..='\u{ffef}').contains(&c) || ('\u{3000}'..='\u{303f}').contains(&c)
+    {
+        1.0
+    } else if c.is_ascii_uppercase() || matches!(c, 'm' | 'w' | 'M' | 'W' | '@') {
+        0.72
+    } else if matches!(
+        c,
+        'i' | 'l' | 'j' | 't' | 'f' | '.' | ',' | ':' | ';' | '!' | '\'' | '|'
+    ) {
+        0.32
+    } else {
+        0.58
+    }
+}
+
+/// 按估算宽度把文本折成最多 `max_lines` 行，放不下时最后一行以省略号结尾
+///
+/// 英文在空格处断行，单词本身超过一行时才在单词中间断开；中文可以在任意字符处断行。
+fn wrap_text(text: &str, font_size: f64, max_width: f64, max_lines: usize) -> Vec<String> {
+    let limit = max_width / font_size;
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut width = 0.0;
+    // 当前行最后一个可断行位置（字节下标）
+    let mut last_break: Option<usize> = None;
+
+    for c in text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+    {
+        let w = char_width(c);
+        if width + w > limit && !line.is_empty() {
+            let (head, tail) = match last_break {
+                Some(pos) if c != ' ' && !is_cjk(c) => {
+                    let tail = line[pos..].trim_start().to_string();
+                    line.truncate(pos);
+                    (line, tail)
+                }
+                _ => (line, String::new()),
+            };
+            lines.push(head.trim_end().to_string());
+            width = tail.chars().map(char_width).sum();
+            line = tail;
+            last_break = None;
+            if c == ' ' {
+                continue;
+            }
+        }
+        if c == ' ' || is_cjk(c) {
+            last_break = Some(line.len() + if c == ' ' { 0 } else { c.len_utf8() });
+        }
+        line.push(c);
+        width += w;
+    }
+    if !line.trim().is_empty() {
+        lines.push(line.trim_end().to_string());
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = &mut lines[max_lines - 1];
+        let mut width: f64 = last.chars().map(char_width).sum();
+        while width + char_width('…') > limit {
+            match last.pop() {
+                Some(c) => width -= char_width(c),
+                None => break,
+            }
+        }
+        last.truncate(last.trim_end().len());
+        last.push('…');
+    }
+    lines
+}
+
+/// 卡片渲染器，持有字体库和磁盘缓存目录
+pub struct OgRenderer {
+    fontdb: Arc<fontdb::Database>,
+    /// 已加载字体的指纹，参与缓存键
+    font_fingerprint: String,
+    cache_dir: PathBuf,
+}
+
+impl OgRenderer {
+    /// 加载内置字体、`OG_FONT_DIR` 中的字体以及（可选的）系统字体
+    pub fn new(config: &OgImageConfig) -> Self {
+        let mut fontdb = fontdb::Database::new();
+        fontdb.load_font_data(notosans::REGULAR_TTF.to_vec());
+        fontdb.load_font_data(notosans::BOLD_TTF.to_vec());
+        #[cfg(embedded_cjk_font)]
+        for font in CJK_FONTS {
+            fontdb.load_font_data(font.to_vec());
+        }
+        #[cfg(not(embedded_cjk_font))]
+        if config.font_dir.is_none() {
+            log::warn!(
+                "⚠️ 未内置中文字体子集，分享卡片中的中文只能使用系统字体，请配置 OG_FONT_DIR"
+            );
+        }
+        if let Some(dir) = &config.font_dir {
+            fontdb.load_fonts_dir(dir);
+        }
+        if config.system_fonts {
+            fontdb.load_system_fonts();
+        }
+        fontdb.set_sans_serif_family(EMBEDDED_FAMILY);
+        log::info!("🔤 分享卡片已加载 {} 个字体", fontdb.len());
+
+        Self {
+            font_fingerprint: font_fingerprint(&fontdb),
+            fontdb: Arc::new(fontdb),
+            cache_dir: PathBuf::from(&config.cache_dir),
+        }
+    }
+
+    /// 已加载字体的指纹
+    pub fn font_fingerprint(&self) -> &str {
+        &self.font_fingerprint
+    }
+
+    /// 获取卡片 PNG，有缓存时直接读取
+    pub async fn render(&self, card: &OgCard) -> AppResult<Vec<u8>> {
+        let path = self
+            .cache_dir
+            .join(format!("{}.png", card.cache_key(&self.font_fingerprint)));
+        match tokio::fs::read(&path).await {
+            Ok(png) => return Ok(png),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(AppError::Internal(format!(
+                    "读取分享卡片缓存 {} 失败: {err}",
+                    path.display()
+                )));
+            }
+        }
+
+        // 光栅化是 CPU 密集型操作，放到阻塞线程池里执行
+        let svg = card.to_svg();
+        let fontdb = Arc::clone(&self.fontdb);
+        let png = tokio::task::spawn_blocking(move || render_png(&svg, fontdb))
+            .await
+            .map_err(|e| AppError::Internal(format!("渲染分享卡片失败: {e}")))??;
+
+        // 先写临时文件再改名，并发请求不会读到写了一半的文件
+        let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        let written = async {
+            tokio::fs::create_dir_all(&self.cache_dir).await?;
+            tokio::fs::write(&tmp, &png).await?;
+            tokio::fs::rename(&tmp, &path).await
+        }
+        .await;
+        if let Err(err) = written {
+            // 缓存写入失败不影响本次响应
+            log::warn!("⚠️ 写入分享卡片缓存 {} 失败: {err}", path.display());
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        Ok(png)
+    }
+}
+
+/// 字体库的指纹：所有字形的 PostScript 名、字重和样式排序后的哈希
+fn font_fingerprint(fontdb: &fontdb::Database) -> String {
+    let mut faces: Vec<String> = fontdb
+        .faces()
+        .map(|face| {
+            format!(
+                "{}:{}:{:?}",
+                face.post_script_name, face.weight.0, face.style
+            )
+        })
+        .collect();
+    faces.sort_unstable();
+    faces.dedup();
+    let mut hasher = Sha256::new();
+    for face in &faces {
+        hasher.update(face.as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(&hasher.finalize()[..8])
+}
+
+/// 把 SVG 光栅化为 PNG
+fn render_png(svg: &str, fontdb: Arc<fontdb::Database>) -> AppResult<Vec<u8>> {
+    let options = usvg::Options {
+        font_family: EMBEDDED_FAMILY.to_string(),
+        fontdb,
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options)
+        .map_err(|e| AppError::Internal(format!("解析分享卡片模板失败: {e}")))?;
+    let mut pixmap = tiny_skia::Pixmap::new(WIDTH, HEIGHT)
+        .ok_or_else(|| AppError::Internal("创建画布失败".to_string()))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|e| AppError::Internal(format!("编码 PNG 失败: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_text() {
+        let lines = wrap_text("Hello wonderful world", 10.0, 60.0, 3);
+        assert_eq!(lines, ["Hello", "wonderful", "world"]);
+
+        let lines = wrap_text(&"很长的中文标题".repeat(10), 10.0, 100.0, 2);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].chars().count(), 10);
+        assert!(lines[1].ends_with('…'));
+    }
+
+    #[tokio::test]
+    async fn test_render_card_and_cache() {
+        let cache_dir = std::env::temp_dir().join(format!("og-test-{}", uuid::Uuid::new_v4()));
+        let renderer = OgRenderer::new(&OgImageConfig {
+            cache_dir: cache_dir.to_string_lossy().into_owned(),
+            font_dir: None,
+            system_fonts: false,
+        });
+        let card = OgCard {
+            title: "Rust & <Axum> 入门".to_string(),
+            tags: vec!["rust".to_string(), "web".to_string()],
+            reading_minutes: 3,
+            site_title: "RowanWeb".to_string(),
+            site_host: "example.com".to_string(),
+        };
+
+        let png = renderer.render(&card).await.unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let fonts = renderer.font_fingerprint();
+        let cached = cache_dir.join(format!("{}.png", card.cache_key(fonts)));
+        assert_eq!(tokio::fs::read(&cached).await.unwrap(), png);
+
+        let other = OgCard {
+            reading_minutes: 4,
+            ..card.clone()
+        };
+        assert_ne!(other.cache_key(fonts), card.cache_key(fonts));
+        // 字体库变化后同一张卡片对应新的缓存
+        assert_ne!(card.cache_key("other-fonts"), card.cache_key(fonts));
+
+        let _ = tokio::fs::remove_dir_all(&cache_dir).await;
+    }
+
+    #[cfg(embedded_cjk_font)]
+    #[test]
+    fn test_cjk_title_is_drawn() {
+        // 不加载系统字体，只依赖内置字体
+        let renderer = OgRenderer::new(&OgImageConfig {
+            cache_dir: std::env::temp_dir().to_string_lossy().into_owned(),
+            font_dir: None,
+            system_fonts: false,
+        });
+        let draw = |text: &str| {
+            let svg = format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}"><text x="{MARGIN}" y="300" font-family="{FONT_FAMILIES}" font-size="{TITLE_SIZE}" font-weight="700">{text}</text></svg>"#
+            );
+            render_png(&svg, Arc::clone(&renderer.fontdb)).unwrap()
+        };
+
+        // 缺字时要么什么都不画，要么画出同样的占位方框；两段不同的中文画出不同的图才说明字形存在
+        let blank = draw("");
+        let title = draw("中文标题");
+        let other = draw("分享卡片");
+        assert_ne!(title, blank);
+        assert_ne!(other, blank);
+        assert_ne!(title, other);
+    }
+}
@@ -0,0 +1,377 @@
+//! 服务端 HTML 页面
+//!
+//! 前端是 Next.js，爬虫和链接预览机器人不一定执行 JS，所以后端也能直接输出首页、笔记、
+//! 随笔、标签页和分类页的 HTML。模板在编译期检查（askama），页面带 OpenGraph / Twitter
+//! 元数据，笔记和随笔页还带 JSON-LD `BlogPosting` 结构化数据。
+//!
+//! 这里只负责把已查询好的数据渲染成 HTML，服务端处理器和静态导出共用。
+//! 正文中的媒体图片由调用方查好（`MediaImages`）后传入，改写成响应式图片。
+
+use askama::Template;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::json;
+
+use crate::{
+    config::SiteConfig,
+    error::{AppError, AppResult},
+    infra::{
+        content::markdown_excerpt,
+        db::entities::{essays, notes_metadata},
+    },
+    service::{image_service::MediaImages, note_service},
+};
+
+/// 首页展示的笔记数量
+pub const HOME_NOTES: u64 = 20;
+/// 首页展示的随笔数量
+pub const HOME_ESSAYS: u64 = 10;
+
+/// 页面描述的最大长度
+const DESCRIPTION_LEN: usize = 160;
+
+/// `<head>` 中的元数据
+pub struct PageMeta {
+    pub title: String,
+    pub description: String,
+    /// 规范地址
+    pub url: String,
+    /// OpenGraph 区域设置，如 `zh_CN`
+    pub locale: String,
+    /// `website` 或 `article`
+    pub og_type: &'static str,
+    pub image: Option<String>,
+    pub published: Option<String>,
+    pub modified: Option<String>,
+    pub section: Option<String>,
+    pub tags: Vec<String>,
+    /// 已转义、可以直接放进 `<script>` 的 JSON-LD
+    pub json_ld: Option<String>,
+}
+
+impl PageMeta {
+    fn website(site: &SiteConfig, title: String, description: String, url: String) -> Self {
+        Self {
+            title,
+            description,
+            url,
+            locale: site.language.replace('-', "_"),
+            og_type: "website",
+            image: None,
+            published: None,
+            modified: None,
+            section: None,
+            tags: Vec::new(),
+            json_ld: None,
+        }
+    }
+}
+
+/// 带地址的名字（标签、分类）
+pub struct Link {
+    pub name: String,
+    pub url: String,
+}
+
+/// 模板中的笔记
+pub struct NoteView {
+    pub title: String,
+    pub url: String,
+    pub summary: Option<String>,
+    pub published: String,
+    /// 展示用日期
+    pub date: String,
+    pub category: Option<String>,
+    pub category_url: String,
+    pub tags: Vec<Link>,
+}
+
+impl NoteView {
+    fn new(site: &SiteConfig, note: &notes_metadata::Model) -> Self {
+        let category = note.category.clone().filter(|c| !c.trim().is_empty());
+        Self {
+            title: note.title.clone(),
+            url: site.note_url(&note.slug),
+            summary: note.summary.clone(),
+            published: rfc3339(note.published_at),
+            date: note.published_at.format("%Y-%m-%d").to_string(),
+            category_url: category
+                .as_deref()
+                .map(|c| site.category_url(c))
+                .unwrap_or_default(),
+            category,
+            tags: note_service::parse_tags(note.tags.as_deref())
+                .into_iter()
+                .map(|tag| Link {
+                    url: site.tag_url(&tag),
+                    name: tag,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 模板中的随笔
+pub struct EssayView {
+    pub title: String,
+    pub url: String,
+    pub published: String,
+    pub date: String,
+}
+
+impl EssayView {
+    fn new(site: &SiteConfig, essay: &essays::Model) -> Self {
+        let published = essay.publish_at.unwrap_or(essay.created_at);
+        Self {
+            title: essay.title.clone(),
+            url: site.essay_url(essay.id),
+            published: rfc3339(published),
+            date: published.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "home.html")]
+struct HomeTemplate<'a> {
+    site: &'a SiteConfig,
+    meta: PageMeta,
+    notes: Vec<NoteView>,
+    essays: Vec<EssayView>,
+}
+
+#[derive(Template)]
+#[template(path = "note.html")]
+struct NoteTemplate<'a> {
+    site: &'a SiteConfig,
+    meta: PageMeta,
+    note: NoteView,
+    content_html: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "essay.html")]
+struct EssayTemplate<'a> {
+    site: &'a SiteConfig,
+    meta: PageMeta,
+    essay: EssayView,
+    content_html: String,
+}
+
+#[derive(Template)]
+#[template(path = "list.html")]
+struct ListTemplate<'a> {
+    site: &'a SiteConfig,
+    meta: PageMeta,
+    heading: String,
+    notes: Vec<NoteView>,
+}
+
+fn rfc3339(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn render(template: &impl Template) -> AppResult<String> {
+    template
+        .render()
+        .map_err(|e| AppError::Internal(format!("渲染页面失败: {e}")))
+}
+
+/// 生成 `BlogPosting` 结构化数据
+///
+/// `<` 转义成 `\u003c`，防止内容中的 `</script>` 提前结束脚本块。
+fn blog_posting(site: &SiteConfig, headline: &str, meta: &PageMeta) -> String {
+    let mut value = json!({
+        "@context": "https://schema.org",
+        "@type": "BlogPosting",
+        "headline": headline,
+        "description": meta.description,
+        "url": meta.url,
+        "mainEntityOfPage": { "@type": "WebPage", "@id": meta.url },
+        "datePublished": meta.published,
+        "dateModified": meta.modified,
+        "inLanguage": site.language,
+        "author": { "@type": "Person", "name": site.author },
+        "publisher": { "@type": "Person", "name": site.author },
+    });
+    if !meta.tags.is_empty() {
+        value["keywords"] = json!(meta.tags.join(","));
+    }
+    if let Some(section) = &meta.section {
+        value["articleSection"] = json!(section);
+    }
+    if let Some(image) = &meta.image {
+        value["image"] = json!(image);
+    }
+    value.to_string().replace('<', "\\u003c")
+}
+
+/// 首页：最近的笔记和随笔
+pub fn home_page(
+    site: &SiteConfig,
+    notes: &[notes_metadata::Model],
+    essays: &[essays::Model],
+) -> AppResult<String> {
+    render(&HomeTemplate {
+        site,
+        meta: PageMeta::website(
+            site,
+            site.title.clone(),
+            site.description.clone(),
+            format!("{}/", site.url),
+        ),
+        notes: notes.iter().map(|note| NoteView::new(site, note)).collect(),
+        essays: essays
+            .iter()
+            .map(|essay| EssayView::new(site, essay))
+            .collect(),
+    })
+}
+
+/// 笔记页，正文文件不存在时 `markdown` 为 `None`
+pub fn note_page(
+    site: &SiteConfig,
+    note: &notes_metadata::Model,
+    markdown: Option<&str>,
+    images: &MediaImages,
+) -> AppResult<String> {
+    let view = NoteView::new(site, note);
+    let description = note
+        .summary
+        .clone()
+        .filter(|summary| !summary.trim().is_empty())
+        .or_else(|| markdown.map(|md| markdown_excerpt(md, DESCRIPTION_LEN)))
+        .unwrap_or_else(|| site.description.clone());
+
+    let mut meta = PageMeta {
+        og_type: "article",
+        published: Some(view.published.clone()),
+        modified: Some(rfc3339(note.updated_at)),
+        section: view.category.clone(),
+        tags: view.tags.iter().map(|tag| tag.name.clone()).collect(),
+        image: Some(site.og_image_url(&note.slug)),
+        ..PageMeta::website(
+            site,
+            format!("{} - {}", note.title, site.title),
+            description,
+            view.url.clone(),
+        )
+    };
+    meta.json_ld = Some(blog_posting(site, &note.title, &meta));
+
+    render(&NoteTemplate {
+        site,
+        meta,
+        note: view,
+        content_html: markdown.map(|md| images.render_markdown(site, md)),
+    })
+}
+
+/// 随笔页
+pub fn essay_page(
+    site: &SiteConfig,
+    essay: &essays::Model,
+    images: &MediaImages,
+) -> AppResult<String> {
+    let view = EssayView::new(site, essay);
+    let mut meta = PageMeta {
+        og_type: "article",
+        published: Some(view.published.clone()),
+        modified: Some(rfc3339(essay.updated_at)),
+        ..PageMeta::website(
+            site,
+            format!("{} - {}", essay.title, site.title),
+            markdown_excerpt(&essay.content, DESCRIPTION_LEN),
+            view.url.clone(),
+        )
+    };
+    meta.json_ld = Some(blog_posting(site, &essay.title, &meta));
+
+    render(&EssayTemplate {
+        site,
+        meta,
+        essay: view,
+        content_html: images.render_markdown(site, &essay.content),
+    })
+}
+
+/// 标签页
+pub fn tag_page<'a>(
+    site: &SiteConfig,
+    tag: &str,
+    notes: impl IntoIterator<Item = &'a notes_metadata::Model>,
+) -> AppResult<String> {
+    list_page(site, format!("#{tag}"), site.tag_url(tag), notes)
+}
+
+/// 分类页
+pub fn category_page<'a>(
+    site: &SiteConfig,
+    category: &str,
+    notes: impl IntoIterator<Item = &'a notes_metadata::Model>,
+) -> AppResult<String> {
+    list_page(
+        site,
+        category.to_string(),
+        site.category_url(category),
+        notes,
+    )
+}
+
+fn list_page<'a>(
+    site: &SiteConfig,
+    heading: String,
+    url: String,
+    notes: impl IntoIterator<Item = &'a notes_metadata::Model>,
+) -> AppResult<String> {
+    let notes: Vec<NoteView> = notes
+        .into_iter()
+        .map(|note| NoteView::new(site, note))
+        .collect();
+    let description = format!("{heading} 下的 {} 篇笔记", notes.len());
+    render(&ListTemplate {
+        site,
+        meta: PageMeta::website(
+            site,
+            format!("{heading} - {}", site.title),
+            description,
+            url,
+        ),
+        heading,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_essay_page_meta_and_json_ld() {
+        let mut site = SiteConfig::from_env();
+        site.url = "https://example.com".to_string();
+        let at = Utc.with_ymd_and_hms(2026, 10, 19, 9, 30, 0).unwrap();
+        let essay = essays::Model {
+            id: 7,
+            title: "</script><b>".to_string(),
+            content: "正文 **加粗**".to_string(),
+            created_at: at,
+            updated_at: at,
+            status: "published".to_string(),
+            publish_at: Some(at),
+        };
+
+        let html = essay_page(&site, &essay, &MediaImages::default()).unwrap();
+        assert!(html.contains(r#"<meta property="og:type" content="article">"#));
+        assert!(
+            html.contains(r#"<meta property="og:url" content="https://example.com/essays/7">"#)
+        );
+        assert!(html.contains(r#"<meta name="twitter:description" content="正文 加粗">"#));
+        assert!(html.contains(r#"content="2026-10-19T09:30:00Z""#));
+        assert!(html.contains(r#""@type":"BlogPosting""#));
+        assert!(html.contains(r#""headline":"\u003c/script>\u003cb>""#));
+        assert!(!html.contains("</script><b>"));
+        assert!(html.contains("<h1>&#60;/script&#62;&#60;b&#62;</h1>"));
+        assert!(html.contains("<strong>加粗</strong>"));
+    }
+}
@@ -0,0 +1,366 @@
+//! 管理员会话与刷新令牌
+//!
+//! 刷新令牌是随机生成的不透明字符串，数据库里只保存它的 SHA-256。
+//! 每次登录开启一个会话族（family），之后每次刷新都会：
+//! 1. 把当前令牌标记为已轮换（`rotated_at`）
+//! 2. 在同一个会话族里签发一个新令牌
+//!
+//! 如果有人拿已经轮换过的旧令牌来刷新，说明令牌可能已经泄露，
+//! 此时整个会话族都会被吊销，合法持有者和攻击者都需要重新登录。
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use rand::RngCore;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait, sea_query::Expr,
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, AppResult},
+    infra::db::entities::sessions::{self, Entity as Session},
+};
+
+/// 创建会话时记录的设备信息
+#[derive(Debug, Clone, Default)]
+pub struct DeviceInfo {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+/// 新签发的刷新令牌
+#[derive(Debug, Clone)]
+pub struct IssuedToken {
+    pub admin_id: i32,
+    pub family_id: String,
+    /// 明文令牌，只在签发时返回给客户端一次
+    pub token: String,
+}
+
+/// 生成 32 字节随机刷新令牌
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// 刷新令牌的哈希（十六进制 SHA-256）
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn truncate(value: Option<String>, max: usize) -> Option<String> {
+    value.map(|value| value.chars().take(max).collect())
+}
+
+async fn insert_token<C: ConnectionTrait>(
+    db: &C,
+    admin_id: i32,
+    family_id: &str,
+    started_at: chrono::DateTime<Utc>,
+    device: DeviceInfo,
+    ttl_secs: i64,
+) -> AppResult<IssuedToken> {
+    let token = generate_token();
+    let now = Utc::now();
+    sessions::ActiveModel {
+        admin_id: Set(admin_id),
+        family_id: Set(family_id.to_string()),
+        token_hash: Set(hash_token(&token)),
+        user_agent: Set(truncate(device.user_agent, 255)),
+        ip: Set(truncate(device.ip, 45)),
+        started_at: Set(started_at),
+        created_at: Set(now),
+        expires_at: Set(now + Duration::seconds(ttl_secs)),
+        rotated_at: Set(None),
+        revoked_at: Set(None),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(IssuedToken {
+        admin_id,
+        family_id: family_id.to_string(),
+        token,
+    })
+}
+
+/// 登录成功后开启新会话
+pub async fn create_session(
+    db: &DatabaseConnection,
+    admin_id: i32,
+    device: DeviceInfo,
+    ttl_secs: i64,
+) -> AppResult<IssuedToken> {
+    let family_id = Uuid::new_v4().to_string();
+    insert_token(db, admin_id, &family_id, Utc::now(), device, ttl_secs).await
+}
+
+/// 使用刷新令牌轮换出新令牌
+pub async fn rotate(
+    db: &DatabaseConnection,
+    token: &str,
+    device: DeviceInfo,
+    ttl_secs: i64,
+) -> AppResult<IssuedToken> {
+    let current = Session::find()
+        .filter(sessions::Column::TokenHash.eq(hash_token(token)))
+        .one(db)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    if current.revoked_at.is_some() || current.expires_at <= Utc::now() {
+        return Err(AppError::Unauthorized);
+    }
+    if current.rotated_at.is_some() {
+        return Err(reuse_detected(db, &current).await);
+    }
+
+    let txn = db.begin().await?;
+    // 条件更新保证并发刷新时只有一个请求能成功轮换
+    let claimed = Session::update_many()
+        .col_expr(sessions::Column::RotatedAt, Expr::value(Utc::now()))
+        .filter(sessions::Column::Id.eq(current.id))
+        .filter(sessions::Column::RotatedAt.is_null())
+        .exec(&txn)
+        .await?;
+    if claimed.rows_affected != 1 {
+        txn.rollback().await?;
+        return Err(reuse_detected(db, &current).await);
+    }
+
+    let issued = insert_token(
+        &txn,
+        current.admin_id,
+        &current.family_id,
+        current.started_at,
+        device,
+        ttl_secs,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(issued)
+}
+
+/// 旧令牌被重复使用：吊销整个会话族
+async fn reuse_detected(db: &DatabaseConnection, session: &sessions::Model) -> AppError {
+    log::warn!(
+        "🚨 检测到已轮换的刷新令牌被重复使用，吊销会话 {} (管理员 {})",
+        session.family_id,
+        session.admin_id
+    );
+    match revoke_family(db, session.admin_id, &session.family_id).await {
+        Ok(_) => AppError::Unauthorized,
+        Err(err) => err,
+    }
+}
+
+/// 吊销某个会话族，返回是否有会话被吊销
+pub async fn revoke_family(
+    db: &DatabaseConnection,
+    admin_id: i32,
+    family_id: &str,
+) -> AppResult<bool> {
+    let result = Session::update_many()
+        .col_expr(sessions::Column::RevokedAt, Expr::value(Utc::now()))
+        .filter(sessions::Column::AdminId.eq(admin_id))
+        .filter(sessions::Column::FamilyId.eq(family_id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// 吊销某个管理员的全部会话，返回吊销的令牌数
+pub async fn revoke_all(db: &DatabaseConnection, admin_id: i32) -> AppResult<u64> {
+    let result = Session::update_many()
+        .col_expr(sessions::Column::RevokedAt, Expr::value(Utc::now()))
+        .filter(sessions::Column::AdminId.eq(admin_id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// 会话是否仍然有效（未吊销且未过期），访问令牌校验时使用
+pub async fn is_active(db: &DatabaseConnection, admin_id: i32, family_id: &str) -> AppResult<bool> {
+    let active = Session::find()
+        .filter(sessions::Column::AdminId.eq(admin_id))
+        .filter(sessions::Column::FamilyId.eq(family_id))
+        .filter(sessions::Column::RotatedAt.is_null())
+        .filter(sessions::Column::RevokedAt.is_null())
+        .filter(sessions::Column::ExpiresAt.gt(Utc::now()))
+        .one(db)
+        .await?;
+    Ok(active.is_some())
+}
+
+/// 列出某个管理员当前有效的会话（每个会话族只返回最新的令牌记录）
+pub async fn list_active(
+    db: &DatabaseConnection,
+    admin_id: i32,
+) -> AppResult<Vec<sessions::Model>> {
+    let sessions = Session::find()
+        .filter(sessions::Column::AdminId.eq(admin_id))
+        .filter(sessions::Column::RotatedAt.is_null())
+        .filter(sessions::Column::RevokedAt.is_null())
+        .filter(sessions::Column::ExpiresAt.gt(Utc::now()))
+        .order_by_desc(sessions::Column::CreatedAt)
+        .all(db)
+        .await?;
+    Ok(sessions)
+}
+
+/// 清理已过期的令牌记录
+pub async fn prune_expired(db: &DatabaseConnection) -> AppResult<u64> {
+    let result = Session::delete_many()
+        .filter(sessions::Column::ExpiresAt.lt(Utc::now()))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::test_db;
+    use crate::service::auth_service;
+
+    const TTL: i64 = 3600;
+
+    async fn setup() -> (DatabaseConnection, i32) {
+        let db = test_db().await;
+        let admin = auth_service::create_admin(&db, "rowan", "correct horse battery")
+            .await
+            .unwrap();
+        (db, admin.id)
+    }
+
+    #[test]
+    fn test_tokens_are_random_and_hashed() {
+        let a = generate_token();
+        let b = generate_token();
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 43);
+        assert_eq!(hash_token(&a).len(), 64);
+        assert_eq!(hash_token(&a), hash_token(&a));
+        assert_ne!(hash_token(&a), hash_token(&b));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_issues_new_token_in_same_family() {
+        let (db, admin_id) = setup().await;
+        let first = create_session(&db, admin_id, DeviceInfo::default(), TTL)
+            .await
+            .unwrap();
+        let second = rotate(&db, &first.token, DeviceInfo::default(), TTL)
+            .await
+            .unwrap();
+
+        assert_ne!(first.token, second.token);
+        assert_eq!(first.family_id, second.family_id);
+        assert!(is_active(&db, admin_id, &second.family_id).await.unwrap());
+        let third = rotate(&db, &second.token, DeviceInfo::default(), TTL)
+            .await
+            .unwrap();
+        assert_eq!(third.family_id, first.family_id);
+        assert!(matches!(
+            rotate(&db, "unknown", DeviceInfo::default(), TTL).await,
+            Err(AppError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reusing_rotated_token_revokes_family() {
+        let (db, admin_id) = setup().await;
+        let first = create_session(&db, admin_id, DeviceInfo::default(), TTL)
+            .await
+            .unwrap();
+        let other = create_session(&db, admin_id, DeviceInfo::default(), TTL)
+            .await
+            .unwrap();
+        let second = rotate(&db, &first.token, DeviceInfo::default(), TTL)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            rotate(&db, &first.token, DeviceInfo::default(), TTL).await,
+            Err(AppError::Unauthorized)
+        ));
+        // 合法持有者手里的新令牌也随整个会话族失效，其他会话不受影响
+        assert!(!is_active(&db, admin_id, &first.family_id).await.unwrap());
+        assert!(matches!(
+            rotate(&db, &second.token, DeviceInfo::default(), TTL).await,
+            Err(AppError::Unauthorized)
+        ));
+        assert!(is_active(&db, admin_id, &other.family_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_family_deactivates_session() {
+        let (db, admin_id) = setup().await;
+        let issued = create_session(&db, admin_id, DeviceInfo::default(), TTL)
+            .await
+            .unwrap();
+        assert!(is_active(&db, admin_id, &issued.family_id).await.unwrap());
+
+        assert!(
+            revoke_family(&db, admin_id, &issued.family_id)
+                .await
+                .unwrap()
+        );
+        assert!(!is_active(&db, admin_id, &issued.family_id).await.unwrap());
+        assert!(
+            !revoke_family(&db, admin_id, &issued.family_id)
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            rotate(&db, &issued.token, DeviceInfo::default(), TTL).await,
+            Err(AppError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_active_and_revoke_all() {
+        let (db, admin_id) = setup().await;
+        let device = DeviceInfo {
+            user_agent: Some("Firefox".to_string()),
+            ip: Some("203.0.113.7".to_string()),
+        };
+        let first = create_session(&db, admin_id, device.clone(), TTL)
+            .await
+            .unwrap();
+        create_session(&db, admin_id, device, TTL).await.unwrap();
+        // 轮换后的旧令牌不再列出，每个会话族只有一条
+        rotate(&db, &first.token, DeviceInfo::default(), TTL)
+            .await
+            .unwrap();
+        // 已过期的会话不算有效
+        create_session(&db, admin_id, DeviceInfo::default(), -1)
+            .await
+            .unwrap();
+
+        let active = list_active(&db, admin_id).await.unwrap();
+        assert_eq!(active.len(), 2);
+        assert!(
+            active
+                .iter()
+                .any(|session| session.family_id == first.family_id)
+        );
+        assert!(
+            active
+                .iter()
+                .any(|session| session.ip.as_deref() == Some("203.0.113.7"))
+        );
+
+        // 包括已轮换和已过期但尚未吊销的令牌记录
+        assert_eq!(revoke_all(&db, admin_id).await.unwrap(), 4);
+        assert!(list_active(&db, admin_id).await.unwrap().is_empty());
+        assert!(!is_active(&db, admin_id, &first.family_id).await.unwrap());
+        assert_eq!(prune_expired(&db).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,332 @@
+//! 站点地图与 robots.txt
+//!
+//! 站点地图包含首页、已发布的笔记和随笔，以及标签页和分类页。生成结果缓存在内存中，
+//! 每次请求只查询一次笔记和随笔的数量与最近更新时间，内容没有变化时直接复用缓存，
+//! 有变化（新增、修改、删除、定时发布）才重新生成。
+//!
+//! 单个站点地图最多 50000 个 URL，超过时 `/sitemap.xml` 改为站点地图索引，
+//! 分片放在 `/sitemaps/{n}.xml`（从 1 开始）。
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, sea_query::Expr,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    config::SiteConfig,
+    error::{AppError, AppResult},
+    infra::db::entities::{
+        essays::{self, Entity as Essay},
+        notes_metadata::{self, Entity as NotesMetadata},
+    },
+    service::{essay_service::EssayStatus, feed_service::xml_escape, note_service},
+};
+
+/// 单个站点地图允许的最大 URL 数（sitemaps.org 协议）
+pub const MAX_URLS_PER_SITEMAP: usize = 50_000;
+
+/// 站点地图中的一个地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SitemapUrl {
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+/// 生成好的站点地图
+#[derive(Debug, Clone)]
+pub struct Sitemap {
+    /// `/sitemap.xml` 的内容：URL 不多时是 urlset，否则是 sitemapindex
+    pub root: String,
+    /// 分片，只有生成了索引时才非空
+    pub parts: Vec<String>,
+    /// 所有地址中最新的修改时间
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+impl Sitemap {
+    /// 按 50000 个一片切分，必要时生成索引
+    pub fn build(site: &SiteConfig, urls: &[SitemapUrl]) -> Self {
+        let last_modified = urls.iter().filter_map(|url| url.lastmod).max();
+        if urls.len() <= MAX_URLS_PER_SITEMAP {
+            return Self {
+                root: render_urlset(urls),
+                parts: Vec::new(),
+                last_modified,
+            };
+        }
+
+        let chunks: Vec<&[SitemapUrl]> = urls.chunks(MAX_URLS_PER_SITEMAP).collect();
+        let mut index = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        index.push_str(r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+        for (i, chunk) in chunks.iter().enumerate() {
+            index.push_str("<sitemap>");
+            index.push_str(&format!(
+                "<loc>{}</loc>",
+                xml_escape(&site.page_url(&["sitemaps", &format!("{}.xml", i + 1)]))
+            ));
+            if let Some(lastmod) = chunk.iter().filter_map(|url| url.lastmod).max() {
+                index.push_str(&format!("<lastmod>{}</lastmod>", w3c_datetime(lastmod)));
+            }
+            index.push_str("</sitemap>");
+        }
+        index.push_str("</sitemapindex>");
+
+        Self {
+            root: index,
+            parts: chunks.into_iter().map(render_urlset).collect(),
+            last_modified,
+        }
+    }
+
+    /// 第 `n` 个分片（从 1 开始）
+    pub fn part(&self, n: usize) -> Option<&str> {
+        n.checked_sub(1)
+            .and_then(|i| self.parts.get(i))
+            .map(String::as_str)
+    }
+}
+
+fn w3c_datetime(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn render_urlset(urls: &[SitemapUrl]) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+    for url in urls {
+        xml.push_str(&format!("<url><loc>{}</loc>", xml_escape(&url.loc)));
+        if let Some(lastmod) = url.lastmod {
+            xml.push_str(&format!("<lastmod>{}</lastmod>", w3c_datetime(lastmod)));
+        }
+        xml.push_str("</url>");
+    }
+    xml.push_str("</urlset>");
+    xml
+}
+
+/// 站点地图用到的笔记字段：slug、更新时间、标签、分类
+type NoteRow = (String, DateTime<Utc>, Option<String>, Option<String>);
+
+/// 收集站点地图中的全部地址
+pub async fn collect_urls(
+    db: &DatabaseConnection,
+    site: &SiteConfig,
+) -> AppResult<Vec<SitemapUrl>> {
+    let notes: Vec<NoteRow> = NotesMetadata::find()
+        .select_only()
+        .columns([
+            notes_metadata::Column::Slug,
+            notes_metadata::Column::UpdatedAt,
+            notes_metadata::Column::Tags,
+            notes_metadata::Column::Category,
+        ])
+        .filter(notes_metadata::Column::PublishedAt.lte(Utc::now()))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let essays: Vec<(i32, DateTime<Utc>)> = Essay::find()
+        .select_only()
+        .columns([essays::Column::Id, essays::Column::UpdatedAt])
+        .filter(essays::Column::Status.eq(EssayStatus::Published.as_str()))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    // 标签页和分类页的修改时间取其中最新笔记的更新时间
+    let mut tags: BTreeMap<String, DateTime<Utc>> = BTreeMap::new();
+    let mut categories: BTreeMap<String, DateTime<Utc>> = BTreeMap::new();
+    for (_, updated_at, note_tags, category) in &notes {
+        for tag in note_service::parse_tags(note_tags.as_deref()) {
+            let lastmod = tags.entry(tag).or_insert(*updated_at);
+            *lastmod = (*lastmod).max(*updated_at);
+        }
+        if let Some(category) = category.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            let lastmod = categories
+                .entry(category.to_string())
+                .or_insert(*updated_at);
+            *lastmod = (*lastmod).max(*updated_at);
+        }
+    }
+
+    let home_lastmod = notes
+        .iter()
+        .map(|(_, updated_at, ..)| *updated_at)
+        .chain(essays.iter().map(|(_, updated_at)| *updated_at))
+        .max();
+
+    let mut urls =
+        Vec::with_capacity(1 + notes.len() + essays.len() + tags.len() + categories.len());
+    urls.push(SitemapUrl {
+        loc: format!("{}/", site.url),
+        lastmod: home_lastmod,
+    });
+    urls.extend(notes.iter().map(|(slug, updated_at, ..)| SitemapUrl {
+        loc: site.note_url(slug),
+        lastmod: Some(*updated_at),
+    }));
+    urls.extend(essays.iter().map(|(id, updated_at)| SitemapUrl {
+        loc: site.essay_url(*id),
+        lastmod: Some(*updated_at),
+    }));
+    urls.extend(tags.iter().map(|(tag, lastmod)| SitemapUrl {
+        loc: site.tag_url(tag),
+        lastmod: Some(*lastmod),
+    }));
+    urls.extend(categories.iter().map(|(category, lastmod)| SitemapUrl {
+        loc: site.category_url(category),
+        lastmod: Some(*lastmod),
+    }));
+    Ok(urls)
+}
+
+/// 内容指纹：已发布的笔记、随笔数量及最近更新时间，任何一项变化都需要重新生成
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fingerprint {
+    notes: (i64, Option<DateTime<Utc>>),
+    essays: (i64, Option<DateTime<Utc>>),
+}
+
+async fn fingerprint(db: &DatabaseConnection) -> AppResult<Fingerprint> {
+    let notes: Option<(i64, Option<DateTime<Utc>>)> = NotesMetadata::find()
+        .select_only()
+        .column_as(Expr::col(notes_metadata::Column::Id).count(), "count")
+        .column_as(
+            Expr::col(notes_metadata::Column::UpdatedAt).max(),
+            "updated",
+        )
+        .filter(notes_metadata::Column::PublishedAt.lte(Utc::now()))
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    let essays: Option<(i64, Option<DateTime<Utc>>)> = Essay::find()
+        .select_only()
+        .column_as(Expr::col(essays::Column::Id).count(), "count")
+        .column_as(Expr::col(essays::Column::UpdatedAt).max(), "updated")
+        .filter(essays::Column::Status.eq(EssayStatus::Published.as_str()))
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    Ok(Fingerprint {
+        notes: notes.unwrap_or_default(),
+        essays: essays.unwrap_or_default(),
+    })
+}
+
+/// 站点地图缓存，robots.txt 在启动时生成一次
+pub struct SitemapCache {
+    robots: String,
+    cached: RwLock<Option<(Fingerprint, Arc<Sitemap>)>>,
+}
+
+impl SitemapCache {
+    pub fn new(robots: String) -> Self {
+        Self {
+            robots,
+            cached: RwLock::new(None),
+        }
+    }
+
+    pub fn robots(&self) -> &str {
+        &self.robots
+    }
+
+    /// 获取站点地图，内容有变化时重新生成
+    pub async fn get(&self, db: &DatabaseConnection, site: &SiteConfig) -> AppResult<Arc<Sitemap>> {
+        let current = fingerprint(db).await?;
+        if let Some((fingerprint, sitemap)) = self.cached.read().await.as_ref()
+            && *fingerprint == current
+        {
+            return Ok(Arc::clone(sitemap));
+        }
+
+        let urls = collect_urls(db, site).await?;
+        let sitemap = Arc::new(Sitemap::build(site, &urls));
+        log::info!("🗺️ 站点地图已重新生成，共 {} 个地址", urls.len());
+        *self.cached.write().await = Some((current, Arc::clone(&sitemap)));
+        Ok(sitemap)
+    }
+}
+
+/// 生成 robots.txt
+///
+/// 配置了 `ROBOTS_FILE` 时使用文件内容，否则按 `ROBOTS_DISALLOW` 生成；
+/// 两种情况下都会补上站点地图地址。
+pub fn robots_txt(site: &SiteConfig) -> AppResult<String> {
+    let mut robots = match &site.robots_file {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| AppError::Internal(format!("读取 robots.txt 文件 {path} 失败: {e}")))?,
+        None => {
+            let mut robots = String::from("User-agent: *\n");
+            if site.robots_disallow.is_empty() {
+                robots.push_str("Disallow:\n");
+            }
+            for path in &site.robots_disallow {
+                robots.push_str(&format!("Disallow: {path}\n"));
+            }
+            robots
+        }
+    };
+
+    if !robots.to_ascii_lowercase().contains("sitemap:") {
+        if !robots.is_empty() && !robots.ends_with('\n') {
+            robots.push('\n');
+        }
+        robots.push_str(&format!("\nSitemap: {}\n", site.sitemap_url()));
+    }
+    Ok(robots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site() -> SiteConfig {
+        let mut site = SiteConfig::from_env();
+        site.url = "https://example.com".to_string();
+        site.robots_disallow = vec!["/api/".to_string()];
+        site.robots_file = None;
+        site
+    }
+
+    #[test]
+    fn test_sitemap_index_above_limit() {
+        let urls: Vec<SitemapUrl> = (0..MAX_URLS_PER_SITEMAP + 1)
+            .map(|i| SitemapUrl {
+                loc: format!("https://example.com/notes/{i}"),
+                lastmod: None,
+            })
+            .collect();
+
+        let small = Sitemap::build(&site(), &urls[..2]);
+        assert!(small.root.contains("<urlset"));
+        assert!(small.parts.is_empty());
+
+        let large = Sitemap::build(&site(), &urls);
+        assert!(large.root.contains("<sitemapindex"));
+        assert!(
+            large
+                .root
+                .contains("<loc>https://example.com/sitemaps/2.xml</loc>")
+        );
+        assert_eq!(large.parts.len(), 2);
+        assert_eq!(large.part(2).unwrap().matches("<url>").count(), 1);
+        assert!(large.part(0).is_none());
+        assert!(large.part(3).is_none());
+    }
+
+    #[test]
+    fn test_robots_txt() {
+        let robots = robots_txt(&site()).unwrap();
+        assert_eq!(
+            robots,
+            "User-agent: *\nDisallow: /api/\n\nSitemap: https://example.com/sitemap.xml\n"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! 笔记 slug 管理
+//!
+//! - 由标题自动生成 slug，中文按拼音转写（`你好 Rust` → `ni-hao-rust`）
+//! - 与已有 slug 冲突时依次追加 `-2`、`-3`……
+//! - 修改 slug 时把旧 slug 记入 `slug_redirects`，访问旧地址时 301 跳转到新地址
+
+use std::collections::HashSet;
+
+use crate::{error::AppResult, infra::repositories::NoteRepository};
+
+/// slug 最大长度（字符数）
+pub const MAX_SLUG_LEN: usize = 80;
+
+/// 标题转写后为空时（例如全是标点符号）使用的 slug
+const FALLBACK_SLUG: &str = "note";
+
+/// 把任意文本转成 URL 友好的 slug：转写成 ASCII、小写、非字母数字替换为 `-`
+pub fn slugify(text: &str) -> String {
+    let ascii = deunicode::deunicode_with_tofu(text, " ");
+    let mut slug = String::with_capacity(ascii.len());
+    for c in ascii.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    // 超长时在单词边界截断
+    if slug.len() > MAX_SLUG_LEN {
+        slug.truncate(MAX_SLUG_LEN);
+        if let Some(pos) = slug.rfind('-')
+            && pos > MAX_SLUG_LEN / 2
+        {
+            slug.truncate(pos);
+        }
+    }
+
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        FALLBACK_SLUG.to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// 在 `base` 的基础上生成不冲突的 slug；`note_id` 为正在修改的笔记，其自身的 slug 不算冲突
+pub async fn unique_slug(
+    repo: &dyn NoteRepository,
+    base: &str,
+    note_id: Option<i32>,
+) -> AppResult<String> {
+    unique_slug_reserving(repo, base, note_id, &HashSet::new()).await
+}
+
+/// 与 `unique_slug` 相同，`reserved` 中的 slug 也算冲突，用于批量创建时还没写入数据库的笔记
+pub async fn unique_slug_reserving(
+    repo: &dyn NoteRepository,
+    base: &str,
+    note_id: Option<i32>,
+    reserved: &HashSet<String>,
+) -> AppResult<String> {
+    let base = slugify(base);
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while reserved.contains(&candidate) || repo.is_slug_taken(&candidate, note_id).await? {
+        let suffix = format!("-{n}");
+        let mut prefix = base.clone();
+        prefix.truncate(MAX_SLUG_LEN - suffix.len());
+        candidate = format!("{}{suffix}", prefix.trim_end_matches('-'));
+        n += 1;
+    }
+    Ok(candidate)
+}
+
+/// 查找旧 slug 对应的笔记当前的 slug
+pub async fn resolve_redirect(
+    repo: &dyn NoteRepository,
+    old_slug: &str,
+) -> AppResult<Option<String>> {
+    Ok(repo
+        .find_note_by_old_slug(old_slug)
+        .await?
+        .map(|note| note.slug))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::{db::entities::notes_metadata, repositories::memory::InMemoryRepository};
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("你好 Rust"), "ni-hao-rust");
+        assert_eq!(slugify("  --Axum 0.8 入门--  "), "axum-0-8-ru-men");
+        assert_eq!(slugify("？！……"), FALLBACK_SLUG);
+
+        let long = slugify(&"word ".repeat(40));
+        assert!(long.len() <= MAX_SLUG_LEN);
+        assert!(!long.ends_with('-'));
+    }
+
+    #[tokio::test]
+    async fn test_unique_slug_skips_old_slugs() {
+        let repo = InMemoryRepository::new();
+        let now = chrono::Utc::now();
+        let note = repo
+            .insert_note(notes_metadata::Model {
+                id: 0,
+                file_id: uuid::Uuid::new_v4(),
+                slug: "hello".to_string(),
+                title: "Hello".to_string(),
+                summary: None,
+                published_at: now,
+                updated_at: now,
+                views: 0,
+                likes_count: 0,
+                tags: None,
+                category: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(unique_slug(&repo, "Hello", None).await.unwrap(), "hello-2");
+        assert_eq!(
+            unique_slug(&repo, "Hello", Some(note.id)).await.unwrap(),
+            "hello"
+        );
+
+        repo.record_slug_change(note.id, "hello", "hello-world")
+            .await
+            .unwrap();
+        repo.update_note(notes_metadata::Model {
+            slug: "hello-world".to_string(),
+            ..note
+        })
+        .await
+        .unwrap();
+        // 旧 slug 仍然保留给跳转，新笔记不能占用
+        assert_eq!(unique_slug(&repo, "hello", None).await.unwrap(), "hello-2");
+        assert_eq!(
+            resolve_redirect(&repo, "hello").await.unwrap().as_deref(),
+            Some("hello-world")
+        );
+    }
+}
@@ -0,0 +1,584 @@
+//! 每日统计汇总
+//!
+//! `note_daily_stats` 按（笔记, 日期）保存浏览量、独立访客、点赞和评论数，
+//! `note_referrer_stats` 按（笔记, 日期, 来源站点）保存浏览量，
+//! `site_daily_stats` 按日期保存全站独立访客数。数据来源：
+//! - 浏览：由 `view_service` 在批量写回时一并累加
+//! - 全站独立访客：同一访客当天看了多篇笔记只算一次，所以不能由各笔记的独立访客数相加得到，
+//!   由 `view_service` 单独去重后累加
+//! - 点赞：点赞/取消点赞时即时累加（取消点赞记为当天 -1，因此是“净点赞数”）
+//! - 评论：由后台汇总任务根据 `comments.created_at` 计算，启动后第一次汇总全部日期
+//!   （补上导入的历史评论），之后定期重新计算最近几天的数量
+//!
+//! 所有统计都在本地完成，不依赖第三方分析服务。
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+    sea_query::{Alias, Expr, OnConflict, SimpleExpr},
+};
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::{AppError, AppResult},
+    infra::{
+        db::entities::{
+            comments::{self, Entity as Comments},
+            note_daily_stats::{self, Entity as NoteDailyStats},
+            note_referrer_stats::{self, Entity as NoteReferrerStats},
+            notes_metadata::{self, Entity as NotesMetadata},
+            site_daily_stats::{self, Entity as SiteDailyStats},
+        },
+        metrics,
+    },
+};
+
+/// 评论汇总任务每次重新计算的天数（包含今天）
+const COMMENT_ROLLUP_DAYS: i64 = 2;
+
+/// 统计查询允许的最大窗口（天）
+pub const MAX_WINDOW_DAYS: u32 = 365;
+
+/// 某一天的各项计数增量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DailyCounts {
+    pub views: i32,
+    pub unique_visitors: i32,
+    pub likes: i32,
+    pub comments: i32,
+}
+
+/// 统计查询的时间窗口，包含首尾两天
+#[derive(Debug, Clone, Copy)]
+pub struct Window {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Window {
+    /// 以今天为结束日、向前 `days` 天的窗口
+    pub fn last_days(days: u32) -> AppResult<Self> {
+        if days == 0 || days > MAX_WINDOW_DAYS {
+            return Err(AppError::BadRequest(format!(
+                "days 必须在 1 到 {MAX_WINDOW_DAYS} 之间"
+            )));
+        }
+        let to = Utc::now().date_naive();
+        let from = to - chrono::Duration::days(i64::from(days) - 1);
+        Ok(Self { from, to })
+    }
+
+    fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        self.from.iter_days().take_while(|day| *day <= self.to)
+    }
+}
+
+/// 时间序列中的一个点
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DailyPoint {
+    pub day: NaiveDate,
+    pub views: i64,
+    /// 单篇笔记时是该笔记的独立访客数，全站时是全站去重后的独立访客数
+    pub unique_visitors: i64,
+    pub likes: i64,
+    pub comments: i64,
+}
+
+/// 排行榜指标
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopMetric {
+    #[default]
+    Views,
+    Likes,
+    Comments,
+}
+
+/// 排行榜条目
+#[derive(Debug, Clone)]
+pub struct TopNote {
+    pub note: notes_metadata::Model,
+    pub views: i64,
+    pub likes: i64,
+    pub comments: i64,
+}
+
+/// 累加某篇笔记某天的计数，不存在时插入新行
+pub async fn add_daily_counts<C: ConnectionTrait>(
+    db: &C,
+    note_id: i32,
+    day: NaiveDate,
+    counts: DailyCounts,
+) -> Result<(), DbErr> {
+    use note_daily_stats::Column;
+
+    let row = note_daily_stats::ActiveModel {
+        note_metadata_id: Set(note_id),
+        day: Set(day),
+        views: Set(counts.views),
+        unique_visitors: Set(counts.unique_visitors),
+        likes: Set(counts.likes),
+        comments: Set(counts.comments),
+        ..Default::default()
+    };
+
+    let mut on_conflict = OnConflict::columns([Column::NoteMetadataId, Column::Day]);
+    for column in [
+        Column::Views,
+        Column::UniqueVisitors,
+        Column::Likes,
+        Column::Comments,
+    ] {
+        on_conflict.value(column, accumulate(NoteDailyStats, column));
+    }
+
+    NoteDailyStats::insert(row)
+        .on_conflict(on_conflict)
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// 累加某篇笔记某天来自某个站点的浏览量
+pub async fn add_referrer_views<C: ConnectionTrait>(
+    db: &C,
+    note_id: i32,
+    day: NaiveDate,
+    referrer: &str,
+    views: i32,
+) -> Result<(), DbErr> {
+    use note_referrer_stats::Column;
+
+    let row = note_referrer_stats::ActiveModel {
+        note_metadata_id: Set(note_id),
+        day: Set(day),
+        referrer: Set(referrer.to_string()),
+        views: Set(views),
+        ..Default::default()
+    };
+
+    NoteReferrerStats::insert(row)
+        .on_conflict(
+            OnConflict::columns([Column::NoteMetadataId, Column::Day, Column::Referrer])
+                .value(Column::Views, accumulate(NoteReferrerStats, Column::Views))
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// 累加某天的全站独立访客数
+pub async fn add_site_unique_visitors<C: ConnectionTrait>(
+    db: &C,
+    day: NaiveDate,
+    unique_visitors: i32,
+) -> Result<(), DbErr> {
+    use site_daily_stats::Column;
+
+    let row = site_daily_stats::ActiveModel {
+        day: Set(day),
+        unique_visitors: Set(unique_visitors),
+        ..Default::default()
+    };
+
+    SiteDailyStats::insert(row)
+        .on_conflict(
+            OnConflict::column(Column::Day)
+                .value(
+                    Column::UniqueVisitors,
+                    accumulate(SiteDailyStats, Column::UniqueVisitors),
+                )
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// `column = table.column + excluded.column`
+fn accumulate<E, C>(table: E, column: C) -> SimpleExpr
+where
+    E: sea_orm::sea_query::IntoIden + 'static,
+    C: sea_orm::sea_query::IntoIden + Copy + 'static,
+{
+    Expr::col((table, column)).add(Expr::col((Alias::new("excluded"), column)))
+}
+
+/// 记录一次点赞（`delta = 1`）或取消点赞（`delta = -1`）
+pub async fn record_like<C: ConnectionTrait>(
+    db: &C,
+    note_id: i32,
+    delta: i32,
+) -> Result<(), DbErr> {
+    let counts = DailyCounts {
+        likes: delta,
+        ..Default::default()
+    };
+    add_daily_counts(db, note_id, Utc::now().date_naive(), counts).await
+}
+
+/// 根据评论表重新计算 `since` 及之后每天的评论数，`since` 为 `None` 时重新计算全部日期；
+/// 返回写入的行数
+pub async fn rollup_comments(
+    db: &DatabaseConnection,
+    since: Option<NaiveDate>,
+) -> AppResult<usize> {
+    let mut query = Comments::find().filter(comments::Column::NoteMetadataId.is_not_null());
+    if let Some(since) = since {
+        let start = since.and_time(chrono::NaiveTime::MIN).and_utc();
+        query = query.filter(comments::Column::CreatedAt.gte(start));
+    }
+    let rows = query.all(db).await?;
+
+    let mut counts: HashMap<(i32, NaiveDate), i32> = HashMap::new();
+    for comment in rows {
+        if let Some(note_id) = comment.note_metadata_id {
+            *counts
+                .entry((note_id, comment.created_at.date_naive()))
+                .or_insert(0) += 1;
+        }
+    }
+
+    let txn = db.begin().await?;
+    // 先清零，这样已删除的评论也能反映到汇总里
+    let mut reset =
+        NoteDailyStats::update_many().col_expr(note_daily_stats::Column::Comments, Expr::value(0));
+    if let Some(since) = since {
+        reset = reset.filter(note_daily_stats::Column::Day.gte(since));
+    }
+    reset.exec(&txn).await?;
+    for ((note_id, day), comments) in &counts {
+        let row = note_daily_stats::ActiveModel {
+            note_metadata_id: Set(*note_id),
+            day: Set(*day),
+            views: Set(0),
+            unique_visitors: Set(0),
+            likes: Set(0),
+            comments: Set(*comments),
+            ..Default::default()
+        };
+        NoteDailyStats::insert(row)
+            .on_conflict(
+                OnConflict::columns([
+                    note_daily_stats::Column::NoteMetadataId,
+                    note_daily_stats::Column::Day,
+                ])
+                .update_column(note_daily_stats::Column::Comments)
+                .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    Ok(counts.len())
+}
+
+/// 后台评论汇总任务
+///
+/// 第一次汇总全部日期，补上导入或停机期间新增的历史评论；成功后只重新计算最近几天。
+pub async fn run_rollup(db: DatabaseConnection, interval: Duration, shutdown: CancellationToken) {
+    let mut ticker = tokio::time::interval(interval);
+    let mut backfilled = false;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+        let since = backfilled
+            .then(|| Utc::now().date_naive() - chrono::Duration::days(COMMENT_ROLLUP_DAYS - 1));
+        match metrics::time_job("stats_rollup", rollup_comments(&db, since)).await {
+            Ok(rows) if !backfilled => {
+                backfilled = true;
+                log::info!("📊 已汇总全部历史评论，共 {rows} 条每日统计");
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("⚠️ 评论统计汇总失败: {err}"),
+        }
+    }
+}
+
+async fn daily_rows(
+    db: &DatabaseConnection,
+    note_id: Option<i32>,
+    window: Window,
+) -> AppResult<Vec<note_daily_stats::Model>> {
+    let mut query = NoteDailyStats::find()
+        .filter(note_daily_stats::Column::Day.gte(window.from))
+        .filter(note_daily_stats::Column::Day.lte(window.to));
+    if let Some(note_id) = note_id {
+        query = query.filter(note_daily_stats::Column::NoteMetadataId.eq(note_id));
+    }
+    Ok(query.all(db).await?)
+}
+
+/// 把若干行按天汇总成连续的时间序列，缺失的日期补 0
+fn to_series(rows: &[note_daily_stats::Model], window: Window) -> Vec<DailyPoint> {
+    let mut by_day: BTreeMap<NaiveDate, DailyPoint> = window
+        .days()
+        .map(|day| {
+            (
+                day,
+                DailyPoint {
+                    day,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+
+    for row in rows {
+        if let Some(point) = by_day.get_mut(&row.day) {
+            point.views += i64::from(row.views);
+            point.unique_visitors += i64::from(row.unique_visitors);
+            point.likes += i64::from(row.likes);
+            point.comments += i64::from(row.comments);
+        }
+    }
+
+    by_day.into_values().collect()
+}
+
+/// 单篇笔记的每日统计
+pub async fn note_series(
+    db: &DatabaseConnection,
+    note_id: i32,
+    window: Window,
+) -> AppResult<Vec<DailyPoint>> {
+    let rows = daily_rows(db, Some(note_id), window).await?;
+    Ok(to_series(&rows, window))
+}
+
+/// 全站每日统计，独立访客数取 `site_daily_stats` 中全站去重后的值
+pub async fn site_series(db: &DatabaseConnection, window: Window) -> AppResult<Vec<DailyPoint>> {
+    let rows = daily_rows(db, None, window).await?;
+    let uniques: HashMap<NaiveDate, i32> = SiteDailyStats::find()
+        .filter(site_daily_stats::Column::Day.gte(window.from))
+        .filter(site_daily_stats::Column::Day.lte(window.to))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| (row.day, row.unique_visitors))
+        .collect();
+
+    let mut series = to_series(&rows, window);
+    for point in &mut series {
+        point.unique_visitors = uniques.get(&point.day).copied().map_or(0, i64::from);
+    }
+    Ok(series)
+}
+
+/// 窗口内按指定指标排序的前 N 篇笔记
+pub async fn top_notes(
+    db: &DatabaseConnection,
+    window: Window,
+    metric: TopMetric,
+    limit: usize,
+) -> AppResult<Vec<TopNote>> {
+    let rows = daily_rows(db, None, window).await?;
+
+    let mut totals: HashMap<i32, (i64, i64, i64)> = HashMap::new();
+    for row in rows {
+        let entry = totals.entry(row.note_metadata_id).or_default();
+        entry.0 += i64::from(row.views);
+        entry.1 += i64::from(row.likes);
+        entry.2 += i64::from(row.comments);
+    }
+
+    let mut ranked: Vec<(i32, (i64, i64, i64))> = totals.into_iter().collect();
+    ranked.sort_by_key(|(note_id, (views, likes, comments))| {
+        let score = match metric {
+            TopMetric::Views => *views,
+            TopMetric::Likes => *likes,
+            TopMetric::Comments => *comments,
+        };
+        (std::cmp::Reverse(score), *note_id)
+    });
+    ranked.truncate(limit);
+
+    let ids: Vec<i32> = ranked.iter().map(|(note_id, _)| *note_id).collect();
+    let mut notes: HashMap<i32, notes_metadata::Model> = NotesMetadata::find()
+        .filter(notes_metadata::Column::Id.is_in(ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|note| (note.id, note))
+        .collect();
+
+    Ok(ranked
+        .into_iter()
+        .filter_map(|(note_id, (views, likes, comments))| {
+            notes.remove(&note_id).map(|note| TopNote {
+                note,
+                views,
+                likes,
+                comments,
+            })
+        })
+        .collect())
+}
+
+/// 窗口内各来源站点带来的浏览量，按浏览量倒序
+pub async fn referrers(
+    db: &DatabaseConnection,
+    note_id: Option<i32>,
+    window: Window,
+    limit: usize,
+) -> AppResult<Vec<(String, i64)>> {
+    let mut query = NoteReferrerStats::find()
+        .filter(note_referrer_stats::Column::Day.gte(window.from))
+        .filter(note_referrer_stats::Column::Day.lte(window.to));
+    if let Some(note_id) = note_id {
+        query = query.filter(note_referrer_stats::Column::NoteMetadataId.eq(note_id));
+    }
+
+    let mut totals: HashMap<String, i64> = HashMap::new();
+    for row in query.all(db).await? {
+        *totals.entry(row.referrer).or_insert(0) += i64::from(row.views);
+    }
+
+    let mut ranked: Vec<(String, i64)> = totals.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(limit);
+    Ok(ranked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(note_id: i32, day: NaiveDate, views: i32) -> note_daily_stats::Model {
+        note_daily_stats::Model {
+            id: 0,
+            note_metadata_id: note_id,
+            day,
+            views,
+            unique_visitors: views,
+            likes: 1,
+            comments: 0,
+        }
+    }
+
+    #[test]
+    fn test_series_fills_missing_days() {
+        let from = NaiveDate::from_ymd_opt(2025, 1, 30).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 2, 2).unwrap();
+        let window = Window { from, to };
+        let rows = vec![
+            row(1, from, 3),
+            row(2, from, 2),
+            row(1, to, 5),
+            row(1, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(), 100),
+        ];
+
+        let series = to_series(&rows, window);
+        assert_eq!(series.len(), 4);
+        assert_eq!(series[0].views, 5);
+        assert_eq!(series[0].likes, 2);
+        assert_eq!(series[1].views, 0);
+        assert_eq!(series[3].views, 5);
+    }
+
+    #[tokio::test]
+    async fn test_site_series_uses_site_unique_visitors() {
+        let db = crate::infra::db::test_db().await;
+        let to = Utc::now().date_naive();
+        let window = Window::last_days(3).unwrap();
+        add_site_unique_visitors(&db, to, 1).await.unwrap();
+        add_site_unique_visitors(&db, to, 2).await.unwrap();
+
+        let series = site_series(&db, window).await.unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[2].unique_visitors, 3);
+        assert_eq!(series[0].unique_visitors, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rollup_backfills_old_comments() {
+        use crate::infra::{
+            db::entities::visitor_profiles,
+            repositories::{
+                CommentRepository, NoteRepository, SeaOrmRepository, VisitorRepository,
+            },
+        };
+
+        let db = crate::infra::db::test_db().await;
+        let repo = SeaOrmRepository::new(&db);
+        let now = Utc::now();
+        let long_ago = now - chrono::Duration::days(30);
+        let note = repo
+            .insert_note(notes_metadata::Model {
+                id: 0,
+                file_id: uuid::Uuid::new_v4(),
+                slug: "old".to_string(),
+                title: "Old".to_string(),
+                summary: None,
+                published_at: long_ago,
+                updated_at: long_ago,
+                views: 0,
+                likes_count: 0,
+                tags: None,
+                category: None,
+            })
+            .await
+            .unwrap();
+        let visitor = repo
+            .insert_visitor(visitor_profiles::Model {
+                id: 0,
+                cookie_id: "cookie".to_string(),
+                name: "访客".to_string(),
+                ip: "1.2.3.4".to_string(),
+                created_at: long_ago,
+                updated_at: long_ago,
+            })
+            .await
+            .unwrap();
+        repo.insert_comment(comments::Model {
+            id: 0,
+            note_metadata_id: Some(note.id),
+            essay_id: None,
+            visitor_profile_id: visitor.id,
+            content: "导入的旧评论".to_string(),
+            parent_id: None,
+            created_at: long_ago,
+            is_approved: true,
+        })
+        .await
+        .unwrap();
+        let window = Window::last_days(31).unwrap();
+        let comments_on = |series: &[DailyPoint]| {
+            series
+                .iter()
+                .find(|point| point.day == long_ago.date_naive())
+                .map_or(0, |point| point.comments)
+        };
+
+        // 只汇总最近几天时，窗口之外的评论不会出现在统计里
+        let recent = now.date_naive() - chrono::Duration::days(COMMENT_ROLLUP_DAYS - 1);
+        rollup_comments(&db, Some(recent)).await.unwrap();
+        let series = note_series(&db, note.id, window).await.unwrap();
+        assert_eq!(comments_on(&series), 0);
+
+        assert_eq!(rollup_comments(&db, None).await.unwrap(), 1);
+        let series = note_series(&db, note.id, window).await.unwrap();
+        assert_eq!(comments_on(&series), 1);
+        // 重复汇总不会累加
+        rollup_comments(&db, None).await.unwrap();
+        let series = note_series(&db, note.id, window).await.unwrap();
+        assert_eq!(comments_on(&series), 1);
+    }
+
+    #[test]
+    fn test_window_bounds() {
+        assert!(Window::last_days(0).is_err());
+        assert!(Window::last_days(MAX_WINDOW_DAYS + 1).is_err());
+        let window = Window::last_days(7).unwrap();
+        assert_eq!(window.days().count(), 7);
+    }
+}
@@ -0,0 +1,561 @@
+//! 管理员两步验证（RFC 6238 TOTP）
+//!
+//! 绑定流程分两步：
+//! 1. `begin_enrollment` 生成密钥并返回 otpauth URI 和二维码，此时还未启用
+//! 2. `confirm_enrollment` 用验证器应用生成的验证码确认后才真正启用，并签发一组恢复码
+//!
+//! 每个时间步的验证码只能使用一次：验证通过后记录时间步，
+//! 之后只接受更晚的时间步。恢复码与刷新令牌一样只保存 SHA-256。
+//!
+//! 登录时的 MFA 令牌只能提交一次，无论验证码是否正确，再试需要重新输入密码；
+//! 连续输错 `TOTP_MAX_ATTEMPTS` 次后锁定两步验证 `TOTP_LOCKOUT_SECS` 秒，防止暴力猜测验证码。
+
+use chrono::{Duration, Utc};
+use qrcode::{QrCode, render::svg};
+use rand::{Rng, RngCore};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, Set, TransactionTrait, sea_query::Expr,
+};
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::{
+    config::{JwtConfig, TotpConfig},
+    error::{AppError, AppResult},
+    infra::db::entities::{
+        admins::{self, Entity as Admin},
+        recovery_codes::{self, Entity as RecoveryCode},
+    },
+    service::auth_service,
+};
+
+/// 每次签发的恢复码数量
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const DIGITS: usize = 6;
+const STEP_SECS: u64 = 30;
+/// 允许前后各一个时间步的时钟误差
+const SKEW: u64 = 1;
+/// 恢复码字符集，去掉了容易混淆的 0/o、1/l
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+
+/// 绑定验证器时返回给前端的信息
+#[derive(Debug, Clone)]
+pub struct Enrollment {
+    /// Base32 密钥，供无法扫码时手动输入
+    pub secret: String,
+    pub otpauth_uri: String,
+    /// 二维码 SVG
+    pub qr_svg: String,
+}
+
+/// 生成 160 位随机密钥（Base32 编码）
+fn generate_secret() -> String {
+    let mut bytes = vec![0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    Secret::Raw(bytes).to_encoded().to_string()
+}
+
+fn build_totp(secret: &str, account_name: &str, issuer: &str) -> AppResult<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|err| AppError::Internal(format!("TOTP 密钥格式错误: {err:?}")))?;
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        SKEW as u8,
+        STEP_SECS,
+        bytes,
+        Some(issuer.replace(':', "")),
+        account_name.replace(':', ""),
+    )
+    .map_err(|err| AppError::Internal(format!("创建 TOTP 失败: {err:?}")))
+}
+
+/// 把 otpauth URI 渲染成 SVG 二维码
+fn qr_svg(uri: &str) -> AppResult<String> {
+    let code = QrCode::new(uri.as_bytes())
+        .map_err(|err| AppError::Internal(format!("生成二维码失败: {err}")))?;
+    Ok(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+/// 查找与验证码匹配的时间步，允许 `SKEW` 个时间步的误差
+fn matching_step(totp: &TOTP, code: &str, now_secs: u64) -> Option<u64> {
+    let current = now_secs / STEP_SECS;
+    (current.saturating_sub(SKEW)..=current + SKEW)
+        .find(|step| totp.generate(step * STEP_SECS) == code)
+}
+
+fn hash_code(code: &str) -> String {
+    hex::encode(Sha256::digest(code.as_bytes()))
+}
+
+/// 恢复码不区分大小写，也忽略分隔符和空白
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// 生成形如 `abcde-fghij` 的恢复码
+fn generate_recovery_code() -> String {
+    let mut rng = rand::thread_rng();
+    let mut code = String::with_capacity(11);
+    for i in 0..10 {
+        if i == 5 {
+            code.push('-');
+        }
+        code.push(RECOVERY_ALPHABET[rng.gen_range(0..RECOVERY_ALPHABET.len())] as char);
+    }
+    code
+}
+
+fn now_secs() -> u64 {
+    Utc::now().timestamp().max(0) as u64
+}
+
+async fn find_admin(db: &DatabaseConnection, admin_id: i32) -> AppResult<admins::Model> {
+    Admin::find_by_id(admin_id)
+        .one(db)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// 开始绑定验证器：生成新密钥（覆盖尚未确认的旧密钥）
+pub async fn begin_enrollment(
+    db: &DatabaseConnection,
+    admin_id: i32,
+    config: &TotpConfig,
+) -> AppResult<Enrollment> {
+    let admin = find_admin(db, admin_id).await?;
+    if admin.totp_enabled_at.is_some() {
+        return Err(AppError::Conflict("已经启用了两步验证".to_string()));
+    }
+
+    let secret = generate_secret();
+    let totp = build_totp(&secret, &admin.username, &config.issuer)?;
+    let otpauth_uri = totp.get_url();
+    let qr_svg = qr_svg(&otpauth_uri)?;
+
+    let mut active: admins::ActiveModel = admin.into();
+    active.totp_secret = Set(Some(secret.clone()));
+    active.totp_last_step = Set(None);
+    active.updated_at = Set(Utc::now());
+    active.update(db).await?;
+
+    Ok(Enrollment {
+        secret,
+        otpauth_uri,
+        qr_svg,
+    })
+}
+
+/// 用验证码确认绑定，启用两步验证并返回恢复码
+pub async fn confirm_enrollment(
+    db: &DatabaseConnection,
+    admin_id: i32,
+    code: &str,
+    config: &TotpConfig,
+) -> AppResult<Vec<String>> {
+    let admin = find_admin(db, admin_id).await?;
+    if admin.totp_enabled_at.is_some() {
+        return Err(AppError::Conflict("已经启用了两步验证".to_string()));
+    }
+    if admin.totp_secret.is_none() {
+        return Err(AppError::BadRequest("请先生成两步验证密钥".to_string()));
+    }
+    verify_totp(db, &admin, code, config).await?;
+
+    let txn = db.begin().await?;
+    Admin::update_many()
+        .col_expr(admins::Column::TotpEnabledAt, Expr::value(Utc::now()))
+        .col_expr(admins::Column::UpdatedAt, Expr::value(Utc::now()))
+        .filter(admins::Column::Id.eq(admin_id))
+        .exec(&txn)
+        .await?;
+    let codes = replace_recovery_codes(&txn, admin_id).await?;
+    txn.commit().await?;
+
+    log::info!("🔐 管理员 {} 启用了两步验证", admin.username);
+    Ok(codes)
+}
+
+/// 关闭两步验证，需要提供验证码或恢复码
+pub async fn disable(
+    db: &DatabaseConnection,
+    admin_id: i32,
+    code: &str,
+    config: &TotpConfig,
+) -> AppResult<()> {
+    let admin = find_admin(db, admin_id).await?;
+    verify_second_factor(db, &admin, code, config).await?;
+
+    let txn = db.begin().await?;
+    RecoveryCode::delete_many()
+        .filter(recovery_codes::Column::AdminId.eq(admin_id))
+        .exec(&txn)
+        .await?;
+    let username = admin.username.clone();
+    let mut active: admins::ActiveModel = admin.into();
+    active.totp_secret = Set(None);
+    active.totp_enabled_at = Set(None);
+    active.totp_last_step = Set(None);
+    active.updated_at = Set(Utc::now());
+    active.update(&txn).await?;
+    txn.commit().await?;
+
+    log::info!("🔓 管理员 {username} 关闭了两步验证");
+    Ok(())
+}
+
+/// 重新生成恢复码，旧的恢复码全部作废
+pub async fn regenerate_recovery_codes(
+    db: &DatabaseConnection,
+    admin_id: i32,
+    code: &str,
+    config: &TotpConfig,
+) -> AppResult<Vec<String>> {
+    let admin = find_admin(db, admin_id).await?;
+    verify_second_factor(db, &admin, code, config).await?;
+
+    let txn = db.begin().await?;
+    let codes = replace_recovery_codes(&txn, admin_id).await?;
+    txn.commit().await?;
+    Ok(codes)
+}
+
+async fn replace_recovery_codes<C: ConnectionTrait>(
+    db: &C,
+    admin_id: i32,
+) -> AppResult<Vec<String>> {
+    RecoveryCode::delete_many()
+        .filter(recovery_codes::Column::AdminId.eq(admin_id))
+        .exec(db)
+        .await?;
+
+    let now = Utc::now();
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+    RecoveryCode::insert_many(codes.iter().map(|code| recovery_codes::ActiveModel {
+        admin_id: Set(admin_id),
+        code_hash: Set(hash_code(&normalize_recovery_code(code))),
+        created_at: Set(now),
+        used_at: Set(None),
+        ..Default::default()
+    }))
+    .exec(db)
+    .await?;
+    Ok(codes)
+}
+
+/// 密码验证通过后开始两步验证：签发 MFA 令牌并记下它的 jti，之前未提交的令牌随之作废
+pub async fn begin_challenge(
+    db: &DatabaseConnection,
+    admin: &admins::Model,
+    config: &TotpConfig,
+    jwt: &JwtConfig,
+) -> AppResult<String> {
+    let jti = Uuid::new_v4().to_string();
+    let token = auth_service::issue_mfa_token(admin, &jti, config.challenge_ttl_secs, jwt)?;
+    Admin::update_many()
+        .col_expr(admins::Column::MfaChallengeJti, Expr::value(jti))
+        .filter(admins::Column::Id.eq(admin.id))
+        .exec(db)
+        .await?;
+    Ok(token)
+}
+
+/// 提交 MFA 令牌时先消耗掉它，每个令牌只能提交一次
+pub async fn consume_challenge(db: &DatabaseConnection, admin_id: i32, jti: &str) -> AppResult<()> {
+    let consumed = Admin::update_many()
+        .col_expr(
+            admins::Column::MfaChallengeJti,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(admins::Column::Id.eq(admin_id))
+        .filter(admins::Column::MfaChallengeJti.eq(jti))
+        .exec(db)
+        .await?;
+    if consumed.rows_affected != 1 {
+        return Err(AppError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// 校验第二因素：6 位数字按 TOTP 验证码处理，其余按恢复码处理
+///
+/// 处于锁定期时直接拒绝；输错会累加失败次数，通过后清零。
+pub async fn verify_second_factor(
+    db: &DatabaseConnection,
+    admin: &admins::Model,
+    code: &str,
+    config: &TotpConfig,
+) -> AppResult<()> {
+    if admin.totp_enabled_at.is_none() {
+        return Err(AppError::BadRequest("尚未启用两步验证".to_string()));
+    }
+    if let Some(until) = admin.mfa_locked_until
+        && until > Utc::now()
+    {
+        let minutes = (until - Utc::now()).num_minutes() + 1;
+        return Err(AppError::TooManyRequests(format!(
+            "验证码错误次数过多，请 {minutes} 分钟后再试"
+        )));
+    }
+
+    let code = code.trim();
+    let result = if code.len() == DIGITS && code.chars().all(|c| c.is_ascii_digit()) {
+        verify_totp(db, admin, code, config).await
+    } else {
+        consume_recovery_code(db, admin, code).await
+    };
+    match &result {
+        Ok(()) => reset_failures(db, admin.id).await?,
+        Err(AppError::Unauthorized) => record_failure(db, admin, config).await?,
+        Err(_) => {}
+    }
+    result
+}
+
+/// 累加一次失败，达到上限时锁定并清零计数
+async fn record_failure(
+    db: &DatabaseConnection,
+    admin: &admins::Model,
+    config: &TotpConfig,
+) -> AppResult<()> {
+    // 先原子地加一再按条件锁定，并发的错误请求也都会被计入
+    Admin::update_many()
+        .col_expr(
+            admins::Column::MfaFailedAttempts,
+            Expr::col(admins::Column::MfaFailedAttempts).add(1),
+        )
+        .filter(admins::Column::Id.eq(admin.id))
+        .exec(db)
+        .await?;
+    let locked = Admin::update_many()
+        .col_expr(admins::Column::MfaFailedAttempts, Expr::value(0))
+        .col_expr(
+            admins::Column::MfaLockedUntil,
+            Expr::value(Utc::now() + Duration::seconds(config.lockout_secs)),
+        )
+        .filter(admins::Column::Id.eq(admin.id))
+        .filter(admins::Column::MfaFailedAttempts.gte(config.max_attempts))
+        .exec(db)
+        .await?;
+    if locked.rows_affected == 1 {
+        log::warn!(
+            "🚨 管理员 {} 连续 {} 次两步验证失败，锁定 {} 秒",
+            admin.username,
+            config.max_attempts,
+            config.lockout_secs
+        );
+    }
+    Ok(())
+}
+
+async fn reset_failures(db: &DatabaseConnection, admin_id: i32) -> AppResult<()> {
+    Admin::update_many()
+        .col_expr(admins::Column::MfaFailedAttempts, Expr::value(0))
+        .col_expr(
+            admins::Column::MfaLockedUntil,
+            Expr::value(Option::<chrono::DateTime<Utc>>::None),
+        )
+        .filter(admins::Column::Id.eq(admin_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// 校验 TOTP 验证码，并记录时间步防止重放
+async fn verify_totp(
+    db: &DatabaseConnection,
+    admin: &admins::Model,
+    code: &str,
+    config: &TotpConfig,
+) -> AppResult<()> {
+    let secret = admin.totp_secret.as_deref().ok_or(AppError::Unauthorized)?;
+    let totp = build_totp(secret, &admin.username, &config.issuer)?;
+    let step = matching_step(&totp, code.trim(), now_secs()).ok_or(AppError::Unauthorized)? as i64;
+
+    // 条件更新：只有比上次更晚的时间步才能通过，并发请求中也只有一个会成功
+    let claimed = Admin::update_many()
+        .col_expr(admins::Column::TotpLastStep, Expr::value(step))
+        .filter(admins::Column::Id.eq(admin.id))
+        .filter(
+            Condition::any()
+                .add(admins::Column::TotpLastStep.is_null())
+                .add(admins::Column::TotpLastStep.lt(step)),
+        )
+        .exec(db)
+        .await?;
+    if claimed.rows_affected != 1 {
+        log::warn!("⚠️ 管理员 {} 的验证码被重复使用", admin.username);
+        return Err(AppError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// 使用一个恢复码，每个恢复码只能用一次
+async fn consume_recovery_code(
+    db: &DatabaseConnection,
+    admin: &admins::Model,
+    code: &str,
+) -> AppResult<()> {
+    let used = RecoveryCode::update_many()
+        .col_expr(recovery_codes::Column::UsedAt, Expr::value(Utc::now()))
+        .filter(recovery_codes::Column::AdminId.eq(admin.id))
+        .filter(recovery_codes::Column::CodeHash.eq(hash_code(&normalize_recovery_code(code))))
+        .filter(recovery_codes::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    if used.rows_affected != 1 {
+        return Err(AppError::Unauthorized);
+    }
+    log::warn!("🔑 管理员 {} 使用了一个恢复码", admin.username);
+    Ok(())
+}
+
+/// 剩余可用的恢复码数量
+pub async fn remaining_recovery_codes(db: &DatabaseConnection, admin_id: i32) -> AppResult<u64> {
+    let count = RecoveryCode::find()
+        .filter(recovery_codes::Column::AdminId.eq(admin_id))
+        .filter(recovery_codes::Column::UsedAt.is_null())
+        .count(db)
+        .await?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::test_db;
+
+    fn config() -> TotpConfig {
+        TotpConfig {
+            issuer: "RowanWeb".to_string(),
+            challenge_ttl_secs: 60,
+            max_attempts: 3,
+            lockout_secs: 600,
+        }
+    }
+
+    /// 创建一个已经启用两步验证的管理员
+    async fn enrolled_admin(db: &DatabaseConnection) -> admins::Model {
+        let admin = auth_service::create_admin(db, "rowan", "correct horse battery")
+            .await
+            .unwrap();
+        let mut active: admins::ActiveModel = admin.into();
+        active.totp_secret = Set(Some(generate_secret()));
+        active.totp_enabled_at = Set(Some(Utc::now()));
+        active.update(db).await.unwrap()
+    }
+
+    #[test]
+    fn test_matching_step_allows_skew() {
+        let secret = generate_secret();
+        let totp = build_totp(&secret, "rowan", "RowanWeb").unwrap();
+        let now = 1_700_000_000;
+        let step = now / STEP_SECS;
+
+        assert_eq!(matching_step(&totp, &totp.generate(now), now), Some(step));
+        let previous = totp.generate(now - STEP_SECS);
+        assert_eq!(matching_step(&totp, &previous, now), Some(step - 1));
+        let stale = totp.generate(now - 3 * STEP_SECS);
+        assert_eq!(matching_step(&totp, &stale, now), None);
+    }
+
+    #[test]
+    fn test_otpauth_uri_and_qr() {
+        let secret = generate_secret();
+        let totp = build_totp(&secret, "rowan", "RowanWeb").unwrap();
+        let uri = totp.get_url();
+        assert!(uri.starts_with("otpauth://totp/RowanWeb:rowan?"));
+        assert!(uri.contains(&format!("secret={secret}")));
+        assert!(qr_svg(&uri).unwrap().contains("<svg"));
+    }
+
+    #[test]
+    fn test_recovery_code_normalization() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(
+            hash_code(&normalize_recovery_code(
+                &code.to_uppercase().replace('-', " ")
+            )),
+            hash_code(&normalize_recovery_code(&code))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mfa_challenge_is_single_use() {
+        let db = test_db().await;
+        let admin = enrolled_admin(&db).await;
+        let jwt = JwtConfig {
+            secret: "test-secret".to_string(),
+            expires_in: 900,
+            refresh_expires_in: 3600,
+        };
+        let jti = |token: &str| {
+            auth_service::verify_token(token, auth_service::TokenType::Mfa, &jwt)
+                .unwrap()
+                .jti
+        };
+
+        let first = begin_challenge(&db, &admin, &config(), &jwt).await.unwrap();
+        // 重新登录签发的新令牌让旧令牌作废
+        let second = begin_challenge(&db, &admin, &config(), &jwt).await.unwrap();
+        assert!(matches!(
+            consume_challenge(&db, admin.id, &jti(&first)).await,
+            Err(AppError::Unauthorized)
+        ));
+
+        consume_challenge(&db, admin.id, &jti(&second))
+            .await
+            .unwrap();
+        assert!(matches!(
+            consume_challenge(&db, admin.id, &jti(&second)).await,
+            Err(AppError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_failures_lock_second_factor() {
+        let db = test_db().await;
+        let admin = enrolled_admin(&db).await;
+        let config = config();
+        let totp = build_totp(admin.totp_secret.as_deref().unwrap(), "rowan", "RowanWeb").unwrap();
+
+        for _ in 0..config.max_attempts {
+            let admin = find_admin(&db, admin.id).await.unwrap();
+            assert!(matches!(
+                verify_second_factor(&db, &admin, "wrong-code", &config).await,
+                Err(AppError::Unauthorized)
+            ));
+        }
+
+        // 锁定期间正确的验证码也被拒绝
+        let locked = find_admin(&db, admin.id).await.unwrap();
+        assert!(locked.mfa_locked_until.is_some());
+        let code = totp.generate(now_secs());
+        assert!(matches!(
+            verify_second_factor(&db, &locked, &code, &config).await,
+            Err(AppError::TooManyRequests(_))
+        ));
+
+        // 锁定结束后可以通过，失败计数清零
+        let mut active: admins::ActiveModel = locked.into();
+        active.mfa_locked_until = Set(Some(Utc::now() - Duration::seconds(1)));
+        active.mfa_failed_attempts = Set(2);
+        let expired = active.update(&db).await.unwrap();
+        verify_second_factor(&db, &expired, &code, &config)
+            .await
+            .unwrap();
+        let admin = find_admin(&db, admin.id).await.unwrap();
+        assert_eq!(admin.mfa_failed_attempts, 0);
+        assert!(admin.mfa_locked_until.is_none());
+    }
+}
@@ -0,0 +1,498 @@
+//! 笔记浏览量统计
+//!
+//! 每次页面访问都去 `UPDATE notes_metadata` 会让 SQLite 频繁加写锁，
+//! 所以浏览量先在内存中聚合：
+//! - 同一访客（Cookie 或 IP）在去重窗口内重复访问同一篇笔记只计一次
+//! - 已知爬虫的 User-Agent 直接忽略
+//! - 去重记录的条数有上限，满了以后新访客照常计数，但不再去重
+//! - 后台任务按固定间隔把累计的增量批量写回数据库，关闭服务时再写回一次
+//!
+//! 写回时会同时累加 `note_daily_stats` 中当天的浏览量、独立访客数以及来源站点统计，
+//! 以及 `site_daily_stats` 中当天的全站独立访客数。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait, sea_query::Expr,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::ViewConfig,
+    error::AppResult,
+    infra::{
+        db::entities::notes_metadata::{self, Entity as NotesMetadata},
+        metrics,
+    },
+    service::stats_service::{self, DailyCounts},
+};
+
+/// 没有来源（直接访问、站内跳转）时记录的来源名称
+pub const DIRECT_REFERRER: &str = "direct";
+
+/// User-Agent 中出现这些关键字即视为爬虫或脚本
+const BOT_KEYWORDS: &[&str] = &[
+    "bot",
+    "crawler",
+    "spider",
+    "slurp",
+    "facebookexternalhit",
+    "embedly",
+    "preview",
+    "headless",
+    "lighthouse",
+    "curl",
+    "wget",
+    "python-requests",
+    "go-http-client",
+];
+
+/// 判断 User-Agent 是否属于爬虫；缺失 User-Agent 的请求同样按爬虫处理
+pub fn is_bot(user_agent: Option<&str>) -> bool {
+    match user_agent {
+        Some(ua) if !ua.trim().is_empty() => {
+            let ua = ua.to_ascii_lowercase();
+            BOT_KEYWORDS.iter().any(|keyword| ua.contains(keyword))
+        }
+        _ => true,
+    }
+}
+
+/// 从 Referer 中提取来源站点；没有 Referer 或来自本站时返回 `DIRECT_REFERRER`
+pub fn referrer_host(referer: Option<&str>, own_host: Option<&str>) -> String {
+    let host = referer
+        .and_then(|referer| referer.split_once("://").map(|(_, rest)| rest))
+        .and_then(|rest| rest.split(['/', '?', '#']).next())
+        .map(|authority| authority.rsplit('@').next().unwrap_or(authority))
+        .map(|host| host.split(':').next().unwrap_or(host).to_ascii_lowercase())
+        .filter(|host| !host.is_empty());
+
+    let own_host = own_host.map(|host| host.split(':').next().unwrap_or(host).to_ascii_lowercase());
+    match host {
+        Some(host) if Some(&host) != own_host.as_ref() => host,
+        _ => DIRECT_REFERRER.to_string(),
+    }
+}
+
+/// 某篇笔记某天尚未写回的统计
+#[derive(Debug, Default)]
+struct PendingDay {
+    views: i32,
+    unique_visitors: i32,
+    referrers: HashMap<String, i32>,
+}
+
+impl PendingDay {
+    fn merge(&mut self, other: PendingDay) {
+        self.views += other.views;
+        self.unique_visitors += other.unique_visitors;
+        for (referrer, views) in other.referrers {
+            *self.referrers.entry(referrer).or_insert(0) += views;
+        }
+    }
+}
+
+/// 尚未写回的全部增量
+#[derive(Debug, Default)]
+struct Pending {
+    /// (笔记 ID, 日期) -> 该笔记当天的统计
+    notes: HashMap<(i32, NaiveDate), PendingDay>,
+    /// 日期 -> 当天新增的全站独立访客数
+    site_visitors: HashMap<NaiveDate, i32>,
+}
+
+impl Pending {
+    fn is_empty(&self) -> bool {
+        self.notes.is_empty() && self.site_visitors.is_empty()
+    }
+
+    fn merge(&mut self, other: Pending) {
+        for (key, day) in other.notes {
+            self.notes.entry(key).or_default().merge(day);
+        }
+        for (day, visitors) in other.site_visitors {
+            *self.site_visitors.entry(day).or_insert(0) += visitors;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    /// (笔记 ID, 访客标识) -> 最近一次计数的时间
+    seen: HashMap<(i32, String), Instant>,
+    /// 当天已经访问过各笔记的访客，用于统计独立访客数
+    visitors: HashSet<(i32, NaiveDate, String)>,
+    /// 当天访问过任意笔记的访客，用于统计全站独立访客数
+    site_visitors: HashSet<(NaiveDate, String)>,
+    /// 尚未写回的统计增量
+    pending: Pending,
+    /// 去重记录已满且清理过期记录后仍然满，下次写回前不再重复清理
+    saturated: bool,
+    /// 独立访客记录已满，下次写回前不再重复告警
+    visitors_saturated: bool,
+}
+
+impl Inner {
+    /// 判断访客是否为当天的新访客
+    ///
+    /// 记录已满时无法判断，按新访客处理：独立访客数宁可偏多也不停止增长。
+    fn first_visit<K: std::hash::Hash + Eq>(
+        set: &mut HashSet<K>,
+        key: K,
+        max_tracked: usize,
+        saturated: &mut bool,
+    ) -> bool {
+        if set.contains(&key) {
+            return false;
+        }
+        if set.len() < max_tracked {
+            set.insert(key);
+        } else if !*saturated {
+            *saturated = true;
+            log::warn!("⚠️ 独立访客记录已达上限 {max_tracked}，新访客均按独立访客计数");
+        }
+        true
+    }
+}
+
+/// 浏览量聚合器，放在 `AppState` 中由所有请求共享
+pub struct ViewTracker {
+    dedup_window: Duration,
+    max_tracked: usize,
+    inner: Mutex<Inner>,
+}
+
+impl ViewTracker {
+    pub fn new(config: &ViewConfig) -> Self {
+        Self {
+            dedup_window: config.dedup_window(),
+            max_tracked: config.max_tracked,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// 记录一次访问，返回这次访问是否被计入浏览量
+    ///
+    /// `referrer` 是经过 `referrer_host` 归一化后的来源站点。
+    pub fn record(
+        &self,
+        note_id: i32,
+        visitor: &str,
+        user_agent: Option<&str>,
+        referrer: &str,
+    ) -> bool {
+        if is_bot(user_agent) {
+            metrics::record_view("bot");
+            return false;
+        }
+        let counted = self.record_at(
+            note_id,
+            visitor,
+            referrer,
+            Instant::now(),
+            Utc::now().date_naive(),
+        );
+        metrics::record_view(if counted { "counted" } else { "duplicate" });
+        counted
+    }
+
+    fn record_at(
+        &self,
+        note_id: i32,
+        visitor: &str,
+        referrer: &str,
+        now: Instant,
+        today: NaiveDate,
+    ) -> bool {
+        let mut inner = self.inner.lock().expect("view tracker lock poisoned");
+        let key = (note_id, visitor.to_string());
+        if let Some(last) = inner.seen.get(&key)
+            && now.duration_since(*last) < self.dedup_window
+        {
+            return false;
+        }
+        if inner.seen.len() >= self.max_tracked
+            && !inner.seen.contains_key(&key)
+            && !inner.saturated
+        {
+            let window = self.dedup_window;
+            inner
+                .seen
+                .retain(|_, last| now.duration_since(*last) < window);
+            if inner.seen.len() >= self.max_tracked {
+                inner.saturated = true;
+                log::warn!(
+                    "⚠️ 浏览量去重记录已达上限 {}，新访客暂不去重",
+                    self.max_tracked
+                );
+            }
+        }
+        if inner.seen.len() < self.max_tracked || inner.seen.contains_key(&key) {
+            inner.seen.insert(key, now);
+        }
+
+        let inner = &mut *inner;
+        let first_today = Inner::first_visit(
+            &mut inner.visitors,
+            (note_id, today, visitor.to_string()),
+            self.max_tracked,
+            &mut inner.visitors_saturated,
+        );
+        let first_on_site = Inner::first_visit(
+            &mut inner.site_visitors,
+            (today, visitor.to_string()),
+            self.max_tracked,
+            &mut inner.visitors_saturated,
+        );
+        if first_on_site {
+            *inner.pending.site_visitors.entry(today).or_insert(0) += 1;
+        }
+        let day = inner.pending.notes.entry((note_id, today)).or_default();
+        day.views += 1;
+        if first_today {
+            day.unique_visitors += 1;
+        }
+        *day.referrers.entry(referrer.to_string()).or_insert(0) += 1;
+        true
+    }
+
+    /// 取出所有待写回的增量，同时清理已过期的去重记录
+    fn drain(&self, now: Instant, today: NaiveDate) -> Pending {
+        let mut inner = self.inner.lock().expect("view tracker lock poisoned");
+        let window = self.dedup_window;
+        inner
+            .seen
+            .retain(|_, last| now.duration_since(*last) < window);
+        inner.visitors.retain(|(_, day, _)| *day >= today);
+        inner.site_visitors.retain(|(day, _)| *day >= today);
+        inner.saturated = false;
+        inner.visitors_saturated = false;
+        std::mem::take(&mut inner.pending)
+    }
+
+    /// 写回失败时把增量放回去，等待下一次写回
+    fn restore(&self, pending: Pending) {
+        let mut inner = self.inner.lock().expect("view tracker lock poisoned");
+        inner.pending.merge(pending);
+    }
+
+    /// 在一个事务中把累计的浏览量批量写回数据库，返回写回的笔记数
+    pub async fn flush(&self, db: &DatabaseConnection) -> AppResult<usize> {
+        let pending = self.drain(Instant::now(), Utc::now().date_naive());
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        match write_back(db, &pending).await {
+            Ok(notes) => Ok(notes),
+            Err(err) => {
+                self.restore(pending);
+                Err(err)
+            }
+        }
+    }
+}
+
+async fn write_back(db: &DatabaseConnection, pending: &Pending) -> AppResult<usize> {
+    let mut totals: HashMap<i32, i32> = HashMap::new();
+    for ((note_id, _), day) in &pending.notes {
+        *totals.entry(*note_id).or_insert(0) += day.views;
+    }
+
+    let txn = db.begin().await?;
+    for (note_id, count) in &totals {
+        NotesMetadata::update_many()
+            .col_expr(
+                notes_metadata::Column::Views,
+                Expr::col(notes_metadata::Column::Views).add(*count),
+            )
+            .filter(notes_metadata::Column::Id.eq(*note_id))
+            .exec(&txn)
+            .await?;
+    }
+    for ((note_id, date), day) in &pending.notes {
+        let counts = DailyCounts {
+            views: day.views,
+            unique_visitors: day.unique_visitors,
+            ..Default::default()
+        };
+        stats_service::add_daily_counts(&txn, *note_id, *date, counts).await?;
+        for (referrer, views) in &day.referrers {
+            stats_service::add_referrer_views(&txn, *note_id, *date, referrer, *views).await?;
+        }
+    }
+    for (date, visitors) in &pending.site_visitors {
+        stats_service::add_site_unique_visitors(&txn, *date, *visitors).await?;
+    }
+    txn.commit().await?;
+    Ok(totals.len())
+}
+
+/// 后台定时写回任务，收到关闭信号后做最后一次写回再退出
+pub async fn run_flusher(
+    tracker: std::sync::Arc<ViewTracker>,
+    db: DatabaseConnection,
+    interval: Duration,
+    shutdown: CancellationToken,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+        match metrics::time_job("view_flush", tracker.flush(&db)).await {
+            Ok(0) => {}
+            Ok(n) => log::debug!("📈 已写回 {n} 篇笔记的浏览量"),
+            Err(err) => log::warn!("⚠️ 浏览量写回失败，将在下次重试: {err}"),
+        }
+    }
+
+    match tracker.flush(&db).await {
+        Ok(n) => log::info!("📈 关闭前写回 {n} 篇笔记的浏览量"),
+        Err(err) => log::error!("❌ 关闭前浏览量写回失败: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(window_secs: u64) -> ViewTracker {
+        ViewTracker::new(&ViewConfig {
+            dedup_window_secs: window_secs,
+            flush_interval_secs: 30,
+            max_tracked: 100,
+        })
+    }
+
+    #[test]
+    fn test_bot_detection() {
+        assert!(is_bot(None));
+        assert!(is_bot(Some("  ")));
+        assert!(is_bot(Some("Mozilla/5.0 (compatible; Googlebot/2.1)")));
+        assert!(is_bot(Some("curl/8.4.0")));
+        assert!(!is_bot(Some(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 Chrome/120.0"
+        )));
+    }
+
+    #[test]
+    fn test_referrer_host() {
+        assert_eq!(referrer_host(None, Some("rowan.dev")), DIRECT_REFERRER);
+        assert_eq!(
+            referrer_host(
+                Some("https://www.Google.com/search?q=rust"),
+                Some("rowan.dev")
+            ),
+            "www.google.com"
+        );
+        assert_eq!(
+            referrer_host(Some("https://rowan.dev/notes/a"), Some("rowan.dev:443")),
+            DIRECT_REFERRER
+        );
+        assert_eq!(referrer_host(Some("not a url"), None), DIRECT_REFERRER);
+    }
+
+    #[test]
+    fn test_views_are_deduplicated_within_window() {
+        let tracker = tracker(60);
+        let start = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2025, 8, 1).unwrap();
+
+        assert!(tracker.record_at(1, "1.2.3.4", "direct", start, today));
+        assert!(!tracker.record_at(
+            1,
+            "1.2.3.4",
+            "direct",
+            start + Duration::from_secs(10),
+            today
+        ));
+        assert!(tracker.record_at(1, "5.6.7.8", "google.com", start, today));
+        assert!(tracker.record_at(2, "1.2.3.4", "direct", start, today));
+        assert!(tracker.record_at(
+            1,
+            "1.2.3.4",
+            "direct",
+            start + Duration::from_secs(61),
+            today
+        ));
+
+        let pending = tracker.drain(start + Duration::from_secs(61), today);
+        let note1 = &pending.notes[&(1, today)];
+        assert_eq!(note1.views, 3);
+        assert_eq!(note1.unique_visitors, 2);
+        assert_eq!(note1.referrers["direct"], 2);
+        assert_eq!(note1.referrers["google.com"], 1);
+        assert_eq!(pending.notes[&(2, today)].views, 1);
+        // 同一访客看了两篇笔记，全站只算一个独立访客
+        assert_eq!(pending.site_visitors[&today], 2);
+        assert!(tracker.drain(start, today).is_empty());
+    }
+
+    #[test]
+    fn test_restore_merges_counts() {
+        let tracker = tracker(60);
+        let start = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2025, 8, 1).unwrap();
+        tracker.record_at(1, "a", "direct", start, today);
+        let pending = tracker.drain(start, today);
+        tracker.record_at(1, "b", "direct", start, today);
+        tracker.restore(pending);
+        let merged = tracker.drain(start, today);
+        assert_eq!(merged.notes[&(1, today)].views, 2);
+        assert_eq!(merged.notes[&(1, today)].unique_visitors, 2);
+        assert_eq!(merged.site_visitors[&today], 2);
+    }
+
+    #[test]
+    fn test_dedup_records_are_capped() {
+        let tracker = ViewTracker::new(&ViewConfig {
+            dedup_window_secs: 60,
+            flush_interval_secs: 30,
+            max_tracked: 2,
+        });
+        let start = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2025, 8, 1).unwrap();
+
+        assert!(tracker.record_at(1, "a", "direct", start, today));
+        assert!(tracker.record_at(1, "b", "direct", start, today));
+        // 记录已满：新访客照常计数但不被记住，已记住的访客仍然去重
+        assert!(tracker.record_at(1, "c", "direct", start, today));
+        assert!(tracker.record_at(1, "c", "direct", start, today));
+        assert!(!tracker.record_at(1, "a", "direct", start, today));
+        assert_eq!(tracker.inner.lock().unwrap().seen.len(), 2);
+
+        // 过期记录清理后又可以记住新访客
+        let later = start + Duration::from_secs(61);
+        let pending = tracker.drain(later, today);
+        assert_eq!(pending.notes[&(1, today)].views, 4);
+        assert_eq!(pending.notes[&(1, today)].unique_visitors, 4);
+        assert!(tracker.record_at(1, "c", "direct", later, today));
+        assert!(!tracker.record_at(1, "c", "direct", later, today));
+    }
+
+    #[test]
+    fn test_unique_visitors_keep_counting_past_cap() {
+        let tracker = ViewTracker::new(&ViewConfig {
+            dedup_window_secs: 0,
+            flush_interval_secs: 30,
+            max_tracked: 2,
+        });
+        let start = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2025, 8, 1).unwrap();
+
+        for visitor in ["a", "b", "c", "d", "a"] {
+            assert!(tracker.record_at(1, visitor, "direct", start, today));
+        }
+
+        // 记录已满后的新访客按独立访客计数，已记住的访客不重复计数
+        let pending = tracker.drain(start, today);
+        assert_eq!(pending.notes[&(1, today)].views, 5);
+        assert_eq!(pending.notes[&(1, today)].unique_visitors, 4);
+        assert_eq!(pending.site_visitors[&today], 4);
+    }
+}